@@ -0,0 +1,57 @@
+//! Throughput of the PTY reader's batching, fed like `cat` of a large file:
+//! full 8 KiB reads as fast as they come, each batch taken from the real
+//! `OutputBatcher` and framed by `PtyOutputEvent::encode` the way the reader
+//! sends it.
+//!
+//! Run with `cargo bench --bench output_batching`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+use termillion_lib::bench::{OutputBatcher, PtyOutputEvent};
+
+const READ_SIZE: usize = 8 * 1024;
+
+fn output(size: usize) -> Vec<u8> {
+    let line = b"drwxr-xr-x  12 user staff   384 Jan  1 12:00 some-directory-name\r\n";
+    line.iter().copied().cycle().take(size).collect()
+}
+
+// Batch and frame `data` like the reader thread does, returns the bytes sent
+fn send_batches(data: &[u8], timeout: Duration) -> usize {
+    let mut sent = 0;
+    let mut batcher = OutputBatcher::new(READ_SIZE, timeout);
+    let mut send = |batch: Vec<u8>| {
+        let frame = PtyOutputEvent::Output(batch).encode().unwrap();
+        sent += black_box(frame).len();
+    };
+    for chunk in data.chunks(READ_SIZE) {
+        batcher.buffer_mut().extend_from_slice(chunk);
+        if let Some(batch) = batcher.take(false) {
+            send(batch);
+        }
+    }
+    if let Some(batch) = batcher.take(true) {
+        send(batch);
+    }
+    sent
+}
+
+fn output_batching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("output_batching");
+    for size in [1024 * 1024, 16 * 1024 * 1024] {
+        let data = output(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        // Every read sent on its own, the most frames the reader can send
+        group.bench_with_input(BenchmarkId::new("every_read", size), &data, |b, data| {
+            b.iter(|| send_batches(data, Duration::ZERO))
+        });
+        // The reader's default batch timeout
+        group.bench_with_input(BenchmarkId::new("batched", size), &data, |b, data| {
+            b.iter(|| send_batches(data, Duration::from_millis(10)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, output_batching);
+criterion_main!(benches);
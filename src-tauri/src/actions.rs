@@ -0,0 +1,144 @@
+use tauri::AppHandle;
+
+use crate::config::Config;
+use crate::pty;
+
+/// Expand the escape sequences and placeholders in a `send_text` value
+pub fn expand_send_text(text: &str, pty_id: &str) -> String {
+    let home = dirs::home_dir().unwrap_or_default();
+    fill_placeholders(&unescape(text), pty_id, &home.to_string_lossy(), || {
+        pty::pty_cwd(pty_id).unwrap_or_default()
+    })
+}
+
+/// Replace `{pty_id}`, `{home}` and `{cwd}` in one pass. Runs after
+/// unescaping, so backslashes in the values, like in Windows paths, are
+/// sent as they are rather than turned into control characters.
+fn fill_placeholders(text: &str, pty_id: &str, home: &str, cwd: impl FnOnce() -> String) -> String {
+    let mut cwd = Some(cwd);
+    let mut cwd_value = String::new();
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder = ["{pty_id}", "{home}", "{cwd}"]
+            .into_iter()
+            .find(|placeholder| rest.starts_with(placeholder));
+        match placeholder {
+            Some("{pty_id}") => output.push_str(pty_id),
+            Some("{home}") => output.push_str(home),
+            Some(_) => {
+                // Only looked up when used
+                if let Some(cwd) = cwd.take() {
+                    cwd_value = cwd();
+                }
+                output.push_str(&cwd_value);
+            }
+            None => output.push('{'),
+        }
+        rest = &rest[placeholder.map_or(1, str::len)..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Turn `\x1b`, `\e`, `\r`, `\n`, `\t`, `\u{...}` and `\\` into the characters they represent.
+/// Unknown escapes are kept verbatim.
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('r') => output.push('\r'),
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('e') => output.push('\x1b'),
+            Some('\\') => output.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if byte.is_ascii() => output.push(byte as char),
+                    _ => {
+                        output.push_str("\\x");
+                        output.push_str(&hex);
+                    }
+                }
+            }
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let hex: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => output.push(ch),
+                    None => output.push_str(&format!("\\u{{{}}}", hex)),
+                }
+            }
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            }
+            None => output.push('\\'),
+        }
+    }
+
+    output
+}
+
+/// Run a configured action against a PTY. Returns false for actions the
+/// frontend handles itself (new_tab, split_vertical, ...).
+pub fn run_action(app: &AppHandle, action_name: &str, pty_id: &str) -> Result<bool, String> {
+    let config = Config::load(app)?;
+    let shortcut = config
+        .shortcuts
+        .get(action_name)
+        .ok_or_else(|| format!("Unknown action '{}'", action_name))?;
+
+    match &shortcut.send_text {
+        Some(text) => {
+            let data = expand_send_text(text, pty_id);
+            pty::write_to_pty(pty_id, data.as_bytes())?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// Run a configured action against a PTY
+#[tauri::command]
+pub async fn execute_action(
+    app: AppHandle,
+    action_name: String,
+    pty_id: String,
+) -> Result<bool, String> {
+    run_action(&app, &action_name, &pty_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"\x1b[A\r"), "\x1b[A\r");
+        assert_eq!(unescape(r"\e\u{2764}\\n"), "\x1b\u{2764}\\n");
+        assert_eq!(unescape(r"\q"), r"\q");
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        // Escapes in the value of a placeholder are not expanded
+        let text = unescape(r"cd {cwd}\r{x}");
+        let filled = fill_placeholders(&text, "1", "/home/me", || r"C:\new\x03".into());
+        assert_eq!(filled, "cd C:\\new\\x03\r{x}");
+        assert_eq!(
+            fill_placeholders("{home}/{pty_id}", "1", "{cwd}", || unreachable!()),
+            "{cwd}/1"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+use serde::Serialize;
+
+use crate::config::AnnotationsConfig;
+
+/// Longest unfinished line kept waiting for its newline, longer ones are
+/// scanned as they are
+const MAX_PENDING: usize = 64 * 1024;
+
+/// Most annotations reported for one batch of output
+const MAX_ANNOTATIONS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    Url,
+    /// File path followed by a line and maybe a column, like compilers print
+    Path,
+    Ip,
+    /// Abbreviated or full git commit hash
+    Sha,
+    /// Matched by one of the configured rules
+    Custom,
+}
+
+/// Clickable text in the output
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    /// Name of the configured rule, for custom annotations
+    pub rule: Option<String>,
+    pub text: String,
+    /// Byte range in the PTY's output stream, counted over all output events
+    pub start: u64,
+    pub end: u64,
+    /// File and position of path annotations
+    pub path: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+lazy_static! {
+    static ref URL: Regex =
+        Regex::new(r#"(?-u:\b)(?:https?|ftp|file)://[^\s\x00-\x1f"'<>`]+"#).unwrap();
+    static ref PATH: Regex = Regex::new(
+        r"(?P<file>(?:~|\.{1,2}|[A-Za-z]:)?[\\/]?(?:[\w.\-]+[\\/])*[\w\-]+\.[A-Za-z]\w*):(?P<line>\d+)(?::(?P<column>\d+))?"
+    )
+    .unwrap();
+    static ref IP: Regex = Regex::new(
+        r"(?-u:\b)(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)(?-u:\b)"
+    )
+    .unwrap();
+    static ref SHA: Regex = Regex::new(r"(?-u:\b)[0-9a-f]{7,40}(?-u:\b)").unwrap();
+}
+
+struct Rule {
+    kind: AnnotationKind,
+    name: Option<String>,
+    regex: Regex,
+}
+
+/// Finds annotations in a PTY's output. Lines are scanned once complete, so
+/// text split across reads is still found.
+pub struct AnnotationScanner {
+    rules: Vec<Rule>,
+    pending: Vec<u8>,
+    /// Stream offset of the first pending byte
+    pending_start: u64,
+}
+
+// Leave out punctuation that usually ends the sentence around a URL
+fn trim_url(url: &[u8]) -> &[u8] {
+    let mut end = url.len();
+    while end > 0 {
+        let last = url[end - 1];
+        let unbalanced = last == b')' && !url[..end].contains(&b'(');
+        if matches!(last, b'.' | b',' | b';' | b':' | b'!' | b'?' | b']' | b'}') || unbalanced {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    &url[..end]
+}
+
+// Hex words like `defaced` or numbers like `1234567` aren't hashes
+fn looks_like_sha(text: &[u8]) -> bool {
+    text.iter().any(u8::is_ascii_digit) && text.iter().any(u8::is_ascii_alphabetic)
+}
+
+impl AnnotationScanner {
+    /// Custom rules come first so they win over the built-in ones. Invalid
+    /// patterns are skipped, validation reports them. `None` when there is
+    /// nothing to look for.
+    pub fn new(config: &AnnotationsConfig) -> Option<Self> {
+        let mut rules: Vec<Rule> = config
+            .rules
+            .iter()
+            .flatten()
+            .filter_map(|rule| {
+                Some(Rule {
+                    kind: AnnotationKind::Custom,
+                    name: Some(rule.name.clone()),
+                    regex: Regex::new(&rule.pattern).ok()?,
+                })
+            })
+            .collect();
+        if config.builtin.unwrap_or(true) {
+            let builtin = [
+                (AnnotationKind::Url, &*URL),
+                (AnnotationKind::Path, &*PATH),
+                (AnnotationKind::Ip, &*IP),
+                (AnnotationKind::Sha, &*SHA),
+            ];
+            rules.extend(builtin.into_iter().map(|(kind, regex)| Rule {
+                kind,
+                name: None,
+                regex: regex.clone(),
+            }));
+        }
+        if rules.is_empty() {
+            return None;
+        }
+        Some(Self {
+            rules,
+            pending: Vec::new(),
+            pending_start: 0,
+        })
+    }
+
+    /// Annotations of the lines completed by `data`, which starts at `offset`
+    /// in the output stream
+    pub fn scan(&mut self, data: &[u8], offset: u64) -> Vec<Annotation> {
+        if self.pending.is_empty() {
+            self.pending_start = offset;
+        }
+        self.pending.extend_from_slice(data);
+        let end = match self.pending.iter().rposition(|&b| b == b'\n') {
+            Some(newline) => newline + 1,
+            None if self.pending.len() > MAX_PENDING => self.pending.len(),
+            None => return Vec::new(),
+        };
+        let text: Vec<u8> = self.pending.drain(..end).collect();
+        let start = self.pending_start;
+        self.pending_start += text.len() as u64;
+        self.annotate(&text, start)
+    }
+
+    fn annotate(&self, text: &[u8], offset: u64) -> Vec<Annotation> {
+        let mut found: Vec<Annotation> = Vec::new();
+        for rule in &self.rules {
+            for captures in rule.regex.captures_iter(text) {
+                let whole = captures.get(0).unwrap();
+                let mut bytes = whole.as_bytes();
+                match rule.kind {
+                    AnnotationKind::Url => bytes = trim_url(bytes),
+                    AnnotationKind::Sha if !looks_like_sha(bytes) => continue,
+                    _ => {}
+                }
+                if bytes.is_empty() {
+                    continue;
+                }
+                let start = offset + whole.start() as u64;
+                let end = start + bytes.len() as u64;
+                // Text already claimed by an earlier rule stays with it
+                if found.iter().any(|a| a.start < end && start < a.end) {
+                    continue;
+                }
+                let number = |name: &str| {
+                    captures
+                        .name(name)
+                        .and_then(|m| std::str::from_utf8(m.as_bytes()).ok()?.parse().ok())
+                };
+                found.push(Annotation {
+                    kind: rule.kind,
+                    rule: rule.name.clone(),
+                    text: String::from_utf8_lossy(bytes).into_owned(),
+                    start,
+                    end,
+                    path: captures
+                        .name("file")
+                        .filter(|_| rule.kind == AnnotationKind::Path)
+                        .map(|m| String::from_utf8_lossy(m.as_bytes()).into_owned()),
+                    line: number("line").filter(|_| rule.kind == AnnotationKind::Path),
+                    column: number("column").filter(|_| rule.kind == AnnotationKind::Path),
+                });
+                if found.len() >= MAX_ANNOTATIONS {
+                    break;
+                }
+            }
+        }
+        found.sort_by_key(|a| a.start);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnnotationRule;
+
+    #[test]
+    fn test_annotate_output() {
+        let mut scanner = AnnotationScanner::new(&AnnotationsConfig {
+            builtin: None,
+            rules: Some(vec![AnnotationRule {
+                name: "jira".into(),
+                pattern: r"\b[A-Z]+-\d+\b".into(),
+            }]),
+        })
+        .unwrap();
+
+        // Nothing is reported until the line is complete
+        assert!(scanner
+            .scan(b"see https://example.com/a_(b).", 100)
+            .is_empty());
+        let found = scanner.scan(b"\r\nerror at src/main.rs:12:5 in 3f2a9c1\n", 130);
+        let kinds: Vec<_> = found.iter().map(|a| (a.kind, a.text.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (AnnotationKind::Url, "https://example.com/a_(b)"),
+                (AnnotationKind::Path, "src/main.rs:12:5"),
+                (AnnotationKind::Sha, "3f2a9c1"),
+            ]
+        );
+        assert_eq!((found[0].start, found[0].end), (104, 129));
+        assert_eq!(found[1].path.as_deref(), Some("src/main.rs"));
+        assert_eq!((found[1].line, found[1].column), (Some(12), Some(5)));
+
+        let found = scanner.scan(b"PROJ-42 on 10.0.0.1, build 1234567 defaced\n", 0);
+        let kinds: Vec<_> = found.iter().map(|a| (a.kind, a.text.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (AnnotationKind::Custom, "PROJ-42"),
+                (AnnotationKind::Ip, "10.0.0.1"),
+            ]
+        );
+        assert_eq!(found[0].rule.as_deref(), Some("jira"));
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::Local;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::config::{get_config_path, Config};
+use crate::validation::ValidationError;
+
+/// Directory next to the config file the backups are written to
+const BACKUP_DIR: &str = "config_backups";
+
+/// Number of backups kept, older ones are deleted
+const MAX_BACKUPS: usize = 20;
+
+/// A backup of the config file
+#[derive(Debug, Serialize)]
+pub struct ConfigBackup {
+    /// File name, used to restore the backup
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    pub created: u64,
+}
+
+fn backup_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+// Backup files sorted oldest first, the timestamp in the name sorts correctly
+fn backup_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Copy the config file into the backup directory with a timestamp in its
+/// name. Nothing is written if the newest backup has the same content.
+pub fn backup_config(config_path: &Path) -> Result<Option<PathBuf>, String> {
+    let Ok(content) = fs::read(config_path) else {
+        return Ok(None);
+    };
+
+    let dir = backup_dir(config_path);
+    let files = backup_files(&dir);
+    if let Some(newest) = files.last() {
+        if fs::read(newest).is_ok_and(|newest| newest == content) {
+            return Ok(None);
+        }
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let stem = config_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("termillion");
+    let extension = config_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("toml");
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S-%3f");
+    let path = dir.join(format!("{}-{}.{}", stem, timestamp, extension));
+    fs::write(&path, content).map_err(|e| format!("Failed to write config backup: {}", e))?;
+
+    prune_backups(&dir)?;
+    Ok(Some(path))
+}
+
+// Delete the oldest backups beyond MAX_BACKUPS
+fn prune_backups(dir: &Path) -> Result<(), String> {
+    let files = backup_files(dir);
+    let excess = files.len().saturating_sub(MAX_BACKUPS);
+    for path in &files[..excess] {
+        fs::remove_file(path).map_err(|e| format!("Failed to delete old backup: {}", e))?;
+    }
+    Ok(())
+}
+
+// List the config backups, newest first
+#[tauri::command]
+pub async fn list_config_backups(app: AppHandle) -> Result<Vec<ConfigBackup>, String> {
+    let config_path = get_config_path(&app)?;
+    let backups = backup_files(&backup_dir(&config_path))
+        .into_iter()
+        .rev()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let created = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            Some(ConfigBackup {
+                name: path.file_name()?.to_str()?.to_string(),
+                size: metadata.len(),
+                created,
+            })
+        })
+        .collect();
+    Ok(backups)
+}
+
+// Replace the config with a backup, the current config is backed up first
+#[tauri::command]
+pub async fn restore_config_backup(
+    app: AppHandle,
+    name: String,
+) -> Result<Vec<ValidationError>, String> {
+    let config_path = get_config_path(&app)?;
+    let backup_path = backup_dir(&config_path).join(&name);
+    if name.contains(['/', '\\']) || !backup_path.is_file() {
+        return Err(format!("Config backup not found: {}", name));
+    }
+
+    let content =
+        fs::read(&backup_path).map_err(|e| format!("Failed to read config backup: {}", e))?;
+    backup_config(&config_path)?;
+
+    // The backup may be in another format than the current config
+    let restored_path = match backup_path.extension() {
+        Some(extension) => config_path.with_extension(extension),
+        None => config_path.clone(),
+    };
+    fs::write(&restored_path, content)
+        .map_err(|e| format!("Failed to restore config backup: {}", e))?;
+    if restored_path != config_path {
+        fs::remove_file(&config_path)
+            .map_err(|e| format!("Failed to remove old config file: {}", e))?;
+    }
+
+    let config = Config::load(&app)?;
+    Ok(config.validate())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backups_are_pruned() {
+        let dir = std::env::temp_dir().join(format!("termillion-backups-{}", std::process::id()));
+        let config_path = dir.join("termillion.toml");
+        let backups = backup_dir(&config_path);
+        fs::create_dir_all(&backups).unwrap();
+        let name = |i: usize| backups.join(format!("termillion-20240101-0000{:02}-000.toml", i));
+        for i in 0..MAX_BACKUPS + 3 {
+            fs::write(name(i), i.to_string()).unwrap();
+        }
+
+        // A config like the newest backup isn't backed up again
+        fs::write(&config_path, (MAX_BACKUPS + 2).to_string()).unwrap();
+        assert_eq!(backup_config(&config_path).unwrap(), None);
+
+        // A changed one is, and the oldest backups beyond the limit go
+        fs::write(&config_path, "changed").unwrap();
+        let newest = backup_config(&config_path).unwrap().unwrap();
+        assert_eq!(backup_files(&backups).len(), MAX_BACKUPS);
+        assert!((0..4).all(|i| !name(i).exists()));
+        assert!((4..MAX_BACKUPS + 3).all(|i| name(i).exists()));
+        assert_eq!(fs::read_to_string(newest).unwrap(), "changed");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
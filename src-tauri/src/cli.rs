@@ -0,0 +1,147 @@
+use clap::{Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::control::ControlRequest;
+
+/// A terminal emulator
+#[derive(Debug, Default, Parser)]
+#[command(name = "termillion", version)]
+pub struct Cli {
+    /// Run a command instead of the shell. Everything after it is passed to
+    /// the command, so it has to come last.
+    #[arg(short = 'e', long = "execute", value_name = "COMMAND", num_args = 1..)]
+    pub execute: Vec<String>,
+
+    /// Directory to start in
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Profile to open
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Window title
+    #[arg(long, value_name = "TITLE")]
+    pub title: Option<String>,
+
+    /// Window size in pixels, like 1200x800
+    #[arg(long, value_name = "WxH", value_parser = parse_window_size)]
+    pub window_size: Option<(f64, f64)>,
+
+    /// Config file to use instead of the one in the config directory
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print the documented default config, or write it to a file, and exit
+    #[arg(long, value_name = "PATH")]
+    pub generate_config: Option<Option<PathBuf>>,
+
+    /// Open the developer tools
+    #[arg(long)]
+    pub debug: bool,
+
+    /// An ssh:// or sftp:// URL to connect to, passed by the OS for links
+    #[arg(value_name = "URL")]
+    pub url: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Control a running termillion, for scripts and window managers
+    #[command(subcommand)]
+    Cli(ControlRequest),
+}
+
+fn parse_window_size(value: &str) -> Result<(f64, f64), String> {
+    let (width, height) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| "expected WIDTHxHEIGHT, like 1200x800".to_string())?;
+    let parse = |n: &str| match n.trim().parse::<f64>() {
+        Ok(n) if n > 0.0 => Ok(n),
+        _ => Err(format!("invalid size '{}'", n)),
+    };
+    Ok((parse(width)?, parse(height)?))
+}
+
+impl Cli {
+    /// Parse the arguments, exiting with a usage message on errors. Arguments
+    /// after `-e` are split off first so options of the command, like
+    /// `-e vim --clean`, aren't taken as ours. A `-e` after `--` belongs to
+    /// the command given there.
+    pub fn parse_args<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let execute_at = args
+            .iter()
+            .take_while(|arg| *arg != "--")
+            .position(|arg| arg == "-e" || arg == "--execute");
+        let execute: Vec<String> = match execute_at {
+            Some(index) => args
+                .split_off(index)
+                .into_iter()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            None => Vec::new(),
+        };
+        if execute_at.is_some() && execute.is_empty() {
+            args.push("--execute".into());
+        }
+
+        let mut cli = Self::parse_from(args);
+        cli.execute = execute;
+        cli
+    }
+
+    /// Whether the first tab was specified on the command line
+    pub fn has_tab_options(&self) -> bool {
+        !self.execute.is_empty()
+            || self.cwd.is_some()
+            || self.profile.is_some()
+            || self.url.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::parse_args([
+            "termillion",
+            "--cwd",
+            "/tmp",
+            "--window-size",
+            "1200x800",
+            "-e",
+            "vim",
+            "--clean",
+            "-e",
+        ]);
+        assert_eq!(cli.execute, ["vim", "--clean", "-e"]);
+        assert_eq!(cli.cwd, Some(PathBuf::from("/tmp")));
+        assert_eq!(cli.window_size, Some((1200.0, 800.0)));
+        assert!(cli.has_tab_options());
+
+        let cli = Cli::parse_args(["termillion", "--generate-config"]);
+        assert_eq!(cli.generate_config, Some(None));
+        assert!(!cli.has_tab_options());
+
+        let cli = Cli::parse_args(["termillion", "cli", "spawn", "--", "vim", "-e"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Cli(ControlRequest::Spawn { ref command, .. })) if command == &["vim", "-e"]
+        ));
+
+        assert!(parse_window_size("800").is_err());
+        assert!(parse_window_size("0x600").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+/// A parsed CSS color, channels range from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Color {
+    /// Blend a translucent color over an opaque background
+    pub fn over(self, background: Color) -> Color {
+        let blend = |fg: f64, bg: f64| fg * self.a + bg * (1.0 - self.a);
+        Color {
+            r: blend(self.r, background.r),
+            g: blend(self.g, background.g),
+            b: blend(self.b, background.b),
+            a: 1.0,
+        }
+    }
+
+    /// WCAG relative luminance
+    pub fn luminance(self) -> f64 {
+        let linear = |c: f64| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+}
+
+/// WCAG contrast ratio between two opaque colors, from 1 to 21
+pub fn contrast_ratio(a: Color, b: Color) -> f64 {
+    let (a, b) = (a.luminance(), b.luminance());
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn hex_digit(c: char) -> Option<f64> {
+    c.to_digit(16).map(f64::from)
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digits: Vec<f64> = hex.chars().map(hex_digit).collect::<Option<_>>()?;
+    let channels: Vec<f64> = match digits.len() {
+        // #rgb and #rgba repeat each digit
+        3 | 4 => digits.iter().map(|d| d * 17.0 / 255.0).collect(),
+        6 | 8 => digits
+            .chunks(2)
+            .map(|pair| (pair[0] * 16.0 + pair[1]) / 255.0)
+            .collect(),
+        _ => return None,
+    };
+    Some(Color {
+        r: channels[0],
+        g: channels[1],
+        b: channels[2],
+        a: channels.get(3).copied().unwrap_or(1.0),
+    })
+}
+
+// A number, or a percentage of `max`, scaled to 0..=1
+fn parse_channel(value: &str, max: f64) -> Option<f64> {
+    let channel = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => value.parse::<f64>().ok()? / max,
+    };
+    (0.0..=1.0).contains(&channel).then_some(channel)
+}
+
+fn parse_hue(value: &str) -> Option<f64> {
+    let degrees: f64 = value.strip_suffix("deg").unwrap_or(value).parse().ok()?;
+    degrees
+        .is_finite()
+        .then_some(degrees.rem_euclid(360.0) / 360.0)
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (f64, f64, f64) {
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        }
+    };
+    (channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0))
+}
+
+// Arguments of rgb() and hsl(), both the comma and the space separated forms
+fn function_args<'a>(value: &'a str, names: [&str; 2]) -> Option<Vec<&'a str>> {
+    let args = names
+        .iter()
+        .find_map(|name| value.strip_prefix(name))?
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let args: Vec<&str> = args
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    matches!(args.len(), 3 | 4).then_some(args)
+}
+
+/// Parse a `#hex`, `rgb()`/`rgba()` or `hsl()`/`hsla()` color
+pub fn parse(value: &str) -> Option<Color> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if value == "transparent" {
+        return Some(Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.0,
+        });
+    }
+
+    let alpha = |args: &[&str]| args.get(3).map_or(Some(1.0), |a| parse_channel(a, 1.0));
+    if let Some(args) = function_args(&value, ["rgba", "rgb"]) {
+        return Some(Color {
+            r: parse_channel(args[0], 255.0)?,
+            g: parse_channel(args[1], 255.0)?,
+            b: parse_channel(args[2], 255.0)?,
+            a: alpha(&args)?,
+        });
+    }
+    if let Some(args) = function_args(&value, ["hsla", "hsl"]) {
+        // Saturation and lightness must be percentages
+        let percent = |arg: &str| arg.ends_with('%').then(|| parse_channel(arg, 100.0))?;
+        let (r, g, b) = hsl_to_rgb(parse_hue(args[0])?, percent(args[1])?, percent(args[2])?);
+        return Some(Color {
+            r,
+            g,
+            b,
+            a: alpha(&args)?,
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_formats() {
+        let red = Color {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        assert_eq!(parse("#f00"), Some(red));
+        assert_eq!(parse("#FF0000"), Some(red));
+        assert_eq!(parse("rgb(255, 0, 0)"), Some(red));
+        assert_eq!(parse("rgb(100% 0% 0%)"), Some(red));
+        assert_eq!(parse("hsl(0deg, 100%, 50%)"), Some(red));
+        assert_eq!(parse("rgba(255, 0, 0, 0.5)").map(|c| c.a), Some(0.5));
+        assert_eq!(parse("#ff000080").map(|c| c.a), Some(128.0 / 255.0));
+
+        assert_eq!(parse("#f000"), Some(Color { a: 0.0, ..red }));
+        assert_eq!(parse("#gg0000"), None);
+        assert_eq!(parse("rgb(300, 0, 0)"), None);
+        assert_eq!(parse("hsl(0, 100, 50)"), None);
+        assert_eq!(parse("red"), None);
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        let black = parse("#000").unwrap();
+        let white = parse("#fff").unwrap();
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 1e-9);
+
+        let half_white = parse("rgba(255, 255, 255, 0.5)").unwrap().over(black);
+        assert!((half_white.r - 0.5).abs() < 1e-9);
+    }
+}
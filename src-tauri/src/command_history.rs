@@ -0,0 +1,358 @@
+use regex::Regex;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::config::CommandHistoryConfig;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty::{self, CommandBlock};
+
+const DEFAULT_LIMIT: usize = 100;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS commands (
+    id INTEGER PRIMARY KEY,
+    command TEXT NOT NULL,
+    cwd TEXT,
+    exit_code INTEGER,
+    duration_ms INTEGER,
+    started_at INTEGER NOT NULL,
+    session TEXT NOT NULL,
+    shell TEXT
+);
+CREATE INDEX IF NOT EXISTS commands_started_at ON commands (started_at);
+CREATE INDEX IF NOT EXISTS commands_command ON commands (command);
+";
+
+/// A command run in any terminal, as recorded from the shell integration marks
+#[derive(Debug, Serialize, PartialEq)]
+pub struct HistoryRecord {
+    pub id: i64,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    /// Unix time in milliseconds
+    pub started_at: u64,
+    /// ID of the PTY the command ran in
+    pub session: String,
+    pub shell: Option<String>,
+}
+
+/// Narrows a history search, unset filters match everything
+#[derive(Debug, Deserialize, Default)]
+pub struct HistoryFilters {
+    /// Only commands run in this directory
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Only commands that failed (true) or succeeded (false)
+    pub failed: Option<bool>,
+    /// Only commands run in this PTY
+    pub session: Option<String>,
+    /// Unix times in milliseconds
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Only the latest run of each command
+    pub unique: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+/// Command history database of the running app, opened on first use
+#[derive(Default)]
+pub struct CommandHistoryState {
+    db: Mutex<Option<Connection>>,
+}
+
+fn db_path(app: &AppHandle) -> CommandResult<PathBuf> {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| TermillionError::new(ErrorKind::NotFound, "Failed to get data directory"))?;
+    path.push("history.sqlite");
+    Ok(path)
+}
+
+fn db_error(action: &str, e: rusqlite::Error) -> TermillionError {
+    TermillionError::new(ErrorKind::Io, format!("{}: {}", action, e))
+}
+
+fn open(path: Option<PathBuf>) -> CommandResult<Connection> {
+    let db = match path {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| TermillionError::io("Failed to create data directory", e))?;
+            }
+            Connection::open(&path).map_err(|e| db_error("Failed to open command history", e))?
+        }
+        None => Connection::open_in_memory()
+            .map_err(|e| db_error("Failed to open command history", e))?,
+    };
+    db.execute_batch(SCHEMA)
+        .map_err(|e| db_error("Failed to create command history", e))?;
+    Ok(db)
+}
+
+fn with_db<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&Connection) -> CommandResult<T>,
+) -> CommandResult<T> {
+    let state = app.state::<CommandHistoryState>();
+    let mut db = state.db.lock().unwrap();
+    if db.is_none() {
+        *db = Some(open(Some(db_path(app)?))?);
+    }
+    f(db.as_ref().unwrap())
+}
+
+fn insert(
+    db: &Connection,
+    session: &str,
+    shell: Option<&str>,
+    block: &CommandBlock,
+) -> CommandResult<()> {
+    let Some((command, started_at)) = block.command.as_deref().zip(block.started_at) else {
+        return Ok(());
+    };
+    let duration_ms = block
+        .finished_at
+        .map(|finished_at| finished_at.saturating_sub(started_at));
+    db.execute(
+        "INSERT INTO commands (command, cwd, exit_code, duration_ms, started_at, session, shell)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            command,
+            block.cwd,
+            block.exit_code,
+            duration_ms.map(|ms| ms as i64),
+            started_at as i64,
+            session,
+            shell
+        ],
+    )
+    .map_err(|e| db_error("Failed to record command", e))?;
+    Ok(())
+}
+
+// `LIKE` treats `%` and `_` as wildcards
+fn like_pattern(word: &str) -> String {
+    let escaped = word
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn search(
+    db: &Connection,
+    query: &str,
+    filters: &HistoryFilters,
+) -> CommandResult<Vec<HistoryRecord>> {
+    // Every word of the query has to appear in the command
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+    for word in query.split_whitespace() {
+        conditions.push("command LIKE ? ESCAPE '\\'");
+        values.push(Value::Text(like_pattern(word)));
+    }
+    if let Some(cwd) = &filters.cwd {
+        conditions.push("cwd = ?");
+        values.push(Value::Text(cwd.clone()));
+    }
+    if let Some(exit_code) = filters.exit_code {
+        conditions.push("exit_code = ?");
+        values.push(Value::Integer(exit_code.into()));
+    }
+    match filters.failed {
+        Some(true) => conditions.push("exit_code != 0"),
+        Some(false) => conditions.push("exit_code = 0"),
+        None => {}
+    }
+    if let Some(session) = &filters.session {
+        conditions.push("session = ?");
+        values.push(Value::Text(session.clone()));
+    }
+    if let Some(since) = filters.since {
+        conditions.push("started_at >= ?");
+        values.push(Value::Integer(since as i64));
+    }
+    if let Some(until) = filters.until {
+        conditions.push("started_at < ?");
+        values.push(Value::Integer(until as i64));
+    }
+    let mut condition = if conditions.is_empty() {
+        "1".to_string()
+    } else {
+        conditions.join(" AND ")
+    };
+    if filters.unique.unwrap_or(false) {
+        condition = format!(
+            "id IN (SELECT MAX(id) FROM commands WHERE {} GROUP BY command)",
+            condition
+        );
+    }
+    values.push(Value::Integer(
+        filters
+            .limit
+            .unwrap_or(DEFAULT_LIMIT)
+            .min(i64::MAX as usize) as i64,
+    ));
+
+    let sql = format!(
+        "SELECT id, command, cwd, exit_code, duration_ms, started_at, session, shell
+         FROM commands WHERE {} ORDER BY id DESC LIMIT ?",
+        condition
+    );
+    let mut statement = db
+        .prepare(&sql)
+        .map_err(|e| db_error("Failed to search command history", e))?;
+    let records = statement
+        .query_map(params_from_iter(values), |row| {
+            Ok(HistoryRecord {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                cwd: row.get(2)?,
+                exit_code: row.get(3)?,
+                duration_ms: row.get::<_, Option<i64>>(4)?.map(|ms| ms as u64),
+                started_at: row.get::<_, i64>(5)? as u64,
+                session: row.get(6)?,
+                shell: row.get(7)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| db_error("Failed to search command history", e))?;
+    Ok(records)
+}
+
+/// Records the commands a PTY finishes, made when it is created
+pub struct HistoryRecorder {
+    ignore: Vec<Regex>,
+}
+
+impl HistoryRecorder {
+    /// `None` when recording is turned off. Invalid ignore patterns are
+    /// skipped, validation reports them.
+    pub fn new(config: Option<&CommandHistoryConfig>) -> Option<Self> {
+        if !config.and_then(|c| c.enabled).unwrap_or(true) {
+            return None;
+        }
+        Some(Self {
+            ignore: config
+                .and_then(|c| c.ignore.as_ref())
+                .into_iter()
+                .flatten()
+                .filter_map(|pattern| Regex::new(pattern).ok())
+                .collect(),
+        })
+    }
+
+    pub fn record(&self, app: &AppHandle, pty_id: &str, block: &CommandBlock) {
+        let ignored = block
+            .command
+            .as_deref()
+            .is_some_and(|command| self.ignore.iter().any(|re| re.is_match(command)));
+        if ignored {
+            return;
+        }
+        let shell = pty::pty_shell(pty_id);
+        if let Err(e) = with_db(app, |db| insert(db, pty_id, shell.as_deref(), block)) {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+// Search the commands run in every terminal, newest first. Each word of the
+// query has to appear in the command.
+#[tauri::command]
+pub async fn search_command_history(
+    app: AppHandle,
+    query: String,
+    filters: Option<HistoryFilters>,
+) -> CommandResult<Vec<HistoryRecord>> {
+    with_db(&app, |db| search(db, &query, &filters.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(command: &str, cwd: &str, exit_code: i32, started_at: u64) -> CommandBlock {
+        CommandBlock {
+            id: 0,
+            prompt_start: 0,
+            command_start: None,
+            output_start: None,
+            end: None,
+            command: Some(command.into()),
+            cwd: Some(cwd.into()),
+            exit_code: Some(exit_code),
+            started_at: Some(started_at),
+            finished_at: Some(started_at + 1500),
+        }
+    }
+
+    #[test]
+    fn test_search_history() {
+        let db = open(None).unwrap();
+        insert(
+            &db,
+            "a",
+            Some("bash"),
+            &block("cargo build", "/src", 0, 1000),
+        )
+        .unwrap();
+        insert(
+            &db,
+            "a",
+            Some("bash"),
+            &block("cargo test", "/src", 101, 2000),
+        )
+        .unwrap();
+        insert(
+            &db,
+            "b",
+            Some("zsh"),
+            &block("cargo build", "/tmp", 0, 3000),
+        )
+        .unwrap();
+        insert(&db, "b", Some("zsh"), &block("echo 100%", "/tmp", 0, 4000)).unwrap();
+
+        let commands = |query: &str, filters: HistoryFilters| -> Vec<String> {
+            search(&db, query, &filters)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.command)
+                .collect()
+        };
+        assert_eq!(
+            commands("cargo", HistoryFilters::default()),
+            ["cargo build", "cargo test", "cargo build"]
+        );
+        let unique = HistoryFilters {
+            unique: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(commands("build cargo", unique), ["cargo build"]);
+        let failed = HistoryFilters {
+            failed: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(commands("", failed), ["cargo test"]);
+        let in_src = HistoryFilters {
+            cwd: Some("/src".into()),
+            since: Some(1500),
+            ..Default::default()
+        };
+        assert_eq!(commands("", in_src), ["cargo test"]);
+        // Wildcards in the query are matched literally
+        assert_eq!(commands("0%", HistoryFilters::default()), ["echo 100%"]);
+
+        let latest = &search(&db, "", &HistoryFilters::default()).unwrap()[0];
+        assert_eq!(latest.duration_ms, Some(1500));
+        assert_eq!(latest.shell.as_deref(), Some("zsh"));
+    }
+}
@@ -0,0 +1,358 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::history::{self, HistoryShell};
+use crate::pty;
+
+/// Most completions returned for one request
+const MAX_COMPLETIONS: usize = 20;
+
+/// Most whole-line suggestions taken from the history
+const MAX_HISTORY_COMPLETIONS: usize = 5;
+
+/// How long the PATH scan is reused before looking again
+const EXECUTABLES_TTL: Duration = Duration::from_secs(60);
+
+static EXECUTABLES: Mutex<Option<(Instant, Vec<String>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    History,
+    Executable,
+    Directory,
+    File,
+}
+
+/// A suggestion that replaces the line from `start` up to the cursor
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    pub text: String,
+    /// Character offset in the line where the replaced text starts
+    pub start: usize,
+}
+
+// Weight of one run of a command, more recent runs count more, as in zoxide
+fn age_weight(seconds: u64) -> f64 {
+    match seconds {
+        0..=3_600 => 4.0,
+        3_601..=86_400 => 2.0,
+        86_401..=604_800 => 0.5,
+        _ => 0.25,
+    }
+}
+
+// Without timestamps, how many commands were run since stands in for the age
+fn position_weight(runs_ago: usize) -> f64 {
+    match runs_ago {
+        0..=50 => 4.0,
+        51..=500 => 2.0,
+        501..=5_000 => 0.5,
+        _ => 0.25,
+    }
+}
+
+/// Frecency of each command in a history, oldest first: every run adds to a
+/// command's score and recent runs add more
+pub fn frecency(commands: &[(String, Option<u64>)], now: u64) -> HashMap<&str, f64> {
+    let mut scores = HashMap::new();
+    for (index, (command, timestamp)) in commands.iter().enumerate() {
+        let weight = match timestamp {
+            Some(timestamp) => age_weight(now.saturating_sub(*timestamp)),
+            None => position_weight(commands.len() - index),
+        };
+        *scores.entry(command.as_str()).or_insert(0.0) += weight;
+    }
+    scores
+}
+
+#[cfg(target_os = "windows")]
+fn matches_prefix(text: &str, prefix: &str) -> bool {
+    text.to_lowercase().starts_with(&prefix.to_lowercase())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn matches_prefix(text: &str, prefix: &str) -> bool {
+    text.starts_with(prefix)
+}
+
+// Character offset where the word under the cursor starts. Quoted and
+// backslash-escaped whitespace doesn't split words.
+fn word_start(line: &str) -> usize {
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.chars().enumerate() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (None, '\\') if cfg!(not(target_os = "windows")) => escaped = true,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, _) if c.is_whitespace() => start = index + 1,
+            _ => {}
+        }
+    }
+    start
+}
+
+// Whether the word starting here is a command rather than an argument
+fn is_command_position(before_word: &str) -> bool {
+    let before = before_word.trim_end();
+    before.is_empty() || before.ends_with(['|', ';', '&', '('])
+}
+
+// Commands from the history that continue what has been typed so far
+fn history_completions(line: &str, history: &[(String, Option<u64>)], now: u64) -> Vec<Completion> {
+    if line.trim().is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<(&str, f64)> = frecency(history, now)
+        .into_iter()
+        .filter(|(command, _)| command.len() > line.len() && command.starts_with(line))
+        .collect();
+    matches.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+    matches
+        .into_iter()
+        .take(MAX_HISTORY_COMPLETIONS)
+        .map(|(command, _)| Completion {
+            kind: CompletionKind::History,
+            text: command.to_string(),
+            start: 0,
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.BAT;.CMD;.COM".into());
+    path.extension().is_some_and(|ext| {
+        extensions.split(';').any(|known| {
+            known
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&ext.to_string_lossy())
+        })
+    })
+}
+
+// Names of the programs on the PATH, sorted
+fn scan_executables() -> Vec<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut names = BTreeSet::new();
+    for dir in std::env::split_paths(&path) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if !is_executable(&path) {
+                continue;
+            }
+            // Windows programs are typed without their extension
+            let name = if cfg!(target_os = "windows") {
+                path.file_stem()
+            } else {
+                path.file_name()
+            };
+            if let Some(name) = name {
+                names.insert(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+fn path_executables() -> Vec<String> {
+    let Ok(mut cache) = EXECUTABLES.lock() else {
+        return scan_executables();
+    };
+    match cache.as_ref() {
+        Some((scanned, names)) if scanned.elapsed() < EXECUTABLES_TTL => names.clone(),
+        _ => {
+            let names = scan_executables();
+            *cache = Some((Instant::now(), names.clone()));
+            names
+        }
+    }
+}
+
+// Entries of the directory the word points into, relative to the shell's cwd
+fn path_completions(word: &str, start: usize, cwd: &Path) -> Vec<Completion> {
+    let quoted = word.starts_with(['"', '\'']);
+    let unquoted = word.trim_start_matches(['"', '\'']);
+    let (dir, prefix) = match unquoted.rfind(['/', std::path::MAIN_SEPARATOR]) {
+        Some(index) => unquoted.split_at(index + 1),
+        None => ("", unquoted),
+    };
+    let typed_dir = &word[..word.len() - prefix.len()];
+    let unescape = |text: &str| {
+        if quoted || cfg!(target_os = "windows") {
+            text.to_string()
+        } else {
+            text.replace("\\ ", " ")
+        }
+    };
+    let prefix = unescape(prefix);
+
+    let mut entries: Vec<(bool, String)> = fs::read_dir(cwd.join(pty::expand_home(&unescape(dir))))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Hidden entries only once a dot is typed
+            if (name.starts_with('.') && !prefix.starts_with('.'))
+                || !matches_prefix(&name, &prefix)
+            {
+                return None;
+            }
+            Some((entry.path().is_dir(), name))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    entries
+        .into_iter()
+        .map(|(is_dir, name)| {
+            let name = if quoted || cfg!(target_os = "windows") {
+                name
+            } else {
+                name.replace(' ', "\\ ")
+            };
+            Completion {
+                kind: if is_dir {
+                    CompletionKind::Directory
+                } else {
+                    CompletionKind::File
+                },
+                text: format!("{}{}{}", typed_dir, name, if is_dir { "/" } else { "" }),
+                start,
+            }
+        })
+        .collect()
+}
+
+/// Completions for the text before the cursor: whole commands from the
+/// history first, then programs for a command word or paths for an argument
+pub fn complete(
+    line: &str,
+    cwd: &Path,
+    history: &[(String, Option<u64>)],
+    executables: &[String],
+    now: u64,
+) -> Vec<Completion> {
+    let mut completions = history_completions(line, history, now);
+
+    let start = word_start(line);
+    let byte_start = line
+        .char_indices()
+        .nth(start)
+        .map_or(line.len(), |(index, _)| index);
+    let word = &line[byte_start..];
+    let is_path = word.contains(['/', std::path::MAIN_SEPARATOR]) || word.starts_with('~');
+
+    if is_command_position(&line[..byte_start]) && !is_path {
+        if !word.is_empty() {
+            completions.extend(
+                executables
+                    .iter()
+                    .filter(|name| name.len() > word.len() && matches_prefix(name, word))
+                    .map(|name| Completion {
+                        kind: CompletionKind::Executable,
+                        text: name.clone(),
+                        start,
+                    }),
+            );
+        }
+    } else {
+        completions.extend(path_completions(word, start, cwd));
+    }
+
+    completions.truncate(MAX_COMPLETIONS);
+    completions
+}
+
+// Suggestions for the line being typed in a terminal, from the shell's
+// history, the PATH and the shell's current directory
+#[tauri::command]
+pub async fn get_completions(
+    pty_id: String,
+    line: String,
+    cursor: usize,
+) -> Result<Vec<Completion>, String> {
+    let cwd = pty::pty_cwd(&pty_id).ok_or_else(|| format!("PTY with ID {} not found", pty_id))?;
+    let history = match pty::pty_shell(&pty_id).and_then(|shell| HistoryShell::from_shell(&shell)) {
+        Some(shell) => history::read_history(shell)?,
+        None => Vec::new(),
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let before_cursor: String = line.chars().take(cursor).collect();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        complete(
+            &before_cursor,
+            Path::new(&cwd),
+            &history,
+            &path_executables(),
+            now,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to get completions: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_line() {
+        let now = 1_700_000_000;
+        let history = vec![
+            ("git stash".to_string(), Some(now - 30 * 86_400)),
+            ("git status".to_string(), Some(now - 60)),
+            ("git stash".to_string(), Some(now - 20 * 86_400)),
+            ("ls".to_string(), Some(now)),
+        ];
+        let executables = vec!["git".to_string(), "gitk".to_string(), "grep".to_string()];
+        let nowhere = Path::new("/nonexistent");
+
+        // The recent command wins over the more frequent old one
+        let completions = complete("git st", nowhere, &history, &executables, now);
+        let texts: Vec<&str> = completions.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["git status", "git stash"]);
+
+        let completions = complete("echo a | gi", nowhere, &[], &executables, now);
+        assert_eq!(
+            completions,
+            [
+                Completion {
+                    kind: CompletionKind::Executable,
+                    text: "git".into(),
+                    start: 9
+                },
+                Completion {
+                    kind: CompletionKind::Executable,
+                    text: "gitk".into(),
+                    start: 9
+                },
+            ]
+        );
+
+        // Arguments complete as paths, never as programs
+        assert!(complete("cat gi", nowhere, &[], &executables, now).is_empty());
+        assert_eq!(word_start("cd \"My Documents/Pro"), 3);
+    }
+}
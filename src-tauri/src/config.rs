@@ -1,20 +1,18 @@
 use documented::DocumentedFields;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tauri::{AppHandle, Manager};
 
-/**
- * When we need to add new config options in the future:
- * 1. Increment CURRENT_CONFIG_VERSION
- * 2. Add a new ConfigVX struct for the old version if needed
- * 3. Add migration logic in the migrate_config match statement
- */
+use crate::config_format::ConfigFormat;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::migrations::{self, CURRENT_VERSION};
+use crate::{backup, config_template, merge, sync};
 
-// Current version of config schema
-const CURRENT_CONFIG_VERSION: u32 = 1;
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
 /// Font configuration settings
 pub struct FontConfig {
     /// Primary font family
@@ -25,9 +23,19 @@ pub struct FontConfig {
     pub size: u16,
     /// Line height multiplier
     pub line_height: f32,
+    /// Render programming ligatures, needs a font that has them
+    pub ligatures: Option<bool>,
+    /// Weight of regular text, from 100 to 900
+    pub font_weight: Option<u16>,
+    /// Full name of the font used for bold text, e.g. "Iosevka Bold"
+    pub bold_font_family: Option<String>,
+    /// Full name of the font used for italic text, e.g. "Iosevka Italic"
+    pub italic_font_family: Option<String>,
+    /// OpenType features to turn on or off, e.g. `{ ss01 = true, zero = true }`
+    pub features: Option<HashMap<String, bool>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
 /// Color theme configuration
 pub struct ThemeConfig {
     /// Terminal background color
@@ -76,7 +84,7 @@ pub struct ThemeConfig {
     pub bright_white: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Default shell commands for different platforms
 pub struct ShellConfig {
     /// Default shell for Windows
@@ -87,16 +95,122 @@ pub struct ShellConfig {
     pub macos: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
 /// Terminal behavior settings
 pub struct TerminalSettings {
     /// Number of lines to keep in scrollback buffer
     pub scrollback: Option<u32>,
+    /// Keep output that no longer fits in memory in compressed files in the
+    /// cache directory, so exports and command output reach further back
+    /// (default: false)
+    pub scrollback_spill: Option<bool>,
+    /// Most disk space the spilled output of one terminal may take, in
+    /// megabytes. The oldest output is dropped beyond it (default: 256)
+    pub scrollback_spill_mb: Option<u64>,
+    /// Programs repainting the screen faster than this many milliseconds only
+    /// get their latest complete frame shown, e.g. 16 for 60 Hz (default: off)
+    pub coalesce_frames_ms: Option<u64>,
+    /// Output of at least this many bytes is sent to the window compressed,
+    /// worth it for remote sessions printing a lot (default: off)
+    pub compress_min_bytes: Option<usize>,
     /// Padding around terminal content
     pub padding: Option<PaddingConfig>, // Changed to struct
+    /// Cursor appearance
+    pub cursor: Option<CursorConfig>,
+    /// Text selection behavior
+    pub selection: Option<SelectionConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Text selection configuration
+pub struct SelectionConfig {
+    /// Characters that end a word when double-click selecting
+    pub word_separators: String,
+    /// Copy selected text to the clipboard as soon as it is selected
+    pub copy_on_select: bool,
+    /// Strip trailing whitespace from each line when copying
+    pub trim_trailing_whitespace_on_copy: bool,
+    /// Put selections in the primary selection and paste it with a middle
+    /// click, on Linux only (default: false)
+    pub primary_selection: Option<bool>,
+}
+
+impl TerminalSettings {
+    /// Layer overrides on top of these settings. Sections are replaced as a
+    /// whole, so an overriding `cursor` must be complete.
+    pub fn with_overrides(&self, overrides: &TerminalSettings) -> TerminalSettings {
+        TerminalSettings {
+            scrollback: overrides.scrollback.or(self.scrollback),
+            scrollback_spill: overrides.scrollback_spill.or(self.scrollback_spill),
+            scrollback_spill_mb: overrides.scrollback_spill_mb.or(self.scrollback_spill_mb),
+            coalesce_frames_ms: overrides.coalesce_frames_ms.or(self.coalesce_frames_ms),
+            compress_min_bytes: overrides.compress_min_bytes.or(self.compress_min_bytes),
+            padding: overrides.padding.clone().or_else(|| self.padding.clone()),
+            cursor: overrides.cursor.clone().or_else(|| self.cursor.clone()),
+            selection: overrides
+                .selection
+                .clone()
+                .or_else(|| self.selection.clone()),
+        }
+    }
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self {
+            word_separators: " ()[]{}',\"`|│".into(),
+            copy_on_select: false,
+            trim_trailing_whitespace_on_copy: true,
+            primary_selection: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+/// Shape of the cursor
+pub enum CursorStyle {
+    Block,
+    Bar,
+    Underline,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+/// Shape of the cursor while the terminal is not focused
+pub enum UnfocusedCursorStyle {
+    Outline,
+    Block,
+    Bar,
+    Underline,
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Cursor appearance configuration
+pub struct CursorConfig {
+    /// Cursor shape: block, bar or underline
+    pub style: CursorStyle,
+    /// Whether the cursor blinks
+    pub blink: bool,
+    /// Blink interval in milliseconds
+    pub blink_interval_ms: Option<u32>,
+    /// Cursor shape when unfocused: outline, block, bar, underline or none
+    pub unfocused_style: Option<UnfocusedCursorStyle>,
+}
+
+impl Default for CursorConfig {
+    fn default() -> Self {
+        Self {
+            style: CursorStyle::Block,
+            blink: true,
+            blink_interval_ms: Some(600),
+            unfocused_style: Some(UnfocusedCursorStyle::Outline),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
 /// Terminal padding configuration
 pub struct PaddingConfig {
     /// Horizontal padding in pixels
@@ -105,7 +219,7 @@ pub struct PaddingConfig {
     pub y: u32, // Vertical padding
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
 /// Terminal profile configuration
 pub struct Profile {
     /// Profile name
@@ -114,13 +228,62 @@ pub struct Profile {
     pub command: String,
     /// Optional command arguments
     pub args: Option<Vec<String>>,
+    /// Optional starting directory
+    pub cwd: Option<String>,
+    /// Extra environment variables for the shell, values of the form
+    /// `keyring:service/account` are read from the OS keyring
+    pub env: Option<HashMap<String, String>>,
+    /// Commands typed into the shell once it is ready
+    pub startup_commands: Option<Vec<String>>,
+    /// Optional icon (path or emoji) shown in the tab
+    pub icon: Option<String>,
+    /// Optional tab color, as `#rrggbb`, `rgb()` or `hsl()`
+    pub color: Option<String>,
+    /// Run the shell as administrator, through UAC on Windows and pkexec or
+    /// sudo elsewhere
+    pub elevated: Option<bool>,
+    /// Locale of the shell, sets `LANG`, e.g. `de_DE.UTF-8`. Defaults to the
+    /// app's if that uses UTF-8 and a UTF-8 locale otherwise.
+    pub locale: Option<String>,
+    /// Run the shell through winpty, for MSYS2 or Cygwin installs too old
+    /// to render right under ConPTY
+    pub winpty: Option<bool>,
+    /// What happens when the shell exits: close, hold or restart (default:
+    /// close). Shells that exit right after starting are held, not restarted.
+    pub on_exit: Option<OnExit>,
+    /// Run the shell in a sandbox, for trying out untrusted scripts
+    pub sandbox: Option<SandboxConfig>,
+    /// Provider that generated the profile (wsl, ssh, docker, kube or command),
+    /// set by the app only, never read from the config file
+    #[serde(skip_deserializing)]
+    pub source: Option<String>,
     /// Optional font overrides
     pub font: Option<FontConfig>,
     /// Optional theme overrides
     pub theme: Option<ThemeConfig>,
+    /// Optional banner shown before the shell output starts
+    pub banner: Option<BannerConfig>,
+    /// Optional terminal setting overrides, unset fields keep the global value
+    pub terminal: Option<TerminalSettings>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Startup banner source for a profile
+pub enum BannerConfig {
+    /// Static text
+    Text { text: String },
+    /// Random entry from a fortune-style file (entries separated by `%` lines)
+    File { path: String },
+    /// Output of a command, abandoned after the timeout
+    Command {
+        command: String,
+        args: Option<Vec<String>>,
+        timeout_ms: Option<u64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Collection of terminal profiles
 pub struct Profiles {
     /// Default profile name
@@ -129,7 +292,14 @@ pub struct Profiles {
     pub list: Vec<Profile>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Profiles {
+    /// Find a profile by name
+    pub fn find(&self, name: &str) -> Option<&Profile> {
+        self.list.iter().find(|p| p.name == name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
 /// Keyboard shortcut configuration
 pub struct Shortcut {
     /// Key to bind
@@ -146,22 +316,56 @@ pub struct Shortcut {
     /// Whether Meta/Command is required
     #[serde(default)]
     pub meta: bool,
+    /// Text written to the terminal instead of running a built-in action.
+    /// Supports escapes like `\x1b` and `\r` and placeholders like `{cwd}`
+    pub send_text: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-/// Keyboard shortcut bindings
-pub struct KeyboardShortcuts {
-    /// Create new tab
-    pub new_tab: Shortcut,
-    /// Close current tab
-    pub close_tab: Shortcut,
-    /// Reload configuration
-    pub reload_config: Shortcut,
-    /// Show profiles menu
-    pub show_profiles: Shortcut,
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+/// Keyboard shortcut bindings, mapping action names to shortcuts.
+/// Built-in actions missing from the config keep their default binding,
+/// set an empty key to unbind one.
+pub struct KeyboardShortcuts(pub IndexMap<String, Shortcut>);
+
+// Built-in actions and their default Ctrl+Shift keys
+const DEFAULT_SHORTCUTS: [(&str, &str); 10] = [
+    ("new_tab", "t"),
+    ("close_tab", "w"),
+    ("reload_config", "r"),
+    ("show_profiles", "p"),
+    ("close_pane", "e"),
+    ("split_horizontal", "/"),
+    ("split_vertical", "\\"),
+    ("focus_next_pane", "]"),
+    ("focus_previous_pane", "["),
+    ("scroll_to_prompt", "ArrowUp"),
+];
+
+impl KeyboardShortcuts {
+    /// Get the shortcut bound to an action
+    pub fn get(&self, action: &str) -> Option<&Shortcut> {
+        self.0.get(action)
+    }
+
+    /// Iterate over all action bindings in config order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Shortcut)> {
+        self.0.iter()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// The old fixed struct serialized as a table of action names, which is exactly
+// the map layout, so old configs load as-is and just gain the new defaults.
+impl<'de> Deserialize<'de> for KeyboardShortcuts {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bindings = IndexMap::<String, Shortcut>::deserialize(deserializer)?;
+        let mut shortcuts = KeyboardShortcuts::default();
+        shortcuts.0.extend(bindings);
+        Ok(shortcuts)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Style configuration for interactive UI elements
 pub struct InteractiveElementStyle {
     /// Background color
@@ -174,7 +378,7 @@ pub struct InteractiveElementStyle {
     pub hover_background: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Window appearance configuration
 pub struct WindowConfig {
     /// Height of the titlebar in pixels
@@ -185,9 +389,49 @@ pub struct WindowConfig {
     pub interactive: InteractiveElementStyle,
     /// Tab styling (left side of titlebar)
     pub tabs: WindowTabsStyle,
+    /// Initial window width in pixels
+    pub width: Option<u32>,
+    /// Initial window height in pixels
+    pub height: Option<u32>,
+    /// Initial window position, centered when not set
+    pub position: Option<WindowPosition>,
+    /// Keep the window above other windows
+    pub always_on_top: Option<bool>,
+    /// Reopen with the size and position the window had when it was closed,
+    /// the initial geometry is then only used on first launch (default: true)
+    pub remember_size: Option<bool>,
+    /// What happens to a window's shells when it closes: destroy them, or
+    /// detach them so another window can take them over (default: destroy)
+    pub close_ptys: Option<ClosePtys>,
+    /// Programs that make closing their tab ask first while they run in it,
+    /// an empty list never asks (default: vim, nvim, vi, nano, emacs, ssh,
+    /// mosh, tmux, screen and less)
+    pub confirm_close_processes: Option<Vec<String>>,
+    /// Tab title template using {index}, {title}, {command}, {cwd},
+    /// {cwd_short}, {shell} and {profile}, e.g. "{index}: {command} — {cwd_short}".
+    /// Tabs show the title the shell sets when not set
+    pub title_format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// What happens to the shells of a closed window
+pub enum ClosePtys {
+    #[default]
+    Destroy,
+    Detach,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, DocumentedFields)]
+/// Window position on the desktop
+pub struct WindowPosition {
+    /// Distance from the left edge of the desktop in pixels
+    pub x: i32,
+    /// Distance from the top edge of the desktop in pixels
+    pub y: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Style configuration for tabs in the titlebar
 pub struct WindowTabsStyle {
     /// Style for active tab
@@ -196,7 +440,7 @@ pub struct WindowTabsStyle {
     pub inactive: TabStyle,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Style configuration for individual tabs
 pub struct TabStyle {
     /// Background color
@@ -205,11 +449,379 @@ pub struct TabStyle {
     pub text_color: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+#[serde(default)]
+/// What the app does when it starts
+pub struct StartupConfig {
+    /// Reopen the tabs and directories of the previous session
+    pub restore_previous_session: bool,
+    /// Profile of the first tab, defaults to profiles.default
+    pub default_profile: Option<String>,
+    /// Starting directory for shells that don't set one, defaults to home
+    pub default_cwd: Option<String>,
+    /// Start with the window maximized
+    pub start_maximized: bool,
+    /// Start in fullscreen, takes precedence over start_maximized
+    pub start_fullscreen: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Which side wins when the local and the synced config set the same value
+pub enum SyncPolicy {
+    #[default]
+    LocalWins,
+    RemoteWins,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Shared config pulled from a URL or a git repository and merged with the
+/// local one. Only read from the main config file.
+pub struct SyncConfig {
+    /// URL of a config file to download
+    pub url: Option<String>,
+    /// Git repository holding the shared config, used when no url is set
+    pub git: Option<String>,
+    /// Branch of the git repository (default: the repository's default branch)
+    pub branch: Option<String>,
+    /// Config file inside the git repository (default: termillion.toml)
+    pub path: Option<String>,
+    /// Minutes between automatic syncs, only synced on demand when not set
+    pub interval_minutes: Option<u64>,
+    /// Which side wins on conflicts: local_wins or remote_wins (default: local_wins)
+    pub policy: Option<SyncPolicy>,
+    /// Also take what runs programs from the synced config: hooks, tasks,
+    /// dynamic profiles, the shell, the editor, link rules, profiles and
+    /// send-text shortcuts (default: false). Only for sources you control.
+    pub allow_commands: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Quake-style terminal that slides down from the top of the screen when a
+/// global hotkey is pressed
+pub struct DropdownConfig {
+    /// Register the hotkey and open the dropdown window
+    #[serde(default)]
+    pub enabled: bool,
+    /// Hotkey that shows and hides the dropdown from any app
+    pub hotkey: Shortcut,
+    /// Profile of the dropdown's first tab, defaults to profiles.default
+    pub profile: Option<String>,
+    /// Height as a percentage of the screen height (default: 40)
+    pub height_percent: Option<u8>,
+    /// Hide the dropdown when another window is focused (default: true)
+    pub hide_on_blur: Option<bool>,
+    /// Length of the slide animation in milliseconds, 0 turns it off (default: 150)
+    pub animation_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// OS notifications for activity in terminals whose window isn't focused
+pub struct NotificationConfig {
+    /// Notify when a terminal rings the bell (default: true)
+    pub bell: Option<bool>,
+    /// Notify when a long command finishes, needs shell integration (default: true)
+    pub command_finished: Option<bool>,
+    /// Seconds a command has to run before its end is reported (default: 10)
+    pub long_command_seconds: Option<u64>,
+    /// Regular expressions, output lines matching one are reported
+    pub triggers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Release channel updates are taken from
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Checking for and installing new releases, in builds signed for updates
+pub struct UpdateConfig {
+    /// Release channel: stable or beta (default: stable)
+    pub channel: Option<UpdateChannel>,
+    /// Download updates in the background as soon as they are found (default: false)
+    pub auto_download: Option<bool>,
+    /// Hours between automatic checks, 0 only checks on demand (default: 24)
+    pub check_interval_hours: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+/// Severity of log messages, from most to least severe
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// How each terminal's output is written to its own log file
+pub enum PtyLogMode {
+    #[default]
+    Off,
+    /// Output as the shell printed it
+    Raw,
+    /// Every line starts with the time it was printed, in ISO 8601
+    Timestamped,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Log file and in-app diagnostics
+pub struct LoggingConfig {
+    /// Least severe messages logged: error, warn, info, debug or trace (default: info)
+    pub level: Option<LogLevel>,
+    /// Log each terminal's output to `pty/<id>.log` in the log directory:
+    /// off, raw or timestamped (default: off)
+    pub pty_output: Option<PtyLogMode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Something done when a hook's event happens
+pub enum HookAction {
+    /// Run a program, the event is described in `TERMILLION_*` environment variables
+    Command {
+        command: String,
+        args: Option<Vec<String>>,
+    },
+    /// Show an OS notification, `{name}` placeholders are filled in from the event
+    Notify { message: String },
+    /// Play a sound file, or the system's alert sound when no path is set
+    Sound { path: Option<String> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Hook run for output lines matching a pattern
+pub struct TriggerHook {
+    /// Regular expression matched against each output line
+    pub pattern: String,
+    /// What to do when a line matches
+    #[serde(flatten)]
+    pub action: HookAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Actions run by the backend when something happens in a terminal
+pub struct HooksConfig {
+    /// Run when a terminal starts
+    pub pty_created: Option<Vec<HookAction>>,
+    /// Run when a terminal's shell exits
+    pub pty_exited: Option<Vec<HookAction>>,
+    /// Run when a terminal rings the bell
+    pub bell: Option<Vec<HookAction>>,
+    /// Run when a command exits with an error, needs shell integration
+    pub command_failed: Option<Vec<HookAction>>,
+    /// Run when an output line matches the hook's pattern
+    pub triggers: Option<Vec<TriggerHook>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Editor that file paths in the output are opened in
+pub struct EditorConfig {
+    /// Editor command, e.g. "code" or "nvim" (default: $VISUAL, $EDITOR or the
+    /// first installed known editor)
+    pub command: Option<String>,
+    /// Arguments replacing the detected ones, `{file}`, `{line}` and
+    /// `{column}` are filled in
+    pub args: Option<Vec<String>>,
+    /// Open the editor in a new tab, detected from the command when not set
+    pub terminal: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Detection of clickable text in the output, done by the backend so the
+/// frontend doesn't have to scan it
+pub struct AnnotationsConfig {
+    /// Detect URLs, file paths with line numbers, IP addresses and commit
+    /// hashes (default: true)
+    pub builtin: Option<bool>,
+    /// Extra patterns to detect, checked before the built-in ones
+    pub rules: Option<Vec<AnnotationRule>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// What happens to a terminal when its shell exits
+pub enum OnExit {
+    /// Close the terminal
+    #[default]
+    Close,
+    /// Keep the terminal and its output, with a note of how the shell ended
+    Hold,
+    /// Start the shell again in the same terminal
+    Restart,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Program that sandboxes a profile's shell
+pub enum SandboxTool {
+    /// `bwrap`, Linux
+    Bubblewrap,
+    /// `firejail`, Linux
+    Firejail,
+    /// `sandbox-exec`, macOS
+    SandboxExec,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Restrictions of a sandboxed shell. It can read the whole filesystem
+/// except for `hidden`, but only write to `writable` and temporary files.
+pub struct SandboxConfig {
+    /// Sandbox program, the first one installed when unset
+    pub tool: Option<SandboxTool>,
+    /// Directories the shell may write to
+    pub writable: Option<Vec<String>>,
+    /// Files and directories the shell can't read, e.g. `~/.ssh`
+    pub hidden: Option<Vec<String>>,
+    /// Allow network access, off by default
+    pub network: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// What running a task does while its last tab is still open
+pub enum ReusePane {
+    /// Switch to the open tab
+    #[default]
+    Focus,
+    /// Close the open tab and run the task again
+    Restart,
+    /// Open another tab
+    New,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Named command run in its own tab, like a dev server or a log tail
+pub struct TaskConfig {
+    /// Program to run
+    pub command: String,
+    /// Arguments of the program
+    pub args: Option<Vec<String>>,
+    /// Directory to run it in, `~` is expanded
+    pub cwd: Option<String>,
+    /// Profile whose settings the tab uses
+    pub profile: Option<String>,
+    /// What running the task again does while its tab is open: focus, restart
+    /// or new (default: focus)
+    pub reuse_pane: Option<ReusePane>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Profiles generated when the app starts and on refresh, added after the
+/// ones in `profiles.list`
+pub struct DynamicProfilesConfig {
+    /// A profile per WSL distro, on Windows
+    pub wsl: Option<bool>,
+    /// A profile per host in `~/.ssh/config`
+    pub ssh_hosts: Option<bool>,
+    /// A profile per running docker container
+    pub docker: Option<bool>,
+    /// A profile per kubectl context
+    pub kube_contexts: Option<bool>,
+    /// Command printing a JSON array of profiles laid out like `profiles.list`
+    pub command: Option<String>,
+    /// Arguments of the command
+    pub args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Picks the profile of new terminals that don't ask for one. Every condition
+/// set has to match, `*` in patterns matches any text and `?` one character.
+pub struct ProfileRule {
+    /// Profile the terminal gets
+    pub profile: String,
+    /// Pattern the directory the shell starts in has to match, `~` is expanded
+    pub cwd: Option<String>,
+    /// Pattern the URL of one of the git remotes of that directory has to match
+    pub git_remote: Option<String>,
+    /// Pattern the name of this machine has to match
+    pub hostname: Option<String>,
+    /// Environment variables that have to be set, with patterns their values
+    /// have to match
+    pub env: Option<IndexMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Pane of a layout preset, placed on the layout's grid
+pub struct LayoutPaneConfig {
+    /// Grid row of the pane's top edge, starting at 0
+    pub row: u16,
+    /// Grid column of the pane's left edge, starting at 0
+    pub column: u16,
+    /// Rows the pane spans (default: 1)
+    pub row_span: Option<u16>,
+    /// Columns the pane spans (default: 1)
+    pub column_span: Option<u16>,
+    /// Profile whose shell and settings the pane uses
+    pub profile: Option<String>,
+    /// Directory the pane starts in, `~` is expanded
+    pub cwd: Option<String>,
+    /// Program to run instead of the profile's shell
+    pub command: Option<String>,
+    /// Arguments of the program
+    pub args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Panes opened together, laid out on a grid of equal rows and columns
+pub struct LayoutConfig {
+    /// Rows of the grid
+    pub rows: u16,
+    /// Columns of the grid
+    pub columns: u16,
+    /// Panes of the layout, each covering one or more grid cells
+    pub panes: Vec<LayoutPaneConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Commands recorded from the shell integration marks, searchable across
+/// all terminals and sessions
+pub struct CommandHistoryConfig {
+    /// Record the commands run in every terminal (default: true)
+    pub enabled: Option<bool>,
+    /// Commands matching any of these patterns aren't recorded, e.g. ones
+    /// that take secrets on the command line
+    pub ignore: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Pattern reported as a custom annotation
+pub struct AnnotationRule {
+    /// Name the frontend gets with each match
+    pub name: String,
+    /// Regular expression to look for
+    pub pattern: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, DocumentedFields)]
+/// Text made clickable, like ticket IDs or issue references
+pub struct LinkRule {
+    /// Regular expression matching the text
+    pub pattern: String,
+    /// URL opened on click, `{0}` is the match, `{1}`, `{2}`... or `{name}`
+    /// its groups
+    pub url: Option<String>,
+    /// Command run on click when there is no URL, placeholders as in `url`
+    pub command: Option<String>,
+    /// Arguments of the command
+    pub args: Option<Vec<String>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, DocumentedFields)]
 /// Main application configuration
 pub struct Config {
     /// Configuration version (used for migrations)
     pub version: u32,
+    /// Other config files merged under this one, relative to the config directory
+    pub include: Option<Vec<String>>,
     /// Font settings for the terminal
     pub font: FontConfig,
     /// Color theme settings
@@ -224,14 +836,50 @@ pub struct Config {
     pub shortcuts: KeyboardShortcuts,
     /// Window appearance and behavior
     pub window: WindowConfig,
+    /// Launch behavior
+    pub startup: Option<StartupConfig>,
+    /// Shared config to keep in sync with
+    pub sync: Option<SyncConfig>,
+    /// Dropdown terminal toggled by a global hotkey
+    pub dropdown: Option<DropdownConfig>,
+    /// Notifications for background terminals, off when not set
+    pub notifications: Option<NotificationConfig>,
+    /// Update checks and the release channel
+    pub updates: Option<UpdateConfig>,
+    /// What gets logged
+    pub logging: Option<LoggingConfig>,
+    /// Commands, notifications and sounds for terminal events
+    pub hooks: Option<HooksConfig>,
+    /// Editor used to open files from the terminal
+    pub editor: Option<EditorConfig>,
+    /// Clickable text detection in the output, off when not set
+    pub annotations: Option<AnnotationsConfig>,
+    /// Patterns turned into links, by rule name
+    pub link_rules: Option<IndexMap<String, LinkRule>>,
+    /// Searchable history of the commands run in every terminal
+    pub command_history: Option<CommandHistoryConfig>,
+    /// Commands run in their own tab, by task name
+    pub tasks: Option<IndexMap<String, TaskConfig>>,
+    /// Split layouts opened in one go, by preset name
+    pub layouts: Option<IndexMap<String, LayoutConfig>>,
+    /// Rules picking the profile of new terminals, the first match wins
+    pub profile_rules: Option<Vec<ProfileRule>>,
+    /// Profiles generated from WSL, ssh, docker, kubectl or a command
+    pub dynamic_profiles: Option<DynamicProfilesConfig>,
+    /// Per-platform settings merged over the rest of the config
+    pub overrides: Option<PlatformOverrides>,
 }
 
-// Config versions for migration
-#[derive(Debug, Serialize, Deserialize)]
-struct ConfigV0 {
-    pub font: FontConfig,
-    pub theme: ThemeConfig,
-    pub shell: ShellConfig,
+#[derive(Debug, Serialize, Deserialize, Clone, Default, DocumentedFields)]
+/// Platform specific config overrides, each table uses the same layout as the
+/// main config and only needs the keys that differ
+pub struct PlatformOverrides {
+    /// Overrides applied on Windows
+    pub windows: Option<toml::Table>,
+    /// Overrides applied on Linux
+    pub linux: Option<toml::Table>,
+    /// Overrides applied on macOS
+    pub macos: Option<toml::Table>,
 }
 
 impl Default for Config {
@@ -243,16 +891,12 @@ impl Default for Config {
                 Profile {
                     name: "PowerShell".into(),
                     command: "powershell.exe".into(),
-                    args: None,
-                    font: None,
-                    theme: None,
+                    ..Default::default()
                 },
                 Profile {
                     name: "WSL".into(),
                     command: "wsl.exe".into(),
-                    args: None,
-                    font: None,
-                    theme: None,
+                    ..Default::default()
                 },
             ],
         };
@@ -263,9 +907,7 @@ impl Default for Config {
             list: vec![Profile {
                 name: "Zsh".into(),
                 command: "/bin/zsh".into(),
-                args: None,
-                font: None,
-                theme: None,
+                ..Default::default()
             }],
         };
 
@@ -275,19 +917,23 @@ impl Default for Config {
             list: vec![Profile {
                 name: "Bash".into(),
                 command: "/bin/bash".into(),
-                args: None,
-                font: None,
-                theme: None,
+                ..Default::default()
             }],
         };
 
         Self {
-            version: CURRENT_CONFIG_VERSION,
+            version: CURRENT_VERSION,
+            include: None,
             font: FontConfig {
                 family: "JetBrains Mono".into(),
                 fallback_family: "Consolas, Monaco, monospace".into(),
                 size: 14,
                 line_height: 1.0,
+                ligatures: None,
+                font_weight: None,
+                bold_font_family: None,
+                italic_font_family: None,
+                features: None,
             },
             theme: ThemeConfig {
                 // Base colors
@@ -319,38 +965,19 @@ impl Default for Config {
             },
             terminal: TerminalSettings {
                 scrollback: Some(5000),
+                scrollback_spill: None,
+                scrollback_spill_mb: None,
+                coalesce_frames_ms: None,
+                compress_min_bytes: None,
                 padding: Some(PaddingConfig {
                     x: 12, // Default horizontal padding
                     y: 8,  // Default vertical padding
                 }),
+                cursor: Some(CursorConfig::default()),
+                selection: Some(SelectionConfig::default()),
             },
             profiles: Some(default_profiles),
-            shortcuts: KeyboardShortcuts {
-                new_tab: Shortcut {
-                    key: "t".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-                close_tab: Shortcut {
-                    key: "w".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-                reload_config: Shortcut {
-                    key: "r".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-                show_profiles: Shortcut {
-                    key: "p".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-            },
+            shortcuts: KeyboardShortcuts::default(),
             window: WindowConfig {
                 titlebar_height: 35,
                 titlebar_background: "#1e2227".into(),
@@ -370,181 +997,321 @@ impl Default for Config {
                         text_color: "#787c99".into(),
                     },
                 },
+                width: None,
+                height: None,
+                position: None,
+                always_on_top: None,
+                remember_size: None,
+                close_ptys: None,
+                confirm_close_processes: None,
+                title_format: None,
             },
+            startup: Some(StartupConfig::default()),
+            sync: None,
+            dropdown: None,
+            notifications: None,
+            updates: None,
+            logging: None,
+            hooks: None,
+            editor: None,
+            annotations: None,
+            link_rules: None,
+            command_history: None,
+            tasks: None,
+            layouts: None,
+            profile_rules: None,
+            dynamic_profiles: None,
+            overrides: None,
         }
     }
 }
 
 impl Config {
-    pub fn load(app: &AppHandle) -> Result<Self, String> {
-        let config_path = get_config_path(app)?;
+    /// Look up a profile by name in the profile list
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.as_ref().and_then(|p| p.find(name))
+    }
 
-        if !config_path.exists() {
-            let config = Config::default();
-            config.save(app)?;
-            return Ok(config);
+    /// Terminal settings for a new tab, with the profile's overrides applied
+    pub fn terminal_settings(&self, profile: Option<&str>) -> TerminalSettings {
+        match profile
+            .and_then(|name| self.profile(name))
+            .and_then(|p| p.terminal.as_ref())
+        {
+            Some(overrides) => self.terminal.with_overrides(overrides),
+            None => self.terminal.clone(),
         }
+    }
 
-        let content = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
-
-        // First try to parse as current version
-        if let Ok(mut config) = toml::from_str::<Config>(&content) {
-            // Check if we need to upgrade from an older version
-            if config.version < CURRENT_CONFIG_VERSION {
-                config = Self::migrate_config(config)?;
-                config.save(app)?;
-            }
-            return Ok(config);
+    /// Restore a single top-level section to its defaults, keeping the rest
+    pub fn reset_section(&mut self, section: &str) -> CommandResult<()> {
+        if section == "version" || Self::get_field_docs(section).is_err() {
+            return Err(TermillionError::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown config section: {}", section),
+            )
+            .with_context(section));
         }
 
-        // Try to parse as V0 (unversioned) config
-        if let Ok(old_config) = toml::from_str::<ConfigV0>(&content) {
-            let config = Self::migrate_from_v0(old_config);
-            config.save(app)?;
-            return Ok(config);
-        }
+        let mut table = toml::Table::try_from(&*self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        let defaults = toml::Table::try_from(Config::default())
+            .map_err(|e| format!("Failed to serialize default config: {}", e))?;
 
-        Err("Unable to parse config file".to_string())
-    }
+        match defaults.get(section) {
+            Some(value) => table.insert(section.to_string(), value.clone()),
+            None => table.remove(section),
+        };
 
-    fn migrate_config(mut config: Config) -> Result<Config, String> {
-        match config.version {
-            0 => {
-                config.font.fallback_family = "Consolas, Monaco, monospace".into();
-                config.terminal = TerminalSettings {
-                    scrollback: Some(5000),
-                    padding: Some(PaddingConfig { x: 12, y: 8 }),
-                };
-                config.version = 1;
+        // Platform overrides of the section would bring the old values back
+        if section != merge::OVERRIDES_KEY {
+            if let Some(toml::Value::Table(overrides)) = table.get_mut(merge::OVERRIDES_KEY) {
+                for (_, platform) in overrides.iter_mut() {
+                    if let toml::Value::Table(platform) = platform {
+                        platform.remove(section);
+                    }
+                }
             }
-            _ => {}
         }
-        Ok(config)
+
+        *self = Config::deserialize(table).map_err(|e| {
+            TermillionError::new(
+                ErrorKind::ConfigParse,
+                format!("Failed to parse config after reset: {}", e),
+            )
+        })?;
+        Ok(())
     }
 
-    fn migrate_from_v0(old: ConfigV0) -> Config {
-        Config {
-            version: CURRENT_CONFIG_VERSION,
-            font: old.font,
-            theme: old.theme,
-            shell: old.shell,
-            terminal: TerminalSettings {
-                scrollback: Some(5000),
-                padding: Some(PaddingConfig { x: 12, y: 8 }),
-            },
-            profiles: None,
-            shortcuts: KeyboardShortcuts {
-                new_tab: Shortcut {
-                    key: "t".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-                close_tab: Shortcut {
-                    key: "w".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
+    pub fn load(app: &AppHandle) -> CommandResult<Self> {
+        let config_path = get_config_path(app)?;
 
-                reload_config: Shortcut {
-                    key: "r".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-                show_profiles: Shortcut {
-                    key: "p".into(),
-                    ctrl: true,
-                    shift: true,
-                    ..Default::default()
-                },
-            },
-            window: WindowConfig {
-                titlebar_height: 35,
-                titlebar_background: "#1e2227".into(),
-                interactive: InteractiveElementStyle {
-                    background_color: "#32344a".into(),
-                    text_color: "#abb2bf".into(),
-                    border_color: "#21252b".into(),
-                    hover_background: "#3e4451".into(),
-                },
-                tabs: WindowTabsStyle {
-                    active: TabStyle {
-                        background_color: "#24283b".into(),
-                        text_color: "#a9b1d6".into(),
-                    },
-                    inactive: TabStyle {
-                        background_color: "#1a1b26".into(),
-                        text_color: "#787c99".into(),
-                    },
-                },
-            },
+        // First run writes the fully documented default config
+        if !config_path.exists() {
+            config_template::write_default_config_to(&config_path)?;
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(&config_path).map_err(|e| {
+            TermillionError::io("Failed to read config file", e).with_context(config_path.display())
+        })?;
+
+        let mut raw = ConfigFormat::from_path(&config_path)
+            .parse(&content)
+            .map_err(|e| parse_error(&config_path, e))?;
+
+        // Bring old config files up to date, keeping a backup of the original.
+        // Configs with includes are judged as a whole once merged.
+        if !raw.contains_key(merge::INCLUDE_KEY) && migrations::needs_migration(&raw) {
+            backup::backup_config(&config_path)?;
+            let (migrated, _) = migrations::migrate(raw.clone())?;
+            write_config_file(&config_path, &migrated, Some(&content), Some(&raw))?;
+            raw = migrated;
         }
+
+        // Merge included files and platform overrides before deserializing
+        let mut table = migrations::merge_includes(&raw, config_dir(&config_path))?;
+        sync::merge_synced(&mut table, &raw, config_dir(&config_path));
+        merge::apply_platform_overrides(&mut table);
+
+        Config::deserialize(table).map_err(|e| parse_error(&config_path, e))
     }
 
-    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+    pub fn save(&self, app: &AppHandle) -> CommandResult<()> {
         let config_path = get_config_path(app)?;
 
         if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            fs::create_dir_all(parent).map_err(|e| {
+                TermillionError::io("Failed to create config directory", e)
+                    .with_context(parent.display())
+            })?;
         }
+        backup::backup_config(&config_path)?;
 
-        // Convert to string first to get the table format
-        let content = toml::to_string_pretty(self)
+        // Keep overridden and included values out of the main config file
+        let mut table = toml::Table::try_from(self)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        let format = ConfigFormat::from_path(&config_path);
+        let existing = fs::read_to_string(&config_path).ok();
+        let main = existing
+            .as_ref()
+            .and_then(|content| format.parse(content).ok());
+        let mut included = main
+            .as_ref()
+            .and_then(|main| merge::load_includes(main, config_dir(&config_path)).ok())
+            .unwrap_or_default();
+        let mut base = included.clone();
+        if let Some(main) = &main {
+            merge::deep_merge(&mut base, main.clone());
+            // Synced values are kept out of the file like included ones
+            sync::merge_synced(&mut included, main, config_dir(&config_path));
+            sync::merge_synced(&mut base, main, config_dir(&config_path));
+        }
+        merge::unapply_platform_overrides(&mut table, Some(&base));
+        merge::strip_included(&mut table, &included, main.as_ref());
 
-        // Parse into toml_edit Document to preserve formatting
-        let mut doc = content
-            .parse::<toml_edit::DocumentMut>()
-            .map_err(|e| format!("Failed to parse TOML document: {}", e))?;
-
-        // Add doc comments for each field
-        for table_key in [
-            "font",
-            "theme",
-            "shell",
-            "terminal",
-            "profiles",
-            "shortcuts",
-            "window",
-        ] {
-            if let Some(table) = doc.get_mut(table_key) {
-                if let Ok(comment) = Self::get_field_docs(table_key) {
-                    let mut formatted_comment = String::new();
-                    formatted_comment.push_str("\n"); // Add newline before comment
-                    for line in comment.lines() {
-                        let line = if line.is_empty() {
-                            String::from("#\n")
-                        } else {
-                            format!("# {line}\n")
-                        };
-                        formatted_comment.push_str(&line);
-                    }
-                    if let Some(decor) = table.as_table_mut().map(|t| t.decor_mut()) {
-                        decor.set_prefix(formatted_comment);
-                    }
+        write_config_file(&config_path, &table, existing.as_deref(), main.as_ref())
+    }
+
+    /// Rewrite the config file in another format, keeping the old file as a
+    /// `.bak` next to it so only one config is detected afterwards
+    pub fn convert_format(app: &AppHandle, format: ConfigFormat) -> CommandResult<PathBuf> {
+        let config_path = get_config_path(app)?;
+        let current = ConfigFormat::from_path(&config_path);
+        if current == format || !config_path.exists() {
+            return Ok(config_path);
+        }
+
+        let content = fs::read_to_string(&config_path).map_err(|e| {
+            TermillionError::io("Failed to read config file", e).with_context(config_path.display())
+        })?;
+        let table = current
+            .parse(&content)
+            .map_err(|e| parse_error(&config_path, e))?;
+
+        let new_path = config_path.with_extension(format.extension());
+        fs::write(&new_path, format.render(&table)?).map_err(|e| {
+            TermillionError::io("Failed to write config file", e).with_context(new_path.display())
+        })?;
+
+        let mut backup = config_path.clone().into_os_string();
+        backup.push(".bak");
+        fs::rename(&config_path, backup).map_err(|e| {
+            TermillionError::io("Failed to back up old config file", e)
+                .with_context(config_path.display())
+        })?;
+
+        Ok(new_path)
+    }
+}
+
+fn parse_error(config_path: &Path, e: impl std::fmt::Display) -> TermillionError {
+    TermillionError::new(
+        ErrorKind::ConfigParse,
+        format!("Failed to parse config file: {}", e),
+    )
+    .with_context(config_path.display())
+}
+
+fn write_error(config_path: &Path, e: std::io::Error) -> TermillionError {
+    TermillionError::io("Failed to write config file", e).with_context(config_path.display())
+}
+
+// Write a config table to the config file. TOML files are updated in place
+// so comments and formatting in `existing` (the current file) are kept.
+fn write_config_file(
+    config_path: &Path,
+    table: &toml::Table,
+    existing: Option<&str>,
+    main: Option<&toml::Table>,
+) -> CommandResult<()> {
+    let format = ConfigFormat::from_path(config_path);
+
+    // JSON configs are written as is, comments are only kept for TOML
+    if format != ConfigFormat::Toml {
+        let content = format.render(table)?;
+        return fs::write(config_path, content).map_err(|e| write_error(config_path, e));
+    }
+
+    // Convert to string first to get the table format
+    let content =
+        toml::to_string_pretty(table).map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    // Parse into toml_edit Document to preserve formatting
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| format!("Failed to parse TOML document: {}", e))?;
+
+    // Add doc comments for each field
+    for table_key in [
+        "font",
+        "theme",
+        "shell",
+        "terminal",
+        "profiles",
+        "shortcuts",
+        "window",
+        "startup",
+        "sync",
+        "dropdown",
+        "notifications",
+        "updates",
+        "logging",
+        "hooks",
+        "editor",
+        "annotations",
+        "link_rules",
+        "command_history",
+        "tasks",
+        "layouts",
+        "dynamic_profiles",
+        "overrides",
+    ] {
+        if let Some(table) = doc.get_mut(table_key) {
+            if let Ok(comment) = Config::get_field_docs(table_key) {
+                let mut formatted_comment = String::new();
+                formatted_comment.push('\n'); // Add newline before comment
+                for line in comment.lines() {
+                    let line = if line.is_empty() {
+                        String::from("#\n")
+                    } else {
+                        format!("# {line}\n")
+                    };
+                    formatted_comment.push_str(&line);
+                }
+                if let Some(decor) = table.as_table_mut().map(|t| t.decor_mut()) {
+                    decor.set_prefix(formatted_comment);
                 }
             }
         }
+    }
 
-        // Write the document to file
-        fs::write(&config_path, doc.to_string())
-            .map_err(|e| format!("Failed to write config file: {}", e))?;
-
-        Ok(())
+    // Update the existing file in place so user comments and ordering survive
+    let existing_doc = existing.and_then(|content| content.parse::<toml_edit::DocumentMut>().ok());
+    if let (Some(main), Some(mut existing_doc)) = (main, existing_doc) {
+        merge::update_document(
+            existing_doc.as_table_mut(),
+            doc.as_table(),
+            table,
+            Some(main),
+        );
+        doc = existing_doc;
     }
+
+    // Write the document to file
+    fs::write(config_path, doc.to_string()).map_err(|e| write_error(config_path, e))?;
+
+    Ok(())
+}
+
+// Directory includes are resolved against
+fn config_dir(config_path: &Path) -> &Path {
+    config_path.parent().unwrap_or(Path::new("."))
+}
+
+/// Config file given with `--config`, used in place of the config directory's
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use another config file for the rest of the run
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
 }
 
-fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+pub fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
     let mut path = app
         .path()
         .app_config_dir()
         .map_err(|_| "Failed to get config directory".to_string())?;
+    // Use whichever config file exists, TOML for a fresh install
+    let config_path = ConfigFormat::ALL
+        .into_iter()
+        .map(|format| path.join(format!("termillion.{}", format.extension())))
+        .find(|candidate| candidate.exists());
     path.push("termillion.toml");
-    Ok(path)
+    Ok(config_path.unwrap_or(path))
 }
 
 // Add Default implementation for Shortcut
@@ -556,38 +1323,107 @@ impl Default for Shortcut {
             shift: false,
             alt: false,
             meta: false,
+            send_text: None,
         }
     }
 }
 
 impl Default for KeyboardShortcuts {
     fn default() -> Self {
-        Self {
-            new_tab: Shortcut {
-                key: "t".into(),
-                ctrl: true,
-                shift: true,
-                ..Default::default()
-            },
-            close_tab: Shortcut {
-                key: "w".into(),
-                ctrl: true,
-                shift: true,
-                ..Default::default()
-            },
+        Self(
+            DEFAULT_SHORTCUTS
+                .iter()
+                .map(|(action, key)| {
+                    let shortcut = Shortcut {
+                        key: key.to_string(),
+                        ctrl: true,
+                        shift: true,
+                        ..Default::default()
+                    };
+                    (action.to_string(), shortcut)
+                })
+                .collect(),
+        )
+    }
+}
 
-            reload_config: Shortcut {
-                key: "r".into(),
-                ctrl: true,
-                shift: true,
-                ..Default::default()
-            },
-            show_profiles: Shortcut {
-                key: "p".into(),
-                ctrl: true,
-                shift: true,
-                ..Default::default()
-            },
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_settings_with_overrides() {
+        let base = TerminalSettings {
+            scrollback: Some(1000),
+            coalesce_frames_ms: Some(16),
+            cursor: Some(CursorConfig::default()),
+            selection: Some(SelectionConfig::default()),
+            ..Default::default()
+        };
+        let overrides = TerminalSettings {
+            scrollback: Some(3000),
+            scrollback_spill: Some(true),
+            cursor: Some(CursorConfig {
+                style: CursorStyle::Bar,
+                blink: false,
+                blink_interval_ms: None,
+                unfocused_style: None,
+            }),
+            ..Default::default()
+        };
+
+        let merged = base.with_overrides(&overrides);
+        assert_eq!(merged.scrollback, Some(3000));
+        assert_eq!(merged.scrollback_spill, Some(true));
+        // Unset overrides keep the base value
+        assert_eq!(merged.coalesce_frames_ms, Some(16));
+        assert!(merged.selection.is_some());
+        assert!(merged.padding.is_none());
+        // Sections are replaced whole
+        let cursor = merged.cursor.unwrap();
+        assert_eq!(cursor.style, CursorStyle::Bar);
+        assert_eq!(cursor.blink_interval_ms, None);
+    }
+
+    #[test]
+    fn test_profile_overrides_follow_platform_overrides() {
+        let mut table = toml::Table::try_from(Config::default()).unwrap();
+        let overlay: toml::Table = format!(
+            r#"
+            [terminal]
+            scrollback = 1000
+            coalesce_frames_ms = 16
+
+            [overrides.{platform}.terminal]
+            scrollback = 2000
+            scrollback_spill_mb = 64
+
+            [profiles]
+            default = "dev"
+
+            [[profiles.list]]
+            name = "dev"
+            command = "/bin/sh"
+            terminal = {{ scrollback = 3000 }}
+            "#,
+            platform = merge::current_platform()
+        )
+        .parse()
+        .unwrap();
+        merge::deep_merge(&mut table, overlay);
+        merge::apply_platform_overrides(&mut table);
+        let config = Config::deserialize(table).unwrap();
+
+        // The platform override replaces the base terminal settings
+        let settings = config.terminal_settings(None);
+        assert_eq!(settings.scrollback, Some(2000));
+        assert_eq!(settings.scrollback_spill_mb, Some(64));
+        assert_eq!(settings.coalesce_frames_ms, Some(16));
+
+        // and the profile's overrides go on top of both
+        let settings = config.terminal_settings(Some("dev"));
+        assert_eq!(settings.scrollback, Some(3000));
+        assert_eq!(settings.scrollback_spill_mb, Some(64));
+        assert_eq!(settings.coalesce_frames_ms, Some(16));
     }
 }
@@ -0,0 +1,81 @@
+use std::path::Path;
+use toml::Table;
+
+use crate::jsonc;
+
+/// File formats a config (or an included file) can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Jsonc,
+}
+
+impl ConfigFormat {
+    /// Formats in the order they are looked for when detecting the config file
+    pub const ALL: [ConfigFormat; 3] =
+        [ConfigFormat::Toml, ConfigFormat::Jsonc, ConfigFormat::Json];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Jsonc => "jsonc",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Detect the format from a file name, anything unknown is read as TOML
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(ConfigFormat::Toml)
+    }
+
+    pub fn parse(self, content: &str) -> Result<Table, String> {
+        match self {
+            ConfigFormat::Toml => content
+                .parse::<Table>()
+                .map_err(|e| format!("Failed to parse TOML: {}", e)),
+            // Plain JSON has no comments, stripping them is harmless
+            ConfigFormat::Json | ConfigFormat::Jsonc => {
+                serde_json::from_str(&jsonc::strip_comments(content))
+                    .map_err(|e| format!("Failed to parse JSON: {}", e))
+            }
+        }
+    }
+
+    pub fn render(self, table: &Table) -> Result<String, String> {
+        match self {
+            ConfigFormat::Toml => toml::to_string_pretty(table)
+                .map_err(|e| format!("Failed to serialize TOML: {}", e)),
+            ConfigFormat::Json | ConfigFormat::Jsonc => serde_json::to_string_pretty(table)
+                .map(|json| json + "\n")
+                .map_err(|e| format!("Failed to serialize JSON: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let table = ConfigFormat::Toml
+            .parse("version = 1\n[font]\nfamily = \"Mono\"\nsize = 14\n")
+            .unwrap();
+        let json = ConfigFormat::Json.render(&table).unwrap();
+        assert_eq!(ConfigFormat::Jsonc.parse(&json).unwrap(), table);
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("termillion.JSONC")),
+            ConfigFormat::Jsonc
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use serde::de::value::{MapDeserializer, StrDeserializer};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use tauri::AppHandle;
+use toml::{Table, Value};
+
+use crate::config::{
+    BannerConfig, ClosePtys, Config, CursorStyle, HookAction, LogLevel, OnExit, PtyLogMode,
+    ReusePane, SandboxTool, SyncPolicy, UnfocusedCursorStyle, UpdateChannel,
+};
+use crate::config_template::{example_config, field_docs};
+use crate::merge;
+use crate::updates;
+use crate::validation::{BLINK_INTERVAL_RANGE, DROPDOWN_HEIGHT_RANGE, FONT_WEIGHT_RANGE};
+
+/// Description of a single config field for generating a settings UI
+#[derive(Debug, Serialize)]
+pub struct FieldMetadata {
+    /// Dotted path of the field, array items are marked with `[]`
+    pub path: String,
+    /// TOML type: string, integer, float, boolean, array or table
+    pub field_type: &'static str,
+    pub doc: Option<&'static str>,
+    /// Value in the default config, `None` for optional fields
+    pub default: Option<Value>,
+    /// Value in the loaded config
+    pub current: Option<Value>,
+    /// Allowed values for enum fields
+    pub allowed_values: Option<&'static [&'static str]>,
+    /// Inclusive minimum and maximum for numeric fields
+    pub range: Option<(i64, i64)>,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Integer(_) => "integer",
+        Value::Float(_) => "float",
+        Value::Boolean(_) => "boolean",
+        Value::Datetime(_) => "datetime",
+        Value::Array(_) => "array",
+        Value::Table(_) => "table",
+    }
+}
+
+// Error that keeps the variants an enum expected instead of a message
+#[derive(Debug)]
+struct Variants(&'static [&'static str]);
+
+impl fmt::Display for Variants {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected one of {:?}", self.0)
+    }
+}
+
+impl std::error::Error for Variants {}
+
+impl serde::de::Error for Variants {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Self(&[])
+    }
+
+    fn unknown_variant(_: &str, expected: &'static [&'static str]) -> Self {
+        Self(expected)
+    }
+}
+
+// Names an enum deserializes from, as serde reports them for a name it
+// doesn't know. Tagged enums are given a table with an unknown `type`.
+fn variants<T: DeserializeOwned>() -> &'static [&'static str] {
+    if let Err(Variants(names)) = T::deserialize(StrDeserializer::<Variants>::new("")) {
+        if !names.is_empty() {
+            return names;
+        }
+    }
+    let tagged = MapDeserializer::<_, Variants>::new(std::iter::once(("type", "")));
+    T::deserialize(tagged)
+        .err()
+        .map_or(&[], |Variants(names)| names)
+}
+
+// Allowed values of fields that deserialize into enums, looked up by the
+// path of the table the field lives in
+fn allowed_values(path: &[String], field: &str) -> Option<&'static [&'static str]> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let values = match (path.as_slice(), field) {
+        (["terminal", "cursor"] | ["profiles", "list", "terminal", "cursor"], "style") => {
+            variants::<CursorStyle>()
+        }
+        (
+            ["terminal", "cursor"] | ["profiles", "list", "terminal", "cursor"],
+            "unfocused_style",
+        ) => variants::<UnfocusedCursorStyle>(),
+        (["profiles", "list", "banner"], "type") => variants::<BannerConfig>(),
+        (["profiles", "list"], "on_exit") => variants::<OnExit>(),
+        (["profiles", "list", "sandbox"], "tool") => variants::<SandboxTool>(),
+        (["sync"], "policy") => variants::<SyncPolicy>(),
+        (["window"], "close_ptys") => variants::<ClosePtys>(),
+        (["updates"], "channel") => variants::<UpdateChannel>(),
+        (["logging"], "level") => variants::<LogLevel>(),
+        (["logging"], "pty_output") => variants::<PtyLogMode>(),
+        (["hooks", _], "type") => variants::<HookAction>(),
+        (["tasks", _], "reuse_pane") => variants::<ReusePane>(),
+        _ => return None,
+    };
+    Some(values)
+}
+
+// Ranges checked by validation
+fn range(path: &str) -> Option<(i64, i64)> {
+    match path {
+        "terminal.cursor.blink_interval_ms"
+        | "profiles.list[].terminal.cursor.blink_interval_ms" => Some((
+            *BLINK_INTERVAL_RANGE.start() as i64,
+            *BLINK_INTERVAL_RANGE.end() as i64,
+        )),
+        "font.font_weight" | "profiles.list[].font.font_weight" => Some((
+            *FONT_WEIGHT_RANGE.start() as i64,
+            *FONT_WEIGHT_RANGE.end() as i64,
+        )),
+        "dropdown.height_percent" => Some((
+            *DROPDOWN_HEIGHT_RANGE.start() as i64,
+            *DROPDOWN_HEIGHT_RANGE.end() as i64,
+        )),
+        _ => None,
+    }
+}
+
+fn walk(
+    out: &mut Vec<FieldMetadata>,
+    path: &mut Vec<String>,
+    prefix: &str,
+    fields: &Table,
+    defaults: Option<&Table>,
+    current: Option<&Table>,
+) {
+    for (key, value) in fields {
+        let field_path = format!("{}{}", prefix, key);
+        let default = defaults.and_then(|d| d.get(key));
+        let current_value = current.and_then(|c| c.get(key));
+
+        out.push(FieldMetadata {
+            field_type: type_name(value),
+            doc: field_docs(path, key),
+            default: default.cloned(),
+            current: current_value.cloned(),
+            allowed_values: allowed_values(path, key),
+            range: range(&field_path),
+            path: field_path.clone(),
+        });
+
+        path.push(key.clone());
+        match value {
+            Value::Table(table) => walk(
+                out,
+                path,
+                &format!("{}.", field_path),
+                table,
+                default.and_then(Value::as_table),
+                current_value.and_then(Value::as_table),
+            ),
+            // Describe the items of arrays of tables by their first entry,
+            // per item values are part of the array's current value
+            Value::Array(items) => {
+                if let Some(Value::Table(item)) = items.first() {
+                    walk(out, path, &format!("{}[].", field_path), item, None, None);
+                }
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// Describe every config field, including optional ones and any the
+/// current config adds (like custom shortcuts)
+pub fn config_metadata(config: &Config) -> Result<Vec<FieldMetadata>, String> {
+    let serialize = |config: &Config| {
+        Table::try_from(config).map_err(|e| format!("Failed to serialize config: {}", e))
+    };
+    let defaults = serialize(&Config::default())?;
+    let current = serialize(config)?;
+
+    let mut fields = serialize(&example_config())?;
+    merge::deep_merge(&mut fields, current.clone());
+
+    let mut metadata = Vec::new();
+    walk(
+        &mut metadata,
+        &mut Vec::new(),
+        "",
+        &fields,
+        Some(&defaults),
+        Some(&current),
+    );
+    Ok(metadata)
+}
+
+// Field descriptions for generating the settings UI
+#[tauri::command]
+pub async fn get_config_metadata(app: AppHandle) -> Result<Vec<FieldMetadata>, String> {
+    let config = Config::load(&app)?;
+    let mut metadata = config_metadata(&config)?;
+    // Builds that can't update have no update settings to show
+    if !updates::can_update(&app) {
+        metadata.retain(|field| field.path != "updates" && !field.path.starts_with("updates."));
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_describes_enums_and_optional_fields() {
+        let metadata = config_metadata(&Config::default()).unwrap();
+        let field = |path: &str| metadata.iter().find(|f| f.path == path).unwrap();
+
+        let style = field("terminal.cursor.style");
+        assert_eq!(style.field_type, "string");
+        assert!(style.allowed_values.unwrap().contains(&"bar"));
+        assert!(style.doc.is_some());
+
+        let include = field("include");
+        assert!(include.default.is_none() && include.current.is_none());
+
+        assert_eq!(field("profiles.list[].name").field_type, "string");
+        assert_eq!(
+            field("profiles.list[].sandbox.tool").allowed_values,
+            Some(&["bubblewrap", "firejail", "sandbox_exec"][..])
+        );
+        assert_eq!(
+            field("hooks.triggers[].type").allowed_values,
+            Some(&["command", "notify", "sound"][..])
+        );
+    }
+
+    // Replace a field's value, in the first item of arrays of tables
+    fn set_field(table: &mut Table, path: &str, value: &str) {
+        let (key, rest) = path.split_once('.').unwrap_or((path, ""));
+        let (key, in_array) = key
+            .strip_suffix("[]")
+            .map_or((key, false), |key| (key, true));
+        let mut entry = table.get_mut(key).unwrap();
+        if in_array {
+            entry = &mut entry.as_array_mut().unwrap()[0];
+        }
+        match entry {
+            Value::Table(table) if !rest.is_empty() => set_field(table, rest, value),
+            _ => *entry = Value::String(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_enum_fields_have_allowed_values() {
+        let example = Table::try_from(example_config()).unwrap();
+        let metadata = config_metadata(&example_config()).unwrap();
+        let strings = metadata
+            .iter()
+            .filter(|field| field.field_type == "string" && field.allowed_values.is_none());
+        // Only enums reject a string for not being one of their variants
+        for field in strings {
+            let mut config = example.clone();
+            set_field(&mut config, &field.path, "not a variant");
+            if let Err(e) = Value::Table(config).try_into::<Config>() {
+                assert!(
+                    !e.to_string().contains("unknown variant"),
+                    "{} has no allowed values",
+                    field.path
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use documented::DocumentedFields;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+use crate::config::*;
+
+/// Default config with every optional field filled in, used to show the
+/// fields that have no default value as commented-out examples
+pub fn example_config() -> Config {
+    let defaults = Config::default();
+    let mut config = Config {
+        include: Some(vec!["keybindings.toml".into()]),
+        startup: Some(StartupConfig {
+            default_profile: defaults.profiles.as_ref().map(|p| p.default.clone()),
+            default_cwd: Some("~/projects".into()),
+            ..Default::default()
+        }),
+        sync: Some(SyncConfig {
+            url: Some("https://example.com/termillion.toml".into()),
+            git: None,
+            branch: None,
+            path: None,
+            interval_minutes: Some(60),
+            policy: Some(SyncPolicy::LocalWins),
+            allow_commands: Some(false),
+        }),
+        dropdown: Some(DropdownConfig {
+            enabled: false,
+            hotkey: Shortcut {
+                key: "`".into(),
+                ctrl: true,
+                ..Default::default()
+            },
+            profile: defaults.profiles.as_ref().map(|p| p.default.clone()),
+            height_percent: Some(40),
+            hide_on_blur: Some(true),
+            animation_ms: Some(150),
+        }),
+        notifications: Some(NotificationConfig {
+            bell: Some(true),
+            command_finished: Some(true),
+            long_command_seconds: Some(10),
+            triggers: Some(vec!["(?i)build (failed|succeeded)".into()]),
+        }),
+        updates: Some(UpdateConfig {
+            channel: Some(UpdateChannel::Stable),
+            auto_download: Some(false),
+            check_interval_hours: Some(24),
+        }),
+        logging: Some(LoggingConfig {
+            level: Some(LogLevel::Info),
+            pty_output: Some(PtyLogMode::Off),
+        }),
+        hooks: Some(HooksConfig {
+            pty_created: None,
+            pty_exited: None,
+            bell: Some(vec![HookAction::Sound { path: None }]),
+            command_failed: Some(vec![HookAction::Notify {
+                message: "Command failed with exit code {exit_code}".into(),
+            }]),
+            triggers: Some(vec![TriggerHook {
+                pattern: "(?i)deploy finished".into(),
+                action: HookAction::Command {
+                    command: "notify-team".into(),
+                    args: Some(vec!["--channel".into(), "ops".into()]),
+                },
+            }]),
+        }),
+        editor: Some(EditorConfig {
+            command: Some("code".into()),
+            args: None,
+            terminal: Some(false),
+        }),
+        annotations: Some(AnnotationsConfig {
+            builtin: Some(true),
+            rules: Some(vec![AnnotationRule {
+                name: "jira".into(),
+                pattern: r"\b[A-Z][A-Z0-9]+-\d+\b".into(),
+            }]),
+        }),
+        link_rules: Some(IndexMap::from_iter([(
+            "jira".to_string(),
+            LinkRule {
+                pattern: r"\b(PROJ-\d+)\b".into(),
+                url: Some("https://jira.example.com/browse/{1}".into()),
+                command: None,
+                args: None,
+            },
+        )])),
+        tasks: Some(IndexMap::from_iter([(
+            "dev server".to_string(),
+            TaskConfig {
+                command: "npm".into(),
+                args: Some(vec!["run".into(), "dev".into()]),
+                cwd: Some("~/projects/app".into()),
+                profile: None,
+                reuse_pane: Some(ReusePane::Restart),
+            },
+        )])),
+        layouts: Some(IndexMap::from_iter([(
+            "dev".to_string(),
+            LayoutConfig {
+                rows: 2,
+                columns: 2,
+                panes: vec![
+                    LayoutPaneConfig {
+                        row: 0,
+                        column: 0,
+                        row_span: Some(2),
+                        column_span: None,
+                        profile: None,
+                        cwd: Some("~/projects/app".into()),
+                        command: Some("nvim".into()),
+                        args: None,
+                    },
+                    LayoutPaneConfig {
+                        row: 0,
+                        column: 1,
+                        row_span: None,
+                        column_span: None,
+                        profile: None,
+                        cwd: Some("~/projects/app".into()),
+                        command: None,
+                        args: None,
+                    },
+                    LayoutPaneConfig {
+                        row: 1,
+                        column: 1,
+                        row_span: None,
+                        column_span: None,
+                        profile: None,
+                        cwd: Some("~/projects/app".into()),
+                        command: Some("npm".into()),
+                        args: Some(vec!["run".into(), "dev".into()]),
+                    },
+                ],
+            },
+        )])),
+        dynamic_profiles: Some(DynamicProfilesConfig {
+            wsl: Some(true),
+            ssh_hosts: Some(true),
+            docker: Some(false),
+            kube_contexts: Some(false),
+            command: Some("~/bin/list-profiles".into()),
+            args: None,
+        }),
+        profile_rules: Some(vec![ProfileRule {
+            profile: "production".into(),
+            git_remote: Some("*github.com:acme/infra*".into()),
+            ..Default::default()
+        }]),
+        command_history: Some(CommandHistoryConfig {
+            enabled: Some(true),
+            ignore: Some(vec![r"(?i)(password|token|secret)=".into()]),
+        }),
+        overrides: Some(PlatformOverrides {
+            macos: Some(Table::from_iter([(
+                "font".to_string(),
+                Value::Table(Table::from_iter([("size".to_string(), Value::Integer(15))])),
+            )])),
+            ..Default::default()
+        }),
+        ..defaults
+    };
+
+    if let Some(profile) = config
+        .profiles
+        .as_mut()
+        .and_then(|profiles| profiles.list.first_mut())
+    {
+        profile.args = Some(vec!["-l".into()]);
+        profile.cwd = Some("~/projects".into());
+        profile.icon = Some("🐚".into());
+        profile.color = Some("#5e81ac".into());
+        profile.locale = Some("en_US.UTF-8".into());
+        profile.on_exit = Some(OnExit::Close);
+        profile.elevated = Some(false);
+        profile.sandbox = Some(SandboxConfig {
+            tool: Some(SandboxTool::Bubblewrap),
+            writable: Some(vec!["~/scratch".into()]),
+            hidden: Some(vec!["~/.ssh".into()]),
+            network: Some(false),
+        });
+        profile.env = Some(HashMap::from([("EDITOR".into(), "nvim".into())]));
+        profile.startup_commands = Some(vec!["git status".into()]);
+        profile.banner = Some(BannerConfig::Text {
+            text: "Welcome back!".into(),
+        });
+        profile.font = Some(config.font.clone());
+        profile.terminal = Some(TerminalSettings {
+            scrollback: Some(100_000),
+            scrollback_spill: Some(true),
+            ..Default::default()
+        });
+    }
+
+    config.font.ligatures = Some(true);
+    config.font.font_weight = Some(400);
+    config.font.bold_font_family = Some("JetBrains Mono Bold".into());
+    config.font.italic_font_family = Some("JetBrains Mono Italic".into());
+    config.font.features = Some(HashMap::from([("zero".into(), true)]));
+
+    config.window.width = Some(800);
+    config.window.height = Some(600);
+    config.window.position = Some(WindowPosition { x: 100, y: 100 });
+    config.window.always_on_top = Some(false);
+    config.window.remember_size = Some(true);
+    config.window.close_ptys = Some(ClosePtys::Destroy);
+    config.window.confirm_close_processes = Some(["vim", "nvim", "ssh"].map(String::from).to_vec());
+    config.window.title_format = Some("{index}: {title}".into());
+
+    config.shortcuts.0.insert(
+        "clear_and_rerun".into(),
+        Shortcut {
+            key: "k".into(),
+            ctrl: true,
+            shift: true,
+            send_text: Some(r"\x0c!!\r".into()),
+            ..Default::default()
+        },
+    );
+
+    config
+}
+
+/// Doc comment of a field, looked up by the path of the table it lives in
+pub fn field_docs(path: &[String], field: &str) -> Option<&'static str> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        [] => Config::get_field_docs(field),
+        ["font"] | ["profiles", "list", "font"] => FontConfig::get_field_docs(field),
+        ["theme"] | ["profiles", "list", "theme"] => ThemeConfig::get_field_docs(field),
+        ["shell"] => ShellConfig::get_field_docs(field),
+        ["terminal"] | ["profiles", "list", "terminal"] => TerminalSettings::get_field_docs(field),
+        ["terminal", "padding"] | ["profiles", "list", "terminal", "padding"] => {
+            PaddingConfig::get_field_docs(field)
+        }
+        ["terminal", "cursor"] | ["profiles", "list", "terminal", "cursor"] => {
+            CursorConfig::get_field_docs(field)
+        }
+        ["terminal", "selection"] | ["profiles", "list", "terminal", "selection"] => {
+            SelectionConfig::get_field_docs(field)
+        }
+        ["profiles"] => Profiles::get_field_docs(field),
+        ["profiles", "list"] => Profile::get_field_docs(field),
+        ["profiles", "list", "sandbox"] => SandboxConfig::get_field_docs(field),
+        ["shortcuts", _] => Shortcut::get_field_docs(field),
+        ["window"] => WindowConfig::get_field_docs(field),
+        ["window", "position"] => WindowPosition::get_field_docs(field),
+        ["window", "interactive"] => InteractiveElementStyle::get_field_docs(field),
+        ["window", "tabs"] => WindowTabsStyle::get_field_docs(field),
+        ["window", "tabs", _] => TabStyle::get_field_docs(field),
+        ["startup"] => StartupConfig::get_field_docs(field),
+        ["sync"] => SyncConfig::get_field_docs(field),
+        ["dropdown"] => DropdownConfig::get_field_docs(field),
+        ["dropdown", "hotkey"] => Shortcut::get_field_docs(field),
+        ["notifications"] => NotificationConfig::get_field_docs(field),
+        ["updates"] => UpdateConfig::get_field_docs(field),
+        ["logging"] => LoggingConfig::get_field_docs(field),
+        ["hooks"] => HooksConfig::get_field_docs(field),
+        ["hooks", "triggers"] => TriggerHook::get_field_docs(field),
+        ["editor"] => EditorConfig::get_field_docs(field),
+        ["annotations"] => AnnotationsConfig::get_field_docs(field),
+        ["annotations", "rules"] => AnnotationRule::get_field_docs(field),
+        ["link_rules", _] => LinkRule::get_field_docs(field),
+        ["command_history"] => CommandHistoryConfig::get_field_docs(field),
+        ["tasks", _] => TaskConfig::get_field_docs(field),
+        ["layouts", _] => LayoutConfig::get_field_docs(field),
+        ["profile_rules"] => ProfileRule::get_field_docs(field),
+        ["dynamic_profiles"] => DynamicProfilesConfig::get_field_docs(field),
+        ["layouts", _, "panes"] => LayoutPaneConfig::get_field_docs(field),
+        ["overrides"] => PlatformOverrides::get_field_docs(field),
+        _ => return None,
+    }
+    .ok()
+}
+
+// Tables written inline to keep the file short
+fn is_inline(path: &[String]) -> bool {
+    matches!(path.first().map(String::as_str), Some("shortcuts")) && path.len() == 2
+        || path == ["terminal", "padding"]
+        || path == ["profiles", "list", "terminal", "padding"]
+        || path == ["window", "position"]
+        || path == ["font", "features"]
+        || path == ["dropdown", "hotkey"]
+}
+
+fn format_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+fn write_docs(out: &mut String, docs: Option<&str>) {
+    for line in docs.unwrap_or_default().lines() {
+        if line.is_empty() {
+            out.push_str("#\n");
+        } else {
+            let _ = writeln!(out, "# {}", line);
+        }
+    }
+}
+
+fn write_table(
+    out: &mut String,
+    path: &mut Vec<String>,
+    table: &Table,
+    present: Option<&Table>,
+    commented: bool,
+) {
+    let comment = |absent: bool| if commented || absent { "# " } else { "" };
+
+    // Plain values have to come before any sub-table
+    for (key, value) in table {
+        let is_table = matches!(value, Value::Table(_)) && {
+            path.push(key.clone());
+            let inline = is_inline(path);
+            path.pop();
+            !inline
+        };
+        let is_table_array = matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_table));
+        if is_table || is_table_array {
+            continue;
+        }
+
+        let absent = present.is_none_or(|p| !p.contains_key(key));
+        write_docs(out, field_docs(path, key));
+        let _ = writeln!(out, "{}{} = {}", comment(absent), format_key(key), value);
+    }
+
+    for (key, value) in table {
+        let present_value = present.and_then(|p| p.get(key));
+        let docs = field_docs(path, key);
+        path.push(key.clone());
+        let header = path
+            .iter()
+            .map(|k| format_key(k))
+            .collect::<Vec<_>>()
+            .join(".");
+
+        match value {
+            Value::Table(child) if !is_inline(path) => {
+                let absent = present_value.is_none();
+                out.push('\n');
+                write_docs(out, docs);
+                let _ = writeln!(out, "{}[{}]", comment(absent), header);
+                write_table(
+                    out,
+                    path,
+                    child,
+                    present_value.and_then(Value::as_table),
+                    commented || absent,
+                );
+            }
+            Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_table) => {
+                let present_items = present_value.and_then(Value::as_array);
+                for (index, item) in items.iter().enumerate() {
+                    let present_item = present_items
+                        .and_then(|items| items.get(index))
+                        .and_then(Value::as_table);
+                    let absent = present_item.is_none();
+                    out.push('\n');
+                    write_docs(out, docs);
+                    let _ = writeln!(out, "{}[[{}]]", comment(absent), header);
+                    if let Some(item) = item.as_table() {
+                        write_table(out, path, item, present_item, commented || absent);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        path.pop();
+    }
+}
+
+/// Render the default config as TOML with every field documented
+pub fn render_default_config() -> Result<String, String> {
+    let defaults = Table::try_from(Config::default())
+        .map_err(|e| format!("Failed to serialize default config: {}", e))?;
+    let example = Table::try_from(example_config())
+        .map_err(|e| format!("Failed to serialize example config: {}", e))?;
+
+    let mut out = String::from(
+        "# Termillion configuration\n#\n# Commented-out entries are optional and show example values.\n\n",
+    );
+    write_table(&mut out, &mut Vec::new(), &example, Some(&defaults), false);
+    Ok(out)
+}
+
+/// Write the documented default config to a file
+pub fn write_default_config_to(path: &Path) -> Result<(), String> {
+    let content = render_default_config()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+// Write a fully documented default config to the given path
+#[tauri::command]
+pub async fn write_default_config(path: String) -> Result<(), String> {
+    write_default_config_to(Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendered_config_parses_as_default() {
+        let rendered = render_default_config().unwrap();
+        let parsed: Config = toml::from_str(&rendered).unwrap();
+        assert_eq!(parsed.version, Config::default().version);
+        assert!(parsed.include.is_none());
+        assert!(rendered.contains("# include = [\"keybindings.toml\"]"));
+    }
+}
@@ -0,0 +1,435 @@
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader as AsyncBufReader,
+};
+
+use crate::pty;
+use crate::session::{self, SessionTab};
+use crate::window;
+
+/// Variable set in every PTY with its ID, the default target of `send-text`
+/// and `get-pty`
+pub const PTY_ID_VAR: &str = "TERMILLION_PTY_ID";
+
+/// Variable set in every PTY with the control socket of the app running it
+pub const SOCKET_VAR: &str = "TERMILLION_SOCKET";
+
+// Socket file this app created, removed again on exit
+static BOUND: OnceLock<PathBuf> = OnceLock::new();
+
+/// A request on the control socket, sent as one line of JSON. The same
+/// commands make up `termillion cli`.
+#[derive(Debug, Clone, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// List the open windows
+    ListWindows,
+    /// List the tabs of every window, or of one
+    List {
+        /// Label of the window
+        #[arg(long)]
+        window: Option<String>,
+    },
+    /// Open a tab, running the command after `--` instead of the shell
+    Spawn {
+        /// Label of the window, defaults to the focused one
+        #[arg(long)]
+        window: Option<String>,
+        #[arg(long)]
+        profile: Option<String>,
+        #[arg(long)]
+        cwd: Option<String>,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// Type text into a terminal, read from stdin when not given
+    SendText {
+        /// Defaults to the terminal the command runs in
+        #[arg(long)]
+        pty_id: Option<String>,
+        text: Option<String>,
+    },
+    /// Show the state of a terminal
+    GetPty {
+        /// Defaults to the terminal the command runs in
+        #[arg(long)]
+        pty_id: Option<String>,
+    },
+}
+
+/// Reply to a request, one line of JSON
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<Result<Value, String>> for ControlResponse {
+    fn from(result: Result<Value, String>) -> Self {
+        match result {
+            Ok(result) => Self {
+                ok: true,
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => Self {
+                ok: false,
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Where the app listens: a socket in a directory of the user's, or a
+/// named pipe on Windows
+pub fn default_socket_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        PathBuf::from(format!(r"\\.\pipe\termillion-{}", user))
+    }
+    #[cfg(not(windows))]
+    {
+        // The temporary directory may be shared, so the user's name goes in
+        let user = std::env::var("USER").unwrap_or_default();
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!("termillion-{}", user))
+            .join("control.sock")
+    }
+}
+
+// Clients inside a tab talk to the app that runs the tab
+fn socket_path() -> PathBuf {
+    std::env::var_os(SOCKET_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(default_socket_path)
+}
+
+fn pty_id(pty_id: Option<String>) -> Result<String, String> {
+    pty_id.ok_or_else(|| {
+        format!(
+            "No terminal given, pass --pty-id or run inside termillion, which sets {}",
+            PTY_ID_VAR
+        )
+    })
+}
+
+fn list_windows(app: &AppHandle) -> Value {
+    let mut windows: Vec<Value> = app
+        .webview_windows()
+        .into_values()
+        .map(|window| {
+            json!({
+                "label": window.label(),
+                "title": window.title().ok(),
+                "focused": window.is_focused().unwrap_or(false),
+                "visible": window.is_visible().unwrap_or(false),
+            })
+        })
+        .collect();
+    windows.sort_by(|a, b| a["label"].as_str().cmp(&b["label"].as_str()));
+    Value::Array(windows)
+}
+
+fn list_tabs(app: &AppHandle, window: Option<&str>) -> Result<Value, String> {
+    let mut tabs = Vec::new();
+    let mut sessions: Vec<_> = session::snapshot(app)?.into_iter().collect();
+    sessions.sort_by(|a, b| a.0.cmp(&b.0));
+    for (label, session) in sessions {
+        if window.is_some_and(|window| window != label) {
+            continue;
+        }
+        for (index, tab) in session.tabs.iter().enumerate() {
+            tabs.push(json!({
+                "window": label,
+                "index": index,
+                "active": index == session.active,
+                "title": tab.title,
+                "profile": tab.profile,
+                "cwd": tab.cwd,
+                "pty_id": tab.pty_id,
+            }));
+        }
+    }
+    Ok(Value::Array(tabs))
+}
+
+fn get_pty(pty_id: &str) -> Result<Value, String> {
+    let counters = pty::pty_counters()
+        .into_iter()
+        .find(|counters| counters.pty_id == pty_id)
+        .ok_or_else(|| format!("PTY with ID {} not found", pty_id))?;
+    Ok(json!({
+        "pty_id": pty_id,
+        "window": counters.window,
+        "shell": pty::pty_shell(pty_id),
+        "cwd": pty::pty_cwd(pty_id),
+        "bytes_read": counters.bytes_read,
+        "bytes_written": counters.bytes_written,
+        "uptime_ms": counters.uptime_ms,
+    }))
+}
+
+fn handle(app: &AppHandle, request: ControlRequest) -> Result<Value, String> {
+    match request {
+        ControlRequest::ListWindows => Ok(list_windows(app)),
+        ControlRequest::List { window } => list_tabs(app, window.as_deref()),
+        ControlRequest::Spawn {
+            window,
+            profile,
+            cwd,
+            title,
+            command,
+        } => {
+            let window = match window {
+                Some(label) => app
+                    .get_webview_window(&label)
+                    .ok_or_else(|| format!("No window {}", label))?,
+                None => window::target_window(app)
+                    .ok_or_else(|| "No window to open the tab in".to_string())?,
+            };
+            let mut command = command.into_iter();
+            let tab = SessionTab {
+                profile,
+                cwd,
+                title,
+                command: command.next(),
+                args: Some(command.collect()),
+                pty_id: None,
+                task: None,
+                decoration: None,
+            };
+            app.emit_to(window.label(), "open-session-tab", tab)
+                .map_err(|e| format!("Failed to open tab: {}", e))?;
+            Ok(json!({ "window": window.label() }))
+        }
+        ControlRequest::SendText { pty_id: id, text } => {
+            let id = pty_id(id)?;
+            pty::write_to_pty(&id, text.unwrap_or_default().as_bytes())?;
+            Ok(Value::Null)
+        }
+        ControlRequest::GetPty { pty_id: id } => get_pty(&pty_id(id)?),
+    }
+}
+
+// Answer requests on one connection until the client hangs up
+async fn serve<S>(app: AppHandle, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = AsyncBufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response: ControlResponse = serde_json::from_str::<ControlRequest>(&line)
+            .map_err(|e| format!("Invalid request: {}", e))
+            .and_then(|request| handle(&app, request))
+            .into();
+        let mut reply = serde_json::to_string(&response).unwrap_or_default();
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(not(windows))]
+async fn listen(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use tokio::net::UnixListener;
+
+    if let Some(dir) = path.parent() {
+        // Only the user may reach the socket
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+    }
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            return Err(format!(
+                "Another termillion is listening on {}",
+                path.display()
+            ));
+        }
+        // Left behind by an app that didn't exit cleanly
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener =
+        UnixListener::bind(&path).map_err(|e| format!("Failed to bind control socket: {}", e))?;
+    let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    let _ = BOUND.set(path);
+
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept control connection: {}", e))?;
+        tauri::async_runtime::spawn(serve(app.clone(), stream));
+    }
+}
+
+#[cfg(windows)]
+async fn listen(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let create = |first| {
+        ServerOptions::new()
+            .first_pipe_instance(first)
+            .reject_remote_clients(true)
+            .create(&path)
+            .map_err(|e| format!("Failed to create control pipe: {}", e))
+    };
+    let mut server = create(true)?;
+    loop {
+        server
+            .connect()
+            .await
+            .map_err(|e| format!("Failed to accept control connection: {}", e))?;
+        // A new instance waits for the next client while this one is served
+        let connected = std::mem::replace(&mut server, create(false)?);
+        tauri::async_runtime::spawn(serve(app.clone(), connected));
+    }
+}
+
+/// Listen on the control socket for the life of the app
+pub fn start_server(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app, default_socket_path()).await {
+            tracing::warn!("Control socket disabled: {}", e);
+        }
+    });
+}
+
+/// Remove the socket file when the app exits
+pub fn stop_server() {
+    if let Some(path) = BOUND.get() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(not(windows))]
+fn connect(path: &PathBuf) -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+// Named pipes open like files
+#[cfg(windows)]
+fn connect(path: &PathBuf) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+}
+
+fn send(mut request: ControlRequest) -> Result<Value, String> {
+    // Inside a tab the tab's own terminal is the default target
+    match &mut request {
+        ControlRequest::SendText { pty_id, text } => {
+            *pty_id = pty_id.take().or_else(|| std::env::var(PTY_ID_VAR).ok());
+            if text.is_none() {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|e| format!("Failed to read stdin: {}", e))?;
+                *text = Some(input);
+            }
+        }
+        ControlRequest::GetPty { pty_id } => {
+            *pty_id = pty_id.take().or_else(|| std::env::var(PTY_ID_VAR).ok());
+        }
+        _ => {}
+    }
+
+    let path = socket_path();
+    let mut stream = connect(&path).map_err(|e| {
+        format!(
+            "Failed to connect to termillion at {}, is it running? {}",
+            path.display(),
+            e
+        )
+    })?;
+    let mut line = serde_json::to_string(&request)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("Failed to read reply: {}", e))?;
+    let response: ControlResponse =
+        serde_json::from_str(&reply).map_err(|e| format!("Invalid reply: {}", e))?;
+    match response {
+        ControlResponse {
+            ok: true, result, ..
+        } => Ok(result.unwrap_or(Value::Null)),
+        ControlResponse { error, .. } => Err(error.unwrap_or_else(|| "Request failed".to_string())),
+    }
+}
+
+/// Run `termillion cli`, printing the reply as JSON. Returns the exit code.
+pub fn run_client(request: ControlRequest) -> i32 {
+    match send(request) {
+        Ok(Value::Null) => 0,
+        Ok(result) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&result).unwrap_or_default()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_protocol() {
+        let request: ControlRequest =
+            serde_json::from_str(r#"{"type":"send_text","pty_id":"abc","text":"ls\r"}"#).unwrap();
+        assert!(matches!(
+            request,
+            ControlRequest::SendText { pty_id: Some(ref id), text: Some(ref text) }
+                if id == "abc" && text == "ls\r"
+        ));
+
+        let spawn = ControlRequest::Spawn {
+            window: None,
+            profile: Some("zsh".into()),
+            cwd: None,
+            title: None,
+            command: vec!["htop".into()],
+        };
+        let json = serde_json::to_value(&spawn).unwrap();
+        assert_eq!(json["type"], "spawn");
+        assert_eq!(json["command"][0], "htop");
+
+        let response = ControlResponse::from(Err("PTY with ID x not found".to_string()));
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"ok":false,"error":"PTY with ID x not found"}"#
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use tauri::{AppHandle, Manager};
+
+use crate::config::Config;
+use crate::logging;
+
+/// Directory in the data directory crash reports are written to
+const CRASH_DIR: &str = "crashes";
+
+/// Reports kept, older ones are removed on startup
+const MAX_REPORTS: usize = 10;
+
+/// Log lines put in crash reports
+const RECENT_LOG_LINES: usize = 100;
+
+static LIVE_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// What was known about the app when it panicked
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub time: String,
+    pub app_version: String,
+    pub config_version: Option<u32>,
+    /// Name of the thread that panicked
+    pub thread: Option<String>,
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// Lines logged before the panic, oldest first
+    pub recent_log: Vec<String>,
+    /// Whether the UI already offered to report it
+    #[serde(default)]
+    pub seen: bool,
+}
+
+// Panics carry a &str or a String, anything else is rare
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
+fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(CRASH_DIR))
+        .map_err(|_| "Failed to get data directory".to_string())
+}
+
+// Report files sorted oldest first, their names start with the time
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash directory: {}", e))?;
+    let name = format!(
+        "crash-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+    );
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    fs::write(dir.join(name), json).map_err(|e| format!("Failed to write crash report: {}", e))
+}
+
+/// Write a crash report for every panic, then let the default hook print it
+pub fn install_panic_hook(app: &AppHandle) {
+    let dir = match crash_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::warn!("Crash reports are disabled: {}", e);
+            return;
+        }
+    };
+    for old in report_files(&dir).iter().rev().skip(MAX_REPORTS) {
+        let _ = fs::remove_file(old);
+    }
+
+    let app_version = app.package_info().version.to_string();
+    let config_version = Config::load(app).ok().map(|config| config.version);
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = CrashReport {
+            time: chrono::Local::now().to_rfc3339(),
+            app_version: app_version.clone(),
+            config_version,
+            thread: thread::current().name().map(str::to_string),
+            message: panic_message(info.payload()),
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+            recent_log: logging::recent_lines(RECENT_LOG_LINES),
+            seen: false,
+        };
+        if let Err(e) = write_report(&dir, &report) {
+            tracing::error!("{}", e);
+        }
+        previous(info);
+    }));
+}
+
+/// Spawn a named thread whose panic is logged instead of ending it silently.
+/// The name shows up in crash reports.
+pub fn spawn<F>(name: &str, f: F) -> JoinHandle<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let thread_name = name.to_string();
+    thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            LIVE_THREADS.fetch_add(1, Ordering::Relaxed);
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            LIVE_THREADS.fetch_sub(1, Ordering::Relaxed);
+            if let Err(payload) = result {
+                tracing::error!(
+                    "Thread {} panicked: {}",
+                    thread_name,
+                    panic_message(payload.as_ref())
+                );
+            }
+        })
+        .expect("failed to spawn thread")
+}
+
+/// Threads started with `spawn` that are still running
+pub fn live_threads() -> usize {
+    LIVE_THREADS.load(Ordering::Relaxed)
+}
+
+// The report of the last abnormal exit, once: later calls return nothing
+// until the app crashes again
+#[tauri::command]
+pub async fn get_last_crash_report(app: AppHandle) -> Result<Option<CrashReport>, String> {
+    let Some(path) = report_files(&crash_dir(&app)?).pop() else {
+        return Ok(None);
+    };
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read crash report: {}", e))?;
+    let mut report: CrashReport = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse crash report: {}", e))?;
+    if report.seen {
+        return Ok(None);
+    }
+
+    report.seen = true;
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write crash report: {}", e))?;
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_thread_panic() {
+        let handle = spawn("crash-test", || panic!("reader failed: {}", 42));
+        assert!(handle.join().is_ok());
+
+        let payload: Box<dyn Any + Send> = Box::new("static message");
+        assert_eq!(panic_message(payload.as_ref()), "static message");
+        let payload: Box<dyn Any + Send> = Box::new(format!("reader failed: {}", 42));
+        assert_eq!(panic_message(payload.as_ref()), "reader failed: 42");
+    }
+}
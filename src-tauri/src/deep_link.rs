@@ -0,0 +1,89 @@
+use tauri::{AppHandle, Emitter, Url};
+
+use crate::session::SessionTab;
+use crate::window;
+
+/// URL schemes termillion opens
+pub const SCHEMES: [&str; 2] = ["ssh", "sftp"];
+
+/// Turn an `ssh://user@host:port` or `sftp://user@host:port/path` URL into a
+/// tab running the `ssh` or `sftp` client
+pub fn url_tab(url: &str) -> Result<SessionTab, String> {
+    let url = Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    let scheme = url.scheme();
+    if !SCHEMES.contains(&scheme) {
+        return Err(format!("Unsupported URL scheme '{}'", scheme));
+    }
+
+    let host = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .ok_or_else(|| format!("No host in {}", url))?;
+    // The ssh URI draft allows connection parameters after a `;`, which
+    // the URL parser percent-encodes
+    let user = url.username();
+    let user = user.split_once("%3B").map_or(user, |(user, _)| user);
+    // Values starting with a dash would be read as client options
+    if host.starts_with('-') || user.starts_with('-') {
+        return Err(format!("Invalid host or user in {}", url));
+    }
+
+    let destination = match user {
+        "" => host.to_string(),
+        user => format!("{}@{}", user, host),
+    };
+    let mut args = Vec::new();
+    if let Some(port) = url.port() {
+        // sftp takes the port as -P
+        args.push(if scheme == "sftp" { "-P" } else { "-p" }.to_string());
+        args.push(port.to_string());
+    }
+    args.push("--".to_string());
+    match url.path() {
+        path if scheme == "sftp" && path.len() > 1 => {
+            args.push(format!("{}:{}", destination, path))
+        }
+        _ => args.push(destination.clone()),
+    }
+
+    Ok(SessionTab {
+        profile: None,
+        cwd: None,
+        title: Some(destination),
+        command: Some(scheme.to_string()),
+        args: Some(args),
+        pty_id: None,
+        task: None,
+        decoration: None,
+    })
+}
+
+/// Open a URL handed to the running app in a new tab of the focused window
+pub fn open_url(app: &AppHandle, url: &str) -> Result<(), String> {
+    let tab = url_tab(url)?;
+    let window =
+        window::target_window(app).ok_or_else(|| "No window to open the URL in".to_string())?;
+    window::bring_to_front(&window)?;
+    app.emit_to(window.label(), "open-session-tab", tab)
+        .map_err(|e| format!("Failed to open tab: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_tab() {
+        let tab = url_tab("ssh://alice@example.com:2222").unwrap();
+        assert_eq!(tab.command.as_deref(), Some("ssh"));
+        assert_eq!(tab.args.unwrap(), ["-p", "2222", "--", "alice@example.com"]);
+        assert_eq!(tab.title.as_deref(), Some("alice@example.com"));
+
+        let tab = url_tab("sftp://bob;fingerprint=abc@example.com/srv/files").unwrap();
+        assert_eq!(tab.args.unwrap(), ["--", "bob@example.com:/srv/files"]);
+
+        assert!(url_tab("ssh://-oProxyCommand=evil").is_err());
+        assert!(url_tab("ssh://-x@example.com").is_err());
+        assert!(url_tab("http://example.com").is_err());
+    }
+}
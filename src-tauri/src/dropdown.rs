@@ -0,0 +1,239 @@
+use std::thread;
+use std::time::Duration;
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut as Hotkey, ShortcutState};
+
+use crate::config::{Config, DropdownConfig, Shortcut};
+
+/// Label of the dropdown window
+pub const DROPDOWN_LABEL: &str = "dropdown";
+
+const DEFAULT_HEIGHT_PERCENT: u8 = 40;
+const DEFAULT_ANIMATION_MS: u64 = 150;
+
+/// Window moves per slide animation
+const ANIMATION_STEPS: u32 = 10;
+
+// Accelerator for the global shortcut plugin, like `ctrl+shift+Backquote`
+fn accelerator(hotkey: &Shortcut) -> String {
+    let mut parts = Vec::new();
+    if hotkey.ctrl {
+        parts.push("ctrl");
+    }
+    if hotkey.shift {
+        parts.push("shift");
+    }
+    if hotkey.alt {
+        parts.push("alt");
+    }
+    if hotkey.meta {
+        parts.push("super");
+    }
+    parts.push(&hotkey.key);
+    parts.join("+")
+}
+
+/// Register the dropdown hotkey, replacing the previous one. Called on
+/// startup and whenever the config is saved.
+pub fn setup(app: &AppHandle, config: &Config) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister dropdown hotkey: {}", e))?;
+
+    let Some(dropdown) = config.dropdown.as_ref().filter(|d| d.enabled) else {
+        // Turning the dropdown off closes it, along with its shells
+        if let Some(window) = app.get_webview_window(DROPDOWN_LABEL) {
+            window
+                .close()
+                .map_err(|e| format!("Failed to close dropdown: {}", e))?;
+        }
+        return Ok(());
+    };
+
+    let hotkey: Hotkey = accelerator(&dropdown.hotkey)
+        .parse()
+        .map_err(|e| format!("Invalid dropdown hotkey: {}", e))?;
+    shortcuts
+        .on_shortcut(hotkey, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                // Animating blocks, so keep it off the event loop
+                let app = app.clone();
+                thread::spawn(move || {
+                    if let Err(e) = toggle(&app) {
+                        tracing::warn!("Failed to toggle dropdown: {}", e);
+                    }
+                });
+            }
+        })
+        .map_err(|e| format!("Failed to register dropdown hotkey: {}", e))
+}
+
+// The dropdown window is created hidden on first use and only hidden
+// afterwards, so its tabs and shells survive between toggles
+fn get_or_create(app: &AppHandle, dropdown: &DropdownConfig) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(DROPDOWN_LABEL) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(app, DROPDOWN_LABEL, WebviewUrl::default())
+        .title("termillion")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to create dropdown window: {}", e))?;
+
+    if dropdown.hide_on_blur.unwrap_or(true) {
+        let app = app.clone();
+        window.on_window_event(move |event| {
+            if let WindowEvent::Focused(false) = event {
+                let app = app.clone();
+                thread::spawn(move || {
+                    if let Err(e) = hide(&app) {
+                        tracing::warn!("Failed to hide dropdown: {}", e);
+                    }
+                });
+            }
+        });
+    }
+
+    Ok(window)
+}
+
+// The monitor under the mouse, so the dropdown opens where the user is looking
+fn active_monitor(app: &AppHandle) -> Result<Monitor, String> {
+    let monitor = match app.cursor_position() {
+        Ok(cursor) => app.monitor_from_point(cursor.x, cursor.y),
+        Err(_) => app.primary_monitor(),
+    };
+    monitor
+        .ok()
+        .flatten()
+        .or_else(|| app.primary_monitor().ok().flatten())
+        .ok_or_else(|| "No monitor found for the dropdown".to_string())
+}
+
+// Move the window between hidden above the monitor's top edge and flush with it
+fn slide(window: &WebviewWindow, top: i32, height: u32, duration: Duration, showing: bool) {
+    let steps = if duration.is_zero() {
+        1
+    } else {
+        ANIMATION_STEPS
+    };
+    let Ok(position) = window.outer_position() else {
+        return;
+    };
+    for step in 1..=steps {
+        let progress = f64::from(step) / f64::from(steps);
+        let hidden = if showing { 1.0 - progress } else { progress };
+        let y = top - (f64::from(height) * hidden) as i32;
+        if let Err(e) = window.set_position(PhysicalPosition::new(position.x, y)) {
+            tracing::warn!("Failed to move dropdown: {}", e);
+            return;
+        }
+        thread::sleep(duration / steps);
+    }
+}
+
+fn animation(dropdown: &DropdownConfig) -> Duration {
+    Duration::from_millis(dropdown.animation_ms.unwrap_or(DEFAULT_ANIMATION_MS))
+}
+
+fn show(app: &AppHandle, dropdown: &DropdownConfig) -> Result<(), String> {
+    let window = get_or_create(app, dropdown)?;
+    let monitor = active_monitor(app)?;
+    let height_percent = dropdown.height_percent.unwrap_or(DEFAULT_HEIGHT_PERCENT);
+    let height = monitor.size().height * u32::from(height_percent) / 100;
+    let top = monitor.position().y;
+
+    // Start fully above the screen and slide down into place
+    window
+        .set_size(PhysicalSize::new(monitor.size().width, height))
+        .and_then(|_| {
+            window.set_position(PhysicalPosition::new(
+                monitor.position().x,
+                top - height as i32,
+            ))
+        })
+        .and_then(|_| window.show())
+        .map_err(|e| format!("Failed to show dropdown: {}", e))?;
+    slide(&window, top, height, animation(dropdown), true);
+    window
+        .set_focus()
+        .map_err(|e| format!("Failed to focus dropdown: {}", e))
+}
+
+fn hide(app: &AppHandle) -> Result<(), String> {
+    let Some(window) = app.get_webview_window(DROPDOWN_LABEL) else {
+        return Ok(());
+    };
+    if !window.is_visible().unwrap_or(false) {
+        return Ok(());
+    }
+
+    let config = Config::load(app)?;
+    let dropdown = config.dropdown.unwrap_or_default();
+    if let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) {
+        slide(
+            &window,
+            position.y,
+            size.height,
+            animation(&dropdown),
+            false,
+        );
+    }
+    window
+        .hide()
+        .map_err(|e| format!("Failed to hide dropdown: {}", e))
+}
+
+/// Slide the dropdown in, or out if it is already showing and focused
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    let config = Config::load(app)?;
+    let Some(dropdown) = config.dropdown.filter(|d| d.enabled) else {
+        return Ok(());
+    };
+
+    match app.get_webview_window(DROPDOWN_LABEL) {
+        Some(window)
+            if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) =>
+        {
+            hide(app)
+        }
+        // Visible behind another window, bring it back to the front
+        Some(window) if window.is_visible().unwrap_or(false) => window
+            .set_focus()
+            .map_err(|e| format!("Failed to focus dropdown: {}", e)),
+        _ => show(app, &dropdown),
+    }
+}
+
+// Show or hide the dropdown, for a tray menu or a keyboard shortcut
+#[tauri::command]
+pub async fn toggle_dropdown(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || toggle(&app))
+        .await
+        .map_err(|e| format!("Failed to toggle dropdown: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accelerator() {
+        let hotkey = Shortcut {
+            key: "`".into(),
+            ctrl: true,
+            shift: true,
+            ..Default::default()
+        };
+        assert_eq!(accelerator(&hotkey), "ctrl+shift+`");
+        assert!(accelerator(&hotkey).parse::<Hotkey>().is_ok());
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::config::{Config, DynamicProfilesConfig, Profile, Profiles};
+use crate::crash;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty;
+
+/// How long a provider may take to list its profiles
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Profiles generated by the providers, as of the last refresh
+#[derive(Default)]
+pub struct DynamicProfilesState(Mutex<Vec<Profile>>);
+
+// Run a provider's command and capture its stdout, giving up after the timeout
+fn run(command: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(pty::expand_home(command))
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+
+    let mut stdout = child.stdout.take();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_end(&mut output);
+        }
+        let _ = tx.send(output);
+    });
+
+    match rx.recv_timeout(PROVIDER_TIMEOUT) {
+        Ok(output) => match child.wait() {
+            Ok(status) if status.success() => Ok(output),
+            Ok(status) => Err(format!("{} failed with {}", command, status)),
+            Err(e) => Err(format!("Failed to wait for {}: {}", command, e)),
+        },
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(format!("{} timed out", command))
+        }
+    }
+}
+
+// Non-empty output lines, for commands listing one name per line
+fn lines(output: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn profile(source: &str, name: String, command: &str, args: Vec<String>) -> Profile {
+    Profile {
+        name,
+        command: command.into(),
+        args: Some(args),
+        source: Some(source.into()),
+        ..Default::default()
+    }
+}
+
+// Shell the kube context profiles run
+fn user_shell() -> String {
+    if cfg!(windows) {
+        "powershell.exe".into()
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".into())
+    }
+}
+
+// `wsl.exe` writes UTF-16
+fn wsl_distros() -> Result<Vec<Profile>, String> {
+    let output = run("wsl.exe", &["--list", "--quiet"])?;
+    let units: Vec<u16> = output
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Ok(lines(String::from_utf16_lossy(&units).as_bytes())
+        .into_iter()
+        .map(|distro| {
+            profile(
+                "wsl",
+                format!("WSL: {}", distro),
+                "wsl.exe",
+                vec!["-d".into(), distro],
+            )
+        })
+        .collect())
+}
+
+/// Hosts named in an ssh config, without patterns like `*` or `!host`
+fn parse_ssh_hosts(config: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for line in config.lines() {
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        // `Host=name` is allowed too
+        let (keyword, first) = match keyword.split_once('=') {
+            Some((keyword, value)) => (keyword, Some(value)),
+            None => (keyword, None),
+        };
+        if !keyword.eq_ignore_ascii_case("host") {
+            continue;
+        }
+        for host in first.into_iter().chain(words) {
+            let host = host.trim_start_matches('=');
+            let is_pattern = host.is_empty() || host.contains(['*', '?', '!']);
+            if !is_pattern && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+    }
+    hosts
+}
+
+fn ssh_hosts() -> Result<Vec<Profile>, String> {
+    let path = pty::expand_home("~/.ssh/config");
+    let config =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(parse_ssh_hosts(&config)
+        .into_iter()
+        .map(|host| profile("ssh", format!("SSH: {}", host), "ssh", vec![host]))
+        .collect())
+}
+
+fn docker_containers() -> Result<Vec<Profile>, String> {
+    let output = run("docker", &["ps", "--format", "{{.Names}}"])?;
+    Ok(lines(&output)
+        .into_iter()
+        .map(|name| {
+            profile(
+                "docker",
+                format!("Docker: {}", name),
+                "docker",
+                vec!["exec".into(), "-it".into(), name, "sh".into()],
+            )
+        })
+        .collect())
+}
+
+// A shell whose kubectl uses the context, through a kubeconfig in `dir` put
+// before the user's that only sets the current context. Switching contexts
+// in that shell changes this file rather than the user's kubeconfig.
+fn kube_contexts(dir: &Path) -> Result<Vec<Profile>, String> {
+    let output = run("kubectl", &["config", "get-contexts", "-o", "name"])?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let user_config = std::env::var_os("KUBECONFIG")
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| pty::expand_home("~/.kube/config").into());
+    let shell = user_shell();
+    lines(&output)
+        .into_iter()
+        .map(|context| {
+            let file_name: String = context
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let path = dir.join(format!("{}.yaml", file_name));
+            // JSON strings are YAML too, and quote any context name
+            let contents = format!(
+                "current-context: {}\n",
+                serde_json::to_string(&context).unwrap_or_default()
+            );
+            fs::write(&path, contents)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            let kubeconfig = std::env::join_paths(
+                std::iter::once(path).chain(std::env::split_paths(&user_config)),
+            )
+            .map_err(|e| format!("Invalid KUBECONFIG: {}", e))?;
+            Ok(Profile {
+                env: Some(HashMap::from([(
+                    "KUBECONFIG".to_string(),
+                    kubeconfig.to_string_lossy().into_owned(),
+                )])),
+                ..profile("kube", format!("Kube: {}", context), &shell, Vec::new())
+            })
+        })
+        .collect()
+}
+
+// Profiles printed by the configured command, as a JSON array laid out like
+// `profiles.list`
+fn command_profiles(command: &str, args: &[String]) -> Result<Vec<Profile>, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run(command, &args)?;
+    let profiles: Vec<Profile> = serde_json::from_slice(&output)
+        .map_err(|e| format!("{} printed invalid profiles: {}", command, e))?;
+    Ok(profiles
+        .into_iter()
+        .map(|profile| Profile {
+            source: Some("command".into()),
+            ..profile
+        })
+        .collect())
+}
+
+/// Ask every enabled provider for its profiles. A provider that fails only
+/// leaves out its own profiles.
+fn generate(app: &AppHandle, config: &DynamicProfilesConfig) -> Vec<Profile> {
+    let mut results = Vec::new();
+    if config.wsl.unwrap_or(false) && cfg!(windows) {
+        results.push(("wsl", wsl_distros()));
+    }
+    if config.ssh_hosts.unwrap_or(false) {
+        results.push(("ssh", ssh_hosts()));
+    }
+    if config.docker.unwrap_or(false) {
+        results.push(("docker", docker_containers()));
+    }
+    if config.kube_contexts.unwrap_or(false) {
+        let dir = app
+            .path()
+            .app_cache_dir()
+            .map_err(|e| format!("Failed to get cache directory: {}", e))
+            .map(|dir| dir.join("kube"));
+        results.push(("kube", dir.and_then(|dir| kube_contexts(&dir))));
+    }
+    if let Some(command) = &config.command {
+        let args = config.args.as_deref().unwrap_or_default();
+        results.push(("command", command_profiles(command, args)));
+    }
+
+    let mut profiles = Vec::new();
+    for (provider, result) in results {
+        match result {
+            Ok(generated) => profiles.extend(generated),
+            Err(e) => tracing::warn!("Profile provider {} failed: {}", provider, e),
+        }
+    }
+    profiles
+}
+
+fn generated(app: &AppHandle) -> Vec<Profile> {
+    app.state::<DynamicProfilesState>()
+        .0
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+/// Add the generated profiles to a loaded config, after the static ones.
+/// Profiles named like a static one are left out.
+pub fn merge(app: &AppHandle, config: &mut Config) {
+    let generated = generated(app);
+    if generated.is_empty() {
+        return;
+    }
+    // Without static profiles the first tab still uses the default shell
+    let profiles = config.profiles.get_or_insert_with(|| Profiles {
+        default: String::new(),
+        list: Vec::new(),
+    });
+    for profile in generated {
+        if profiles.find(&profile.name).is_none() {
+            profiles.list.push(profile);
+        }
+    }
+}
+
+/// Drop the generated profiles `get_config` added from a config about to be
+/// saved. Profiles in the config file are kept even when a provider
+/// generated one of the same name.
+pub fn strip(app: &AppHandle, config: &mut Config) {
+    let saved = Config::load(app).ok().and_then(|saved| saved.profiles);
+    let Some(profiles) = config.profiles.as_mut() else {
+        return;
+    };
+    let generated = generated(app);
+    profiles.list.retain(|profile| {
+        !generated.iter().any(|g| g.name == profile.name)
+            || saved
+                .as_ref()
+                .is_some_and(|saved| saved.find(&profile.name).is_some())
+    });
+    if profiles.list.is_empty() && saved.is_none() {
+        config.profiles = None;
+    }
+}
+
+fn refresh(app: &AppHandle) -> CommandResult<Vec<Profile>> {
+    let config = Config::load(app)?;
+    let profiles = config
+        .dynamic_profiles
+        .as_ref()
+        .map(|dynamic| generate(app, dynamic))
+        .unwrap_or_default();
+    *app.state::<DynamicProfilesState>().0.lock().unwrap() = profiles.clone();
+    Ok(profiles)
+}
+
+/// Generate the profiles once at startup, without holding it up
+pub fn refresh_in_background(app: &AppHandle) {
+    let app = app.clone();
+    crash::spawn("dynamic-profiles", move || {
+        if let Err(e) = refresh(&app) {
+            tracing::warn!("Failed to generate profiles: {}", e);
+        }
+    });
+}
+
+// Ask the providers for their profiles again, e.g. after starting a
+// container. Returns the generated profiles, `get_config` includes them.
+#[tauri::command]
+pub async fn refresh_dynamic_profiles(app: AppHandle) -> CommandResult<Vec<Profile>> {
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| {
+            TermillionError::new(
+                ErrorKind::Internal,
+                format!("Failed to generate profiles: {}", e),
+            )
+        })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_hosts() {
+        let config = "\
+Host *
+    ServerAliveInterval 60
+Host prod-db bastion
+    HostName 10.0.0.5
+host=staging
+Host !internal *.corp
+Host bastion
+";
+        assert_eq!(parse_ssh_hosts(config), ["prod-db", "bastion", "staging"]);
+    }
+}
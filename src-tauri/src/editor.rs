@@ -0,0 +1,279 @@
+use serde::Serialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+use crate::config::{Config, EditorConfig};
+use crate::pty;
+use crate::session::SessionTab;
+use crate::window;
+
+/// Editors looked for on the PATH when neither the config nor `$VISUAL` or
+/// `$EDITOR` name one, in order of preference
+const KNOWN_EDITORS: [&str; 17] = [
+    "code",
+    "cursor",
+    "codium",
+    "zed",
+    "subl",
+    "idea",
+    "pycharm",
+    "webstorm",
+    "goland",
+    "clion",
+    "rustrover",
+    "phpstorm",
+    "rider",
+    "nvim",
+    "vim",
+    "hx",
+    "nano",
+];
+
+/// How an editor takes the line and column to open a file at
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditorKind {
+    /// `-g file:line:column`
+    VsCode,
+    /// `file:line:column`
+    Colon,
+    /// `--line line --column column file`
+    JetBrains,
+    /// `+call cursor(line,column) file`
+    Vim,
+    /// `+line:column file`
+    Emacs,
+    /// `+line,column file`
+    Nano,
+    /// Just the file
+    Other,
+}
+
+impl EditorKind {
+    fn from_program(program: &str) -> Self {
+        // Split by hand, Windows paths have to work on every platform
+        let file = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let name = file.split('.').next().unwrap_or(file).to_lowercase();
+        match name.as_str() {
+            "code" | "code-insiders" | "codium" | "cursor" | "windsurf" => Self::VsCode,
+            "subl" | "sublime_text" | "zed" | "hx" | "helix" => Self::Colon,
+            "idea" | "idea64" | "pycharm" | "pycharm64" | "webstorm" | "webstorm64" | "goland"
+            | "goland64" | "clion" | "clion64" | "rustrover" | "rustrover64" | "phpstorm"
+            | "phpstorm64" | "rider" | "rider64" | "rubymine" | "rubymine64" => Self::JetBrains,
+            "vi" | "vim" | "nvim" | "gvim" | "mvim" => Self::Vim,
+            "emacs" | "emacsclient" | "micro" => Self::Emacs,
+            "nano" => Self::Nano,
+            _ => Self::Other,
+        }
+    }
+
+    fn args(self, file: &str, line: Option<u32>, column: Option<u32>) -> Vec<String> {
+        let Some(line) = line else {
+            return vec![file.to_string()];
+        };
+        let column = column.unwrap_or(1);
+        match self {
+            Self::VsCode => vec!["-g".into(), format!("{}:{}:{}", file, line, column)],
+            Self::Colon => vec![format!("{}:{}:{}", file, line, column)],
+            Self::JetBrains => vec![
+                "--line".into(),
+                line.to_string(),
+                "--column".into(),
+                column.to_string(),
+                file.into(),
+            ],
+            Self::Vim => vec![format!("+call cursor({},{})", line, column), file.into()],
+            Self::Emacs => vec![format!("+{}:{}", line, column), file.into()],
+            Self::Nano => vec![format!("+{},{}", line, column), file.into()],
+            Self::Other => vec![file.into()],
+        }
+    }
+}
+
+// Editors that draw in a terminal and so get a tab of their own
+fn runs_in_terminal(program: &str) -> bool {
+    let file = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    let name = file.split('.').next().unwrap_or(file).to_lowercase();
+    matches!(
+        name.as_str(),
+        "vi" | "vim" | "nvim" | "nano" | "micro" | "hx" | "helix" | "kak" | "emacsclient"
+    )
+}
+
+/// Program and arguments that open a file in an editor
+#[derive(Debug, PartialEq)]
+pub struct EditorInvocation {
+    pub program: String,
+    pub args: Vec<String>,
+    pub terminal: bool,
+}
+
+// Fill in `{file}`, `{line}` and `{column}` in configured arguments
+fn expand_args(args: &[String], file: &str, line: Option<u32>, column: Option<u32>) -> Vec<String> {
+    let line = line.unwrap_or(1).to_string();
+    let column = column.unwrap_or(1).to_string();
+    args.iter()
+        .map(|arg| {
+            arg.replace("{file}", file)
+                .replace("{line}", &line)
+                .replace("{column}", &column)
+        })
+        .collect()
+}
+
+/// Build the invocation for an editor command line like `code --wait`. Extra
+/// arguments in the command are kept in front of the file.
+fn invocation(
+    command: &str,
+    config: Option<&EditorConfig>,
+    file: &str,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Option<EditorInvocation> {
+    let mut words = command.split_whitespace().map(str::to_string);
+    let program = words.next()?;
+    let mut args: Vec<String> = words.collect();
+    match config.and_then(|c| c.args.as_ref()) {
+        Some(configured) => args.extend(expand_args(configured, file, line, column)),
+        None => args.extend(EditorKind::from_program(&program).args(file, line, column)),
+    }
+    Some(EditorInvocation {
+        terminal: config
+            .and_then(|c| c.terminal)
+            .unwrap_or_else(|| runs_in_terminal(&program)),
+        program,
+        args,
+    })
+}
+
+/// Editors from the known list that are installed
+fn installed_editors() -> Vec<String> {
+    KNOWN_EDITORS
+        .iter()
+        .filter(|editor| pty::find_executable(editor).is_some())
+        .map(|editor| editor.to_string())
+        .collect()
+}
+
+// The configured editor, then `$VISUAL` and `$EDITOR`, then the first
+// installed known editor
+fn editor_command(config: Option<&EditorConfig>) -> Option<String> {
+    config
+        .and_then(|c| c.command.clone())
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|command| !command.trim().is_empty())
+        .or_else(|| installed_editors().into_iter().next())
+}
+
+/// What `open_in_editor` started
+#[derive(Debug, Serialize)]
+pub struct EditorLaunch {
+    pub editor: String,
+    /// Whether the editor was opened in a new tab
+    pub terminal: bool,
+}
+
+// Open a file at a line and column, e.g. a path from a compiler error.
+// Relative paths are resolved against the directory of the given PTY.
+#[tauri::command]
+pub async fn open_in_editor(
+    app: AppHandle,
+    path: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    pty_id: Option<String>,
+) -> Result<EditorLaunch, String> {
+    let config = Config::load(&app)?;
+    let editor_config = config.editor.as_ref();
+
+    let mut file = Path::new(&pty::expand_home(&path)).to_path_buf();
+    if file.is_relative() {
+        if let Some(cwd) = pty_id.as_deref().and_then(pty::pty_cwd) {
+            file = Path::new(&cwd).join(file);
+        }
+    }
+    let file = file.to_string_lossy().into_owned();
+
+    let command = editor_command(editor_config)
+        .ok_or_else(|| "No editor found, set editor.command or $EDITOR".to_string())?;
+    let invocation = invocation(&command, editor_config, &file, line, column)
+        .ok_or_else(|| "The editor command is empty".to_string())?;
+
+    if invocation.terminal {
+        let window = window::target_window(&app)
+            .ok_or_else(|| "No window to open the editor in".to_string())?;
+        let tab = SessionTab {
+            profile: None,
+            cwd: Path::new(&file)
+                .parent()
+                .map(|dir| dir.to_string_lossy().into_owned()),
+            title: None,
+            command: Some(invocation.program.clone()),
+            args: Some(invocation.args),
+            pty_id: None,
+            task: None,
+            decoration: None,
+        };
+        app.emit_to(window.label(), "open-session-tab", tab)
+            .map_err(|e| format!("Failed to open tab: {}", e))?;
+    } else {
+        let mut child = Command::new(pty::expand_home(&invocation.program))
+            .args(&invocation.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", invocation.program, e))?;
+        // Reap the editor's launcher once it exits
+        thread::spawn(move || child.wait());
+    }
+
+    Ok(EditorLaunch {
+        editor: invocation.program,
+        terminal: invocation.terminal,
+    })
+}
+
+// Known editors installed on this machine
+#[tauri::command]
+pub async fn detect_editors() -> Result<Vec<String>, String> {
+    Ok(installed_editors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_invocation() {
+        let open = |command: &str| invocation(command, None, "src/main.rs", Some(12), Some(5));
+
+        let code = open("code --wait").unwrap();
+        assert_eq!(code.args, ["--wait", "-g", "src/main.rs:12:5"]);
+        assert!(!code.terminal);
+
+        let nvim = open("/usr/bin/nvim").unwrap();
+        assert_eq!(nvim.args, ["+call cursor(12,5)", "src/main.rs"]);
+        assert!(nvim.terminal);
+
+        let idea = open(r"C:\Tools\idea64.exe").unwrap();
+        assert_eq!(idea.args, ["--line", "12", "--column", "5", "src/main.rs"]);
+
+        assert_eq!(
+            invocation("subl", None, "a.rs", None, None).unwrap().args,
+            ["a.rs"]
+        );
+        assert!(open("  ").is_none());
+
+        // Configured arguments replace the detected ones
+        let config = EditorConfig {
+            command: Some("kate".into()),
+            args: Some(vec!["--line".into(), "{line}".into(), "{file}".into()]),
+            terminal: None,
+        };
+        let kate = invocation("kate", Some(&config), "a.rs", Some(3), None).unwrap();
+        assert_eq!(kate.args, ["--line", "3", "a.rs"]);
+    }
+}
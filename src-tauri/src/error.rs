@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+
+/// What went wrong, so the frontend can react without matching messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A PTY, window or file that doesn't exist (anymore)
+    NotFound,
+    PermissionDenied,
+    /// Arguments the command can't work with
+    InvalidInput,
+    /// The config file couldn't be read as a config
+    ConfigParse,
+    Io,
+    /// Starting or talking to a shell failed
+    Pty,
+    Internal,
+}
+
+impl ErrorKind {
+    // Whether the user can fix it, e.g. by editing the config or retrying
+    fn recoverable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::InvalidInput | ErrorKind::ConfigParse | ErrorKind::Io
+        )
+    }
+}
+
+/// Error returned by commands, serialized as
+/// `{ kind, message, context, recoverable }`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TermillionError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// What the error is about, like a PTY ID or a file path
+    pub context: Option<String>,
+    pub recoverable: bool,
+}
+
+pub type CommandResult<T> = Result<T, TermillionError>;
+
+impl TermillionError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            context: None,
+            recoverable: kind.recoverable(),
+        }
+    }
+
+    pub fn with_context(mut self, context: impl fmt::Display) -> Self {
+        self.context = Some(context.to_string());
+        self
+    }
+
+    pub fn pty_not_found(pty_id: &str) -> Self {
+        Self::new(
+            ErrorKind::NotFound,
+            format!("PTY with ID {} not found", pty_id),
+        )
+        .with_context(pty_id)
+    }
+
+    /// Errors from the PTY library, which doesn't say more than the message
+    pub fn pty(action: &str, e: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Pty, format!("{}: {}", action, e))
+    }
+
+    /// I/O errors keep missing files and denied access apart
+    pub fn io(action: &str, e: io::Error) -> Self {
+        let kind = match e.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Io,
+        };
+        Self::new(kind, format!("{}: {}", action, e))
+    }
+}
+
+impl fmt::Display for TermillionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TermillionError {}
+
+// Helpers that still fail with a message
+impl From<String> for TermillionError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+}
+
+// Commands that still fail with a message can use `?` on the typed ones
+impl From<TermillionError> for String {
+    fn from(e: TermillionError) -> Self {
+        e.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kinds() {
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let error = TermillionError::io("Failed to read config file", denied)
+            .with_context("/etc/termillion.toml");
+        assert_eq!(error.kind, ErrorKind::PermissionDenied);
+        assert!(!error.recoverable);
+        assert_eq!(error.message, "Failed to read config file: denied");
+
+        let json = serde_json::to_value(TermillionError::pty_not_found("1")).unwrap();
+        assert_eq!(json["kind"], "not_found");
+        assert_eq!(json["context"], "1");
+        assert_eq!(json["recoverable"], false);
+
+        let parse = TermillionError::new(ErrorKind::ConfigParse, "bad");
+        assert!(parse.recoverable);
+        assert_eq!(String::from(parse), "bad");
+    }
+}
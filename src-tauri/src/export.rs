@@ -0,0 +1,371 @@
+use serde::Deserialize;
+use std::fmt::Write;
+use tauri::AppHandle;
+
+use crate::config::{Config, ThemeConfig};
+use crate::pty;
+
+/// xterm's default colors for ANSI 0-15, used where the theme has none
+const DEFAULT_PALETTE: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TermColor {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Style {
+    foreground: Option<TermColor>,
+    background: Option<TermColor>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+    strikethrough: bool,
+}
+
+/// Runs of text sharing a style
+type Line = Vec<(Style, String)>;
+
+/// Lines of the export, 0-based with an exclusive end
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+// `38;5;n` and `38;2;r;g;b`, returning the color and the parameters used
+fn extended_color(params: &[u16]) -> Option<(TermColor, usize)> {
+    match params {
+        [5, index, ..] => Some((TermColor::Indexed(*index as u8), 2)),
+        [2, r, g, b, ..] => Some((TermColor::Rgb(*r as u8, *g as u8, *b as u8), 4)),
+        _ => None,
+    }
+}
+
+impl Style {
+    fn apply_sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strikethrough = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strikethrough = false,
+                code @ 30..=37 => self.foreground = Some(TermColor::Indexed(code as u8 - 30)),
+                code @ 90..=97 => self.foreground = Some(TermColor::Indexed(code as u8 - 82)),
+                code @ 40..=47 => self.background = Some(TermColor::Indexed(code as u8 - 40)),
+                code @ 100..=107 => self.background = Some(TermColor::Indexed(code as u8 - 92)),
+                39 => self.foreground = None,
+                49 => self.background = None,
+                code @ (38 | 48) => {
+                    if let Some((color, used)) = extended_color(&params[i + 1..]) {
+                        if code == 38 {
+                            self.foreground = Some(color);
+                        } else {
+                            self.background = Some(color);
+                        }
+                        i += used;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+fn push_char(line: &mut Line, style: Style, c: char) {
+    match line.last_mut() {
+        Some((last, text)) if *last == style => text.push(c),
+        _ => line.push((style, c.to_string())),
+    }
+}
+
+/// Split raw output into styled lines. Only colors and text attributes are
+/// kept, a carriage return without a newline starts the line over, like a
+/// progress bar redrawing itself.
+fn parse(output: &str) -> Vec<Line> {
+    let mut lines = vec![Line::new()];
+    let mut style = Style::default();
+    let mut chars = output.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut sequence = String::new();
+                    let mut last = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            last = Some(c);
+                            break;
+                        }
+                        sequence.push(c);
+                    }
+                    if last == Some('m') {
+                        let params: Vec<u16> = sequence
+                            .split([';', ':'])
+                            .map(|p| p.parse().unwrap_or(0))
+                            .collect();
+                        let params = if sequence.is_empty() { vec![] } else { params };
+                        style.apply_sgr(&params);
+                    }
+                }
+                // Titles, directories and other OSC sequences aren't shown
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // Character set selection takes one more character
+                Some('(' | ')' | '*' | '+') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' if chars.peek() != Some(&'\n') => lines.last_mut().unwrap().clear(),
+            '\n' => lines.push(Line::new()),
+            '\x08' => {
+                let line = lines.last_mut().unwrap();
+                if let Some((_, text)) = line.last_mut() {
+                    text.pop();
+                    if text.is_empty() {
+                        line.pop();
+                    }
+                }
+            }
+            '\t' => push_char(lines.last_mut().unwrap(), style, c),
+            c if c.is_control() => {}
+            c => push_char(lines.last_mut().unwrap(), style, c),
+        }
+    }
+    while lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+fn palette(theme: &ThemeConfig) -> [String; 16] {
+    let configured = [
+        &theme.black,
+        &theme.red,
+        &theme.green,
+        &theme.yellow,
+        &theme.blue,
+        &theme.magenta,
+        &theme.cyan,
+        &theme.white,
+        &theme.bright_black,
+        &theme.bright_red,
+        &theme.bright_green,
+        &theme.bright_yellow,
+        &theme.bright_blue,
+        &theme.bright_magenta,
+        &theme.bright_cyan,
+        &theme.bright_white,
+    ];
+    std::array::from_fn(|i| {
+        configured[i]
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PALETTE[i].to_string())
+    })
+}
+
+fn css_color(color: TermColor, palette: &[String; 16]) -> String {
+    match color {
+        TermColor::Indexed(index @ 0..=15) => palette[index as usize].clone(),
+        // 6x6x6 color cube
+        TermColor::Indexed(index @ 16..=231) => {
+            let index = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(index / 36),
+                level(index / 6 % 6),
+                level(index % 6)
+            )
+        }
+        TermColor::Indexed(index) => {
+            let gray = 8 + (index - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+        TermColor::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn span_css(style: &Style, theme: &ThemeConfig, palette: &[String; 16]) -> String {
+    let mut foreground = style.foreground.map(|c| {
+        // Bold text in the first 8 colors is shown bright, like most terminals
+        match c {
+            TermColor::Indexed(index @ 0..=7) if style.bold => TermColor::Indexed(index + 8),
+            c => c,
+        }
+    });
+    let mut background = style.background;
+    let mut css = String::new();
+    if style.inverse {
+        std::mem::swap(&mut foreground, &mut background);
+        let fg = foreground.map_or(theme.background.clone(), |c| css_color(c, palette));
+        let bg = background.map_or(theme.foreground.clone(), |c| css_color(c, palette));
+        let _ = write!(css, "color:{};background:{};", fg, bg);
+    } else {
+        if let Some(fg) = foreground {
+            let _ = write!(css, "color:{};", css_color(fg, palette));
+        }
+        if let Some(bg) = background {
+            let _ = write!(css, "background:{};", css_color(bg, palette));
+        }
+    }
+    if style.bold {
+        css.push_str("font-weight:bold;");
+    }
+    if style.dim {
+        css.push_str("opacity:0.6;");
+    }
+    if style.italic {
+        css.push_str("font-style:italic;");
+    }
+    match (style.underline, style.strikethrough) {
+        (true, true) => css.push_str("text-decoration:underline line-through;"),
+        (true, false) => css.push_str("text-decoration:underline;"),
+        (false, true) => css.push_str("text-decoration:line-through;"),
+        (false, false) => {}
+    }
+    css
+}
+
+/// Standalone HTML page showing the lines with the theme's colors
+fn render_html(lines: &[Line], config: &Config, title: &str) -> String {
+    let theme = &config.theme;
+    let palette = palette(theme);
+    let mut body = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 {
+            body.push('\n');
+        }
+        for (style, text) in line {
+            let css = span_css(style, theme, &palette);
+            if css.is_empty() {
+                escape_html(text, &mut body);
+            } else {
+                let _ = write!(body, "<span style=\"{}\">", css);
+                escape_html(text, &mut body);
+                body.push_str("</span>");
+            }
+        }
+    }
+
+    let mut font = String::new();
+    escape_html(&config.font.family, &mut font);
+    let mut escaped_title = String::new();
+    escape_html(title, &mut escaped_title);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\nbody {{ margin: 0; background: {bg}; }}\npre {{ margin: 0; padding: 16px; color: {fg}; background: {bg}; font-family: {font}, monospace; font-size: {size}px; line-height: {line_height}; white-space: pre-wrap; }}\n</style>\n</head>\n<body>\n<pre>{body}</pre>\n</body>\n</html>\n",
+        title = escaped_title,
+        bg = theme.background,
+        fg = theme.foreground,
+        font = font,
+        size = config.font.size,
+        line_height = config.font.line_height,
+        body = body,
+    )
+}
+
+/// Most output of a terminal exported at once, the latest is kept
+const MAX_EXPORT_BYTES: u64 = 32 * 1024 * 1024;
+
+// Turn a terminal's output into an HTML page with its colors, for sharing
+// build logs. Without a range all kept output is exported, up to the last
+// `MAX_EXPORT_BYTES`.
+#[tauri::command]
+pub async fn export_pty_buffer_html(
+    app: AppHandle,
+    pty_id: String,
+    range: Option<LineRange>,
+) -> Result<String, String> {
+    let output = {
+        let pty_id = pty_id.clone();
+        tauri::async_runtime::spawn_blocking(move || pty::pty_scrollback(&pty_id, MAX_EXPORT_BYTES))
+            .await
+            .map_err(|e| format!("Failed to read scrollback: {}", e))?
+    }
+    .ok_or_else(|| format!("PTY with ID {} not found", pty_id))?;
+    let config = Config::load(&app)?;
+
+    let mut lines = parse(&String::from_utf8_lossy(&output));
+    if let Some(range) = range {
+        let end = range.end.unwrap_or(lines.len()).min(lines.len());
+        let start = range.start.min(end);
+        lines = lines.drain(start..end).collect();
+    }
+    let title = pty::pty_shell(&pty_id).unwrap_or_else(|| "Terminal".to_string());
+    Ok(render_html(&lines, &config, &title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_styled_output() {
+        let lines = parse(
+            "a \x1b[1;31mred\x1b[0m <b>\r\n\x1b]0;title\x0750%\r100%\n\x1b[38;2;1;2;3mrgb\n\n",
+        );
+        assert_eq!(lines.len(), 3);
+
+        let red = Style {
+            foreground: Some(TermColor::Indexed(1)),
+            bold: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines[0],
+            [
+                (Style::default(), "a ".to_string()),
+                (red, "red".to_string()),
+                (Style::default(), " <b>".to_string()),
+            ]
+        );
+        // The progress line only keeps what was drawn last
+        assert_eq!(lines[1], [(Style::default(), "100%".to_string())]);
+        assert_eq!(lines[2][0].0.foreground, Some(TermColor::Rgb(1, 2, 3)));
+
+        let palette = palette(&Config::default().theme);
+        let mut html = String::new();
+        escape_html("<b>", &mut html);
+        assert_eq!(html, "&lt;b&gt;");
+        assert_eq!(css_color(TermColor::Indexed(196), &palette), "#ff0000");
+        assert_eq!(css_color(TermColor::Indexed(232), &palette), "#080808");
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+use tauri::{Manager, Window};
+
+use crate::pty;
+use crate::session;
+
+/// How dropped paths are written for the shell in a PTY
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropTarget {
+    Posix,
+    /// A POSIX shell inside WSL, which sees Windows drives under /mnt
+    Wsl,
+    PowerShell,
+    Cmd,
+}
+
+impl DropTarget {
+    /// Pick the quoting from the program a PTY runs, POSIX unless it's a
+    /// Windows shell
+    pub fn from_shell(shell: &str) -> Self {
+        // Split by hand, Windows paths have to work on every platform
+        let file = shell.rsplit(['/', '\\']).next().unwrap_or(shell);
+        let name = file.split('.').next().unwrap_or(file).to_lowercase();
+        match name.as_str() {
+            "pwsh" | "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            "wsl" => Self::Wsl,
+            _ => Self::Posix,
+        }
+    }
+}
+
+// `C:\Users\me` becomes `/mnt/c/Users/me`, `\\wsl$\Ubuntu\home\me` becomes
+// `/home/me`. Other paths are left alone.
+fn wsl_path(path: &str) -> String {
+    for prefix in ["\\\\wsl$\\", "\\\\wsl.localhost\\"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            // Skip the distribution name
+            let inner = rest.split_once('\\').map_or("", |(_, inner)| inner);
+            return format!("/{}", inner.replace('\\', "/"));
+        }
+    }
+    let mut chars = path.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(drive), Some(':'), Some('\\' | '/') | None) if drive.is_ascii_alphabetic() => {
+            let rest = path.get(3..).unwrap_or_default().replace('\\', "/");
+            format!("/mnt/{}/{}", drive.to_ascii_lowercase(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+// Paths the shell reads as one argument without quotes. Backslashes
+// escape in POSIX shells but are separators on Windows.
+fn is_plain(path: &str, target: DropTarget) -> bool {
+    let windows = matches!(target, DropTarget::PowerShell | DropTarget::Cmd);
+    path.chars().all(|c| {
+        c.is_alphanumeric()
+            || matches!(c, '/' | '.' | '_' | '-' | ':' | '+')
+            || (windows && c == '\\')
+    })
+}
+
+/// Quote a path so the shell reads it as a single argument
+pub fn quote(path: &str, target: DropTarget) -> String {
+    if is_plain(path, target) {
+        return path.to_string();
+    }
+    match target {
+        DropTarget::Posix | DropTarget::Wsl => format!("'{}'", path.replace('\'', "'\\''")),
+        DropTarget::PowerShell => format!("'{}'", path.replace('\'', "''")),
+        // Windows paths can't contain double quotes
+        DropTarget::Cmd => format!("\"{}\"", path),
+    }
+}
+
+/// The text typed for dropped paths: each quoted, separated and followed by
+/// a space so the next argument can follow
+pub fn format_paths(paths: &[PathBuf], target: DropTarget) -> String {
+    let mut text = paths
+        .iter()
+        .map(|path| {
+            let path = path.to_string_lossy();
+            match target {
+                DropTarget::Wsl => quote(&wsl_path(&path), target),
+                _ => quote(&path, target),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    text.push(' ');
+    text
+}
+
+/// Type the paths of files dropped on a window into its active terminal
+pub fn handle_drop(window: &Window, paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+    let Some(pty_id) = session::active_pty(window.app_handle(), window.label()) else {
+        return;
+    };
+    let shell = pty::pty_shell(&pty_id).unwrap_or_default();
+    let text = format_paths(paths, DropTarget::from_shell(&shell));
+    if let Err(e) = pty::write_to_pty(&pty_id, text.as_bytes()) {
+        tracing::warn!("Failed to write dropped paths: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_dropped_paths() {
+        let paths = [
+            PathBuf::from("/home/me/notes.txt"),
+            PathBuf::from("/home/me/it's here"),
+        ];
+        assert_eq!(
+            format_paths(&paths, DropTarget::Posix),
+            "/home/me/notes.txt '/home/me/it'\\''s here' "
+        );
+
+        let paths = [PathBuf::from("C:\\Users\\me\\My File's.txt")];
+        assert_eq!(
+            format_paths(&paths, DropTarget::PowerShell),
+            "'C:\\Users\\me\\My File''s.txt' "
+        );
+        assert_eq!(
+            format_paths(&paths, DropTarget::Cmd),
+            "\"C:\\Users\\me\\My File's.txt\" "
+        );
+        assert_eq!(
+            format_paths(&paths, DropTarget::Wsl),
+            "'/mnt/c/Users/me/My File'\\''s.txt' "
+        );
+
+        assert_eq!(wsl_path("\\\\wsl$\\Ubuntu\\home\\me"), "/home/me");
+        assert_eq!(
+            DropTarget::from_shell("C:\\Windows\\System32\\wsl.exe"),
+            DropTarget::Wsl
+        );
+        assert_eq!(DropTarget::from_shell("/bin/zsh"), DropTarget::Posix);
+    }
+}
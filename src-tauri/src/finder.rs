@@ -0,0 +1,216 @@
+use ignore::{WalkBuilder, WalkState};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+use crate::palette;
+use crate::pty;
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Files looked at before the search stops, so a picker opened in `/` still
+/// finishes
+const MAX_SCANNED: usize = 500_000;
+
+/// Matches found are sent at most this often
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Extra score for matches inside the file name, `main` should find
+/// `src/main.rs` before `domain/types.rs`
+const FILE_NAME_BONUS: i64 = 32;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMatch {
+    /// Path relative to the search root, with `/` separators
+    pub path: String,
+    pub score: i64,
+    /// Character positions in the path that matched the query
+    pub matches: Vec<usize>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum FileSearchEvent {
+    /// Matches found since the last batch, in no particular order
+    Matches(Vec<FileMatch>),
+    /// Best matches of the whole search, sent last
+    Done {
+        files: Vec<FileMatch>,
+        scanned: usize,
+        /// The search stopped at `MAX_SCANNED` files
+        truncated: bool,
+    },
+}
+
+/// Match the query against a relative path, preferring the file name
+pub fn score_path(query: &str, path: &str) -> Option<FileMatch> {
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    if let Some((score, matches)) = palette::fuzzy_match(query, &path[name_start..]) {
+        let offset = path[..name_start].chars().count();
+        return Some(FileMatch {
+            path: path.to_string(),
+            score: score + FILE_NAME_BONUS,
+            matches: matches.into_iter().map(|i| i + offset).collect(),
+        });
+    }
+    let (score, matches) = palette::fuzzy_match(query, path)?;
+    Some(FileMatch {
+        path: path.to_string(),
+        score,
+        matches,
+    })
+}
+
+/// Keep the `limit` best matches, best first
+fn keep_best(files: &mut Vec<FileMatch>, limit: usize) {
+    files.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.path.len().cmp(&b.path.len()))
+            .then(a.path.cmp(&b.path))
+    });
+    files.truncate(limit);
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?.to_string_lossy();
+    Some(if cfg!(windows) {
+        relative.replace('\\', "/")
+    } else {
+        relative.into_owned()
+    })
+}
+
+// Walk the tree on all cores, skipping what .gitignore and friends exclude
+fn walk(root: PathBuf, query: String, sender: mpsc::Sender<FileMatch>, scanned: Arc<AtomicUsize>) {
+    let stop = Arc::new(AtomicBool::new(false));
+    WalkBuilder::new(&root)
+        .hidden(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .require_git(false)
+        .build_parallel()
+        .run(|| {
+            let root = root.clone();
+            let query = query.clone();
+            let sender = sender.clone();
+            let scanned = scanned.clone();
+            let stop = stop.clone();
+            Box::new(move |entry| {
+                if stop.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
+                }
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    return WalkState::Continue;
+                }
+                if scanned.fetch_add(1, Ordering::Relaxed) >= MAX_SCANNED {
+                    stop.store(true, Ordering::Relaxed);
+                    return WalkState::Quit;
+                }
+                let found =
+                    relative_path(&root, entry.path()).and_then(|path| score_path(&query, &path));
+                if let Some(found) = found {
+                    if sender.send(found).is_err() {
+                        stop.store(true, Ordering::Relaxed);
+                        return WalkState::Quit;
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+}
+
+// Fuzzy find files under a directory for a Ctrl+P style picker. Matches are
+// streamed as they are found, the best ones are sent last with `done`.
+#[tauri::command]
+pub async fn find_files(
+    root: String,
+    query: String,
+    limit: Option<usize>,
+    on_event: Channel<FileSearchEvent>,
+) -> Result<(), String> {
+    let root = PathBuf::from(pty::expand_home(&root));
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let query = query.trim().to_string();
+
+    let (sender, receiver) = mpsc::channel();
+    let scanned = Arc::new(AtomicUsize::new(0));
+    let walker_scanned = scanned.clone();
+    thread::spawn(move || walk(root, query, sender, walker_scanned));
+
+    thread::spawn(move || {
+        let mut best = Vec::new();
+        let mut batch = Vec::new();
+        let mut last_sent = Instant::now();
+        // Ends once every walker thread dropped its sender
+        for found in receiver {
+            batch.push(found.clone());
+            best.push(found);
+            if best.len() > limit * 4 {
+                keep_best(&mut best, limit);
+            }
+            if last_sent.elapsed() >= BATCH_INTERVAL {
+                // Dropping the receiver stops the walk once the frontend
+                // stopped listening
+                if on_event
+                    .send(FileSearchEvent::Matches(std::mem::take(&mut batch)))
+                    .is_err()
+                {
+                    return;
+                }
+                last_sent = Instant::now();
+            }
+        }
+        if !batch.is_empty() {
+            let _ = on_event.send(FileSearchEvent::Matches(batch));
+        }
+        keep_best(&mut best, limit);
+        let scanned = scanned.load(Ordering::Relaxed);
+        let _ = on_event.send(FileSearchEvent::Done {
+            files: best,
+            scanned: scanned.min(MAX_SCANNED),
+            truncated: scanned > MAX_SCANNED,
+        });
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_ranking() {
+        let paths = [
+            "domain/types.rs",
+            "src/main.rs",
+            "docs/maintainers.md",
+            "README.md",
+        ];
+        let mut files: Vec<FileMatch> = paths
+            .iter()
+            .filter_map(|path| score_path("main", path))
+            .collect();
+        assert_eq!(files.len(), 3);
+        keep_best(&mut files, 2);
+        assert_eq!(files[0].path, "src/main.rs");
+        assert_eq!(files[0].matches, [4, 5, 6, 7]);
+        assert_eq!(files.len(), 2);
+
+        // Queries spanning directories match the whole path
+        let found = score_path("srcmain", "src/main.rs").unwrap();
+        assert_eq!(found.matches, [0, 1, 2, 4, 5, 6, 7]);
+        assert!(score_path("xyz", "src/main.rs").is_none());
+    }
+}
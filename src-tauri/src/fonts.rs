@@ -0,0 +1,68 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use ttf_parser::Tag;
+
+/// An installed monospace font family
+#[derive(Debug, Serialize, Clone)]
+pub struct MonospaceFont {
+    pub family: String,
+    /// Whether any face of the family has ligature features
+    pub ligatures: bool,
+}
+
+// Ligatures in coding fonts come from one of these GSUB features
+const LIGATURE_FEATURES: [&[u8; 4]; 3] = [b"calt", b"liga", b"dlig"];
+
+fn has_ligatures(data: &[u8], index: u32) -> bool {
+    ttf_parser::Face::parse(data, index)
+        .ok()
+        .and_then(|face| face.tables().gsub)
+        .is_some_and(|gsub| {
+            gsub.features.into_iter().any(|feature| {
+                LIGATURE_FEATURES
+                    .iter()
+                    .any(|tag| feature.tag == Tag::from_bytes(tag))
+            })
+        })
+}
+
+fn load_monospace_fonts() -> Vec<MonospaceFont> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let mut families: BTreeMap<String, bool> = BTreeMap::new();
+    for face in db.faces().filter(|face| face.monospaced) {
+        let Some((family, _)) = face.families.first() else {
+            continue;
+        };
+        let ligatures = db.with_face_data(face.id, has_ligatures).unwrap_or(false);
+        *families.entry(family.clone()).or_default() |= ligatures;
+    }
+
+    families
+        .into_iter()
+        .map(|(family, ligatures)| MonospaceFont { family, ligatures })
+        .collect()
+}
+
+/// Installed monospace fonts sorted by family name. Scanning the system
+/// fonts is slow, so it only happens once.
+pub fn monospace_fonts() -> &'static [MonospaceFont] {
+    static FONTS: OnceLock<Vec<MonospaceFont>> = OnceLock::new();
+    FONTS.get_or_init(load_monospace_fonts)
+}
+
+/// Whether a font family is installed, generic CSS families always are
+pub fn is_installed(family: &str) -> bool {
+    family == "monospace"
+        || monospace_fonts()
+            .iter()
+            .any(|font| font.family.eq_ignore_ascii_case(family))
+}
+
+// Installed monospace fonts for the font picker
+#[tauri::command]
+pub async fn list_monospace_fonts() -> Result<Vec<MonospaceFont>, String> {
+    Ok(monospace_fonts().to_vec())
+}
@@ -0,0 +1,360 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::palette;
+use crate::pty::{self, EscapeScanner};
+
+/// How often the shells' directories are checked, for shells that don't
+/// report them with OSC 7, and the database is written when it changed
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Sum of all ranks above which every rank is scaled down, so old
+/// favourites make way for new ones
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+
+/// Factor ranks are scaled by when they are aged
+const AGING_FACTOR: f64 = 0.9;
+
+/// Entries whose rank drops below this are forgotten
+const MIN_RANK: f64 = 1.0;
+
+const DEFAULT_LIMIT: usize = 20;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DirEntry {
+    rank: f64,
+    /// Seconds since the Unix epoch
+    last_visit: u64,
+}
+
+/// Visited directories ranked by how often and how recently they were
+/// visited, the way zoxide does it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DirectoryDb {
+    dirs: HashMap<String, DirEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// A directory offered to the user
+#[derive(Debug, Serialize)]
+pub struct RecentDir {
+    pub path: String,
+    pub score: f64,
+    /// Character positions in the path that matched the query
+    pub matches: Vec<usize>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl DirectoryDb {
+    pub fn visit(&mut self, path: &str, now: u64) {
+        let entry = self.dirs.entry(path.to_string()).or_insert(DirEntry {
+            rank: 0.0,
+            last_visit: now,
+        });
+        entry.rank += 1.0;
+        entry.last_visit = now;
+        self.dirty = true;
+
+        let total: f64 = self.dirs.values().map(|entry| entry.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            for entry in self.dirs.values_mut() {
+                entry.rank *= AGING_FACTOR;
+            }
+            self.dirs.retain(|_, entry| entry.rank >= MIN_RANK);
+        }
+    }
+
+    fn score(entry: &DirEntry, now: u64) -> f64 {
+        let age = now.saturating_sub(entry.last_visit);
+        let factor = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        entry.rank * factor
+    }
+
+    /// Directories fuzzy matching the query, highest frecency first
+    pub fn query(&self, query: &str, now: u64, limit: usize) -> Vec<RecentDir> {
+        let query = query.trim();
+        let mut results: Vec<RecentDir> = self
+            .dirs
+            .iter()
+            .filter_map(|(path, entry)| {
+                let (_, matches) = palette::fuzzy_match(query, path)?;
+                Some(RecentDir {
+                    path: path.clone(),
+                    score: Self::score(entry, now),
+                    matches,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+        results.truncate(limit);
+        results
+    }
+
+    fn remove(&mut self, path: &str) {
+        if self.dirs.remove(path).is_some() {
+            self.dirty = true;
+        }
+    }
+}
+
+/// Directory database of the running app, loaded on first use
+#[derive(Default)]
+pub struct FrecencyState {
+    db: Mutex<Option<DirectoryDb>>,
+    /// Last directory seen in each PTY, so only changes count as visits
+    last_seen: Mutex<HashMap<String, String>>,
+}
+
+fn db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "Failed to get data directory".to_string())?;
+    path.push("directories.json");
+    Ok(path)
+}
+
+fn with_db<T>(app: &AppHandle, f: impl FnOnce(&mut DirectoryDb) -> T) -> Result<T, String> {
+    let state = app.state::<FrecencyState>();
+    let mut db = state
+        .db
+        .lock()
+        .map_err(|e| format!("Failed to lock directory database: {}", e))?;
+    let db = db.get_or_insert_with(|| {
+        db_path(app)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    });
+    Ok(f(db))
+}
+
+/// Record that a PTY's shell is now in `path`. Repeated reports of the same
+/// directory count once.
+pub fn record(app: &AppHandle, pty_id: &str, path: &str) {
+    let Some(state) = app.try_state::<FrecencyState>() else {
+        return;
+    };
+    if let Ok(mut last_seen) = state.last_seen.lock() {
+        if last_seen.get(pty_id).is_some_and(|last| last == path) {
+            return;
+        }
+        last_seen.insert(pty_id.to_string(), path.to_string());
+    }
+    if let Err(e) = with_db(app, |db| db.visit(path, now())) {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// Write the database if it changed since it was last written
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    let content = with_db(app, |db| {
+        if !db.dirty {
+            return None;
+        }
+        db.dirty = false;
+        Some(serde_json::to_string(db))
+    })?;
+    let Some(content) = content else {
+        return Ok(());
+    };
+    let content = content.map_err(|e| format!("Failed to serialize directories: {}", e))?;
+    let path = db_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write directories: {}", e))
+}
+
+/// Poll the shells' directories and write the database periodically
+pub fn start_tracking(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let ids = pty::active_pty_ids();
+        for pty_id in &ids {
+            if let Some(cwd) = pty::pty_cwd(pty_id) {
+                record(&app, pty_id, &cwd);
+            }
+        }
+        // Forget the PTYs that are gone
+        if let Ok(mut last_seen) = app.state::<FrecencyState>().last_seen.lock() {
+            last_seen.retain(|pty_id, _| ids.contains(pty_id));
+        }
+        if let Err(e) = save(&app) {
+            tracing::warn!("{}", e);
+        }
+    });
+}
+
+/// Top directories for an empty query, used by the command palette
+pub fn top_dirs(app: &AppHandle, limit: usize) -> Vec<String> {
+    with_db(app, |db| db.query("", now(), limit))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dir| dir.path)
+        .collect()
+}
+
+/// Directory reported by an OSC 7 sequence, `file://host/path` with
+/// percent-encoded bytes
+pub fn parse_osc7(payload: &str) -> Option<String> {
+    let rest = payload.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+
+    // `/C:/Users` on Windows
+    if cfg!(windows) && path.get(2..3) == Some(":") {
+        return Some(path[1..].to_string());
+    }
+    Some(path)
+}
+
+/// Longest OSC payload kept, longer ones can't be a sensible path
+const MAX_OSC_LENGTH: usize = 4096;
+
+/// Finds the directories shells report with OSC 7 in PTY output
+pub struct Osc7Scanner {
+    escape: EscapeScanner,
+}
+
+impl Default for Osc7Scanner {
+    fn default() -> Self {
+        Self {
+            escape: EscapeScanner::new(MAX_OSC_LENGTH),
+        }
+    }
+}
+
+impl Osc7Scanner {
+    /// The last directory reported in `data`, if any
+    pub fn scan(&mut self, data: &[u8]) -> Option<String> {
+        let mut reported = None;
+        for &byte in data {
+            self.escape.advance(byte, |osc| {
+                if let Some(payload) = osc.strip_prefix(b"7;") {
+                    reported = parse_osc7(&String::from_utf8_lossy(payload)).or(reported.take());
+                }
+            });
+        }
+        reported
+    }
+}
+
+// Jump-to-directory suggestions from the directories visited in this
+// terminal, best first. Directories that no longer exist are forgotten.
+#[tauri::command]
+pub async fn query_recent_dirs(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<RecentDir>, String> {
+    with_db(&app, |db| {
+        let mut results = db.query(&query, now(), usize::MAX);
+        results.retain(|dir| {
+            let exists = Path::new(&dir.path).is_dir();
+            if !exists {
+                db.remove(&dir.path);
+            }
+            exists
+        });
+        results.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+        results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frecency_ranking() {
+        let now = 10 * WEEK;
+        let mut db = DirectoryDb::default();
+        // Visited often, but long ago
+        for _ in 0..4 {
+            db.visit("/home/me/old-project", now - 2 * WEEK);
+        }
+        db.visit("/home/me/termillion", now - 60);
+        db.visit("/tmp", now - 60);
+
+        let results = db.query("", now, 10);
+        assert_eq!(results[0].path, "/home/me/termillion");
+        assert_eq!(results[2].path, "/home/me/old-project");
+
+        let results = db.query("old", now, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "/home/me/old-project");
+
+        // Ranks are aged once they add up to too much
+        for _ in 0..MAX_TOTAL_RANK as usize {
+            db.visit("/home/me/termillion", now);
+        }
+        let total: f64 = db.dirs.values().map(|entry| entry.rank).sum();
+        assert!(total <= MAX_TOTAL_RANK);
+    }
+
+    #[test]
+    fn test_parse_osc7() {
+        assert_eq!(
+            parse_osc7("file://laptop/home/me/My%20Projects").as_deref(),
+            Some("/home/me/My Projects")
+        );
+        assert_eq!(parse_osc7("file:///tmp").as_deref(), Some("/tmp"));
+        assert_eq!(parse_osc7("http://example.com/"), None);
+
+        // Sequences may be split across reads
+        let mut scanner = Osc7Scanner::default();
+        assert_eq!(scanner.scan(b"$ cd src\r\n\x1b]7;file://host/ho"), None);
+        assert_eq!(
+            scanner.scan(b"me/src\x1b\\\x1b]0;title\x07").as_deref(),
+            Some("/home/src")
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use git2::{BranchType, Repository, Status, StatusOptions};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::pty;
+
+/// Repositories watched at once, statuses of others are read every time
+const MAX_WATCHED_REPOS: usize = 32;
+
+/// Length of the abbreviated commit hashes reported
+const SHORT_SHA_LENGTH: usize = 7;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitSummary {
+    pub sha: String,
+    pub summary: String,
+    pub author: String,
+    /// Seconds since the Unix epoch
+    pub time: i64,
+}
+
+/// Counts of changed files by kind, a file can count as staged and modified
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChangeCounts {
+    pub staged: usize,
+    pub modified: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitStatus {
+    /// Working directory of the repository
+    pub root: String,
+    /// Checked out branch, unset when HEAD is detached
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    #[serde(flatten)]
+    pub changes: ChangeCounts,
+    /// Unset in a repository without commits
+    pub last_commit: Option<CommitSummary>,
+}
+
+/// Statuses of the repositories seen so far, each dropped when something in
+/// its working directory changes
+#[derive(Default)]
+pub struct GitState {
+    statuses: Arc<Mutex<HashMap<PathBuf, GitStatus>>>,
+    watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+}
+
+fn count_changes(statuses: impl IntoIterator<Item = Status>) -> ChangeCounts {
+    let staged = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let modified =
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+    let mut counts = ChangeCounts::default();
+    for status in statuses {
+        if status.is_conflicted() {
+            counts.conflicted += 1;
+            continue;
+        }
+        if status.intersects(staged) {
+            counts.staged += 1;
+        }
+        if status.intersects(modified) {
+            counts.modified += 1;
+        }
+        if status.contains(Status::WT_NEW) {
+            counts.untracked += 1;
+        }
+    }
+    counts
+}
+
+fn last_commit(repo: &Repository) -> Option<CommitSummary> {
+    let commit = repo.head().ok()?.peel_to_commit().ok()?;
+    let sha = commit.id().to_string();
+    let author = commit.author();
+    let summary = CommitSummary {
+        sha: sha[..SHORT_SHA_LENGTH.min(sha.len())].to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        author: author.name().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+    };
+    Some(summary)
+}
+
+// Branch HEAD points to, also in a repository without commits yet
+fn branch_name(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+fn read_status(repo: &Repository, root: &Path) -> Result<GitStatus, String> {
+    let branch = branch_name(repo);
+
+    let mut upstream = None;
+    let (mut ahead, mut behind) = (0, 0);
+    if let Some(local) = branch
+        .as_deref()
+        .and_then(|name| repo.find_branch(name, BranchType::Local).ok())
+    {
+        if let Ok(remote) = local.upstream() {
+            upstream = remote.name().ok().flatten().map(str::to_string);
+            if let (Some(local), Some(remote)) = (local.get().target(), remote.get().target()) {
+                (ahead, behind) = repo.graph_ahead_behind(local, remote).unwrap_or_default();
+            }
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .exclude_submodules(true);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| format!("Failed to read git status: {}", e))?;
+
+    Ok(GitStatus {
+        root: root.to_string_lossy().into_owned(),
+        branch,
+        upstream,
+        ahead,
+        behind,
+        changes: count_changes(statuses.iter().map(|entry| entry.status())),
+        last_commit: last_commit(repo),
+    })
+}
+
+// Drop the cached status whenever something in the working directory
+// changes, and tell the frontend so it can ask again
+fn watch(app: &AppHandle, state: &GitState, root: &Path) {
+    let mut watchers = state.watchers.lock().unwrap();
+    if watchers.contains_key(root) || watchers.len() >= MAX_WATCHED_REPOS {
+        return;
+    }
+    let statuses = state.statuses.clone();
+    let handle = app.clone();
+    let repo_root = root.to_path_buf();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // Reading the status opens files too, that mustn't count as a change
+        if !event.is_ok_and(|event| !event.kind.is_access()) {
+            return;
+        }
+        // Only the first change after a read is reported
+        if statuses.lock().unwrap().remove(&repo_root).is_some() {
+            let _ = handle.emit("git-status-changed", repo_root.to_string_lossy());
+        }
+    });
+    let watcher = watcher.and_then(|mut watcher| {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    match watcher {
+        Ok(watcher) => {
+            watchers.insert(root.to_path_buf(), watcher);
+        }
+        Err(e) => tracing::warn!("Failed to watch {}: {}", root.display(), e),
+    }
+}
+
+// Git state of the repository a directory is in, `None` outside of one
+#[tauri::command]
+pub async fn get_git_status(
+    app: AppHandle,
+    state: State<'_, GitState>,
+    cwd: String,
+) -> Result<Option<GitStatus>, String> {
+    let Ok(repo) = Repository::discover(pty::expand_home(&cwd)) else {
+        return Ok(None);
+    };
+    // Bare repositories have nothing checked out to report on
+    let Some(root) = repo.workdir().map(Path::to_path_buf) else {
+        return Ok(None);
+    };
+
+    if let Some(status) = state.statuses.lock().unwrap().get(&root) {
+        return Ok(Some(status.clone()));
+    }
+    // Watch first so changes made while reading aren't missed
+    watch(&app, &state, &root);
+    let status = read_status(&repo, &root)?;
+    if state.watchers.lock().unwrap().contains_key(&root) {
+        state.statuses.lock().unwrap().insert(root, status.clone());
+    }
+    Ok(Some(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_changes() {
+        let counts = count_changes([
+            Status::INDEX_NEW,
+            Status::INDEX_MODIFIED | Status::WT_MODIFIED,
+            Status::WT_DELETED,
+            Status::WT_NEW,
+            Status::WT_NEW,
+            Status::CONFLICTED | Status::WT_MODIFIED,
+            Status::IGNORED,
+        ]);
+        assert_eq!(
+            counts,
+            ChangeCounts {
+                staged: 2,
+                modified: 2,
+                untracked: 2,
+                conflicted: 1,
+            }
+        );
+    }
+}
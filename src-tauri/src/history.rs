@@ -0,0 +1,384 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::palette;
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Score added per doubling of how often a command was run
+const FREQUENCY_BONUS: i64 = 4;
+
+/// Shells whose history files can be read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryShell {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+    PowerShell,
+}
+
+impl HistoryShell {
+    /// Accepts a shell name or a path to its executable, like `/bin/zsh` or `pwsh.exe`
+    pub fn from_shell(shell: &str) -> Option<Self> {
+        let name = Path::new(shell).file_stem()?.to_str()?.to_lowercase();
+        match name.as_str() {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "nu" => Some(Self::Nushell),
+            "pwsh" | "powershell" => Some(Self::PowerShell),
+            _ => None,
+        }
+    }
+
+    fn history_path(self) -> Option<PathBuf> {
+        let home = dirs::home_dir()?;
+        // Shells export HISTFILE, but only the running shell sees it
+        let histfile = || std::env::var_os("HISTFILE").map(PathBuf::from);
+        match self {
+            Self::Bash => histfile().or_else(|| Some(home.join(".bash_history"))),
+            Self::Zsh => histfile()
+                .or_else(|| Some(home.join(".zsh_history")))
+                .filter(|path| path.exists())
+                .or_else(|| Some(home.join(".histfile"))),
+            Self::Fish => {
+                // fish follows XDG on every platform
+                let data = std::env::var_os("XDG_DATA_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| home.join(".local").join("share"));
+                Some(data.join("fish").join("fish_history"))
+            }
+            Self::Nushell => {
+                let config = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(dirs::config_dir)?
+                    .join("nushell");
+                // The SQLite history if configured, else the plain text one
+                Some(config.join("history.sqlite3"))
+                    .filter(|path| path.exists())
+                    .or_else(|| Some(config.join("history.txt")))
+            }
+            Self::PowerShell => {
+                #[cfg(target_os = "windows")]
+                let dir = dirs::data_dir()?
+                    .join("Microsoft")
+                    .join("Windows")
+                    .join("PowerShell");
+                #[cfg(not(target_os = "windows"))]
+                let dir = home.join(".local").join("share").join("powershell");
+                Some(dir.join("PSReadLine").join("ConsoleHost_history.txt"))
+            }
+        }
+    }
+
+    /// Commands in the order they were run, oldest first
+    fn parse(self, content: &[u8]) -> Vec<(String, Option<u64>)> {
+        match self {
+            Self::Bash => parse_bash(&String::from_utf8_lossy(content)),
+            Self::Zsh => parse_zsh(&String::from_utf8_lossy(&unmetafy(content))),
+            Self::Fish => parse_fish(&String::from_utf8_lossy(content)),
+            Self::Nushell => parse_nushell(&String::from_utf8_lossy(content)),
+            Self::PowerShell => parse_psreadline(&String::from_utf8_lossy(content)),
+        }
+    }
+}
+
+/// A command from a shell's history
+#[derive(Debug, Serialize, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// When it was last run, in seconds since the epoch, if the shell records it
+    pub timestamp: Option<u64>,
+    /// How often it appears in the history
+    pub count: usize,
+}
+
+// With HISTTIMEFORMAT set bash writes a `#<timestamp>` line before each command
+fn parse_bash(content: &str) -> Vec<(String, Option<u64>)> {
+    let mut commands = Vec::new();
+    let mut timestamp = None;
+    for line in content.lines() {
+        if let Some(time) = line.strip_prefix('#').and_then(|t| t.parse().ok()) {
+            timestamp = Some(time);
+        } else if !line.trim().is_empty() {
+            commands.push((line.to_string(), timestamp.take()));
+        }
+    }
+    commands
+}
+
+// zsh escapes the bytes 0x00 and 0x83-0xa2 as 0x83 followed by the byte xor 32
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(content.len());
+    let mut bytes = content.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            0x83 => output.extend(bytes.next().map(|b| b ^ 32)),
+            _ => output.push(byte),
+        }
+    }
+    output
+}
+
+// Plain lines, or `: <start>:<duration>;<command>` with EXTENDED_HISTORY.
+// Multi-line commands end their lines with a backslash.
+fn parse_zsh(content: &str) -> Vec<(String, Option<u64>)> {
+    let mut commands: Vec<(String, Option<u64>)> = Vec::new();
+    let mut continued = false;
+    for line in content.lines() {
+        let (text, ends_with_backslash) = match line.strip_suffix('\\') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+        match commands.last_mut() {
+            Some((command, _)) if continued => {
+                command.push('\n');
+                command.push_str(text);
+            }
+            _ => {
+                let extended = text
+                    .strip_prefix(": ")
+                    .and_then(|rest| rest.split_once(';'))
+                    .and_then(|(meta, command)| {
+                        let start = meta.split(':').next()?.trim().parse().ok()?;
+                        Some((command.to_string(), Some(start)))
+                    });
+                commands.push(extended.unwrap_or_else(|| (text.to_string(), None)));
+            }
+        }
+        continued = ends_with_backslash;
+    }
+    commands.retain(|(command, _)| !command.trim().is_empty());
+    commands
+}
+
+// fish writes a YAML-like list of `- cmd: ...` entries with a `when:` line
+fn parse_fish(content: &str) -> Vec<(String, Option<u64>)> {
+    let mut commands: Vec<(String, Option<u64>)> = Vec::new();
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            commands.push((unescape_fish(command), None));
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            if let Some((_, timestamp)) = commands.last_mut() {
+                *timestamp = when.trim().parse().ok();
+            }
+        }
+    }
+    commands
+}
+
+fn unescape_fish(command: &str) -> String {
+    let mut output = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                output.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                output.push('\\');
+                chars.next();
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+// One command per line, with newlines inside commands written as `<\n>`
+fn parse_nushell(content: &str) -> Vec<(String, Option<u64>)> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| (line.replace("<\\n>", "\n"), None))
+        .collect()
+}
+
+// nushell's SQLite history records when each command started, in
+// milliseconds
+fn read_nushell_db(path: &Path) -> Result<Vec<(String, Option<u64>)>, String> {
+    let error = |e: rusqlite::Error| format!("Failed to read shell history: {}", e);
+    let db =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(error)?;
+    let mut statement = db
+        .prepare("SELECT command_line, start_timestamp FROM history ORDER BY id")
+        .map_err(error)?;
+    let rows = statement
+        .query_map([], |row| {
+            let started: Option<i64> = row.get(1)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                started.and_then(|ms| u64::try_from(ms / 1000).ok()),
+            ))
+        })
+        .map_err(error)?;
+    rows.collect::<Result<_, _>>().map_err(error)
+}
+
+// One command per line, lines of multi-line commands end with a backtick
+fn parse_psreadline(content: &str) -> Vec<(String, Option<u64>)> {
+    let mut commands: Vec<(String, Option<u64>)> = Vec::new();
+    let mut continued = false;
+    for line in content.lines() {
+        let (text, ends_with_backtick) = match line.strip_suffix('`') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+        match commands.last_mut() {
+            Some((command, _)) if continued => {
+                command.push('\n');
+                command.push_str(text);
+            }
+            _ if !text.trim().is_empty() => commands.push((text.to_string(), None)),
+            _ => {}
+        }
+        continued = ends_with_backtick;
+    }
+    commands
+}
+
+/// Collapse repeated commands into one entry and rank them. Without a query the
+/// most recent come first, with one the best fuzzy matches, where commands run
+/// often rank higher.
+pub fn rank(
+    commands: Vec<(String, Option<u64>)>,
+    query: Option<&str>,
+    limit: usize,
+) -> Vec<HistoryEntry> {
+    // Index of the last time each command was run
+    let mut entries: HashMap<String, (usize, HistoryEntry)> = HashMap::new();
+    for (index, (command, timestamp)) in commands.into_iter().enumerate() {
+        let entry = entries.entry(command.clone()).or_insert((
+            index,
+            HistoryEntry {
+                command,
+                timestamp: None,
+                count: 0,
+            },
+        ));
+        entry.0 = index;
+        entry.1.count += 1;
+        entry.1.timestamp = timestamp.or(entry.1.timestamp);
+    }
+
+    let query = query.map(str::trim).filter(|q| !q.is_empty());
+    let mut ranked: Vec<(i64, usize, HistoryEntry)> = entries
+        .into_values()
+        .filter_map(|(last, entry)| {
+            let score = match query {
+                Some(query) => {
+                    let (score, _) = palette::fuzzy_match(query, &entry.command)?;
+                    score + FREQUENCY_BONUS * i64::from(entry.count.ilog2())
+                }
+                None => 0,
+            };
+            Some((score, last, entry))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, entry)| entry)
+        .collect()
+}
+
+/// Commands from a shell's history file, oldest first. Empty when the shell
+/// hasn't written one yet.
+pub fn read_history(shell: HistoryShell) -> Result<Vec<(String, Option<u64>)>, String> {
+    let Some(path) = shell.history_path().filter(|path| path.exists()) else {
+        return Ok(Vec::new());
+    };
+    if path.extension().is_some_and(|ext| ext == "sqlite3") {
+        return read_nushell_db(&path);
+    }
+    let content = fs::read(&path).map_err(|e| format!("Failed to read shell history: {}", e))?;
+    Ok(shell.parse(&content))
+}
+
+// Search a shell's history, for history search and autocomplete
+#[tauri::command]
+pub async fn get_shell_history(
+    shell: String,
+    limit: Option<usize>,
+    query: Option<String>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let kind = HistoryShell::from_shell(&shell)
+        .ok_or_else(|| format!("No history support for shell '{}'", shell))?;
+    Ok(rank(
+        read_history(kind)?,
+        query.as_deref(),
+        limit.unwrap_or(DEFAULT_LIMIT),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(parsed: Vec<(String, Option<u64>)>) -> Vec<String> {
+        parsed.into_iter().map(|(command, _)| command).collect()
+    }
+
+    #[test]
+    fn test_parse_history_formats() {
+        let bash = parse_bash("#1700000000\nls -la\n\ncd /tmp\n");
+        assert_eq!(bash[0], ("ls -la".to_string(), Some(1700000000)));
+        assert_eq!(bash[1], ("cd /tmp".to_string(), None));
+
+        let zsh = HistoryShell::Zsh
+            .parse(b": 1700000000:0;echo a\\\nb\n: 1700000005:2;stra\xc3\x83\xbfe\ngit status\n");
+        assert_eq!(zsh[0], ("echo a\nb".to_string(), Some(1700000000)));
+        assert_eq!(
+            commands(zsh)[1..],
+            ["straße".to_string(), "git status".to_string()]
+        );
+
+        let fish =
+            parse_fish("- cmd: echo a\\nb\n  when: 1700000000\n  paths:\n    - /tmp\n- cmd: ls\n");
+        assert_eq!(fish[0], ("echo a\nb".to_string(), Some(1700000000)));
+        assert_eq!(fish[1], ("ls".to_string(), None));
+
+        let psreadline = parse_psreadline("Get-ChildItem\nif ($true) {`\n  'yes'`\n}\n");
+        assert_eq!(
+            commands(psreadline),
+            ["Get-ChildItem", "if ($true) {\n  'yes'\n}"]
+        );
+
+        let nushell = parse_nushell("ls | first 3\nif true {<\\n>  'yes'<\\n>}\n");
+        assert_eq!(commands(nushell), ["ls | first 3", "if true {\n  'yes'\n}"]);
+
+        assert_eq!(
+            HistoryShell::from_shell("/usr/bin/zsh"),
+            Some(HistoryShell::Zsh)
+        );
+        assert_eq!(
+            HistoryShell::from_shell("pwsh.exe"),
+            Some(HistoryShell::PowerShell)
+        );
+        assert_eq!(HistoryShell::from_shell("cmd.exe"), None);
+    }
+
+    #[test]
+    fn test_rank_history() {
+        let history = ["git status", "ls", "git stash", "git status", "ls"]
+            .map(|command| (command.to_string(), None))
+            .to_vec();
+
+        // Most recent first, duplicates collapsed
+        let recent = rank(history.clone(), None, 10);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].command, "ls");
+        assert_eq!(recent[0].count, 2);
+        assert_eq!(recent[1].command, "git status");
+
+        let matches = rank(history, Some("gst"), 1);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].command, "git status");
+    }
+}
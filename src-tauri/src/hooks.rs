@@ -0,0 +1,303 @@
+use regex::Regex;
+use std::process::{Command, Stdio};
+
+use crate::config::{HookAction, HooksConfig, NotificationConfig};
+use crate::crash;
+use crate::notifications::{self, Activity, ActivityScanner};
+use crate::pty;
+
+/// Something that happened in a terminal that hooks can react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookEvent {
+    PtyCreated,
+    PtyExited { status: String },
+    Bell,
+    CommandFailed { exit_code: i32, seconds: u64 },
+    Trigger { line: String },
+}
+
+impl HookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PtyCreated => "pty_created",
+            HookEvent::PtyExited { .. } => "pty_exited",
+            HookEvent::Bell => "bell",
+            HookEvent::CommandFailed { .. } => "command_failed",
+            HookEvent::Trigger { .. } => "trigger",
+        }
+    }
+
+    // Values describing the event, passed to commands as `TERMILLION_<NAME>`
+    // variables and to notifications as `{name}` placeholders
+    fn vars(&self, pty_id: &str) -> Vec<(&'static str, String)> {
+        let mut vars = vec![("event", self.name().to_string())];
+        vars.push(("pty_id", pty_id.to_string()));
+        if let Some(cwd) = pty::pty_cwd(pty_id) {
+            vars.push(("cwd", cwd));
+        }
+        match self {
+            HookEvent::PtyExited { status } => vars.push(("status", status.clone())),
+            HookEvent::CommandFailed { exit_code, seconds } => {
+                vars.push(("exit_code", exit_code.to_string()));
+                vars.push(("seconds", seconds.to_string()));
+            }
+            HookEvent::Trigger { line } => vars.push(("line", line.clone())),
+            HookEvent::PtyCreated | HookEvent::Bell => {}
+        }
+        vars
+    }
+
+    // Events hooks react to among what the output scanner found
+    fn from_activity(activity: &Activity) -> Option<Self> {
+        match activity {
+            Activity::Bell => Some(HookEvent::Bell),
+            Activity::CommandFinished {
+                seconds,
+                exit_code: Some(code),
+            } if *code != 0 => Some(HookEvent::CommandFailed {
+                exit_code: *code,
+                seconds: *seconds,
+            }),
+            Activity::CommandFinished { .. } => None,
+            Activity::TriggerMatched { line } => Some(HookEvent::Trigger { line: line.clone() }),
+        }
+    }
+}
+
+/// Configured hooks of one terminal
+#[derive(Clone)]
+pub struct Hooks {
+    config: HooksConfig,
+    triggers: Vec<(Regex, HookAction)>,
+    pty_id: String,
+}
+
+impl Hooks {
+    /// Invalid trigger patterns are skipped, validation reports them
+    pub fn new(config: &HooksConfig, pty_id: &str) -> Self {
+        Self {
+            config: config.clone(),
+            triggers: config
+                .triggers
+                .iter()
+                .flatten()
+                .filter_map(|trigger| {
+                    Some((Regex::new(&trigger.pattern).ok()?, trigger.action.clone()))
+                })
+                .collect(),
+            pty_id: pty_id.to_string(),
+        }
+    }
+
+    fn actions(&self, event: &HookEvent) -> Vec<HookAction> {
+        let listed = |actions: &Option<Vec<HookAction>>| actions.clone().unwrap_or_default();
+        match event {
+            HookEvent::PtyCreated => listed(&self.config.pty_created),
+            HookEvent::PtyExited { .. } => listed(&self.config.pty_exited),
+            HookEvent::Bell => listed(&self.config.bell),
+            HookEvent::CommandFailed { .. } => listed(&self.config.command_failed),
+            HookEvent::Trigger { line } => self
+                .triggers
+                .iter()
+                .filter(|(pattern, _)| pattern.is_match(line))
+                .map(|(_, action)| action.clone())
+                .collect(),
+        }
+    }
+
+    /// Run the event's actions in order on a thread of their own
+    pub fn fire(&self, event: &HookEvent) {
+        let actions = self.actions(event);
+        if actions.is_empty() {
+            return;
+        }
+        let vars = event.vars(&self.pty_id);
+        let name = event.name();
+        crash::spawn("hook", move || {
+            for action in &actions {
+                if let Err(e) = run(action, &vars) {
+                    tracing::warn!("{} hook failed: {}", name, e);
+                }
+            }
+        });
+    }
+
+    /// Scanner that finds the output events these hooks need, if any
+    pub fn scanner(&self) -> Option<ActivityScanner> {
+        let has =
+            |actions: &Option<Vec<HookAction>>| actions.as_ref().is_some_and(|a| !a.is_empty());
+        let bell = has(&self.config.bell);
+        let command_failed = has(&self.config.command_failed);
+        if !bell && !command_failed && self.triggers.is_empty() {
+            return None;
+        }
+        Some(ActivityScanner::new(&NotificationConfig {
+            bell: Some(bell),
+            command_finished: Some(command_failed),
+            // Every failed command counts, not just long ones
+            long_command_seconds: Some(0),
+            triggers: Some(
+                self.triggers
+                    .iter()
+                    .map(|(pattern, _)| pattern.as_str().to_string())
+                    .collect(),
+            ),
+        }))
+    }
+
+    /// Fire the hooks of what a scanner found in the output
+    pub fn handle(&self, activities: &[Activity]) {
+        for event in activities.iter().filter_map(HookEvent::from_activity) {
+            self.fire(&event);
+        }
+    }
+}
+
+// Replace `{name}` placeholders with the event's values
+fn expand(template: &str, vars: &[(&str, String)]) -> String {
+    vars.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+fn run(action: &HookAction, vars: &[(&str, String)]) -> Result<(), String> {
+    match action {
+        HookAction::Command { command, args } => {
+            let mut process = Command::new(pty::expand_home(command));
+            process.args(args.iter().flatten());
+            for (name, value) in vars {
+                process.env(format!("TERMILLION_{}", name.to_uppercase()), value);
+            }
+            let status = process
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+            if !status.success() {
+                return Err(format!("'{}' exited with {}", command, status));
+            }
+            Ok(())
+        }
+        HookAction::Notify { message } => notifications::show(&expand(message, vars)),
+        HookAction::Sound { path } => play_sound(path.as_deref()),
+    }
+}
+
+// Sounds are played by the tools every desktop ships with
+fn play_sound(path: Option<&str>) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("afplay");
+        command.arg(path.map_or(
+            "/System/Library/Sounds/Ping.aiff".to_string(),
+            pty::expand_home,
+        ));
+        command
+    };
+    #[cfg(windows)]
+    let mut command = {
+        let script = match path {
+            Some(path) => format!(
+                "(New-Object Media.SoundPlayer '{}').PlaySync()",
+                pty::expand_home(path).replace('\'', "''")
+            ),
+            None => {
+                "[System.Media.SystemSounds]::Beep.Play(); Start-Sleep -Milliseconds 500".into()
+            }
+        };
+        let mut command = Command::new("powershell.exe");
+        command.args(["-NoProfile", "-Command", &script]);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = match path {
+        Some(path) => {
+            let mut command = Command::new("paplay");
+            command.arg(pty::expand_home(path));
+            command
+        }
+        None => {
+            let mut command = Command::new("canberra-gtk-play");
+            command.args(["--id", "bell"]);
+            command
+        }
+    };
+
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to play sound: {}", e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to play sound: player exited with {}",
+            status
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TriggerHook;
+
+    #[test]
+    fn test_hook_actions() {
+        let notify = HookAction::Notify {
+            message: "Exit {exit_code} in {pty_id}".into(),
+        };
+        let hooks = Hooks::new(
+            &HooksConfig {
+                command_failed: Some(vec![notify.clone()]),
+                triggers: Some(vec![
+                    TriggerHook {
+                        pattern: "deploy (done|failed)".into(),
+                        action: HookAction::Sound { path: None },
+                    },
+                    TriggerHook {
+                        pattern: "(unclosed".into(),
+                        action: HookAction::Sound { path: None },
+                    },
+                ]),
+                ..Default::default()
+            },
+            "abc",
+        );
+
+        // Only failed commands are reported
+        let failed = HookEvent::from_activity(&Activity::CommandFinished {
+            seconds: 3,
+            exit_code: Some(2),
+        })
+        .unwrap();
+        assert!(HookEvent::from_activity(&Activity::CommandFinished {
+            seconds: 3,
+            exit_code: Some(0),
+        })
+        .is_none());
+        assert!(matches!(
+            hooks.actions(&failed).as_slice(),
+            [HookAction::Notify { .. }]
+        ));
+        assert!(hooks.actions(&HookEvent::Bell).is_empty());
+
+        // Invalid patterns are dropped, the rest only match their lines
+        assert_eq!(hooks.triggers.len(), 1);
+        let line = |line: &str| HookEvent::Trigger { line: line.into() };
+        assert_eq!(hooks.actions(&line("deploy done")).len(), 1);
+        assert!(hooks.actions(&line("build done")).is_empty());
+
+        let vars = [
+            ("exit_code", "2".to_string()),
+            ("pty_id", "abc".to_string()),
+        ];
+        assert_eq!(
+            expand("Exit {exit_code} in {pty_id}", &vars),
+            "Exit 2 in abc"
+        );
+    }
+}
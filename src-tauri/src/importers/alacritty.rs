@@ -0,0 +1,119 @@
+use serde::Deserialize;
+
+use super::{default_selection, normalize_color, ImportedTheme};
+use crate::config::ThemeConfig;
+
+// Alacritty uses the same color layout in its YAML and TOML formats
+#[derive(Debug, Deserialize)]
+struct AlacrittyFile {
+    colors: Option<AlacrittyColors>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlacrittyColors {
+    primary: Option<Primary>,
+    cursor: Option<Cursor>,
+    selection: Option<Selection>,
+    normal: Option<Palette>,
+    bright: Option<Palette>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Primary {
+    background: Option<String>,
+    foreground: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cursor {
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Selection {
+    background: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Palette {
+    black: Option<String>,
+    red: Option<String>,
+    green: Option<String>,
+    yellow: Option<String>,
+    blue: Option<String>,
+    magenta: Option<String>,
+    cyan: Option<String>,
+    white: Option<String>,
+}
+
+/// Alacritty config syntax
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+/// Convert the colors of an Alacritty config file into a theme
+pub fn convert(name: &str, content: &str, format: Format) -> Result<ImportedTheme, String> {
+    let file: AlacrittyFile = match format {
+        Format::Yaml => serde_yaml::from_str(content)
+            .map_err(|e| format!("Failed to parse Alacritty YAML: {}", e))?,
+        Format::Toml => {
+            toml::from_str(content).map_err(|e| format!("Failed to parse Alacritty TOML: {}", e))?
+        }
+    };
+
+    let colors = file
+        .colors
+        .ok_or_else(|| "Alacritty config has no [colors] section".to_string())?;
+    let primary = colors
+        .primary
+        .ok_or_else(|| "Alacritty config has no primary colors".to_string())?;
+
+    let color = |value: Option<String>| value.map(|v| normalize_color(&v));
+    let background = color(primary.background).unwrap_or_else(|| "#000000".into());
+    let foreground = color(primary.foreground).unwrap_or_else(|| "#ffffff".into());
+    let normal = colors.normal.unwrap_or_default();
+    let bright = colors.bright.unwrap_or_default();
+
+    // `CellForeground` and `CellBackground` take the color of the cell under
+    // the cursor or selection, the closest fixed colors are the primary ones
+    let cell_color = |value: Option<String>| {
+        color(value).map(|value| match value.as_str() {
+            "CellForeground" => foreground.clone(),
+            "CellBackground" => background.clone(),
+            _ => value,
+        })
+    };
+    let cursor =
+        cell_color(colors.cursor.and_then(|c| c.cursor)).unwrap_or_else(|| foreground.clone());
+    // A selection in the background color wouldn't show
+    let selection = cell_color(colors.selection.and_then(|s| s.background))
+        .filter(|selection| *selection != background)
+        .unwrap_or_else(|| default_selection(&foreground, &background));
+
+    Ok(ImportedTheme {
+        name: name.to_string(),
+        theme: ThemeConfig {
+            cursor,
+            selection,
+            background,
+            foreground,
+            black: color(normal.black),
+            red: color(normal.red),
+            green: color(normal.green),
+            yellow: color(normal.yellow),
+            blue: color(normal.blue),
+            magenta: color(normal.magenta),
+            cyan: color(normal.cyan),
+            white: color(normal.white),
+            bright_black: color(bright.black),
+            bright_red: color(bright.red),
+            bright_green: color(bright.green),
+            bright_yellow: color(bright.yellow),
+            bright_blue: color(bright.blue),
+            bright_magenta: color(bright.magenta),
+            bright_cyan: color(bright.cyan),
+            bright_white: color(bright.white),
+        },
+    })
+}
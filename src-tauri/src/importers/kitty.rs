@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use super::{default_selection, normalize_color, ImportedTheme};
+use crate::config::ThemeConfig;
+
+/// Convert a kitty `.conf` color definition into a theme
+pub fn convert(name: &str, content: &str) -> Result<ImportedTheme, String> {
+    // kitty uses "key value" lines, later lines win
+    let mut values = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(char::is_whitespace) {
+            // "none" means "use the default", which is what a missing key does
+            if value.trim() != "none" {
+                values.insert(key, normalize_color(value.trim()));
+            }
+        }
+    }
+
+    let background = values
+        .get("background")
+        .cloned()
+        .ok_or_else(|| "kitty theme has no background color".to_string())?;
+    let foreground = values
+        .get("foreground")
+        .cloned()
+        .ok_or_else(|| "kitty theme has no foreground color".to_string())?;
+    let ansi = |index: u8| values.get(format!("color{}", index).as_str()).cloned();
+
+    Ok(ImportedTheme {
+        name: name.to_string(),
+        theme: ThemeConfig {
+            cursor: values
+                .get("cursor")
+                .cloned()
+                .unwrap_or_else(|| foreground.clone()),
+            selection: values
+                .get("selection_background")
+                .cloned()
+                .unwrap_or_else(|| default_selection(&foreground, &background)),
+            black: ansi(0),
+            red: ansi(1),
+            green: ansi(2),
+            yellow: ansi(3),
+            blue: ansi(4),
+            magenta: ansi(5),
+            cyan: ansi(6),
+            white: ansi(7),
+            bright_black: ansi(8),
+            bright_red: ansi(9),
+            bright_green: ansi(10),
+            bright_yellow: ansi(11),
+            bright_blue: ansi(12),
+            bright_magenta: ansi(13),
+            bright_cyan: ansi(14),
+            bright_white: ansi(15),
+            background,
+            foreground,
+        },
+    })
+}
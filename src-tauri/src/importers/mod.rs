@@ -0,0 +1,177 @@
+mod alacritty;
+mod kitty;
+mod windows_terminal;
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::color::{self, Color};
+use crate::config::{Config, Profile, Profiles, ThemeConfig};
+
+/// Selection color of the default theme
+const DEFAULT_SELECTION: &str = "#3e4451";
+
+// Re-export the importer entry points
+pub use windows_terminal::*;
+
+#[derive(Debug, Serialize, Clone)]
+/// A named color scheme converted from another terminal
+pub struct ImportedTheme {
+    pub name: String,
+    pub theme: ThemeConfig,
+}
+
+#[derive(Debug, Serialize, Default)]
+/// Everything an importer managed to convert
+pub struct ImportResult {
+    pub themes: Vec<ImportedTheme>,
+    pub profiles: Vec<Profile>,
+    /// Name of the profile the source marked as default, if any
+    pub default_profile: Option<String>,
+    /// Entries that were skipped or only partially converted
+    pub warnings: Vec<String>,
+}
+
+/// Merge imported profiles into the config, skipping names that already exist.
+/// Returns the number of profiles added.
+pub fn merge_profiles(config: &mut Config, result: &ImportResult) -> usize {
+    let profiles = config.profiles.get_or_insert_with(|| Profiles {
+        default: result.default_profile.clone().unwrap_or_default(),
+        list: Vec::new(),
+    });
+
+    let mut added = 0;
+    for profile in &result.profiles {
+        if profiles.find(&profile.name).is_none() {
+            profiles.list.push(profile.clone());
+            added += 1;
+        }
+    }
+
+    // Fall back to the first profile if the default doesn't point anywhere
+    if profiles.find(&profiles.default).is_none() {
+        if let Some(first) = profiles.list.first() {
+            profiles.default = first.name.clone();
+        }
+    }
+
+    added
+}
+
+/// Save imported profiles into the user's config. Imported themes aren't
+/// saved, there is only one theme and applying it is up to the user.
+pub fn apply_import(app: &AppHandle, result: &ImportResult) -> Result<usize, String> {
+    let mut config = Config::load(app)?;
+    let added = merge_profiles(&mut config, result);
+    if added > 0 {
+        config.save(app)?;
+    }
+    Ok(added)
+}
+
+/// Normalize the color notations used by other terminals to `#rrggbb`
+pub fn normalize_color(value: &str) -> String {
+    let value = value.trim().trim_matches(|c| c == '\'' || c == '"');
+    match value.strip_prefix("0x") {
+        Some(hex) => format!("#{}", hex),
+        None => value.to_string(),
+    }
+}
+
+/// Selection color for themes that don't set one, the foreground blended a
+/// quarter of the way into the background. Falls back to the default theme's
+/// when either color can't be parsed.
+pub fn default_selection(foreground: &str, background: &str) -> String {
+    match (color::parse(foreground), color::parse(background)) {
+        (Some(foreground), Some(background)) => {
+            let blended = Color {
+                a: 0.25,
+                ..foreground
+            }
+            .over(background);
+            let channel = |c: f64| (c * 255.0).round() as u8;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                channel(blended.r),
+                channel(blended.g),
+                channel(blended.b)
+            )
+        }
+        _ => DEFAULT_SELECTION.to_string(),
+    }
+}
+
+/// Convert a theme file, picking the importer from the file extension
+pub fn convert_theme_file(path: &Path) -> Result<ImportedTheme, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported".into());
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "yml" | "yaml" => alacritty::convert(&name, &content, alacritty::Format::Yaml),
+        "toml" => alacritty::convert(&name, &content, alacritty::Format::Toml),
+        "conf" => kitty::convert(&name, &content),
+        "json" => convert_scheme(&content),
+        _ => Err(format!("Unsupported theme file format: .{}", extension)),
+    }
+}
+
+// Import a theme from an Alacritty, kitty or Windows Terminal file
+#[tauri::command]
+pub async fn import_theme(
+    app: AppHandle,
+    path: String,
+    apply: Option<bool>,
+) -> Result<ImportedTheme, String> {
+    let imported = convert_theme_file(Path::new(&path))?;
+
+    if apply.unwrap_or(false) {
+        let mut config = Config::load(&app)?;
+        config.theme = imported.theme.clone();
+        config.save(&app)?;
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alacritty_yaml_and_toml_agree() {
+        let yaml = "colors:\n  primary:\n    background: '0x1d1f21'\n    foreground: '0xc5c8c6'\n  normal:\n    red: '0xcc6666'\n";
+        let toml = "[colors.primary]\nbackground = \"#1d1f21\"\nforeground = \"#c5c8c6\"\n[colors.normal]\nred = \"#cc6666\"\n";
+
+        let from_yaml = alacritty::convert("t", yaml, alacritty::Format::Yaml).unwrap();
+        let from_toml = alacritty::convert("t", toml, alacritty::Format::Toml).unwrap();
+        assert_eq!(from_yaml.theme.background, from_toml.theme.background);
+        assert_eq!(from_yaml.theme.red.as_deref(), Some("#cc6666"));
+
+        // Colors taken from the cell under the cursor or selection
+        let toml = "[colors.primary]\nbackground = \"#000000\"\nforeground = \"#ffffff\"\n[colors.cursor]\ncursor = \"CellForeground\"\n[colors.selection]\nbackground = \"CellBackground\"\n";
+        let theme = alacritty::convert("t", toml, alacritty::Format::Toml)
+            .unwrap()
+            .theme;
+        assert_eq!(theme.cursor, "#ffffff");
+        assert_eq!(theme.selection, "#404040");
+    }
+
+    #[test]
+    fn test_kitty_palette() {
+        let conf = "# comment\nbackground #000000\nforeground #ffffff\ncolor1 #ff0000\nselection_background none\n";
+        let theme = kitty::convert("t", conf).unwrap().theme;
+        assert_eq!(theme.red.as_deref(), Some("#ff0000"));
+        // No selection color, so one between the background and foreground
+        assert_eq!(theme.selection, "#404040");
+    }
+}
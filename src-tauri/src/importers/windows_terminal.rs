@@ -0,0 +1,337 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::{apply_import, default_selection, ImportResult, ImportedTheme};
+use crate::config::{Profile, ThemeConfig};
+use crate::jsonc;
+
+// Package folders Windows Terminal stores its settings under
+const PACKAGE_DIRS: [&str; 2] = [
+    "Microsoft.WindowsTerminal_8wekyb3d8bbwe",
+    "Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WtSettings {
+    default_profile: Option<String>,
+    profiles: Option<WtProfiles>,
+    #[serde(default)]
+    schemes: Vec<WtScheme>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum WtProfiles {
+    // Older settings files store the profiles as a plain array
+    List(Vec<WtProfile>),
+    Object {
+        #[serde(default)]
+        list: Vec<WtProfile>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WtProfile {
+    guid: Option<String>,
+    name: Option<String>,
+    commandline: Option<String>,
+    starting_directory: Option<String>,
+    icon: Option<String>,
+    color_scheme: Option<WtSchemeRef>,
+    source: Option<String>,
+    elevate: Option<bool>,
+    #[serde(default)]
+    hidden: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum WtSchemeRef {
+    Name(String),
+    // Light/dark pairs, we only have one theme so prefer dark
+    Pair { dark: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WtScheme {
+    name: String,
+    background: Option<String>,
+    foreground: Option<String>,
+    cursor_color: Option<String>,
+    selection_background: Option<String>,
+    black: Option<String>,
+    red: Option<String>,
+    green: Option<String>,
+    yellow: Option<String>,
+    blue: Option<String>,
+    purple: Option<String>,
+    cyan: Option<String>,
+    white: Option<String>,
+    bright_black: Option<String>,
+    bright_red: Option<String>,
+    bright_green: Option<String>,
+    bright_yellow: Option<String>,
+    bright_blue: Option<String>,
+    bright_purple: Option<String>,
+    bright_cyan: Option<String>,
+    bright_white: Option<String>,
+}
+
+impl From<WtScheme> for ImportedTheme {
+    fn from(scheme: WtScheme) -> Self {
+        let foreground = scheme.foreground.unwrap_or_else(|| "#cccccc".into());
+        let background = scheme.background.unwrap_or_else(|| "#0c0c0c".into());
+        ImportedTheme {
+            name: scheme.name,
+            theme: ThemeConfig {
+                cursor: scheme.cursor_color.unwrap_or_else(|| foreground.clone()),
+                selection: scheme
+                    .selection_background
+                    .unwrap_or_else(|| default_selection(&foreground, &background)),
+                background,
+                foreground,
+                black: scheme.black,
+                red: scheme.red,
+                green: scheme.green,
+                yellow: scheme.yellow,
+                blue: scheme.blue,
+                magenta: scheme.purple,
+                cyan: scheme.cyan,
+                white: scheme.white,
+                bright_black: scheme.bright_black,
+                bright_red: scheme.bright_red,
+                bright_green: scheme.bright_green,
+                bright_yellow: scheme.bright_yellow,
+                bright_blue: scheme.bright_blue,
+                bright_magenta: scheme.bright_purple,
+                bright_cyan: scheme.bright_cyan,
+                bright_white: scheme.bright_white,
+            },
+        }
+    }
+}
+
+/// Find the Windows Terminal settings file in its default locations
+fn default_settings_path() -> Option<PathBuf> {
+    let local = dirs::data_local_dir()?;
+    PACKAGE_DIRS
+        .iter()
+        .map(|dir| {
+            local
+                .join("Packages")
+                .join(dir)
+                .join("LocalState")
+                .join("settings.json")
+        })
+        .chain(std::iter::once(
+            local
+                .join("Microsoft")
+                .join("Windows Terminal")
+                .join("settings.json"),
+        ))
+        .find(|path| path.exists())
+}
+
+/// Split a command line into the program and its arguments, honouring quotes
+fn split_command_line(commandline: &str) -> (String, Vec<String>) {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in commandline.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    let mut parts = parts.into_iter();
+    let command = parts.next().unwrap_or_default();
+    (command, parts.collect())
+}
+
+/// Convert a profile generated by a Windows Terminal dynamic source
+fn command_for_source(source: &str, name: &str) -> Option<(String, Vec<String>)> {
+    match source {
+        "Windows.Terminal.Wsl" => Some(("wsl.exe".into(), vec!["-d".into(), name.into()])),
+        "Windows.Terminal.PowershellCore" => Some(("pwsh.exe".into(), Vec::new())),
+        "Git" => Some(("bash.exe".into(), vec!["--login".into(), "-i".into()])),
+        _ => None,
+    }
+}
+
+/// Convert the contents of a Windows Terminal `settings.json`
+pub fn convert_settings(content: &str) -> Result<ImportResult, String> {
+    let settings: WtSettings = serde_json::from_str(&jsonc::strip_comments(content))
+        .map_err(|e| format!("Failed to parse Windows Terminal settings: {}", e))?;
+
+    let mut result = ImportResult {
+        themes: settings
+            .schemes
+            .into_iter()
+            .map(ImportedTheme::from)
+            .collect(),
+        ..Default::default()
+    };
+
+    let wt_profiles = match settings.profiles {
+        Some(WtProfiles::List(list)) | Some(WtProfiles::Object { list }) => list,
+        None => Vec::new(),
+    };
+
+    for wt in wt_profiles {
+        let Some(name) = wt.name else {
+            result
+                .warnings
+                .push("Skipped a profile without a name".into());
+            continue;
+        };
+        if wt.hidden {
+            continue;
+        }
+
+        let (command, args) = match (&wt.commandline, &wt.source) {
+            (Some(commandline), _) => split_command_line(commandline),
+            (None, Some(source)) => match command_for_source(source, &name) {
+                Some(command) => command,
+                None => {
+                    result.warnings.push(format!(
+                        "Skipped profile '{}': unsupported source {}",
+                        name, source
+                    ));
+                    continue;
+                }
+            },
+            (None, None) => {
+                result
+                    .warnings
+                    .push(format!("Skipped profile '{}': no command line", name));
+                continue;
+            }
+        };
+
+        let theme = match wt.color_scheme {
+            Some(WtSchemeRef::Name(scheme)) | Some(WtSchemeRef::Pair { dark: scheme }) => {
+                let found = result.themes.iter().find(|t| t.name == scheme);
+                if found.is_none() {
+                    result.warnings.push(format!(
+                        "Profile '{}' uses unknown color scheme '{}'",
+                        name, scheme
+                    ));
+                }
+                found.map(|t| t.theme.clone())
+            }
+            None => None,
+        };
+
+        // Windows Terminal uses the guid of the profile for the default
+        if wt.guid.is_some() && wt.guid == settings.default_profile {
+            result.default_profile = Some(name.clone());
+        }
+
+        result.profiles.push(Profile {
+            name,
+            command,
+            args: (!args.is_empty()).then_some(args),
+            // "%USERPROFILE%" is the Windows Terminal default, same as ours
+            cwd: wt
+                .starting_directory
+                .filter(|dir| !dir.eq_ignore_ascii_case("%USERPROFILE%")),
+            icon: wt.icon,
+            elevated: wt.elevate,
+            theme,
+            ..Default::default()
+        });
+    }
+
+    Ok(result)
+}
+
+/// Convert a single Windows Terminal color scheme object
+pub fn convert_scheme(content: &str) -> Result<ImportedTheme, String> {
+    let scheme: WtScheme = serde_json::from_str(&jsonc::strip_comments(content))
+        .map_err(|e| format!("Failed to parse Windows Terminal color scheme: {}", e))?;
+    Ok(scheme.into())
+}
+
+// Import color schemes and profiles from Windows Terminal. `apply` saves the
+// profiles only, each keeping the colors of its scheme. The schemes are
+// returned for picking one to apply with `import_theme`.
+#[tauri::command]
+pub async fn import_windows_terminal(
+    app: AppHandle,
+    path: Option<String>,
+    apply: Option<bool>,
+) -> Result<ImportResult, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => default_settings_path()
+            .ok_or_else(|| "Windows Terminal settings file not found".to_string())?,
+    };
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut result = convert_settings(&content)?;
+
+    if apply.unwrap_or(false) {
+        apply_import(&app, &result)?;
+        if !result.themes.is_empty() {
+            result
+                .warnings
+                .push("Color schemes weren't applied, only the profiles using them".to_string());
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_settings() {
+        let content = r##"{
+            "defaultProfile": "{1}",
+            // comments are allowed in settings.json
+            "profiles": {
+                "defaults": {},
+                "list": [
+                    { "guid": "{1}", "name": "Dev", "commandline": "\"C:\\Program Files\\Git\\bin\\bash.exe\" -l", "colorScheme": "One Half Dark" },
+                    { "guid": "{2}", "name": "Ubuntu", "source": "Windows.Terminal.Wsl" },
+                    { "guid": "{3}", "name": "Hidden", "commandline": "cmd.exe", "hidden": true },
+                ]
+            },
+            "schemes": [
+                { "name": "One Half Dark", "background": "#282C34", "foreground": "#DCDFE4", "purple": "#C678DD" }
+            ]
+        }"##;
+
+        let result = convert_settings(content).unwrap();
+        assert_eq!(result.profiles.len(), 2);
+        assert_eq!(result.default_profile.as_deref(), Some("Dev"));
+
+        let dev = &result.profiles[0];
+        assert_eq!(dev.command, "C:\\Program Files\\Git\\bin\\bash.exe");
+        assert_eq!(dev.args.as_deref(), Some(&["-l".to_string()][..]));
+        assert_eq!(
+            dev.theme.as_ref().unwrap().magenta.as_deref(),
+            Some("#C678DD")
+        );
+
+        assert_eq!(result.profiles[1].command, "wsl.exe");
+    }
+}
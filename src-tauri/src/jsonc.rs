@@ -0,0 +1,102 @@
+/// Strip `//` and `/* */` comments and trailing commas from JSONC input so it
+/// can be handed to serde_json. String contents are left untouched.
+pub fn strip_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    output.push(escaped);
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                // Line comment, skip to the end of the line
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                // Block comment, skip to the closing marker
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    strip_trailing_commas(&output)
+}
+
+// Remove commas directly followed (ignoring whitespace) by a closing bracket
+fn strip_trailing_commas(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            output.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                output.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            output.push(c);
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                output.push(c);
+            }
+        } else {
+            output.push(c);
+        }
+        i += 1;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_comments_keeps_strings() {
+        let input = r#"{
+            // comment
+            "url": "http://example.com", /* block */
+            "list": [1, 2,],
+        }"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_comments(input)).unwrap();
+        assert_eq!(value["url"], "http://example.com");
+        assert_eq!(value["list"].as_array().unwrap().len(), 2);
+    }
+}
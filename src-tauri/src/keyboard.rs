@@ -0,0 +1,184 @@
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Keys of a keyboard layout that shortcuts can't use directly
+pub struct KeyboardLayout {
+    /// XKB layout name, like `de`
+    id: &'static str,
+    pub name: &'static str,
+    /// Characters typed with AltGr
+    altgr: &'static str,
+    /// Characters that only exist as dead keys
+    dead: &'static str,
+}
+
+const fn layout(
+    id: &'static str,
+    name: &'static str,
+    altgr: &'static str,
+    dead: &'static str,
+) -> KeyboardLayout {
+    KeyboardLayout {
+        id,
+        name,
+        altgr,
+        dead,
+    }
+}
+
+// Punctuation that moves on common non-US layouts. Layouts not listed here,
+// like US and UK, reach every ASCII character without AltGr.
+const LAYOUTS: &[KeyboardLayout] = &[
+    layout("de", "German", "\\|{}[]~@", "^`"),
+    layout("ch", "Swiss", "\\|{}[]~@#", "^`"),
+    layout("fr", "French", "~#{[|`\\^@]}", ""),
+    layout("be", "Belgian", "|@#{}[]`~\\", "^"),
+    layout("es", "Spanish", "\\|@#~[]{}", "^`"),
+    layout("it", "Italian", "[]@#{}", "`~"),
+    layout("pt", "Portuguese", "@{[]}", "~^`"),
+    layout("se", "Swedish", "@${[]}\\|", "^`~"),
+    layout("fi", "Finnish", "@${[]}\\|", "^`~"),
+    layout("no", "Norwegian", "@${[]}", "^`~"),
+    layout("dk", "Danish", "@${[]}|\\", "^`~"),
+];
+
+/// Why a shortcut key can't be pressed on a layout
+#[derive(Debug, PartialEq)]
+pub enum KeyIssue {
+    /// AltGr is reported as Ctrl+Alt, so the modifiers never match
+    NeedsAltGr,
+    /// Typing the character takes two key presses
+    DeadKey,
+}
+
+impl KeyboardLayout {
+    /// Whether a shortcut key is hard or impossible to press on this layout
+    pub fn key_issue(&self, key: &str) -> Option<KeyIssue> {
+        let mut chars = key.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        if self.altgr.contains(c) {
+            Some(KeyIssue::NeedsAltGr)
+        } else if self.dead.contains(c) {
+            Some(KeyIssue::DeadKey)
+        } else {
+            None
+        }
+    }
+}
+
+fn find_layout(id: &str) -> Option<&'static KeyboardLayout> {
+    // Only the first layout counts, variants like `de(nodeadkeys)` are ignored
+    let id = id.split([',', '(', '+', ':']).next()?.trim();
+    LAYOUTS.iter().find(|layout| layout.id == id)
+}
+
+// Run a command and return its stdout
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Value of a `key: value` line, as printed by setxkbmap and localectl
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn field<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix(':'))
+        .map(str::trim)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn detect_layout() -> Option<&'static KeyboardLayout> {
+    if let Ok(layout) = std::env::var("XKB_DEFAULT_LAYOUT") {
+        return find_layout(&layout);
+    }
+    if let Some(output) = command_output("setxkbmap", &["-query"]) {
+        return field(&output, "layout").and_then(find_layout);
+    }
+    let output = command_output("localectl", &["status"])?;
+    field(&output, "X11 Layout").and_then(find_layout)
+}
+
+#[cfg(target_os = "macos")]
+fn detect_layout() -> Option<&'static KeyboardLayout> {
+    // Prints an id like com.apple.keylayout.German
+    let output = command_output(
+        "defaults",
+        &[
+            "read",
+            "com.apple.HIToolbox",
+            "AppleCurrentKeyboardLayoutInputSourceID",
+        ],
+    )?;
+    let id = match output.trim().rsplit('.').next()? {
+        "German" | "Austrian" => "de",
+        "Swiss-German" | "Swiss-French" => "ch",
+        "French" => "fr",
+        "Belgian" => "be",
+        "Spanish" | "Spanish-ISO" => "es",
+        "Italian" | "Italian-Pro" => "it",
+        "Portuguese" => "pt",
+        "Swedish" | "Swedish-Pro" => "se",
+        "Finnish" => "fi",
+        "Norwegian" => "no",
+        "Danish" => "dk",
+        _ => return None,
+    };
+    find_layout(id)
+}
+
+#[cfg(target_os = "windows")]
+fn detect_layout() -> Option<&'static KeyboardLayout> {
+    // The first preloaded layout id, like 00000407, ends in the language id
+    let output = command_output(
+        "reg",
+        &["query", "HKCU\\Keyboard Layout\\Preload", "/v", "1"],
+    )?;
+    let id = output.split_whitespace().last()?;
+    let language = u16::from_str_radix(id.get(id.len().checked_sub(4)?..)?, 16).ok()?;
+    let id = match language {
+        0x0407 | 0x0c07 => "de",
+        0x0807 | 0x100c => "ch",
+        0x040c => "fr",
+        0x080c | 0x0813 => "be",
+        0x040a | 0x0c0a => "es",
+        0x0410 => "it",
+        0x0816 => "pt",
+        0x041d => "se",
+        0x040b => "fi",
+        0x0414 => "no",
+        0x0406 => "dk",
+        _ => return None,
+    };
+    find_layout(id)
+}
+
+/// The active keyboard layout if it's one with known problem keys. Detecting
+/// it runs a system command, so it only happens once.
+pub fn current_layout() -> Option<&'static KeyboardLayout> {
+    static LAYOUT: OnceLock<Option<&'static KeyboardLayout>> = OnceLock::new();
+    *LAYOUT.get_or_init(detect_layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_key_issues() {
+        let german = find_layout("de,us").unwrap();
+        assert_eq!(german.name, "German");
+        assert_eq!(german.key_issue("\\"), Some(KeyIssue::NeedsAltGr));
+        assert_eq!(german.key_issue("^"), Some(KeyIssue::DeadKey));
+        assert_eq!(german.key_issue("/"), None);
+        assert_eq!(german.key_issue("ArrowUp"), None);
+
+        assert!(find_layout("de(nodeadkeys)").is_some());
+        assert!(find_layout("us").is_none());
+    }
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use tauri::{ipc::JavaScriptChannelId, AppHandle, Webview, Window};
+
+use crate::config::{Config, LayoutConfig};
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty;
+
+/// Most rows or columns a layout grid may have
+pub const MAX_GRID_SIZE: u16 = 16;
+
+/// Grid cells a pane covers, spans filled in
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct PanePlacement {
+    pub row: u16,
+    pub column: u16,
+    pub row_span: u16,
+    pub column_span: u16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresetPane {
+    #[serde(flatten)]
+    pub placement: PanePlacement,
+    pub profile: Option<String>,
+    pub cwd: Option<String>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+}
+
+/// A layout preset as the frontend lays it out
+#[derive(Debug, Serialize)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub rows: u16,
+    pub columns: u16,
+    pub panes: Vec<PresetPane>,
+}
+
+/// Output channel and size of one pane, in the order of the preset's panes
+#[derive(Deserialize)]
+pub struct PaneChannel {
+    pub channel: JavaScriptChannelId,
+    pub rows: u16,
+    pub cols: u16,
+}
+
+/// A pane opened by `open_layout` and the PTY running in it
+#[derive(Debug, Serialize)]
+pub struct OpenedPane {
+    pub pty_id: String,
+    #[serde(flatten)]
+    pub placement: PanePlacement,
+}
+
+/// Where each pane of a layout goes. Panes have to fit the grid without
+/// overlapping, cells no pane covers are left empty.
+pub fn place(layout: &LayoutConfig) -> Result<Vec<PanePlacement>, String> {
+    if !(1..=MAX_GRID_SIZE).contains(&layout.rows) || !(1..=MAX_GRID_SIZE).contains(&layout.columns)
+    {
+        return Err(format!(
+            "Grid of {}x{} cells, rows and columns have to be between 1 and {}",
+            layout.rows, layout.columns, MAX_GRID_SIZE
+        ));
+    }
+    if layout.panes.is_empty() {
+        return Err("Layout has no panes".to_string());
+    }
+
+    let mut taken = vec![false; (layout.rows * layout.columns) as usize];
+    let mut placements = Vec::with_capacity(layout.panes.len());
+    for (i, pane) in layout.panes.iter().enumerate() {
+        let placement = PanePlacement {
+            row: pane.row,
+            column: pane.column,
+            row_span: pane.row_span.unwrap_or(1),
+            column_span: pane.column_span.unwrap_or(1),
+        };
+        let fits = placement.row_span > 0
+            && placement.column_span > 0
+            && placement.row as u32 + placement.row_span as u32 <= layout.rows as u32
+            && placement.column as u32 + placement.column_span as u32 <= layout.columns as u32;
+        if !fits {
+            return Err(format!("Pane {} doesn't fit the grid", i + 1));
+        }
+        for row in placement.row..placement.row + placement.row_span {
+            for column in placement.column..placement.column + placement.column_span {
+                let cell = &mut taken[(row * layout.columns + column) as usize];
+                if *cell {
+                    return Err(format!(
+                        "Pane {} overlaps another pane at row {}, column {}",
+                        i + 1,
+                        row,
+                        column
+                    ));
+                }
+                *cell = true;
+            }
+        }
+        placements.push(placement);
+    }
+    Ok(placements)
+}
+
+// The layout preset with its panes placed
+fn preset(config: &Config, name: &str) -> CommandResult<LayoutPreset> {
+    let layout = config
+        .layouts
+        .as_ref()
+        .and_then(|layouts| layouts.get(name))
+        .ok_or_else(|| {
+            TermillionError::new(ErrorKind::NotFound, format!("Layout '{}' not found", name))
+                .with_context(name)
+        })?;
+    let placements = place(layout).map_err(|e| {
+        TermillionError::new(ErrorKind::InvalidInput, format!("Layout '{}': {}", name, e))
+            .with_context(name)
+    })?;
+    Ok(LayoutPreset {
+        name: name.to_string(),
+        rows: layout.rows,
+        columns: layout.columns,
+        panes: layout
+            .panes
+            .iter()
+            .zip(placements)
+            .map(|(pane, placement)| PresetPane {
+                placement,
+                profile: pane.profile.clone(),
+                cwd: pane.cwd.as_deref().map(pty::expand_home),
+                command: pane.command.clone(),
+                args: pane.args.clone(),
+            })
+            .collect(),
+    })
+}
+
+// A layout preset from the `[layouts]` config, so the frontend can set up
+// its panes before opening it
+#[tauri::command]
+pub async fn get_layout_preset(app: AppHandle, name: String) -> CommandResult<LayoutPreset> {
+    preset(&Config::load(&app)?, &name)
+}
+
+// Start the PTYs of every pane of a layout preset, as panes of one tab.
+// Takes one output channel per pane, in the order of the preset's panes.
+// Either all PTYs start or none do.
+#[tauri::command]
+pub async fn open_layout(
+    window: Window,
+    webview: Webview,
+    app: AppHandle,
+    name: String,
+    tab_id: Option<String>,
+    panes: Vec<PaneChannel>,
+) -> CommandResult<Vec<OpenedPane>> {
+    let preset = preset(&Config::load(&app)?, &name)?;
+    if panes.len() != preset.panes.len() {
+        return Err(TermillionError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Layout '{}' has {} panes, got {} channels",
+                name,
+                preset.panes.len(),
+                panes.len()
+            ),
+        )
+        .with_context(&name));
+    }
+
+    let mut opened: Vec<OpenedPane> = Vec::with_capacity(panes.len());
+    for (pane, output) in preset.panes.into_iter().zip(panes) {
+        let created = pty::create_pty(
+            window.clone(),
+            app.clone(),
+            pane.cwd,
+            output.rows,
+            output.cols,
+            pane.command,
+            pane.args,
+            pane.profile,
+            output.channel.channel_on(webview.clone()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            tab_id.clone(),
+            None,
+        )
+        .await;
+        match created {
+            Ok(pty_id) => opened.push(OpenedPane {
+                pty_id,
+                placement: pane.placement,
+            }),
+            Err(e) => {
+                for pane in opened {
+                    let _ = pty::destroy_pty(pane.pty_id).await;
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(opened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LayoutPaneConfig;
+
+    fn pane(row: u16, column: u16, row_span: Option<u16>) -> LayoutPaneConfig {
+        LayoutPaneConfig {
+            row,
+            column,
+            row_span,
+            column_span: None,
+            profile: None,
+            cwd: None,
+            command: None,
+            args: None,
+        }
+    }
+
+    #[test]
+    fn test_place_layout() {
+        let mut layout = LayoutConfig {
+            rows: 2,
+            columns: 2,
+            panes: vec![pane(0, 0, Some(2)), pane(0, 1, None), pane(1, 1, None)],
+        };
+        let placements = place(&layout).unwrap();
+        assert_eq!(placements[0].row_span, 2);
+        assert_eq!(placements[2].column_span, 1);
+
+        layout.panes.push(pane(1, 0, None));
+        assert!(place(&layout).unwrap_err().contains("overlaps"));
+        layout.panes[3] = pane(2, 0, None);
+        assert!(place(&layout).unwrap_err().contains("doesn't fit"));
+        layout.panes[3] = pane(0, 0, Some(0));
+        assert!(place(&layout).unwrap_err().contains("doesn't fit"));
+
+        layout.rows = 0;
+        assert!(place(&layout).is_err());
+    }
+}
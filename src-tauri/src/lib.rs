@@ -1,53 +1,284 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+mod actions;
+mod annotations;
+mod backup;
+mod cli;
+mod color;
+mod command_history;
+mod completions;
 mod config;
+mod config_format;
+mod config_metadata;
+mod config_template;
+mod control;
+mod crash;
+mod deep_link;
+mod dropdown;
+mod dynamic_profiles;
+mod editor;
+mod error;
+mod export;
+mod file_drop;
+mod finder;
+mod fonts;
+mod frecency;
+mod git;
+mod history;
+mod hooks;
+mod importers;
+mod jsonc;
+mod keyboard;
+mod layouts;
+mod links;
+mod lint;
+mod logging;
+mod merge;
+mod metrics;
+mod migrations;
+mod normalize;
+mod notifications;
+mod palette;
+mod paste;
+mod profile_rules;
+mod progress;
 mod pty;
+mod scripting;
+mod search;
+mod secrets;
+mod selection;
+mod session;
+mod shell_integration;
+mod sync;
+mod tasks;
+mod tray;
+mod updates;
 mod validation;
+mod watch;
+mod window;
+
+/// Internals the benchmarks in `benches/` measure, not a stable API
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::pty::{OutputBatcher, PtyOutputEvent};
+}
 
 use std::env;
 
-use config::Config;
-use tauri::Manager;
+use cli::{Cli, Command};
+use config::{ClosePtys, Config};
+use config_format::ConfigFormat;
+use error::{CommandResult, ErrorKind, TermillionError};
+use session::SessionTab;
+use tauri::{LogicalSize, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 use validation::ValidationError;
 
 #[tauri::command]
-async fn validate_config(app: tauri::AppHandle) -> Result<Vec<ValidationError>, String> {
+async fn validate_config(app: tauri::AppHandle) -> CommandResult<Vec<ValidationError>> {
     let config = Config::load(&app)?;
     Ok(config.validate())
 }
 
 #[tauri::command]
-async fn get_config(app: tauri::AppHandle) -> Result<Config, String> {
-    Config::load(&app)
+async fn get_config(app: tauri::AppHandle) -> CommandResult<Config> {
+    let mut config = Config::load(&app)?;
+    dynamic_profiles::merge(&app, &mut config);
+    Ok(config)
+}
+
+#[tauri::command]
+async fn save_config(app: tauri::AppHandle, mut config: Config) -> CommandResult<()> {
+    // Generated profiles come back from `get_config` but aren't saved
+    dynamic_profiles::strip(&app, &mut config);
+    config.save(&app)?;
+    logging::apply_config(&config)?;
+    tray::refresh(&app, &config)?;
+    dropdown::setup(&app, &config)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn reset_config_section(
+    app: tauri::AppHandle,
+    section: String,
+) -> CommandResult<Vec<ValidationError>> {
+    let mut config = Config::load(&app)?;
+    config.reset_section(&section)?;
+    config.save(&app)?;
+    Ok(config.validate())
 }
 
 #[tauri::command]
-async fn save_config(app: tauri::AppHandle, config: Config) -> Result<(), String> {
-    config.save(&app)
+async fn convert_config_format(app: tauri::AppHandle, format: String) -> CommandResult<String> {
+    let format = ConfigFormat::from_extension(&format).ok_or_else(|| {
+        TermillionError::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown config format: {}", format),
+        )
+        .with_context(&format)
+    })?;
+    let path = Config::convert_format(&app, format)?;
+    Ok(path.display().to_string())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let cli = Cli::parse_args(env::args_os());
+
+    // `termillion cli ...` talks to the running app and exits
+    if let Some(Command::Cli(request)) = cli.command {
+        std::process::exit(control::run_client(request));
+    }
+
+    // --generate-config [path] writes the documented default config and exits
+    if let Some(path) = &cli.generate_config {
+        let result = match path {
+            Some(path) => config_template::write_default_config_to(path),
+            None => config_template::render_default_config().map(|config| print!("{}", config)),
+        };
+        if let Err(e) = result {
+            // Logging isn't set up yet, the error is for whoever ran the command
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Paths on the command line are relative to where termillion was started
+    let absolute = |path: &std::path::Path| {
+        env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    if let Some(path) = &cli.config {
+        config::set_config_path(absolute(path));
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_window_state::Builder::new().build())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![
-            get_config,
-            save_config,
-            validate_config,
-            // PTY commands
-            pty::create_pty,
-            pty::write_pty,
-            pty::resize_pty,
-            pty::destroy_pty,
-            pty::is_pty_alive,
-            pty::get_active_ptys,
-            pty::get_pty_metrics
-        ])
-        .setup(|app| {
-            let process_arg: Vec<String> = env::args().collect();
-            if process_arg.contains(&"--debug".to_string()) {
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(session::SessionState::default())
+        .manage(palette::PaletteState::default())
+        .manage(updates::UpdateState::default())
+        .manage(scripting::ScriptState::default())
+        .manage(frecency::FrecencyState::default())
+        .manage(command_history::CommandHistoryState::default())
+        .manage(search::SearchState::default())
+        .manage(git::GitState::default())
+        .manage(watch::WatchState::default())
+        .manage(dynamic_profiles::DynamicProfilesState::default())
+        .invoke_handler({
+            let handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+                get_config,
+                save_config,
+                validate_config,
+                validation::apply_validation_fixes,
+                reset_config_section,
+                convert_config_format,
+                backup::list_config_backups,
+                backup::restore_config_backup,
+                sync::sync_config_now,
+                secrets::set_secret,
+                secrets::delete_secret,
+                config_template::write_default_config,
+                config_metadata::get_config_metadata,
+                lint::lint_config,
+                fonts::list_monospace_fonts,
+                migrations::preview_config_migration,
+                selection::process_selection,
+                selection::set_primary_selection,
+                selection::get_primary_selection,
+                actions::execute_action,
+                palette::palette_query,
+                palette::record_palette_entry,
+                frecency::query_recent_dirs,
+                editor::open_in_editor,
+                editor::detect_editors,
+                finder::find_files,
+                search::search_in_directory,
+                search::cancel_search,
+                git::get_git_status,
+                watch::watch_and_run,
+                watch::stop_watch,
+                watch::get_watches,
+                tasks::run_task,
+                dynamic_profiles::refresh_dynamic_profiles,
+                layouts::get_layout_preset,
+                layouts::open_layout,
+                links::resolve_link,
+                export::export_pty_buffer_html,
+                normalize::strip_ansi,
+                normalize::normalize_output,
+                paste::analyze_paste,
+                paste::begin_paste_stream,
+                paste::paste_chunk,
+                paste::end_paste,
+                history::get_shell_history,
+                shell_integration::get_shell_integration,
+                command_history::search_command_history,
+                completions::get_completions,
+                crash::get_last_crash_report,
+                logging::get_recent_logs,
+                logging::set_log_level,
+                metrics::get_app_metrics,
+                updates::check_for_updates,
+                updates::install_update,
+                scripting::reload_scripts,
+                scripting::list_scripts,
+                // Session commands
+                session::update_session,
+                session::get_startup_session,
+                session::save_session,
+                session::restore_session,
+                // Import commands
+                importers::import_windows_terminal,
+                importers::import_theme,
+                // PTY commands
+                pty::create_pty,
+                pty::create_elevated_pty,
+                pty::set_pane_sync,
+                pty::get_pane_sync,
+                pty::write_pty,
+                pty::resize_pty,
+                pty::get_pty_size,
+                pty::get_command_blocks,
+                pty::get_command_output,
+                pty::get_scrollback,
+                pty::audit_ptys,
+                pty::destroy_pty,
+                pty::is_pty_alive,
+                pty::get_blocking_processes,
+                pty::get_active_ptys,
+                pty::list_detached_ptys,
+                pty::set_pty_decoration,
+                pty::set_pty_logging,
+                pty::get_pty_metrics,
+                pty::get_all_pty_metrics,
+                pty::transfer_pty,
+                pty::attach_pty,
+                pty::benchmark_pty_pipeline,
+                // Window commands
+                window::create_window,
+                window::close_window,
+                dropdown::toggle_dropdown
+            ];
+            // Counted for the IPC rates of get_app_metrics
+            move |invoke| {
+                metrics::record_invoke();
+                handler(invoke)
+            }
+        })
+        .setup(move |app| {
+            logging::init(app.handle());
+            crash::install_panic_hook(app.handle());
+            scripting::init(app.handle());
+
+            if cli.debug {
                 // in prod build, if --debug is passed, open devtools
                 app.get_webview_window("main").unwrap().open_devtools();
             }
@@ -55,8 +286,128 @@ pub fn run() {
             #[cfg(debug_assertions)]
             app.get_webview_window("main").unwrap().open_devtools();
 
+            // Apply the configured window geometry and startup state
+            if let Ok(config) = Config::load(app.handle()) {
+                if let Some(window) = app.get_webview_window("main") {
+                    window::apply_startup_config(&window, &config)?;
+                }
+                if let Err(e) = dropdown::setup(app.handle(), &config) {
+                    tracing::warn!("{}", e);
+                }
+                tray::setup(app.handle(), &config)?;
+            }
+
+            // Command line options win over the config and the previous session
+            if let Some(window) = app.get_webview_window("main") {
+                if let Some((width, height)) = cli.window_size {
+                    window.set_size(LogicalSize::new(width, height))?;
+                }
+                if let Some(title) = &cli.title {
+                    window.set_title(title)?;
+                }
+            }
+            if let Some(url) = &cli.url {
+                match deep_link::url_tab(url) {
+                    Ok(tab) => session::set_startup_tab(app.handle(), tab),
+                    Err(e) => tracing::warn!("{}", e),
+                }
+            } else if cli.has_tab_options() {
+                let mut execute = cli.execute.iter().cloned();
+                let tab = SessionTab {
+                    profile: cli.profile.clone(),
+                    cwd: cli
+                        .cwd
+                        .as_deref()
+                        .map(|cwd| absolute(cwd).to_string_lossy().into_owned()),
+                    title: cli.title.clone(),
+                    command: execute.next(),
+                    args: Some(execute.collect()),
+                    pty_id: None,
+                    task: None,
+                    decoration: None,
+                };
+                session::set_startup_tab(app.handle(), tab);
+            }
+
+            // Installers register the URL schemes, development builds do it here
+            #[cfg(all(debug_assertions, any(target_os = "linux", target_os = "windows")))]
+            app.deep_link().register_all()?;
+
+            // Linux and Windows start a new process for each link, macOS hands
+            // links to the running app
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    if let Err(e) = deep_link::open_url(&handle, url.as_str()) {
+                        tracing::warn!("Failed to open {}: {}", url, e);
+                    }
+                }
+            });
+
+            sync::start_auto_sync(app.handle().clone());
+            session::start_autosave(app.handle().clone());
+            frecency::start_tracking(app.handle().clone());
+            pty::remove_stale_spills(app.handle());
+            dynamic_profiles::refresh_in_background(app.handle());
+            control::start_server(app.handle().clone());
+            updates::start_update_checks(app.handle().clone());
+
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_page_load(|webview, payload| {
+            if payload.event() == tauri::webview::PageLoadEvent::Started {
+                pty::window_reloaded(webview.label());
+            }
+        })
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { .. } => {
+                if let Err(e) = session::save(window.app_handle()) {
+                    tracing::warn!("Failed to save session: {}", e);
+                }
+            }
+            tauri::WindowEvent::Focused(true) => tray::clear_bell(window.app_handle()),
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                file_drop::handle_drop(window, paths)
+            }
+            tauri::WindowEvent::Destroyed => {
+                let app = window.app_handle();
+                let others_open = app
+                    .webview_windows()
+                    .keys()
+                    .any(|label| label != window.label() && label != dropdown::DROPDOWN_LABEL);
+
+                // Shells of a closed window have nowhere to show their output,
+                // unless they are kept for another window to take over
+                let close_ptys = Config::load(app)
+                    .ok()
+                    .and_then(|config| config.window.close_ptys)
+                    .unwrap_or_default();
+                if close_ptys == ClosePtys::Detach && others_open {
+                    pty::detach_window_ptys(window.label());
+                } else {
+                    pty::destroy_window_ptys(window.label());
+                }
+                session::forget(app, window.label());
+
+                // The hidden dropdown alone shouldn't keep the app running
+                if window.label() != dropdown::DROPDOWN_LABEL && !others_open {
+                    app.exit(0);
+                }
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Quitting from the tray skips CloseRequested
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = session::save(app) {
+                    tracing::warn!("Failed to save session: {}", e);
+                }
+                if let Err(e) = frecency::save(app) {
+                    tracing::warn!("Failed to save directories: {}", e);
+                }
+                control::stop_server();
+            }
+        });
 }
@@ -0,0 +1,163 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::process::{Command, Stdio};
+use std::thread;
+use tauri::AppHandle;
+
+use crate::config::{Config, LinkRule};
+use crate::pty;
+
+/// What a link resolved to
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResolvedLink {
+    /// The frontend opens the URL
+    Url { url: String },
+    /// The backend started the rule's command
+    Command { command: String, args: Vec<String> },
+}
+
+// Keep URL-safe characters, percent-encode the rest
+fn encode_component(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Fill in `{0}` for the whole match, `{1}`, `{2}`... for groups and
+/// `{name}` for named groups. Unknown placeholders are left as they are.
+fn expand(template: &str, captures: &Captures, names: &[&str], encode: bool) -> String {
+    let value = |text: &str| {
+        if encode {
+            encode_component(text)
+        } else {
+            text.to_string()
+        }
+    };
+    let mut expanded = template.to_string();
+    for index in 0..captures.len() {
+        let text = captures.get(index).map_or("", |m| m.as_str());
+        expanded = expanded.replace(&format!("{{{}}}", index), &value(text));
+    }
+    for name in names {
+        let text = captures.name(name).map_or("", |m| m.as_str());
+        expanded = expanded.replace(&format!("{{{}}}", name), &value(text));
+    }
+    expanded
+}
+
+/// Resolve text matched by a rule to its URL or command line
+pub fn resolve(rule_name: &str, rule: &LinkRule, text: &str) -> Result<ResolvedLink, String> {
+    let pattern = Regex::new(&rule.pattern)
+        .map_err(|e| format!("Link rule '{}' has an invalid pattern: {}", rule_name, e))?;
+    let captures = pattern
+        .captures(text)
+        .ok_or_else(|| format!("'{}' doesn't match link rule '{}'", text, rule_name))?;
+    let names: Vec<&str> = pattern.capture_names().flatten().collect();
+
+    match (&rule.url, &rule.command) {
+        (Some(url), _) => Ok(ResolvedLink::Url {
+            url: expand(url, &captures, &names, true),
+        }),
+        (None, Some(command)) => Ok(ResolvedLink::Command {
+            command: expand(command, &captures, &names, false),
+            args: rule
+                .args
+                .iter()
+                .flatten()
+                .map(|arg| expand(arg, &captures, &names, false))
+                .collect(),
+        }),
+        (None, None) => Err(format!(
+            "Link rule '{}' needs a url or a command",
+            rule_name
+        )),
+    }
+}
+
+// Turn text the user clicked into what it links to. Without a rule name the
+// first rule matching the text is used. Commands are started right away.
+#[tauri::command]
+pub async fn resolve_link(
+    app: AppHandle,
+    text: String,
+    rule: Option<String>,
+) -> Result<ResolvedLink, String> {
+    let config = Config::load(&app)?;
+    let rules = config.link_rules.unwrap_or_default();
+    let (name, link_rule) = match rule {
+        Some(name) => {
+            let link_rule = rules
+                .get(&name)
+                .ok_or_else(|| format!("No link rule named '{}'", name))?;
+            (name.clone(), link_rule)
+        }
+        None => rules
+            .iter()
+            .find(|(_, r)| Regex::new(&r.pattern).is_ok_and(|p| p.is_match(&text)))
+            .map(|(name, r)| (name.clone(), r))
+            .ok_or_else(|| format!("No link rule matches '{}'", text))?,
+    };
+
+    let resolved = resolve(&name, link_rule, &text)?;
+    if let ResolvedLink::Command { command, args } = &resolved {
+        let mut child = Command::new(pty::expand_home(command))
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+        thread::spawn(move || child.wait());
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_link() {
+        let jira = LinkRule {
+            pattern: r"\b(?P<project>[A-Z]+)-(\d+)\b".into(),
+            url: Some("https://jira.example.com/browse/{project}-{2}?from={0}".into()),
+            command: None,
+            args: None,
+        };
+        assert_eq!(
+            resolve("jira", &jira, "fixes OPS-12").unwrap(),
+            ResolvedLink::Url {
+                url: "https://jira.example.com/browse/OPS-12?from=OPS-12".into()
+            }
+        );
+        assert!(resolve("jira", &jira, "nothing here").is_err());
+
+        let issue = LinkRule {
+            pattern: r"#(\d+)".into(),
+            url: None,
+            command: Some("gh".into()),
+            args: Some(vec![
+                "issue".into(),
+                "view".into(),
+                "{1}".into(),
+                "--web".into(),
+            ]),
+        };
+        assert_eq!(
+            resolve("issue", &issue, "#42").unwrap(),
+            ResolvedLink::Command {
+                command: "gh".into(),
+                args: vec!["issue".into(), "view".into(), "42".into(), "--web".into()],
+            }
+        );
+
+        assert_eq!(encode_component("a b/c"), "a%20b%2Fc");
+    }
+}
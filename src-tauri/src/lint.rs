@@ -0,0 +1,312 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use tauri::AppHandle;
+use toml::{Table, Value};
+
+use crate::config::get_config_path;
+use crate::config_format::ConfigFormat;
+use crate::config_template::{example_config, field_docs};
+use crate::merge;
+use crate::migrations;
+
+/// Tables whose keys are chosen by the user
+const FREE_FORM: [&str; 2] = ["env", "features"];
+
+/// Tables of entries that all share one layout, like `shortcuts.<action>`
+const MAPS: [&str; 1] = ["shortcuts"];
+
+/// Kind of problem a lint finding describes
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    /// A key the config doesn't know, usually a typo
+    UnknownKey,
+    /// A value in a format that is no longer supported
+    Deprecated,
+    /// A profile that can never be opened
+    UnreachableProfile,
+}
+
+/// A single change to the config file, `None` removes the key
+#[derive(Debug, Serialize)]
+pub struct LintEdit {
+    pub path: String,
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintFinding {
+    pub kind: LintKind,
+    /// Path of the offending key, array items are written as `list[0]`
+    pub path: String,
+    pub message: String,
+    /// Edits that resolve the finding, empty when there is no safe fix
+    pub fix: Vec<LintEdit>,
+}
+
+// Number of single character edits between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Closest known key, close enough to be a typo
+fn suggest<'a>(key: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let limit = (key.len() / 3).max(1);
+    known
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+struct Linter<'a> {
+    root_schema: &'a Table,
+    findings: Vec<LintFinding>,
+}
+
+impl Linter<'_> {
+    // `path` is the path used for field docs, `prefix` the one reported
+    fn walk(&mut self, path: &mut Vec<String>, prefix: &str, table: &Table, schema: &Table) {
+        for (key, value) in table {
+            let key_path = format!("{}{}", prefix, key);
+            let known = schema.contains_key(key) || field_docs(path, key).is_some();
+
+            if !known {
+                self.unknown_key(&key_path, prefix, key, value, schema);
+                continue;
+            }
+            if let Some(finding) = deprecated(path, key, value, &key_path) {
+                self.findings.push(finding);
+                continue;
+            }
+
+            path.push(key.clone());
+            match (value, schema.get(key)) {
+                // Platform overrides take the same keys as the main config
+                (Value::Table(table), _) if *path == [merge::OVERRIDES_KEY] => {
+                    let root_schema = self.root_schema;
+                    for (platform, overrides) in table {
+                        if let Value::Table(overrides) = overrides {
+                            let prefix = format!("{}.{}.", key_path, platform);
+                            self.walk(&mut Vec::new(), &prefix, overrides, root_schema);
+                        }
+                    }
+                }
+                _ if FREE_FORM.contains(&key.as_str()) => {}
+                (Value::Table(table), Some(Value::Table(schema)))
+                    if path.len() == 1 && MAPS.contains(&key.as_str()) =>
+                {
+                    if let Some(Value::Table(entry_schema)) = schema.values().next() {
+                        for (name, entry) in table {
+                            if let Value::Table(entry) = entry {
+                                path.push(name.clone());
+                                let prefix = format!("{}.{}.", key_path, name);
+                                self.walk(path, &prefix, entry, entry_schema);
+                                path.pop();
+                            }
+                        }
+                    }
+                }
+                (Value::Table(table), Some(Value::Table(schema))) => {
+                    self.walk(path, &format!("{}.", key_path), table, schema);
+                }
+                (Value::Array(items), Some(Value::Array(schema_items))) => {
+                    if let Some(Value::Table(item_schema)) = schema_items.first() {
+                        for (index, item) in items.iter().enumerate() {
+                            if let Value::Table(item) = item {
+                                let prefix = format!("{}[{}].", key_path, index);
+                                self.walk(path, &prefix, item, item_schema);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+            path.pop();
+        }
+    }
+
+    fn unknown_key(
+        &mut self,
+        key_path: &str,
+        prefix: &str,
+        key: &str,
+        value: &Value,
+        schema: &Table,
+    ) {
+        let suggestion = suggest(key, schema.keys());
+        let (message, fix) = match suggestion {
+            Some(suggestion) => (
+                format!("Unknown key '{}', did you mean '{}'?", key, suggestion),
+                vec![
+                    LintEdit {
+                        path: key_path.to_string(),
+                        value: None,
+                    },
+                    LintEdit {
+                        path: format!("{}{}", prefix, suggestion),
+                        value: Some(value.clone()),
+                    },
+                ],
+            ),
+            None => (
+                format!("Unknown key '{}' is ignored", key),
+                vec![LintEdit {
+                    path: key_path.to_string(),
+                    value: None,
+                }],
+            ),
+        };
+        self.findings.push(LintFinding {
+            kind: LintKind::UnknownKey,
+            path: key_path.to_string(),
+            message,
+            fix,
+        });
+    }
+
+    // Later profiles with the same name are shadowed by the first one, and
+    // profiles without a name can't be picked at all
+    fn profiles(&mut self, table: &Table) {
+        let Some(Value::Array(list)) = table
+            .get("profiles")
+            .and_then(Value::as_table)
+            .and_then(|profiles| profiles.get("list"))
+        else {
+            return;
+        };
+
+        let names: Vec<&str> = list
+            .iter()
+            .map(|p| p.get("name").and_then(Value::as_str).unwrap_or_default())
+            .collect();
+        let mut seen = HashSet::new();
+        for (index, name) in names.iter().enumerate() {
+            let path = format!("profiles.list[{}].name", index);
+            let message = if name.trim().is_empty() {
+                "Profile has no name and can't be selected".to_string()
+            } else if !seen.insert(*name) {
+                format!(
+                    "Profile '{}' is shadowed by an earlier profile with the same name",
+                    name
+                )
+            } else {
+                continue;
+            };
+
+            // Pick a name no other profile uses
+            let base = if name.trim().is_empty() {
+                "Profile"
+            } else {
+                name
+            };
+            let fixed = (2..)
+                .map(|n| format!("{} ({})", base, n))
+                .find(|candidate| !names.contains(&candidate.as_str()))
+                .unwrap_or_default();
+            self.findings.push(LintFinding {
+                kind: LintKind::UnreachableProfile,
+                path: path.clone(),
+                message,
+                fix: vec![LintEdit {
+                    path,
+                    value: Some(Value::String(fixed)),
+                }],
+            });
+        }
+    }
+}
+
+// Values in a format older versions wrote
+fn deprecated(path: &[String], key: &str, value: &Value, key_path: &str) -> Option<LintFinding> {
+    match (path, key, value) {
+        // Padding used to be a single number for both directions
+        ([terminal], "padding", Value::Integer(padding)) if terminal == "terminal" => {
+            Some(LintFinding {
+                kind: LintKind::Deprecated,
+                path: key_path.to_string(),
+                message: "A single padding number is no longer supported, use { x, y } instead"
+                    .into(),
+                fix: vec![LintEdit {
+                    path: key_path.to_string(),
+                    value: Some(Value::Table(Table::from_iter([
+                        ("x".to_string(), Value::Integer(*padding)),
+                        ("y".to_string(), Value::Integer(*padding)),
+                    ]))),
+                }],
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Find likely mistakes in a config table that validation can't see because
+/// they are dropped or shadowed when the config is parsed
+pub fn lint(table: &Table) -> Result<Vec<LintFinding>, String> {
+    let schema = Table::try_from(example_config())
+        .map_err(|e| format!("Failed to serialize example config: {}", e))?;
+    let mut linter = Linter {
+        root_schema: &schema,
+        findings: Vec::new(),
+    };
+    linter.walk(&mut Vec::new(), "", table, &schema);
+    linter.profiles(table);
+    Ok(linter.findings)
+}
+
+// Lint the config file as written, after bringing it up to the current version
+#[tauri::command]
+pub async fn lint_config(app: AppHandle) -> Result<Vec<LintFinding>, String> {
+    let config_path = get_config_path(&app)?;
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let table = ConfigFormat::from_path(&config_path)
+        .parse(&content)
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+    let (table, _) = migrations::migrate(table)?;
+    lint(&table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_findings() {
+        let mut table = Table::try_from(crate::config::Config::default()).unwrap();
+        table["font"]
+            .as_table_mut()
+            .unwrap()
+            .insert("sise".into(), Value::Integer(16));
+        table["terminal"]
+            .as_table_mut()
+            .unwrap()
+            .insert("padding".into(), Value::Integer(4));
+        let profiles = table["profiles"]["list"].as_array_mut().unwrap();
+        profiles.push(profiles[0].clone());
+
+        let findings = lint(&table).unwrap();
+        assert_eq!(findings.len(), 3);
+
+        let typo = &findings[0];
+        assert_eq!(typo.kind, LintKind::UnknownKey);
+        assert_eq!(typo.path, "font.sise");
+        assert_eq!(typo.fix[1].path, "font.size");
+
+        assert_eq!(findings[1].kind, LintKind::Deprecated);
+        assert_eq!(findings[2].kind, LintKind::UnreachableProfile);
+        assert_eq!(findings[2].path, "profiles.list[1].name");
+    }
+}
@@ -0,0 +1,289 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Layer, Registry};
+
+use crate::config::{Config, LogLevel};
+
+/// Name of the log files in the log directory, rotated daily
+const LOG_FILE: &str = "termillion.log";
+
+/// Records kept in memory for `get_recent_logs` and crash reports
+const RECENT_CAPACITY: usize = 1000;
+
+const DEFAULT_RECENT_LIMIT: usize = 200;
+
+static RECENT: Mutex<VecDeque<LogRecord>> = Mutex::new(VecDeque::new());
+
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+// Flushes the file writer when dropped, so it lives as long as the app
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            Level::TRACE => LogLevel::Trace,
+        }
+    }
+}
+
+/// A logged event
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    pub time: String,
+    pub level: LogLevel,
+    /// Module that logged it
+    pub target: String,
+    /// Spans the event happened in, outermost first, like `pty{id=...}`
+    pub spans: String,
+    /// The message followed by the event's other fields
+    pub message: String,
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?} {}", self.time, self.level, self.target)?;
+        if !self.spans.is_empty() {
+            write!(f, " {}", self.spans)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// Collects the message and the other fields of an event into one line
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.0);
+            let _ = write!(self.0, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+// Fields of a span, formatted once when it's created
+struct SpanFields(String);
+
+// Keeps the latest events in memory
+struct RecentLayer;
+
+impl<S> Layer<S> for RecentLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            let fields = visitor.0.trim_start().to_string();
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| {
+                        let extensions = span.extensions();
+                        match extensions.get::<SpanFields>() {
+                            Some(SpanFields(fields)) if !fields.is_empty() => {
+                                format!("{}{{{}}}", span.name(), fields)
+                            }
+                            _ => span.name().to_string(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+
+        push_record(LogRecord {
+            time: chrono::Local::now().to_rfc3339(),
+            level: event.metadata().level().into(),
+            target: event.metadata().target().to_string(),
+            spans,
+            message: visitor.0,
+        });
+    }
+}
+
+fn push_record(record: LogRecord) {
+    // Wait for the lock so records aren't lost, only the panic hook reading
+    // them has to avoid blocking
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(record);
+}
+
+/// The latest records at `level` or more severe, oldest first
+pub fn select(records: &VecDeque<LogRecord>, level: LogLevel, limit: usize) -> Vec<LogRecord> {
+    let mut selected: Vec<LogRecord> = records
+        .iter()
+        .rev()
+        .filter(|record| record.level <= level)
+        .take(limit)
+        .cloned()
+        .collect();
+    selected.reverse();
+    selected
+}
+
+/// The last lines logged, formatted, for crash reports
+pub fn recent_lines(limit: usize) -> Vec<String> {
+    RECENT
+        .try_lock()
+        .map(|recent| {
+            select(&recent, LogLevel::Trace, limit)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Log to stderr, to a daily log file in the app's log directory and to
+/// memory, at the level set in `logging.level`
+pub fn init(app: &AppHandle) {
+    let level = Config::load(app)
+        .ok()
+        .and_then(|config| config.logging)
+        .and_then(|logging| logging.level)
+        .unwrap_or_default();
+    let (filter, handle) = reload::Layer::new(LevelFilter::from(level));
+
+    let file = app.path().app_log_dir().ok().map(|dir| {
+        let (writer, guard) =
+            tracing_appender::non_blocking(tracing_appender::rolling::daily(dir, LOG_FILE));
+        let _ = FILE_GUARD.set(guard);
+        fmt::layer().with_ansi(false).with_writer(writer)
+    });
+
+    // Both fmt layers share the formatted span fields, so neither can use
+    // colors without them ending up in the file
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(false).with_writer(std::io::stderr))
+        .with(file)
+        .with(RecentLayer)
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = LEVEL.set(handle);
+        }
+        Err(e) => eprintln!("Failed to set up logging: {}", e),
+    }
+}
+
+/// Change the level of a running app
+pub fn set_level(level: LogLevel) -> Result<(), String> {
+    LEVEL
+        .get()
+        .ok_or_else(|| "Logging isn't set up".to_string())?
+        .modify(|filter| *filter = level.into())
+        .map_err(|e| format!("Failed to set log level: {}", e))
+}
+
+/// Apply the level of a saved config
+pub fn apply_config(config: &Config) -> Result<(), String> {
+    match config.logging.as_ref().and_then(|logging| logging.level) {
+        Some(level) => set_level(level),
+        None => Ok(()),
+    }
+}
+
+// Latest log records at the given level or more severe, for diagnostics
+#[tauri::command]
+pub async fn get_recent_logs(
+    level: Option<LogLevel>,
+    limit: Option<usize>,
+) -> Result<Vec<LogRecord>, String> {
+    let recent = RECENT
+        .lock()
+        .map_err(|e| format!("Failed to lock logs: {}", e))?;
+    Ok(select(
+        &recent,
+        level.unwrap_or(LogLevel::Trace),
+        limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+    ))
+}
+
+// Change the log level until the app restarts, `logging.level` sets it for good
+#[tauri::command]
+pub async fn set_log_level(level: LogLevel) -> Result<(), String> {
+    set_level(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: LogLevel, message: &str) -> LogRecord {
+        LogRecord {
+            time: "2024-01-01T00:00:00+00:00".into(),
+            level,
+            target: "termillion_lib::pty::core".into(),
+            spans: String::new(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn test_select_records() {
+        let records = VecDeque::from([
+            record(LogLevel::Error, "read failed"),
+            record(LogLevel::Debug, "exit event sent"),
+            record(LogLevel::Warn, "slow resize"),
+            record(LogLevel::Info, "PTY started"),
+        ]);
+
+        let warnings = select(&records, LogLevel::Warn, 10);
+        let messages: Vec<&str> = warnings.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["read failed", "slow resize"]);
+
+        // The limit keeps the latest ones
+        let latest = select(&records, LogLevel::Trace, 2);
+        assert_eq!(latest[0].message, "slow resize");
+        assert_eq!(
+            latest[1].to_string(),
+            "2024-01-01T00:00:00+00:00 Info termillion_lib::pty::core: PTY started"
+        );
+    }
+}
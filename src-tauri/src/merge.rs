@@ -0,0 +1,427 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use toml_edit::TableLike;
+
+use crate::config_format::ConfigFormat;
+
+/// Key of the config table holding the platform overrides
+pub const OVERRIDES_KEY: &str = "overrides";
+
+/// Key of the list of files included into a config
+pub const INCLUDE_KEY: &str = "include";
+
+/// Name of the current platform as used in `[overrides.<platform>]`
+pub fn current_platform() -> &'static str {
+    #[cfg(target_os = "windows")]
+    {
+        "windows"
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        "macos"
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        "linux"
+    }
+}
+
+/// Recursively merge `overlay` into `base`. Tables are merged key by key,
+/// any other value (including arrays) in the overlay replaces the base value.
+pub fn deep_merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                deep_merge(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Load and merge every file listed in the table's `include` key, in order.
+/// Paths are relative to `base_dir` and included files may include others.
+pub fn load_includes(table: &Table, base_dir: &Path) -> Result<Table, String> {
+    load_includes_inner(table, base_dir, &mut Vec::new())
+}
+
+fn load_includes_inner(
+    table: &Table,
+    base_dir: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<Table, String> {
+    let mut merged = Table::new();
+    let Some(includes) = table.get(INCLUDE_KEY).and_then(Value::as_array) else {
+        return Ok(merged);
+    };
+
+    for include in includes {
+        let relative = include
+            .as_str()
+            .ok_or_else(|| "Config include entries must be strings".to_string())?;
+        let path = base_dir.join(relative);
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve include {}: {}", path.display(), e))?;
+
+        if chain.contains(&canonical) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(format!("Config include cycle: {}", cycle.join(" -> ")));
+        }
+
+        let content = fs::read_to_string(&canonical)
+            .map_err(|e| format!("Failed to read include {}: {}", path.display(), e))?;
+        let mut included = ConfigFormat::from_path(&canonical)
+            .parse(&content)
+            .map_err(|e| format!("Failed to parse include {}: {}", path.display(), e))?;
+
+        // Nested includes are resolved relative to the including file
+        chain.push(canonical.clone());
+        let nested_dir = canonical.parent().unwrap_or(base_dir);
+        let mut nested = load_includes_inner(&included, nested_dir, chain)?;
+        chain.pop();
+
+        included.remove(INCLUDE_KEY);
+        deep_merge(&mut nested, included);
+        deep_merge(&mut merged, nested);
+    }
+
+    Ok(merged)
+}
+
+/// Merge the included files under the table, values in the table itself win
+pub fn apply_includes(table: &mut Table, base_dir: &Path) -> Result<(), String> {
+    let mut merged = load_includes(table, base_dir)?;
+    deep_merge(&mut merged, std::mem::take(table));
+    *table = merged;
+    Ok(())
+}
+
+/// Reverse `apply_includes` before saving: drop values that come from an
+/// included file and weren't set in the main config or changed since loading.
+pub fn strip_included(table: &mut Table, included: &Table, main: Option<&Table>) {
+    let keys: Vec<String> = table.keys().cloned().collect();
+    for key in keys {
+        let Some(included_value) = included.get(&key) else {
+            continue;
+        };
+        let main_value = main.and_then(|m| m.get(&key));
+
+        match (table.get_mut(&key), included_value) {
+            (Some(Value::Table(current)), Value::Table(included_table)) => {
+                // Recurse unless the main config sets this key to a non-table
+                if main_value.is_none() || main_value.is_some_and(Value::is_table) {
+                    strip_included(
+                        current,
+                        included_table,
+                        main_value.and_then(Value::as_table),
+                    );
+                    if current.is_empty() && main_value.is_none() {
+                        table.remove(&key);
+                    }
+                }
+            }
+            (Some(current), included_value) => {
+                if main_value.is_none() && current == included_value {
+                    table.remove(&key);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+/// Get the override table for the current platform, if any
+fn platform_overrides(table: &Table) -> Option<&Table> {
+    table
+        .get(OVERRIDES_KEY)?
+        .as_table()?
+        .get(current_platform())?
+        .as_table()
+}
+
+/// Merge the current platform's overrides over the base config
+pub fn apply_platform_overrides(table: &mut Table) {
+    if let Some(overrides) = platform_overrides(table).cloned() {
+        deep_merge(table, overrides);
+    }
+}
+
+/// Reverse `apply_platform_overrides` before saving: values covered by an
+/// override are restored from the base config on disk, and values that were
+/// changed since loading are written into the override instead, so the base
+/// config other platforms see is left untouched.
+pub fn unapply_platform_overrides(table: &mut Table, base: Option<&Table>) {
+    let Some(mut overrides) = platform_overrides(table).cloned() else {
+        return;
+    };
+
+    unapply_table(table, &mut overrides, base);
+
+    if let Some(Value::Table(all)) = table.get_mut(OVERRIDES_KEY) {
+        all.insert(current_platform().to_string(), Value::Table(overrides));
+    }
+}
+
+fn unapply_table(table: &mut Table, overrides: &mut Table, base: Option<&Table>) {
+    for (key, override_value) in overrides.iter_mut() {
+        let base_value = base.and_then(|b| b.get(key));
+
+        if let Value::Table(override_table) = override_value {
+            if let Some(Value::Table(current)) = table.get_mut(key) {
+                let base_table = base_value.and_then(Value::as_table);
+                unapply_table(current, override_table, base_table);
+            }
+            continue;
+        }
+
+        // Keep edits made since loading by moving them into the override
+        if let Some(current) = table.get(key) {
+            if current != override_value {
+                *override_value = current.clone();
+            }
+        }
+
+        match base_value {
+            Some(value) => {
+                table.insert(key.clone(), value.clone());
+            }
+            None => {
+                table.remove(key);
+            }
+        }
+    }
+}
+
+/// Write `new` into an existing document in place, so comments, ordering and
+/// formatting of unchanged entries survive. `fresh` is `new` rendered as a
+/// document and supplies the formatting of added entries, `old` is the table
+/// the document was parsed from.
+pub fn update_document(
+    doc: &mut dyn TableLike,
+    fresh: &dyn TableLike,
+    new: &Table,
+    old: Option<&Table>,
+) {
+    let removed: Vec<String> = doc
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in removed {
+        doc.remove(&key);
+    }
+
+    for (key, new_value) in new {
+        let Some(fresh_item) = fresh.get(key) else {
+            continue;
+        };
+        let old_value = old.and_then(|o| o.get(key));
+        if old_value == Some(new_value) && doc.contains_key(key) {
+            continue;
+        }
+        let Some(item) = doc.get_mut(key) else {
+            doc.insert(key, fresh_item.clone());
+            continue;
+        };
+
+        match new_value {
+            Value::Table(new_table) => {
+                if let (Some(table), Some(fresh_table)) =
+                    (item.as_table_like_mut(), fresh_item.as_table_like())
+                {
+                    let old_table = old_value.and_then(Value::as_table);
+                    update_document(table, fresh_table, new_table, old_table);
+                    continue;
+                }
+            }
+            Value::Array(new_items) => {
+                // Update arrays of tables entry by entry while the length matches
+                let old_items = old_value.and_then(Value::as_array);
+                if let (Some(tables), Some(fresh_tables)) = (
+                    item.as_array_of_tables_mut(),
+                    fresh_item.as_array_of_tables(),
+                ) {
+                    if tables.len() == new_items.len() && fresh_tables.len() == new_items.len() {
+                        for (index, (table, fresh_table)) in
+                            tables.iter_mut().zip(fresh_tables.iter()).enumerate()
+                        {
+                            let (Some(new_table), old_table) = (
+                                new_items[index].as_table(),
+                                old_items
+                                    .and_then(|items| items.get(index))
+                                    .and_then(Value::as_table),
+                            ) else {
+                                continue;
+                            };
+                            update_document(table, fresh_table, new_table, old_table);
+                        }
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Replace the value, keeping any trailing comment on it
+        match (item.as_value_mut(), fresh_item.as_value()) {
+            (Some(value), Some(fresh_value)) => {
+                let decor = value.decor().clone();
+                *value = fresh_value.clone();
+                *value.decor_mut() = decor;
+            }
+            _ => *item = fresh_item.clone(),
+        }
+    }
+}
+
+// Split `list[0]` into the key and the array index
+fn parse_segment(segment: &str) -> (&str, Option<usize>) {
+    match segment
+        .strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+    {
+        Some((key, index)) => match index.parse() {
+            Ok(index) => (key, Some(index)),
+            Err(_) => (segment, None),
+        },
+        None => (segment, None),
+    }
+}
+
+/// Set the value at a dotted path like `profiles.list[0].name`, creating
+/// missing tables on the way. `None` removes the key.
+pub fn set_path(table: &mut Table, path: &str, value: Option<Value>) -> Result<(), String> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments
+        .pop()
+        .filter(|last| !last.is_empty())
+        .ok_or_else(|| format!("Invalid config path: {}", path))?;
+
+    let mut current = table;
+    for segment in segments {
+        let (key, index) = parse_segment(segment);
+        let entry = current
+            .entry(key)
+            .or_insert_with(|| Value::Table(Table::new()));
+        let entry = match index {
+            Some(index) => entry
+                .as_array_mut()
+                .and_then(|items| items.get_mut(index))
+                .ok_or_else(|| format!("No item at {} in config path {}", segment, path))?,
+            None => entry,
+        };
+        current = entry
+            .as_table_mut()
+            .ok_or_else(|| format!("{} is not a table in config path {}", segment, path))?;
+    }
+
+    match value {
+        Some(value) => current.insert(last.to_string(), value),
+        None => current.remove(last),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_path() {
+        let mut table: Table = "[[profiles.list]]\nname = \"a\"\ncwd = \"/tmp\"\n"
+            .parse()
+            .unwrap();
+        set_path(&mut table, "profiles.list[0].name", Some("b".into())).unwrap();
+        set_path(&mut table, "profiles.list[0].cwd", None).unwrap();
+        set_path(&mut table, "terminal.scrollback", Some(Value::Integer(10))).unwrap();
+
+        let profile = table["profiles"]["list"][0].as_table().unwrap();
+        assert_eq!(profile["name"].as_str(), Some("b"));
+        assert!(!profile.contains_key("cwd"));
+        assert_eq!(table["terminal"]["scrollback"].as_integer(), Some(10));
+        assert!(set_path(&mut table, "profiles.list[3].name", None).is_err());
+    }
+
+    #[test]
+    fn test_strip_included_keeps_main_and_edits() {
+        let included: Table =
+            "[font]\nsize = 14\nfamily = \"Mono\"\n[theme]\nbackground = \"#000\"\n"
+                .parse()
+                .unwrap();
+        let main: Table = "[font]\nsize = 14\n".parse().unwrap();
+
+        let mut saved = included.clone();
+        deep_merge(&mut saved, main.clone());
+        saved["font"]
+            .as_table_mut()
+            .unwrap()
+            .insert("family".into(), Value::String("Fira".into()));
+
+        strip_included(&mut saved, &included, Some(&main));
+        assert_eq!(saved["font"]["size"].as_integer(), Some(14));
+        assert_eq!(saved["font"]["family"].as_str(), Some("Fira"));
+        assert!(!saved.contains_key("theme"));
+    }
+
+    #[test]
+    fn test_update_document_keeps_comments() {
+        let content = "# My config\n[font]\nsize = 14 # readable\nfamily = \"Mono\"\n\n# Colors\n[theme]\nbackground = \"#000\"\n";
+        let old: Table = content.parse().unwrap();
+        let new: Table = "[font]\nsize = 16\nfamily = \"Mono\"\n[shell]\nlinux = \"/bin/zsh\"\n"
+            .parse()
+            .unwrap();
+        let fresh = toml::to_string(&new)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+
+        let mut doc = content.parse::<toml_edit::DocumentMut>().unwrap();
+        update_document(doc.as_table_mut(), fresh.as_table(), &new, Some(&old));
+        let updated = doc.to_string();
+
+        assert!(updated.starts_with("# My config\n[font]\nsize = 16 # readable\n"));
+        assert!(!updated.contains("[theme]"));
+        assert_eq!(updated.parse::<Table>().unwrap(), new);
+    }
+
+    #[test]
+    fn test_overrides_round_trip() {
+        let platform = current_platform();
+        let content = format!(
+            "[font]\nsize = 14\nfamily = \"Mono\"\n\n[overrides.{}.font]\nsize = 18\n",
+            platform
+        );
+        let base: Table = content.parse().unwrap();
+
+        let mut merged = base.clone();
+        apply_platform_overrides(&mut merged);
+        assert_eq!(merged["font"]["size"].as_integer(), Some(18));
+
+        // Unchanged values go back to the base
+        let mut saved = merged.clone();
+        unapply_platform_overrides(&mut saved, Some(&base));
+        assert_eq!(saved, base);
+
+        // Edited values land in the override
+        merged["font"]
+            .as_table_mut()
+            .unwrap()
+            .insert("size".into(), Value::Integer(20));
+        unapply_platform_overrides(&mut merged, Some(&base));
+        assert_eq!(merged["font"]["size"].as_integer(), Some(14));
+        assert_eq!(
+            merged[OVERRIDES_KEY][platform]["font"]["size"].as_integer(),
+            Some(20)
+        );
+    }
+}
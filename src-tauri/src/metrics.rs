@@ -0,0 +1,229 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::crash;
+use crate::pty::{self, PtyCounters};
+
+static INVOKES: AtomicU64 = AtomicU64::new(0);
+
+// Counters at the previous `get_app_metrics` call, rates are measured since then
+static LAST_SAMPLE: Mutex<Option<Sample>> = Mutex::new(None);
+
+#[derive(Clone)]
+struct Sample {
+    taken: Instant,
+    invokes: u64,
+    ptys: HashMap<String, PtyCounters>,
+}
+
+/// Throughput of one PTY
+#[derive(Debug, Serialize)]
+pub struct PtyThroughput {
+    #[serde(flatten)]
+    pub counters: PtyCounters,
+    /// Output read per second since the last call, or since the PTY started
+    pub read_bytes_per_sec: f64,
+    pub written_bytes_per_sec: f64,
+    pub messages_per_sec: f64,
+}
+
+/// Traffic between the backend and the windows
+#[derive(Debug, Serialize)]
+pub struct IpcRates {
+    /// Commands invoked by the windows since startup
+    pub invokes: u64,
+    pub invokes_per_sec: f64,
+    /// Output messages sent over PTY channels, per second
+    pub channel_messages_per_sec: f64,
+    pub channel_bytes_per_sec: f64,
+}
+
+/// Memory and threads of the backend process
+#[derive(Debug, Default, Serialize)]
+pub struct ProcessUsage {
+    /// Resident memory in bytes
+    pub memory_bytes: Option<u64>,
+    pub virtual_memory_bytes: Option<u64>,
+    /// Threads of the whole process, only known on Linux
+    pub os_threads: Option<usize>,
+    /// Background threads the app started, like PTY readers
+    pub worker_threads: usize,
+}
+
+/// Everything a diagnostics panel shows, in one call
+#[derive(Debug, Serialize)]
+pub struct AppMetrics {
+    pub pty_count: usize,
+    pub ptys: Vec<PtyThroughput>,
+    pub ipc: IpcRates,
+    pub process: ProcessUsage,
+}
+
+/// Count a command invoked by a window
+pub fn record_invoke() {
+    INVOKES.fetch_add(1, Ordering::Relaxed);
+}
+
+fn rate(now: u64, before: u64, seconds: f64) -> f64 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+    now.saturating_sub(before) as f64 / seconds
+}
+
+/// Per-PTY and total rates between two samples. PTYs started since the
+/// previous sample are measured over their whole lifetime.
+fn throughput(current: &Sample, previous: Option<&Sample>) -> (Vec<PtyThroughput>, IpcRates) {
+    let elapsed =
+        previous.map(|previous| current.taken.duration_since(previous.taken).as_secs_f64());
+    let mut total_messages = 0.0;
+    let mut total_bytes = 0.0;
+
+    let mut ptys: Vec<PtyThroughput> = current
+        .ptys
+        .values()
+        .map(|counters| {
+            let before = previous.and_then(|previous| previous.ptys.get(&counters.pty_id));
+            let (seconds, before) = match (before, elapsed) {
+                (Some(before), Some(elapsed)) => (
+                    elapsed,
+                    (
+                        before.bytes_read,
+                        before.bytes_written,
+                        before.messages_sent,
+                    ),
+                ),
+                _ => (counters.uptime_ms as f64 / 1000.0, (0, 0, 0)),
+            };
+            let throughput = PtyThroughput {
+                read_bytes_per_sec: rate(counters.bytes_read, before.0, seconds),
+                written_bytes_per_sec: rate(counters.bytes_written, before.1, seconds),
+                messages_per_sec: rate(counters.messages_sent, before.2, seconds),
+                counters: counters.clone(),
+            };
+            total_messages += throughput.messages_per_sec;
+            total_bytes += throughput.read_bytes_per_sec;
+            throughput
+        })
+        .collect();
+    ptys.sort_by(|a, b| b.read_bytes_per_sec.total_cmp(&a.read_bytes_per_sec));
+
+    let ipc = IpcRates {
+        invokes: current.invokes,
+        invokes_per_sec: match (previous, elapsed) {
+            (Some(previous), Some(elapsed)) => rate(current.invokes, previous.invokes, elapsed),
+            _ => 0.0,
+        },
+        channel_messages_per_sec: total_messages,
+        channel_bytes_per_sec: total_bytes,
+    };
+    (ptys, ipc)
+}
+
+fn process_usage() -> ProcessUsage {
+    let mut usage = ProcessUsage {
+        worker_threads: crash::live_threads(),
+        ..Default::default()
+    };
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return usage;
+    };
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_memory().with_tasks(),
+    );
+    if let Some(process) = system.process(pid) {
+        usage.memory_bytes = Some(process.memory());
+        usage.virtual_memory_bytes = Some(process.virtual_memory());
+        usage.os_threads = process.tasks().map(|tasks| tasks.len());
+    }
+    usage
+}
+
+// PTY throughput, IPC rates, memory and thread counts of the backend, with
+// rates measured since the previous call
+#[tauri::command]
+pub async fn get_app_metrics() -> Result<AppMetrics, String> {
+    let current = Sample {
+        taken: Instant::now(),
+        invokes: INVOKES.load(Ordering::Relaxed),
+        ptys: pty::pty_counters()
+            .into_iter()
+            .map(|counters| (counters.pty_id.clone(), counters))
+            .collect(),
+    };
+    let previous = LAST_SAMPLE
+        .lock()
+        .map_err(|e| format!("Failed to lock metrics: {}", e))?
+        .replace(current.clone());
+    let (ptys, ipc) = throughput(&current, previous.as_ref());
+
+    Ok(AppMetrics {
+        pty_count: ptys.len(),
+        ptys,
+        ipc,
+        process: tauri::async_runtime::spawn_blocking(process_usage)
+            .await
+            .map_err(|e| format!("Failed to read process usage: {}", e))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn counters(pty_id: &str, bytes_read: u64, messages_sent: u64, uptime_ms: u64) -> PtyCounters {
+        PtyCounters {
+            pty_id: pty_id.into(),
+            window: "main".into(),
+            bytes_read,
+            bytes_written: 0,
+            messages_sent,
+            dropped_bytes: 0,
+            uptime_ms,
+        }
+    }
+
+    fn sample(taken: Instant, invokes: u64, ptys: Vec<PtyCounters>) -> Sample {
+        Sample {
+            taken,
+            invokes,
+            ptys: ptys.into_iter().map(|c| (c.pty_id.clone(), c)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_throughput_between_samples() {
+        let start = Instant::now();
+        let previous = sample(start, 10, vec![counters("a", 1_000, 10, 5_000)]);
+        let current = sample(
+            start + Duration::from_secs(2),
+            30,
+            vec![
+                counters("a", 5_000, 30, 7_000),
+                // Started after the previous sample
+                counters("b", 4_000, 8, 1_000),
+            ],
+        );
+
+        let (ptys, ipc) = throughput(&current, Some(&previous));
+        assert_eq!(ptys[0].counters.pty_id, "b");
+        assert_eq!(ptys[0].read_bytes_per_sec, 4_000.0);
+        assert_eq!(ptys[1].read_bytes_per_sec, 2_000.0);
+        assert_eq!(ptys[1].messages_per_sec, 10.0);
+        assert_eq!(ipc.invokes_per_sec, 10.0);
+        assert_eq!(ipc.channel_bytes_per_sec, 6_000.0);
+
+        // Without a previous sample rates cover the PTY's lifetime
+        let (ptys, ipc) = throughput(&current, None);
+        assert_eq!(ptys[1].read_bytes_per_sec, 5_000.0 / 7.0);
+        assert_eq!(ipc.invokes_per_sec, 0.0);
+    }
+}
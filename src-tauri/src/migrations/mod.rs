@@ -0,0 +1,211 @@
+// Config schema migrations.
+//
+// When a config change needs existing files to be rewritten:
+// 1. Add a `vN_to_vM` module with the old and new layouts as structs and a
+//    `MIGRATION` step converting between them, plus a unit test
+// 2. Register the step in MIGRATIONS
+// 3. Bump CURRENT_VERSION
+mod v0_to_v1;
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+use toml::{Table, Value};
+
+use crate::config::get_config_path;
+use crate::config_format::ConfigFormat;
+use crate::merge;
+
+/// Current version of the config schema
+pub const CURRENT_VERSION: u32 = 1;
+
+/// A single schema upgrade from one version to the next
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    pub apply: fn(Table) -> Result<Table, String>,
+}
+
+/// Every migration step, in order
+const MIGRATIONS: &[Migration] = &[v0_to_v1::MIGRATION];
+
+/// A value changed by a migration
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// What a migration did, or would do in a dry run
+#[derive(Debug, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Descriptions of the steps that ran
+    pub steps: Vec<String>,
+    pub changes: Vec<ConfigChange>,
+}
+
+/// Schema version of a config table, files from before versioning are 0
+pub fn version_of(table: &Table) -> u32 {
+    table
+        .get("version")
+        .and_then(Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+pub fn needs_migration(table: &Table) -> bool {
+    version_of(table) < CURRENT_VERSION
+}
+
+/// Run every step needed to bring the table up to CURRENT_VERSION. Nothing
+/// is written, so this doubles as the dry run.
+pub fn migrate(table: Table) -> Result<(Table, MigrationReport), String> {
+    let from_version = version_of(&table);
+    let original = table.clone();
+    let mut table = table;
+    let mut version = from_version;
+    let mut steps = Vec::new();
+
+    while version < CURRENT_VERSION {
+        let step = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| format!("No migration from config version {}", version))?;
+        table = (step.apply)(table)
+            .map_err(|e| format!("Failed to migrate config to version {}: {}", step.to, e))?;
+        table.insert("version".into(), Value::Integer(step.to.into()));
+        steps.push(step.description.to_string());
+        version = step.to;
+    }
+
+    let mut changes = Vec::new();
+    diff(&mut changes, "", Some(&original), Some(&table));
+    Ok((
+        table,
+        MigrationReport {
+            from_version,
+            to_version: version,
+            steps,
+            changes,
+        },
+    ))
+}
+
+/// Merge the files a config includes under it and bring the result up to
+/// date. The version may be set in any of the files, so it is read from the
+/// merged config, which is only migrated in memory: rewriting one of the
+/// files on its own could leave the others behind.
+pub fn merge_includes(raw: &Table, dir: &Path) -> Result<Table, String> {
+    let mut table = raw.clone();
+    merge::apply_includes(&mut table, dir)?;
+    if needs_migration(&table) {
+        table = migrate(table)?.0;
+    }
+    Ok(table)
+}
+
+// Collect changed leaf values, tables are compared key by key
+fn diff(
+    changes: &mut Vec<ConfigChange>,
+    prefix: &str,
+    before: Option<&Table>,
+    after: Option<&Table>,
+) {
+    let mut keys: Vec<&String> = before.into_iter().flat_map(|t| t.keys()).collect();
+    for key in after.into_iter().flat_map(|t| t.keys()) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    for key in keys {
+        let path = format!("{}{}", prefix, key);
+        let old = before.and_then(|t| t.get(key));
+        let new = after.and_then(|t| t.get(key));
+        match (old, new) {
+            (Some(Value::Table(_)), _) | (_, Some(Value::Table(_))) => diff(
+                changes,
+                &format!("{}.", path),
+                old.and_then(Value::as_table),
+                new.and_then(Value::as_table),
+            ),
+            _ if old != new => changes.push(ConfigChange {
+                path,
+                before: old.cloned(),
+                after: new.cloned(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+// Report what migrating the config file would change without writing it
+#[tauri::command]
+pub async fn preview_config_migration(app: AppHandle) -> Result<MigrationReport, String> {
+    let config_path = get_config_path(&app)?;
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let table = ConfigFormat::from_path(&config_path)
+        .parse(&content)
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+    let (_, report) = migrate(table)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_is_complete() {
+        for version in 0..CURRENT_VERSION {
+            let step = MIGRATIONS.iter().find(|m| m.from == version).unwrap();
+            assert_eq!(step.to, version + 1);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_reports_changes() {
+        let table: Table = "[font]\nfamily = \"Mono\"\nfallback_family = \"monospace\"\nsize = 14\nline_height = 1.0\n[theme]\n[shell]\n"
+            .parse()
+            .unwrap();
+        let (migrated, report) = migrate(table).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, CURRENT_VERSION);
+        assert_eq!(version_of(&migrated), CURRENT_VERSION);
+        assert!(report.changes.contains(&ConfigChange {
+            path: "version".into(),
+            before: None,
+            after: Some(Value::Integer(CURRENT_VERSION.into())),
+        }));
+        assert!(report.changes.iter().all(|c| !c.path.starts_with("font.")));
+    }
+
+    #[test]
+    fn test_version_in_include() {
+        let dir = std::env::temp_dir().join(format!("termillion-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let base = format!(
+            "version = {}\n[font]\nfamily = \"Mono\"\nfallback_family = \"monospace\"\nsize = 14\nline_height = 1.0\n",
+            CURRENT_VERSION
+        );
+        fs::write(dir.join("base.toml"), base).unwrap();
+        let raw: Table = "include = [\"base.toml\"]\n[font]\nsize = 16\n"
+            .parse()
+            .unwrap();
+
+        // The main file alone looks like version 0, the merged config is current
+        assert!(needs_migration(&raw));
+        let merged = merge_includes(&raw, &dir).unwrap();
+        assert_eq!(version_of(&merged), CURRENT_VERSION);
+        assert_eq!(merged["font"]["size"].as_integer(), Some(16));
+        assert_eq!(merged["font"]["family"].as_str(), Some("Mono"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
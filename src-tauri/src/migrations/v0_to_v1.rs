@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use super::Migration;
+use crate::config::Config;
+
+pub const MIGRATION: Migration = Migration {
+    from: 0,
+    to: 1,
+    description: "Add version, terminal, shortcuts and window sections",
+    apply,
+};
+
+/// Unversioned config, only font, theme and shell existed
+#[derive(Deserialize)]
+struct ConfigV0 {
+    font: Table,
+    theme: Table,
+    shell: Table,
+    terminal: Option<Table>,
+    shortcuts: Option<Table>,
+    window: Option<Table>,
+    #[serde(flatten)]
+    rest: Table,
+}
+
+#[derive(Serialize)]
+struct ConfigV1 {
+    version: u32,
+    font: Table,
+    theme: Table,
+    shell: Table,
+    terminal: Table,
+    shortcuts: Table,
+    window: Table,
+    #[serde(flatten)]
+    rest: Table,
+}
+
+// Sections added in v1 start out with their defaults
+fn default_section(key: &str) -> Result<Table, String> {
+    let defaults = Table::try_from(Config::default())
+        .map_err(|e| format!("Failed to serialize default config: {}", e))?;
+    match defaults.get(key) {
+        Some(Value::Table(table)) => Ok(table.clone()),
+        _ => Err(format!("Default config has no {} section", key)),
+    }
+}
+
+fn migrate(old: ConfigV0) -> Result<ConfigV1, String> {
+    let mut font = old.font;
+    font.entry("fallback_family")
+        .or_insert_with(|| "Consolas, Monaco, monospace".into());
+
+    let terminal = old.terminal.unwrap_or_else(|| {
+        Table::from_iter([
+            ("scrollback".to_string(), Value::Integer(5000)),
+            (
+                "padding".to_string(),
+                Value::Table(Table::from_iter([
+                    ("x".to_string(), Value::Integer(12)),
+                    ("y".to_string(), Value::Integer(8)),
+                ])),
+            ),
+        ])
+    });
+
+    let mut rest = old.rest;
+    rest.remove("version");
+
+    Ok(ConfigV1 {
+        version: 1,
+        font,
+        theme: old.theme,
+        shell: old.shell,
+        terminal,
+        shortcuts: old
+            .shortcuts
+            .map_or_else(|| default_section("shortcuts"), Ok)?,
+        window: old.window.map_or_else(|| default_section("window"), Ok)?,
+        rest,
+    })
+}
+
+fn apply(table: Table) -> Result<Table, String> {
+    let old = ConfigV0::deserialize(table).map_err(|e| e.to_string())?;
+    Table::try_from(migrate(old)?).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v0_gets_new_sections() {
+        let table: Table = "[font]\nfamily = \"Mono\"\nsize = 14\nline_height = 1.0\n\n[theme]\nbackground = \"#000\"\n\n[shell]\nlinux = \"/bin/zsh\"\n"
+            .parse()
+            .unwrap();
+        let migrated = apply(table).unwrap();
+
+        assert_eq!(migrated["version"].as_integer(), Some(1));
+        assert_eq!(migrated["shell"]["linux"].as_str(), Some("/bin/zsh"));
+        assert_eq!(
+            migrated["font"]["fallback_family"].as_str(),
+            Some("Consolas, Monaco, monospace")
+        );
+        assert_eq!(migrated["terminal"]["scrollback"].as_integer(), Some(5000));
+        assert!(migrated["shortcuts"]
+            .as_table()
+            .unwrap()
+            .contains_key("new_tab"));
+        assert!(migrated.contains_key("window"));
+    }
+}
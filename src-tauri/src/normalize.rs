@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+use crate::pty::{EscapeScanner, Scanned};
+
+/// Line ending of normalized text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+/// What `normalize_output` does, everything but trimming by default
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct NormalizeOptions {
+    /// Remove escape sequences like colors, cursor movement and titles
+    pub strip_ansi: bool,
+    /// Apply carriage returns and backspaces the way a terminal shows them,
+    /// so a redrawn progress bar leaves only its last state
+    pub collapse_carriage_returns: bool,
+    pub line_ending: LineEnding,
+    pub trim_trailing_whitespace: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            strip_ansi: true,
+            collapse_carriage_returns: true,
+            line_ending: LineEnding::Lf,
+            trim_trailing_whitespace: false,
+        }
+    }
+}
+
+/// Remove escape sequences and control characters other than tabs, line
+/// breaks and backspaces
+pub fn strip_escapes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut escape = EscapeScanner::default();
+    for c in text.chars() {
+        // Escape sequences are ASCII, any other byte stands in for the rest
+        let byte = if c.is_ascii() { c as u8 } else { 0x80 };
+        if escape.advance(byte, |_| {}) != Scanned::Text {
+            continue;
+        }
+        if matches!(c, '\t' | '\n' | '\r' | '\x08') || !c.is_control() {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+// Write a line the way a terminal would, a carriage return goes back to its
+// start and later text overwrites what is there
+fn collapse_line(line: &str) -> String {
+    let mut cells: Vec<char> = Vec::with_capacity(line.len());
+    let mut cursor: usize = 0;
+    for c in line.chars() {
+        match c {
+            '\r' => cursor = 0,
+            '\x08' => cursor = cursor.saturating_sub(1),
+            c if cursor < cells.len() => {
+                cells[cursor] = c;
+                cursor += 1;
+            }
+            c => {
+                cells.push(c);
+                cursor += 1;
+            }
+        }
+    }
+    cells.into_iter().collect()
+}
+
+pub fn normalize(text: &str, options: &NormalizeOptions) -> String {
+    let stripped;
+    let text = if options.strip_ansi {
+        stripped = strip_escapes(text);
+        &stripped
+    } else {
+        text
+    };
+    let separator = match options.line_ending {
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+    };
+    text.split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let line = if options.collapse_carriage_returns {
+                collapse_line(line)
+            } else {
+                line.to_string()
+            };
+            if options.trim_trailing_whitespace {
+                line.trim_end().to_string()
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// Remove escape sequences from terminal output, for copying it as plain text
+#[tauri::command]
+pub async fn strip_ansi(text: String) -> String {
+    strip_escapes(&text)
+}
+
+// Turn terminal output into clean text for copying and log exports. Without
+// options escape sequences are removed, carriage returns applied and line
+// endings turned into `\n`.
+#[tauri::command]
+pub async fn normalize_output(text: String, options: Option<NormalizeOptions>) -> String {
+    normalize(&text, &options.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_output() {
+        let output = "\x1b]0;title\x07\x1b[1;32mok\x1b[0m \x1b(Bdone\x07\r\n";
+        assert_eq!(strip_escapes(output), "ok done\r\n");
+
+        let progress = "\x1b[33m 10%\x1b[0m\r 50%\r100% done  \r\nnext\x08\x08XT";
+        assert_eq!(
+            normalize(progress, &NormalizeOptions::default()),
+            "100% done  \nneXT"
+        );
+        let options = NormalizeOptions {
+            line_ending: LineEnding::Crlf,
+            trim_trailing_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize(progress, &options), "100% done\r\nneXT");
+        // Shorter text only overwrites the start of the line
+        assert_eq!(collapse_line("downloading\rdone"), "doneloading");
+    }
+}
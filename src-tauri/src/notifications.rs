@@ -0,0 +1,248 @@
+use notify_rust::Notification;
+use regex::Regex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::config::NotificationConfig;
+use crate::pty::{EscapeScanner, Scanned};
+
+const DEFAULT_LONG_COMMAND_SECONDS: u64 = 10;
+
+/// Bells closer together than this only notify once
+const BELL_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Longest line kept for trigger matching, the rest is dropped
+const MAX_LINE_LENGTH: usize = 4096;
+
+/// Longest OSC payload kept, OSC 133 marks are a few bytes
+const MAX_OSC_LENGTH: usize = 64;
+
+/// Something worth telling the user about in a terminal they aren't looking at
+#[derive(Debug, PartialEq)]
+pub enum Activity {
+    Bell,
+    CommandFinished {
+        seconds: u64,
+        exit_code: Option<i32>,
+    },
+    TriggerMatched {
+        line: String,
+    },
+}
+
+/// Finds bells, finished commands and trigger matches in PTY output. Command
+/// ends come from the OSC 133 marks shell integration scripts print.
+pub struct ActivityScanner {
+    bell: bool,
+    long_command: Option<Duration>,
+    triggers: Vec<Regex>,
+    escape: EscapeScanner,
+    line: Vec<u8>,
+    command_started: Option<Instant>,
+    last_bell: Option<Instant>,
+}
+
+impl ActivityScanner {
+    /// Invalid trigger patterns are skipped, validation reports them
+    pub fn new(config: &NotificationConfig) -> Self {
+        let seconds = config
+            .long_command_seconds
+            .unwrap_or(DEFAULT_LONG_COMMAND_SECONDS);
+        Self {
+            bell: config.bell.unwrap_or(true),
+            long_command: config
+                .command_finished
+                .unwrap_or(true)
+                .then(|| Duration::from_secs(seconds)),
+            triggers: config
+                .triggers
+                .iter()
+                .flatten()
+                .filter_map(|pattern| Regex::new(pattern).ok())
+                .collect(),
+            escape: EscapeScanner::new(MAX_OSC_LENGTH),
+            line: Vec::new(),
+            command_started: None,
+            last_bell: None,
+        }
+    }
+
+    pub fn scan(&mut self, data: &[u8]) -> Vec<Activity> {
+        self.scan_at(data, Instant::now())
+    }
+
+    fn scan_at(&mut self, data: &[u8], now: Instant) -> Vec<Activity> {
+        let mut activities = Vec::new();
+        for &byte in data {
+            let mut osc = None;
+            let scanned = self.escape.advance(byte, |payload| {
+                osc = Some(String::from_utf8_lossy(payload).into_owned());
+            });
+            if let Some(osc) = osc {
+                activities.extend(self.end_osc(&osc, now));
+            }
+            if scanned != Scanned::Text {
+                continue;
+            }
+            match byte {
+                0x07 => {
+                    let cooled_down = self
+                        .last_bell
+                        .is_none_or(|last| now.duration_since(last) >= BELL_COOLDOWN);
+                    if self.bell && cooled_down {
+                        self.last_bell = Some(now);
+                        activities.push(Activity::Bell);
+                    }
+                }
+                b'\n' => activities.extend(self.end_line()),
+                b'\r' => {}
+                _ => {
+                    if self.line.len() < MAX_LINE_LENGTH {
+                        self.line.push(byte);
+                    }
+                }
+            }
+        }
+        activities
+    }
+
+    // `133;C` is printed when a command starts, `133;D;<exit code>` when it ends
+    fn end_osc(&mut self, osc: &str, now: Instant) -> Option<Activity> {
+        let mut parts = osc.split(';');
+        if parts.next() != Some("133") {
+            return None;
+        }
+        match parts.next() {
+            Some("C") => {
+                self.command_started = Some(now);
+                None
+            }
+            Some("D") => {
+                let elapsed = now.duration_since(self.command_started.take()?);
+                (elapsed >= self.long_command?).then(|| Activity::CommandFinished {
+                    seconds: elapsed.as_secs(),
+                    exit_code: parts.next().and_then(|code| code.parse().ok()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn end_line(&mut self) -> Option<Activity> {
+        let line = String::from_utf8_lossy(&self.line).trim().to_string();
+        self.line.clear();
+        self.triggers
+            .iter()
+            .any(|trigger| trigger.is_match(&line))
+            .then_some(Activity::TriggerMatched { line })
+    }
+}
+
+fn focus(window: &WebviewWindow) {
+    let result = window
+        .unminimize()
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus());
+    if let Err(e) = result {
+        tracing::warn!("Failed to focus window from notification: {}", e);
+    }
+}
+
+/// Show an OS notification whether or not a window is focused
+pub fn show(body: &str) -> Result<(), String> {
+    Notification::new()
+        .summary("termillion")
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+/// Show an OS notification unless the window is focused. Clicking it brings
+/// the window forward, on Linux through the notification's default action
+/// and elsewhere because the OS activates the app.
+pub fn notify(app: &AppHandle, window_label: &str, activity: &Activity) {
+    let Some(window) = app.get_webview_window(window_label) else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) {
+        return;
+    }
+
+    let body = match activity {
+        Activity::Bell => "A terminal rang the bell".to_string(),
+        Activity::CommandFinished {
+            seconds,
+            exit_code: Some(code),
+        } if *code != 0 => format!("Command failed with exit code {} after {}s", code, seconds),
+        Activity::CommandFinished { seconds, .. } => format!("Command finished after {}s", seconds),
+        Activity::TriggerMatched { line } => line.clone(),
+    };
+
+    let mut notification = Notification::new();
+    notification.summary("termillion").body(&body);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.action("default", "Show");
+
+    match notification.show() {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        Ok(handle) => {
+            // Waiting for the click blocks, so it gets its own thread
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    if action == "default" {
+                        focus(&window);
+                    }
+                });
+            });
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to show notification: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_activity() {
+        let mut scanner = ActivityScanner::new(&NotificationConfig {
+            triggers: Some(vec!["build (failed|passed)".into()]),
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        // Title sequences end in BEL but aren't bells, colors don't break matches
+        let activities = scanner.scan_at(
+            b"\x1b]0;title\x07\x1b[31mbuild failed\x1b[0m\r\n\x07",
+            start,
+        );
+        assert_eq!(
+            activities,
+            [
+                Activity::TriggerMatched {
+                    line: "build failed".into()
+                },
+                Activity::Bell
+            ]
+        );
+
+        scanner.scan_at(b"\x1b]133;C\x07", start);
+        let activities = scanner.scan_at(b"\x1b]133;D;1\x1b\\", start + Duration::from_secs(12));
+        assert_eq!(
+            activities,
+            [Activity::CommandFinished {
+                seconds: 12,
+                exit_code: Some(1)
+            }]
+        );
+
+        // Short commands aren't reported
+        scanner.scan_at(b"\x1b]133;C\x07", start);
+        assert!(scanner
+            .scan_at(b"\x1b]133;D;0\x07", start + Duration::from_secs(1))
+            .is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+use crate::config::{Config, Shortcut};
+use crate::dynamic_profiles;
+use crate::frecency;
+use crate::pty;
+use crate::session;
+
+/// Most results returned for one query
+const MAX_RESULTS: usize = 50;
+
+/// Recent directories and commands kept per kind
+const MAX_RECENT: usize = 100;
+
+/// Most visited directories offered besides the picked ones
+const MAX_FRECENT_DIRS: usize = 30;
+
+// Actions without a default shortcut that the frontend can still run
+const EXTRA_ACTIONS: [&str; 2] = ["new_window", "toggle_dropdown"];
+
+// Match scoring
+const MATCH_SCORE: i64 = 16;
+const WORD_START_BONUS: i64 = 12;
+const CONSECUTIVE_BONUS: i64 = 8;
+const PREFIX_BONUS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteKind {
+    Action,
+    Profile,
+    Task,
+    Workspace,
+    Directory,
+    Command,
+}
+
+/// Something the palette can offer
+#[derive(Debug, Clone, Serialize)]
+pub struct PaletteEntry {
+    pub kind: PaletteKind,
+    /// What the frontend acts on: an action, profile or task name, a saved
+    /// session name, a directory or a command line
+    pub value: String,
+    /// Text shown and matched against
+    pub label: String,
+    /// Secondary text, like the action's shortcut or the profile's command
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaletteResult {
+    #[serde(flatten)]
+    pub entry: PaletteEntry,
+    pub score: i64,
+    /// Character positions in the label that matched, for highlighting
+    pub matches: Vec<usize>,
+}
+
+/// Directories and commands recently picked from the palette, newest first
+#[derive(Default)]
+pub struct PaletteState {
+    directories: Mutex<VecDeque<String>>,
+    commands: Mutex<VecDeque<String>>,
+}
+
+fn is_word_start(text: &[char], index: usize) -> bool {
+    index == 0
+        || matches!(text[index - 1], ' ' | '_' | '-' | '/' | '\\' | '.' | ':')
+        || (text[index - 1].is_lowercase() && text[index].is_uppercase())
+}
+
+/// Match `query` as a case-insensitive subsequence of `text`. Returns the score,
+/// higher is better, and the matched character positions.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut score = 0;
+    let mut positions: Vec<usize> = Vec::new();
+    let mut start = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let first = (start..lower.len()).find(|&i| lower[i] == q)?;
+        // Jump ahead to a word start unless this continues the previous match
+        let continues = positions.last().is_some_and(|&last| last + 1 == first);
+        let index = if continues || is_word_start(&chars, first) {
+            first
+        } else {
+            (first..lower.len())
+                .find(|&i| lower[i] == q && is_word_start(&chars, i))
+                .unwrap_or(first)
+        };
+
+        score += MATCH_SCORE;
+        if is_word_start(&chars, index) {
+            score += WORD_START_BONUS;
+        }
+        match positions.last() {
+            Some(&last) if last + 1 == index => score += CONSECUTIVE_BONUS,
+            Some(&last) => score -= (index - last - 1) as i64,
+            None => score -= index as i64,
+        }
+        positions.push(index);
+        start = index + 1;
+    }
+
+    if lower.starts_with(&query.to_lowercase().chars().collect::<Vec<_>>()) {
+        score += PREFIX_BONUS;
+    }
+    // Among equal matches the shorter text is the closer one
+    score -= (chars.len() / 8) as i64;
+    Some((score, positions))
+}
+
+// `new_tab` becomes `New tab`
+fn action_label(action: &str) -> String {
+    let label = action.replace('_', " ");
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => label,
+    }
+}
+
+fn shortcut_label(shortcut: &Shortcut) -> Option<String> {
+    if shortcut.key.is_empty() {
+        return None;
+    }
+    let mut parts = Vec::new();
+    if shortcut.ctrl {
+        parts.push("Ctrl".to_string());
+    }
+    if shortcut.shift {
+        parts.push("Shift".to_string());
+    }
+    if shortcut.alt {
+        parts.push("Alt".to_string());
+    }
+    if shortcut.meta {
+        parts.push("Meta".to_string());
+    }
+    let mut key = shortcut.key.chars();
+    parts.push(match (key.next(), key.next()) {
+        (Some(c), None) => c.to_uppercase().to_string(),
+        _ => shortcut.key.clone(),
+    });
+    Some(parts.join("+"))
+}
+
+fn entry(kind: PaletteKind, value: String, label: String, detail: Option<String>) -> PaletteEntry {
+    PaletteEntry {
+        kind,
+        value,
+        label,
+        detail,
+    }
+}
+
+// Everything the palette can offer, in the order shown for an empty query
+fn collect_entries(app: &AppHandle, config: &Config, state: &PaletteState) -> Vec<PaletteEntry> {
+    let mut entries: Vec<PaletteEntry> = config
+        .shortcuts
+        .iter()
+        .map(|(action, shortcut)| {
+            entry(
+                PaletteKind::Action,
+                action.clone(),
+                action_label(action),
+                shortcut_label(shortcut),
+            )
+        })
+        .collect();
+    let dropdown_enabled = config.dropdown.as_ref().is_some_and(|d| d.enabled);
+    for action in EXTRA_ACTIONS {
+        if action == "toggle_dropdown" && !dropdown_enabled {
+            continue;
+        }
+        if config.shortcuts.get(action).is_none() {
+            entries.push(entry(
+                PaletteKind::Action,
+                action.to_string(),
+                action_label(action),
+                None,
+            ));
+        }
+    }
+
+    for profile in config.profiles.iter().flat_map(|p| &p.list) {
+        entries.push(entry(
+            PaletteKind::Profile,
+            profile.name.clone(),
+            profile.name.clone(),
+            Some(profile.command.clone()),
+        ));
+    }
+
+    for (name, task) in config.tasks.iter().flatten() {
+        let command = std::iter::once(&task.command)
+            .chain(task.args.iter().flatten())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        entries.push(entry(
+            PaletteKind::Task,
+            name.clone(),
+            name.clone(),
+            Some(command),
+        ));
+    }
+
+    for name in session::list_sessions(app) {
+        entries.push(entry(PaletteKind::Workspace, name.clone(), name, None));
+    }
+
+    // Picked directories first, then the most visited ones, then where the
+    // open shells are
+    let mut seen = HashSet::new();
+    let recent = state
+        .directories
+        .lock()
+        .map(|d| d.iter().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let open = pty::active_pty_ids()
+        .into_iter()
+        .filter_map(|id| pty::pty_cwd(&id));
+    let frecent = frecency::top_dirs(app, MAX_FRECENT_DIRS);
+    for dir in recent.into_iter().chain(frecent).chain(open) {
+        if seen.insert(dir.clone()) {
+            entries.push(entry(PaletteKind::Directory, dir.clone(), dir, None));
+        }
+    }
+
+    if let Ok(commands) = state.commands.lock() {
+        for command in commands.iter() {
+            entries.push(entry(
+                PaletteKind::Command,
+                command.clone(),
+                command.clone(),
+                None,
+            ));
+        }
+    }
+    entries
+}
+
+/// Rank entries against a query, best first. Ties keep the entries' order.
+pub fn rank(entries: Vec<PaletteEntry>, query: &str) -> Vec<PaletteResult> {
+    let query = query.trim();
+    let mut results: Vec<PaletteResult> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (score, matches) = fuzzy_match(query, &entry.label)?;
+            Some(PaletteResult {
+                entry,
+                score,
+                matches,
+            })
+        })
+        .collect();
+    if !query.is_empty() {
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+    }
+    results.truncate(MAX_RESULTS);
+    results
+}
+
+// Fuzzy search actions, profiles, tasks, saved sessions, recent directories
+// and recent commands
+#[tauri::command]
+pub async fn palette_query(
+    app: AppHandle,
+    state: State<'_, PaletteState>,
+    query: String,
+) -> Result<Vec<PaletteResult>, String> {
+    let mut config = Config::load(&app)?;
+    dynamic_profiles::merge(&app, &mut config);
+    Ok(rank(collect_entries(&app, &config, &state), &query))
+}
+
+// Remember a directory or command picked from the palette so it's offered
+// again. Other kinds aren't recorded.
+#[tauri::command]
+pub async fn record_palette_entry(
+    state: State<'_, PaletteState>,
+    kind: PaletteKind,
+    value: String,
+) -> Result<(), String> {
+    let recent = match kind {
+        PaletteKind::Directory => &state.directories,
+        PaletteKind::Command => &state.commands,
+        _ => return Ok(()),
+    };
+    let mut recent = recent
+        .lock()
+        .map_err(|e| format!("Failed to lock palette history: {}", e))?;
+    recent.retain(|v| *v != value);
+    recent.push_front(value);
+    recent.truncate(MAX_RECENT);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str) -> PaletteEntry {
+        entry(PaletteKind::Action, name.into(), action_label(name), None)
+    }
+
+    #[test]
+    fn test_rank_palette_entries() {
+        let entries = vec![
+            action("scroll_to_prompt"),
+            action("split_vertical"),
+            action("split_horizontal"),
+            action("new_tab"),
+        ];
+
+        let results = rank(entries.clone(), "sv");
+        assert_eq!(results[0].entry.value, "split_vertical");
+        assert_eq!(results[0].matches, [0, 6]);
+
+        let results = rank(entries.clone(), "new");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.label, "New tab");
+
+        assert!(rank(entries.clone(), "xyz").is_empty());
+        // An empty query lists everything in order
+        assert_eq!(rank(entries, "").len(), 4);
+    }
+}
@@ -0,0 +1,347 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::crash;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty;
+
+/// Bytes written to the PTY at once while streaming a paste, so a rate limit
+/// is kept smoothly
+const PASTE_WRITE_SIZE: usize = 4096;
+
+/// Chunks a paste stream holds before `paste_chunk` waits for the shell
+const PASTE_QUEUE_CHUNKS: usize = 4;
+
+/// Why a paste may need a second look before it reaches the shell
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteIssueKind {
+    /// More than one line, the shell runs every line that ends in a newline
+    Multiline,
+    /// Control character other than tab and line endings
+    ControlCharacter,
+    /// Character that reorders the displayed text, so what is shown isn't what runs
+    BidiOverride,
+    /// Invisible character that can hide inside a command
+    ZeroWidth,
+    /// Command run as root
+    Sudo,
+    /// Download piped straight into a shell
+    PipeToShell,
+    /// Recursive delete of a root or home directory
+    DangerousDelete,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PasteIssue {
+    pub kind: PasteIssueKind,
+    /// Line of the paste the issue is on, starting at 1
+    pub line: usize,
+    /// Character offset in the line, for issues about a single character
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasteAnalysis {
+    pub lines: usize,
+    /// Whether the paste ends with a newline, which runs the last line at once
+    pub ends_with_newline: bool,
+    pub issues: Vec<PasteIssue>,
+    /// The frontend should ask before writing the paste to the shell
+    pub needs_confirmation: bool,
+}
+
+lazy_static! {
+    static ref SUDO: Regex = Regex::new(r"(^|[;&|(]\s*|\s)(sudo|doas|pkexec|su)(\s|$)").unwrap();
+    static ref PIPE_TO_SHELL: Regex = Regex::new(
+        r"\b(curl|wget|fetch|iwr|Invoke-WebRequest)\b[^|]*\|\s*(sudo\s+)?(ba|z|k|da|fi)?sh\b|\|\s*(iex|Invoke-Expression)\b"
+    )
+    .unwrap();
+    static ref DANGEROUS_DELETE: Regex =
+        Regex::new(r"\brm\s+(-[a-zA-Z]*[rR][a-zA-Z]*\s+)+(-\S+\s+)*(/|~|\$HOME|/\*|~/\*)(\s|$)")
+            .unwrap();
+    // Pastes being streamed, by stream ID
+    static ref PASTE_STREAMS: Mutex<HashMap<String, PasteStream>> = Mutex::new(HashMap::new());
+}
+
+/// A large paste written to a PTY a chunk at a time by its own thread
+struct PasteStream {
+    pty_id: String,
+    chunks: SyncSender<String>,
+    /// Bytes written once the stream ends, or why writing stopped
+    result: Receiver<CommandResult<u64>>,
+}
+
+// Characters that change the display order of text, see CVE-2021-42574
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{200E}' | '\u{200F}' | '\u{061C}')
+}
+
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+fn describe(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+/// Look for things in pasted text the user should confirm before it runs
+pub fn analyze(text: &str) -> PasteAnalysis {
+    let mut issues = Vec::new();
+    let ends_with_newline = text.ends_with('\n') || text.ends_with('\r');
+    let lines: Vec<&str> = text.lines().collect();
+
+    if lines.len() > 1 {
+        issues.push(PasteIssue {
+            kind: PasteIssueKind::Multiline,
+            line: 1,
+            column: None,
+            message: format!(
+                "The paste has {} lines, each one runs as a command",
+                lines.len()
+            ),
+        });
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_number = index + 1;
+        for (column, c) in line.chars().enumerate() {
+            let kind = if is_bidi_control(c) {
+                PasteIssueKind::BidiOverride
+            } else if is_zero_width(c) {
+                PasteIssueKind::ZeroWidth
+            } else if c.is_control() && c != '\t' {
+                PasteIssueKind::ControlCharacter
+            } else {
+                continue;
+            };
+            let message = match kind {
+                PasteIssueKind::BidiOverride => {
+                    format!("{} changes how the text is displayed", describe(c))
+                }
+                PasteIssueKind::ZeroWidth => format!("Invisible character {}", describe(c)),
+                _ => format!("Hidden control character {}", describe(c)),
+            };
+            issues.push(PasteIssue {
+                kind,
+                line: line_number,
+                column: Some(column),
+                message,
+            });
+        }
+
+        let patterns: [(&Regex, PasteIssueKind, &str); 3] = [
+            (&SUDO, PasteIssueKind::Sudo, "Runs a command as root"),
+            (
+                &PIPE_TO_SHELL,
+                PasteIssueKind::PipeToShell,
+                "Runs a downloaded script without showing it",
+            ),
+            (
+                &DANGEROUS_DELETE,
+                PasteIssueKind::DangerousDelete,
+                "Deletes a root or home directory",
+            ),
+        ];
+        for (pattern, kind, message) in patterns {
+            if pattern.is_match(line) {
+                issues.push(PasteIssue {
+                    kind,
+                    line: line_number,
+                    column: None,
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+
+    PasteAnalysis {
+        lines: lines.len(),
+        ends_with_newline,
+        needs_confirmation: !issues.is_empty(),
+        issues,
+    }
+}
+
+// Check pasted text before it is written to the shell
+#[tauri::command]
+pub async fn analyze_paste(text: String) -> Result<PasteAnalysis, String> {
+    Ok(analyze(&text))
+}
+
+// How long to wait before the next write so no more than `bytes_per_sec`
+// have been written by then
+fn rate_delay(written: u64, bytes_per_sec: Option<u64>, elapsed: Duration) -> Option<Duration> {
+    let rate = bytes_per_sec.filter(|&rate| rate > 0)?;
+    Duration::from_secs_f64(written as f64 / rate as f64).checked_sub(elapsed)
+}
+
+fn write_stream(
+    pty_id: &str,
+    chunks: Receiver<String>,
+    bytes_per_sec: Option<u64>,
+) -> CommandResult<u64> {
+    let start = Instant::now();
+    let mut written = 0u64;
+    for chunk in chunks {
+        for piece in chunk.as_bytes().chunks(PASTE_WRITE_SIZE) {
+            if let Some(delay) = rate_delay(written, bytes_per_sec, start.elapsed()) {
+                thread::sleep(delay);
+            }
+            pty::write_input(pty_id, piece)?;
+            written += piece.len() as u64;
+        }
+    }
+    Ok(written)
+}
+
+fn stream_not_found(stream_id: &str) -> TermillionError {
+    TermillionError::new(
+        ErrorKind::NotFound,
+        format!("Paste stream {} not found", stream_id),
+    )
+    .with_context(stream_id)
+}
+
+// Start streaming a large paste into a PTY, instead of sending it in one
+// `write_pty` call. Chunks are written in order by a thread of their own, at
+// most `bytes_per_sec` when set. Returns the ID `paste_chunk` and `end_paste`
+// take.
+#[tauri::command]
+pub async fn begin_paste_stream(
+    pty_id: String,
+    bytes_per_sec: Option<u64>,
+) -> CommandResult<String> {
+    if !pty::active_pty_ids().contains(&pty_id) {
+        return Err(TermillionError::pty_not_found(&pty_id));
+    }
+
+    let stream_id = Uuid::new_v4().to_string();
+    let (chunks, receiver) = mpsc::sync_channel(PASTE_QUEUE_CHUNKS);
+    let (result_sender, result) = mpsc::channel();
+    let writer_pty_id = pty_id.clone();
+    crash::spawn("paste-stream", move || {
+        let _ = result_sender.send(write_stream(&writer_pty_id, receiver, bytes_per_sec));
+    });
+    PASTE_STREAMS.lock().unwrap().insert(
+        stream_id.clone(),
+        PasteStream {
+            pty_id,
+            chunks,
+            result,
+        },
+    );
+    Ok(stream_id)
+}
+
+/// Forget the pastes streamed into a PTY, for when nobody will end them.
+/// Their threads stop after the chunks already queued.
+pub fn drop_streams(pty_id: &str) {
+    PASTE_STREAMS
+        .lock()
+        .unwrap()
+        .retain(|_, stream| stream.pty_id != pty_id);
+}
+
+// Queue the next chunk of a paste. Waits while the stream is full, so the
+// frontend sends no faster than the shell reads.
+#[tauri::command]
+pub async fn paste_chunk(stream_id: String, data: String) -> CommandResult<()> {
+    let chunks = PASTE_STREAMS
+        .lock()
+        .unwrap()
+        .get(&stream_id)
+        .map(|stream| stream.chunks.clone())
+        .ok_or_else(|| stream_not_found(&stream_id))?;
+    let sent = tauri::async_runtime::spawn_blocking(move || chunks.send(data).is_ok())
+        .await
+        .unwrap_or(false);
+    if sent {
+        return Ok(());
+    }
+    // The writer stopped, tell why
+    end_paste(stream_id).await.map(drop)
+}
+
+// Finish a paste once every chunk was sent, returning the bytes written
+#[tauri::command]
+pub async fn end_paste(stream_id: String) -> CommandResult<u64> {
+    let PasteStream { chunks, result, .. } = PASTE_STREAMS
+        .lock()
+        .unwrap()
+        .remove(&stream_id)
+        .ok_or_else(|| stream_not_found(&stream_id))?;
+    drop(chunks);
+    tauri::async_runtime::spawn_blocking(move || result.recv())
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .unwrap_or_else(|| {
+            Err(TermillionError::new(
+                ErrorKind::Internal,
+                "Paste stream stopped unexpectedly",
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<PasteIssueKind> {
+        analyze(text)
+            .issues
+            .iter()
+            .map(|issue| issue.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_paste() {
+        let safe = analyze("git status");
+        assert!(!safe.needs_confirmation);
+        assert_eq!(safe.lines, 1);
+
+        assert_eq!(kinds("ls\npwd\n"), [PasteIssueKind::Multiline]);
+        assert!(analyze("ls\n").ends_with_newline);
+
+        assert_eq!(
+            kinds("echo \u{202E}txt.exe\x1b[8m"),
+            [
+                PasteIssueKind::BidiOverride,
+                PasteIssueKind::ControlCharacter
+            ]
+        );
+        assert_eq!(kinds("l\u{200B}s"), [PasteIssueKind::ZeroWidth]);
+
+        assert_eq!(
+            kinds("curl -fsSL https://example.com/install.sh | sudo bash"),
+            [PasteIssueKind::Sudo, PasteIssueKind::PipeToShell]
+        );
+        assert_eq!(kinds("sudo apt install ripgrep"), [PasteIssueKind::Sudo]);
+        assert!(kinds("pseudo-code").is_empty());
+        assert_eq!(kinds("rm -rf ~"), [PasteIssueKind::DangerousDelete]);
+        assert!(kinds("rm -rf ./target").is_empty());
+    }
+
+    #[test]
+    fn test_rate_delay() {
+        let second = Duration::from_secs(1);
+        assert_eq!(rate_delay(1000, None, Duration::ZERO), None);
+        assert_eq!(rate_delay(1000, Some(0), Duration::ZERO), None);
+        assert_eq!(
+            rate_delay(1000, Some(1000), Duration::from_millis(250)),
+            Some(Duration::from_millis(750))
+        );
+        // Behind schedule, write right away
+        assert_eq!(rate_delay(1000, Some(1000), 2 * second), None);
+    }
+}
@@ -0,0 +1,148 @@
+use git2::Repository;
+use std::cell::OnceCell;
+use std::path::Path;
+
+use crate::config::ProfileRule;
+use crate::pty;
+
+/// Whether `text` matches `pattern`, where `*` matches any text and `?` one
+/// character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and the text position it is tried from
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the `*` take one more character and try again
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// What rules are matched against, looked up once and only when a rule
+// needs it
+struct Facts<'a> {
+    cwd: &'a Path,
+    git_remotes: OnceCell<Vec<String>>,
+    hostname: OnceCell<Option<String>>,
+}
+
+impl Facts<'_> {
+    fn git_remotes(&self) -> &[String] {
+        self.git_remotes.get_or_init(|| {
+            let Ok(repo) = Repository::discover(self.cwd) else {
+                return Vec::new();
+            };
+            let Ok(names) = repo.remotes() else {
+                return Vec::new();
+            };
+            names
+                .iter()
+                .flatten()
+                .filter_map(|name| repo.find_remote(name).ok()?.url().map(str::to_string))
+                .collect()
+        })
+    }
+
+    fn hostname(&self) -> Option<&str> {
+        self.hostname
+            .get_or_init(sysinfo::System::host_name)
+            .as_deref()
+    }
+}
+
+fn matches(rule: &ProfileRule, facts: &Facts) -> bool {
+    if let Some(pattern) = &rule.cwd {
+        let cwd = facts.cwd.to_string_lossy();
+        if !glob_match(&pty::expand_home(pattern), &cwd) {
+            return false;
+        }
+    }
+    if let Some(pattern) = &rule.git_remote {
+        if !facts
+            .git_remotes()
+            .iter()
+            .any(|url| glob_match(pattern, url))
+        {
+            return false;
+        }
+    }
+    if let Some(pattern) = &rule.hostname {
+        if !facts
+            .hostname()
+            .is_some_and(|name| glob_match(pattern, name))
+        {
+            return false;
+        }
+    }
+    rule.env
+        .iter()
+        .flatten()
+        .all(|(name, pattern)| std::env::var(name).is_ok_and(|value| glob_match(pattern, &value)))
+}
+
+/// Profile of the first rule matching a shell started in `cwd`
+pub fn select<'a>(rules: &'a [ProfileRule], cwd: &Path) -> Option<&'a str> {
+    let facts = Facts {
+        cwd,
+        git_remotes: OnceCell::new(),
+        hostname: OnceCell::new(),
+    };
+    rules
+        .iter()
+        .find(|rule| matches(rule, &facts))
+        .map(|rule| rule.profile.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+
+    #[test]
+    fn test_select_profile() {
+        assert!(glob_match("/srv/*/logs", "/srv/app/v2/logs"));
+        assert!(glob_match("prod-??", "prod-01"));
+        assert!(!glob_match("prod-??", "prod-1"));
+        assert!(glob_match("*", ""));
+
+        let rules = vec![
+            ProfileRule {
+                profile: "wsl".into(),
+                env: Some(IndexMap::from([(
+                    "TERMILLION_TEST_UNSET_VAR".into(),
+                    "*".into(),
+                )])),
+                ..Default::default()
+            },
+            ProfileRule {
+                profile: "production".into(),
+                cwd: Some("/srv/prod*".into()),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            select(&rules, Path::new("/srv/prod/api")),
+            Some("production")
+        );
+        assert_eq!(select(&rules, Path::new("/home/me")), None);
+    }
+}
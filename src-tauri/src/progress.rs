@@ -0,0 +1,154 @@
+use serde::Serialize;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+use crate::pty::EscapeScanner;
+
+/// Longest OSC payload kept, progress sequences are a few bytes
+const MAX_OSC_LENGTH: usize = 64;
+
+/// State of a progress report, the numbers ConEmu and Windows Terminal use
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressState {
+    /// 0, progress is done and no longer shown
+    Hidden,
+    /// 1
+    Normal,
+    /// 2
+    Error,
+    /// 3, busy without a percentage
+    Indeterminate,
+    /// 4
+    Paused,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub state: ProgressState,
+    /// 0-100, not every state comes with one
+    pub percent: Option<u8>,
+}
+
+impl Progress {
+    pub const HIDDEN: Progress = Progress {
+        state: ProgressState::Hidden,
+        percent: None,
+    };
+}
+
+/// Progress reported by an `OSC 9;4;state;percent` payload
+pub fn parse_osc94(payload: &str) -> Option<Progress> {
+    let mut params = payload.split(';');
+    if params.next() != Some("9") || params.next() != Some("4") {
+        return None;
+    }
+    let state = match params.next().unwrap_or("0") {
+        "" | "0" => ProgressState::Hidden,
+        "1" => ProgressState::Normal,
+        "2" => ProgressState::Error,
+        "3" => ProgressState::Indeterminate,
+        "4" => ProgressState::Paused,
+        _ => return None,
+    };
+    let percent = params
+        .next()
+        .and_then(|percent| percent.parse::<u32>().ok())
+        .map(|percent| percent.min(100) as u8);
+    Some(Progress { state, percent })
+}
+
+/// Finds the progress programs report with OSC 9;4 in PTY output
+pub struct ProgressScanner {
+    escape: EscapeScanner,
+}
+
+impl Default for ProgressScanner {
+    fn default() -> Self {
+        Self {
+            escape: EscapeScanner::new(MAX_OSC_LENGTH),
+        }
+    }
+}
+
+impl ProgressScanner {
+    /// The last progress reported in `data`, if any
+    pub fn scan(&mut self, data: &[u8]) -> Option<Progress> {
+        let mut reported = None;
+        for &byte in data {
+            self.escape.advance(byte, |osc| {
+                reported = parse_osc94(&String::from_utf8_lossy(osc)).or(reported);
+            });
+        }
+        reported
+    }
+}
+
+/// Show a terminal's progress on its window's taskbar button (Windows) or
+/// dock icon (macOS)
+pub fn show(app: &AppHandle, window_label: &str, progress: Progress) {
+    let Some(window) = app.get_webview_window(window_label) else {
+        return;
+    };
+    let status = match progress.state {
+        ProgressState::Hidden => ProgressBarStatus::None,
+        ProgressState::Normal => ProgressBarStatus::Normal,
+        ProgressState::Error => ProgressBarStatus::Error,
+        ProgressState::Indeterminate => ProgressBarStatus::Indeterminate,
+        ProgressState::Paused => ProgressBarStatus::Paused,
+    };
+    let result = window.set_progress_bar(ProgressBarState {
+        status: Some(status),
+        progress: progress.percent.map(u64::from),
+    });
+    if let Err(e) = result {
+        tracing::error!("Failed to show progress on the taskbar: {}", e);
+    }
+
+    // The dock shows the percentage as a badge too
+    #[cfg(target_os = "macos")]
+    {
+        let label = match (progress.state, progress.percent) {
+            (ProgressState::Hidden, _) => None,
+            (_, Some(percent)) => Some(format!("{}%", percent)),
+            (_, None) => Some("…".to_string()),
+        };
+        if let Err(e) = window.set_badge_label(label) {
+            tracing::error!("Failed to show progress on the dock icon: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            parse_osc94("9;4;1;42"),
+            Some(Progress {
+                state: ProgressState::Normal,
+                percent: Some(42)
+            })
+        );
+        assert_eq!(
+            parse_osc94("9;4;3").map(|p| p.state),
+            Some(ProgressState::Indeterminate)
+        );
+        assert_eq!(
+            parse_osc94("9;4;0;0").map(|p| p.state),
+            Some(ProgressState::Hidden)
+        );
+        assert_eq!(parse_osc94("9;4;9;0"), None);
+        // Other OSC 9 sequences are notifications
+        assert_eq!(parse_osc94("9;build done"), None);
+
+        let mut scanner = ProgressScanner::default();
+        assert_eq!(scanner.scan(b"\x1b]9;4;1;"), None);
+        assert_eq!(
+            scanner.scan(b"150\x1b\\").and_then(|p| p.percent),
+            Some(100)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::BannerConfig;
+
+// Default time a banner command is allowed to run
+const DEFAULT_COMMAND_TIMEOUT_MS: u64 = 2000;
+
+/// Resolve a banner configuration into the text to display
+pub fn render(banner: &BannerConfig) -> Result<String, String> {
+    match banner {
+        BannerConfig::Text { text } => Ok(text.clone()),
+        BannerConfig::File { path } => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read banner file {}: {}", path, e))?;
+            Ok(pick_fortune(&content))
+        }
+        BannerConfig::Command {
+            command,
+            args,
+            timeout_ms,
+        } => run_command(
+            command,
+            args.as_deref().unwrap_or_default(),
+            Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_COMMAND_TIMEOUT_MS)),
+        ),
+    }
+}
+
+/// Pick a random entry from a fortune-style file, where entries are
+/// separated by lines containing a single `%`
+fn pick_fortune(content: &str) -> String {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        if line.trim() == "%" {
+            if !current.trim().is_empty() {
+                entries.push(current.trim_end().to_string());
+            }
+            current.clear();
+        } else {
+            current.push_str(line);
+            current.push('\n');
+        }
+    }
+    if !current.trim().is_empty() {
+        entries.push(current.trim_end().to_string());
+    }
+
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    // No need for a proper RNG, the clock is random enough for a banner
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as usize)
+        .unwrap_or(0);
+
+    entries.swap_remove(seed % entries.len())
+}
+
+/// Run a command and capture its stdout, giving up after the timeout
+fn run_command(command: &str, args: &[String], timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run banner command {}: {}", command, e))?;
+
+    let mut stdout = child.stdout.take();
+    let (tx, rx) = mpsc::channel();
+
+    // Read on a separate thread so we can enforce the timeout
+    thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = std::io::Read::read_to_string(stdout, &mut output);
+        }
+        let _ = tx.send(output);
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(output) => {
+            let _ = child.wait();
+            Ok(output)
+        }
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(format!(
+                "Banner command {} timed out after {}ms",
+                command,
+                timeout.as_millis()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_fortune_skips_empty_entries() {
+        let content = "%\nonly entry\n%\n\n%\n";
+        assert_eq!(pick_fortune(content), "only entry");
+    }
+}
@@ -0,0 +1,260 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, InvokeResponseBody};
+
+use super::core::types::{OutputBatcher, PtyOutput, PtyOutputEvent, FRAME_OUTPUT};
+use crate::crash;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+
+/// Bytes the synthetic producer hands over per read, like a PTY read
+const READ_SIZE: usize = 8192;
+
+/// Batch timeout of the reader when the frontend doesn't set one
+const BATCH_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Longest benchmark run allowed
+const MAX_DURATION: Duration = Duration::from_secs(60);
+
+/// How long to wait for queued output to arrive after the producer stops
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Counts allocations in builds with the benchmark, so it can report them
+#[cfg(feature = "bench-command")]
+mod allocations {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+}
+
+#[cfg(feature = "bench-command")]
+fn allocations() -> u64 {
+    allocations::COUNT.load(Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "bench-command"))]
+fn allocations() -> u64 {
+    0
+}
+
+/// What the PTY output pipeline achieved under synthetic load
+#[derive(Debug, Serialize)]
+pub struct PipelineBenchmark {
+    pub bytes_produced: u64,
+    pub bytes_delivered: u64,
+    /// Output the bounded queue dropped because the sender fell behind
+    pub bytes_dropped: u64,
+    pub frames: u64,
+    pub elapsed_ms: u64,
+    pub throughput_bytes_per_sec: f64,
+    /// Time from a read to the frame holding its last byte being sent
+    pub latency_p50_us: u64,
+    pub latency_p90_us: u64,
+    pub latency_p99_us: u64,
+    pub latency_max_us: u64,
+    /// Allocations made by the whole app during the run
+    pub allocations: u64,
+}
+
+#[derive(Default)]
+struct Delivery {
+    bytes: u64,
+    frames: u64,
+    last: Option<Instant>,
+    latencies: Vec<Duration>,
+}
+
+// Value below which `percent` of the sorted samples fall
+fn percentile(sorted: &[Duration], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = (sorted.len() * percent).div_ceil(100).max(1) - 1;
+    sorted[index.min(sorted.len() - 1)].as_micros() as u64
+}
+
+fn run(bytes_per_sec: u64, duration: Duration) -> PipelineBenchmark {
+    // When each read was produced, by the stream offset it ends at
+    let reads: Arc<Mutex<VecDeque<(u64, Instant)>>> = Arc::default();
+    let delivery: Arc<Mutex<Delivery>> = Arc::default();
+    let dropped: Arc<OnceLock<Arc<AtomicU64>>> = Arc::default();
+
+    // Frames end up here instead of in a window
+    let sink_reads = reads.clone();
+    let sink_delivery = delivery.clone();
+    let sink_dropped = dropped.clone();
+    let sink = Channel::new(move |body| {
+        let now = Instant::now();
+        let InvokeResponseBody::Raw(frame) = body else {
+            return Ok(());
+        };
+        if frame.first() != Some(&FRAME_OUTPUT) {
+            return Ok(());
+        }
+        let mut delivery = sink_delivery.lock().unwrap();
+        delivery.bytes += frame.len() as u64 - 1;
+        delivery.frames += 1;
+        delivery.last = Some(now);
+        // Dropped output is the oldest, so it counts as arrived
+        let arrived = delivery.bytes
+            + sink_dropped
+                .get()
+                .map_or(0, |dropped| dropped.load(Ordering::Relaxed));
+        let mut reads = sink_reads.lock().unwrap();
+        while reads.front().is_some_and(|&(end, _)| end <= arrived) {
+            let (_, produced) = reads.pop_front().unwrap();
+            delivery.latencies.push(now - produced);
+        }
+        Ok(())
+    });
+    let output = PtyOutput::new(sink, None, None);
+    let _ = dropped.set(output.dropped_bytes());
+
+    let chunk: Arc<[u8]> = b"synthetic output line for the pty pipeline benchmark 0123456789\r\n"
+        .iter()
+        .copied()
+        .cycle()
+        .take(READ_SIZE)
+        .collect();
+    let produced = Arc::new(AtomicU64::new(0));
+    let allocations_before = allocations();
+    let start = Instant::now();
+
+    // Stands in for the PTY, handing over reads at the requested rate
+    let (reads_tx, reads_rx) = mpsc::channel::<Arc<[u8]>>();
+    let producer_reads = reads.clone();
+    let producer_produced = produced.clone();
+    crash::spawn("pty-benchmark-producer", move || {
+        let mut total = 0u64;
+        while start.elapsed() < duration {
+            let due = start + Duration::from_secs_f64(total as f64 / bytes_per_sec as f64);
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+            total += chunk.len() as u64;
+            producer_reads
+                .lock()
+                .unwrap()
+                .push_back((total, Instant::now()));
+            producer_produced.store(total, Ordering::Relaxed);
+            if reads_tx.send(chunk.clone()).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Batches the reads like the PTY reader does
+    let reader = crash::spawn("pty-benchmark-reader", move || {
+        let mut batcher = OutputBatcher::new(READ_SIZE, BATCH_TIMEOUT);
+        for read in reads_rx {
+            batcher.buffer_mut().extend_from_slice(&read);
+            if let Some(batch) = batcher.take(false) {
+                output.send(PtyOutputEvent::Output(batch));
+            }
+        }
+        if let Some(batch) = batcher.take(true) {
+            output.send(PtyOutputEvent::Output(batch));
+        }
+    });
+    let _ = reader.join();
+
+    // Wait for the sender to get through what's still queued
+    let drain_start = Instant::now();
+    let bytes_produced = produced.load(Ordering::Relaxed);
+    let bytes_dropped = || dropped.get().map_or(0, |d| d.load(Ordering::Relaxed));
+    while delivery.lock().unwrap().bytes + bytes_dropped() < bytes_produced
+        && drain_start.elapsed() < DRAIN_TIMEOUT
+    {
+        thread::sleep(Duration::from_millis(1));
+    }
+    let allocations = allocations() - allocations_before;
+
+    let mut delivery = delivery.lock().unwrap();
+    let elapsed = delivery.last.unwrap_or_else(Instant::now) - start;
+    delivery.latencies.sort();
+    let latencies = &delivery.latencies;
+    PipelineBenchmark {
+        bytes_produced,
+        bytes_delivered: delivery.bytes,
+        bytes_dropped: bytes_dropped(),
+        frames: delivery.frames,
+        elapsed_ms: elapsed.as_millis() as u64,
+        throughput_bytes_per_sec: delivery.bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency_p50_us: percentile(latencies, 50),
+        latency_p90_us: percentile(latencies, 90),
+        latency_p99_us: percentile(latencies, 99),
+        latency_max_us: percentile(latencies, 100),
+        allocations,
+    }
+}
+
+// Push synthetic output through the reader's batching, the output queue and
+// a channel, and measure how it does. Only in builds with the `bench-command`
+// feature, which also counts allocations.
+#[tauri::command]
+pub async fn benchmark_pty_pipeline(
+    bytes_per_sec: u64,
+    duration_ms: u64,
+) -> CommandResult<PipelineBenchmark> {
+    if !cfg!(feature = "bench-command") {
+        return Err(TermillionError::new(
+            ErrorKind::Internal,
+            "The PTY benchmark needs a build with the bench-command feature",
+        ));
+    }
+    if bytes_per_sec == 0 {
+        return Err(TermillionError::new(
+            ErrorKind::InvalidInput,
+            "bytes_per_sec must be greater than 0",
+        ));
+    }
+    let duration = Duration::from_millis(duration_ms).min(MAX_DURATION);
+    tauri::async_runtime::spawn_blocking(move || run(bytes_per_sec, duration))
+        .await
+        .map_err(|e| {
+            TermillionError::new(
+                ErrorKind::Internal,
+                format!("Failed to run PTY benchmark: {}", e),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_micros).collect();
+        assert_eq!(percentile(&samples, 50), 50);
+        assert_eq!(percentile(&samples, 99), 99);
+        assert_eq!(percentile(&samples, 100), 100);
+        assert_eq!(percentile(&samples[..1], 90), 1);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+}
@@ -0,0 +1,252 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::escape::{EscapeScanner, Scanned};
+use crate::frecency;
+
+/// Commands remembered per PTY, the oldest are forgotten first
+const MAX_BLOCKS: usize = 1000;
+
+/// Longest command line kept, the rest is dropped
+const MAX_COMMAND_LENGTH: usize = 4096;
+
+/// Longest OSC payload kept, enough for the directories OSC 7 reports
+const MAX_OSC_LENGTH: usize = 4096;
+
+/// One prompt and the command run from it, found through the OSC 133 marks
+/// shell integration scripts print. Offsets are byte positions in the PTY's
+/// output stream, counted over all output events like annotations.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CommandBlock {
+    pub id: u64,
+    pub prompt_start: u64,
+    /// Where the prompt ends and the command line starts
+    pub command_start: Option<u64>,
+    /// Where the command's output starts, set once it runs
+    pub output_start: Option<u64>,
+    /// Where the command's output ends, set once it finished
+    pub end: Option<u64>,
+    /// Command line as it was typed, without the shell's redrawing
+    pub command: Option<String>,
+    /// Directory the shell reported with OSC 7 when the command ran
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Unix times in milliseconds
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+impl CommandBlock {
+    fn new(id: u64, prompt_start: u64) -> Self {
+        Self {
+            id,
+            prompt_start,
+            command_start: None,
+            output_start: None,
+            end: None,
+            command: None,
+            cwd: None,
+            exit_code: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Splits a PTY's output into command blocks. `133;A` starts a prompt, `133;B`
+/// the command line, `133;C` the output and `133;D;<exit code>` ends it.
+pub struct CommandBlocks {
+    blocks: VecDeque<CommandBlock>,
+    next_id: u64,
+    escape: EscapeScanner,
+    /// Stream offset of the escape sequence being read
+    escape_start: u64,
+    /// Command line typed since `133;B`
+    command: Option<Vec<u8>>,
+    /// Last directory the shell reported
+    cwd: Option<String>,
+}
+
+impl Default for CommandBlocks {
+    fn default() -> Self {
+        Self {
+            blocks: VecDeque::new(),
+            next_id: 0,
+            escape: EscapeScanner::new(MAX_OSC_LENGTH),
+            escape_start: 0,
+            command: None,
+            cwd: None,
+        }
+    }
+}
+
+impl CommandBlocks {
+    /// Scan output starting at stream `offset`, returning the blocks that
+    /// finished in it
+    pub fn scan(&mut self, data: &[u8], offset: u64) -> Vec<CommandBlock> {
+        let mut finished = Vec::new();
+        for (i, &byte) in data.iter().enumerate() {
+            let position = offset + i as u64;
+            let mut osc = None;
+            let scanned = self.escape.advance(byte, |payload| {
+                osc = Some(String::from_utf8_lossy(payload).into_owned());
+            });
+            match scanned {
+                Scanned::Text => self.type_byte(byte),
+                Scanned::EscapeStart => self.escape_start = position,
+                Scanned::Escape => {}
+            }
+            if let Some(osc) = osc {
+                finished.extend(self.end_osc(&osc, position + 1));
+            }
+        }
+        finished
+    }
+
+    // Keep what is typed on the command line, backspaces take it back
+    fn type_byte(&mut self, byte: u8) {
+        let Some(command) = self.command.as_mut() else {
+            return;
+        };
+        match byte {
+            0x08 | 0x7f => {
+                command.pop();
+            }
+            0x00..=0x1f => {}
+            _ if command.len() < MAX_COMMAND_LENGTH => command.push(byte),
+            _ => {}
+        }
+    }
+
+    fn end_osc(&mut self, osc: &str, end: u64) -> Option<CommandBlock> {
+        if let Some(payload) = osc.strip_prefix("7;") {
+            self.cwd = frecency::parse_osc7(payload).or(self.cwd.take());
+            return None;
+        }
+        let mut parts = osc.split(';');
+        if parts.next() != Some("133") {
+            return None;
+        }
+        let start = self.escape_start;
+        match parts.next() {
+            Some("A") => {
+                match self.blocks.back_mut() {
+                    // A prompt drawn again before anything ran replaces the old one
+                    Some(last) if last.output_start.is_none() => {
+                        self.blocks.pop_back();
+                    }
+                    // Shells that don't print `133;D` end a command at the next prompt
+                    Some(last) if last.end.is_none() => {
+                        last.end = Some(start);
+                        last.finished_at = Some(now_ms());
+                    }
+                    _ => {}
+                }
+                self.push(start);
+                None
+            }
+            Some("B") => {
+                let block = self.open_block(start);
+                block.command_start = Some(end);
+                self.command = Some(Vec::new());
+                None
+            }
+            Some("C") => {
+                let command = self.command.take();
+                let cwd = self.cwd.clone();
+                let block = self.open_block(start);
+                block.output_start = Some(end);
+                block.started_at = Some(now_ms());
+                block.cwd = cwd;
+                block.command = command
+                    .map(|command| String::from_utf8_lossy(&command).trim().to_string())
+                    .filter(|command| !command.is_empty());
+                None
+            }
+            Some("D") => {
+                let block = self
+                    .blocks
+                    .back_mut()
+                    .filter(|block| block.output_start.is_some() && block.end.is_none())?;
+                block.end = Some(start);
+                block.exit_code = parts.next().and_then(|code| code.parse().ok());
+                block.finished_at = Some(now_ms());
+                Some(block.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn push(&mut self, prompt_start: u64) {
+        if self.blocks.len() >= MAX_BLOCKS {
+            self.blocks.pop_front();
+        }
+        self.next_id += 1;
+        self.blocks
+            .push_back(CommandBlock::new(self.next_id, prompt_start));
+    }
+
+    // The block a mark belongs to, started at the mark when the shell skipped
+    // `133;A` or the last block already ran
+    fn open_block(&mut self, start: u64) -> &mut CommandBlock {
+        if self
+            .blocks
+            .back()
+            .is_none_or(|block| block.output_start.is_some())
+        {
+            self.push(start);
+        }
+        self.blocks.back_mut().unwrap()
+    }
+
+    pub fn list(&self) -> Vec<CommandBlock> {
+        self.blocks.iter().cloned().collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<CommandBlock> {
+        self.blocks.iter().find(|block| block.id == id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_blocks() {
+        let mut blocks = CommandBlocks::default();
+        blocks.scan(b"\x1b]7;file://host/tmp\x07", 0);
+        let prompt = b"\x1b]133;A\x07$ \x1b]133;B\x07";
+        assert!(blocks.scan(prompt, 0).is_empty());
+        let offset = prompt.len() as u64;
+        assert!(blocks
+            .scan(b"lsx\x08 -l\r\n\x1b]133;C\x07", offset)
+            .is_empty());
+        let finished = blocks.scan(b"total 0\r\n\x1b]133;D;2\x1b\\", 35);
+
+        assert_eq!(finished.len(), 1);
+        let block = &finished[0];
+        assert_eq!(block.prompt_start, 0);
+        assert_eq!(block.command_start, Some(offset));
+        assert_eq!(block.output_start, Some(35));
+        assert_eq!(block.end, Some(44));
+        assert_eq!(block.command.as_deref(), Some("ls -l"));
+        assert_eq!(block.cwd.as_deref(), Some("/tmp"));
+        assert_eq!(block.exit_code, Some(2));
+
+        // Redrawn prompts don't leave empty blocks behind
+        blocks.scan(b"\x1b]133;A\x07$ \x1b]133;A\x07$ ", 60);
+        let list = blocks.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1].prompt_start, 70);
+        assert_eq!(blocks.get(block.id), Some(block.clone()));
+    }
+}
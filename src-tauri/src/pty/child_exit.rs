@@ -0,0 +1,110 @@
+use portable_pty::Child;
+use std::io;
+
+/// Wakes the reaper when a child process may have exited. On Unix that is
+/// any SIGCHLD, so children that aren't shells wake it too.
+#[cfg(unix)]
+pub struct ExitSignal(signal_hook::iterator::Signals);
+
+#[cfg(unix)]
+impl ExitSignal {
+    pub fn new() -> io::Result<Self> {
+        signal_hook::iterator::Signals::new([signal_hook::consts::SIGCHLD]).map(Self)
+    }
+
+    /// Block until a child exited since the last call
+    pub fn wait(&mut self) {
+        self.0.wait().for_each(drop);
+    }
+}
+
+// SIGCHLD covers every child, nothing to register
+#[cfg(unix)]
+pub fn watch(_pty_id: &str, _child: &(dyn Child + Send)) {}
+
+#[cfg(unix)]
+pub fn unwatch(_pty_id: &str) {}
+
+#[cfg(windows)]
+pub use windows::*;
+
+// The system thread pool waits on the shells' process handles and wakes the
+// reaper from its callback
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::Mutex;
+    use windows_sys::Win32::Foundation::{BOOLEAN, HANDLE};
+    use windows_sys::Win32::System::Threading::{
+        RegisterWaitForSingleObject, UnregisterWaitEx, INFINITE, WT_EXECUTEONLYONCE,
+    };
+
+    lazy_static::lazy_static! {
+        static ref WAKE: Mutex<Option<Sender<()>>> = Mutex::new(None);
+        // Registered waits by PTY ID, handles stored as numbers so they can be shared
+        static ref WAITS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    }
+
+    pub struct ExitSignal(Receiver<()>);
+
+    impl ExitSignal {
+        pub fn new() -> io::Result<Self> {
+            let (sender, receiver) = mpsc::channel();
+            *WAKE.lock().unwrap() = Some(sender);
+            Ok(Self(receiver))
+        }
+
+        /// Block until a shell exited since the last call
+        pub fn wait(&mut self) {
+            if self.0.recv().is_ok() {
+                self.0.try_iter().for_each(drop);
+            }
+        }
+    }
+
+    unsafe extern "system" fn on_exit(_context: *mut c_void, _timed_out: BOOLEAN) {
+        if let Some(wake) = WAKE.lock().unwrap().as_ref() {
+            let _ = wake.send(());
+        }
+    }
+
+    pub fn watch(pty_id: &str, child: &(dyn Child + Send)) {
+        let Some(process) = child.as_raw_handle() else {
+            return;
+        };
+        let mut wait: HANDLE = std::ptr::null_mut();
+        let registered = unsafe {
+            RegisterWaitForSingleObject(
+                &mut wait,
+                process as HANDLE,
+                Some(on_exit),
+                std::ptr::null(),
+                INFINITE,
+                WT_EXECUTEONLYONCE,
+            )
+        };
+        if registered == 0 {
+            tracing::error!(
+                "Failed to wait for PTY process exit: {}",
+                io::Error::last_os_error()
+            );
+            return;
+        }
+        WAITS
+            .lock()
+            .unwrap()
+            .insert(pty_id.to_string(), wait as usize);
+    }
+
+    // Must happen before the process handle is closed
+    pub fn unwatch(pty_id: &str) {
+        if let Some(wait) = WAITS.lock().unwrap().remove(pty_id) {
+            unsafe {
+                UnregisterWaitEx(wait as HANDLE, std::ptr::null_mut());
+            }
+        }
+    }
+}
@@ -3,18 +3,57 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    atomic::{AtomicBool, Ordering},
     Arc, Mutex,
 };
 use std::thread;
-use std::time::Duration;
-use tauri::{ipc::Channel, AppHandle, Emitter, Window};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{
+    ipc::{Channel, InvokeResponseBody},
+    AppHandle, Emitter, Manager, Window,
+};
+use tracing::{debug, error, info, info_span, instrument};
 use uuid::Uuid;
 
-use super::utils;
+use super::blocks::{CommandBlock, CommandBlocks};
+use super::child_exit::{self, ExitSignal};
+use super::echo::EchoProbe;
+use super::elevation;
+use super::locale;
+use super::msys;
+use super::output_log::{self, PtyLog};
+use super::pane_sync;
+use super::processes;
+use super::queue::{OutputQueue, Queued, MAX_QUEUED_BYTES};
+use super::sandbox;
+use super::scrollback::{Scrollback, Spill, DEFAULT_SPILL_MB, MAX_SCROLLBACK_BYTES};
+use super::title::{self, TitleVars};
+use super::{banner, utils};
+use crate::annotations::{Annotation, AnnotationScanner};
+use crate::color;
+use crate::command_history::HistoryRecorder;
+use crate::config::{Config, OnExit, PtyLogMode, TerminalSettings};
+use crate::control;
+use crate::crash;
+use crate::dynamic_profiles;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::frecency::{self, Osc7Scanner};
+use crate::hooks::{HookEvent, Hooks};
+use crate::notifications::{self, ActivityScanner};
+use crate::paste;
+use crate::profile_rules;
+use crate::progress::{self, ProgressScanner, ProgressState};
+use crate::scripting;
+use crate::secrets;
+use crate::session;
+use crate::shell_integration;
+use crate::tray;
+
+/// Shells that exit sooner after starting are held instead of restarted
+const MIN_RESTART_UPTIME_MS: u64 = 2000;
 
 // Module for PTY data structures
-mod types {
+pub(super) mod types {
     use super::*;
     use std::io::Write;
     use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -24,10 +63,231 @@ mod types {
         pub master: Box<dyn MasterPty + Send>,
         pub child: Box<dyn Child + Send>,
         pub reader_thread: Option<thread::JoinHandle<()>>,
-        pub exit_watcher: Option<thread::JoinHandle<()>>,
-        pub writer: Option<Box<dyn Write + Send>>,
+        // Locked on its own, a write blocking on a full PTY holds up no other
+        pub writer: Arc<Mutex<Box<dyn Write + Send>>>,
         pub exit_event_sent: Arc<AtomicBool>, // Track if exit event has been sent
         pub metrics: PtyMetrics,
+        pub cwd: String,    // Directory the shell was started in
+        pub shell: String,  // Program the PTY runs
+        pub window: String, // Label of the window showing the PTY
+        pub output: PtyOutput,
+        pub scrollback: Arc<Mutex<Scrollback>>, // Output kept for exports
+        pub blocks: Arc<Mutex<CommandBlocks>>,  // Commands found in the output
+        pub hooks: Option<Hooks>,               // Fired when the shell exits
+        pub title: Arc<Mutex<Option<String>>>,  // Last title the shell set
+        pub profile: Option<String>,            // Profile the PTY was created with
+        pub task: Option<String>,               // Task from the config the PTY runs
+        pub tab: Option<String>,                // Frontend tab the PTY is a pane of
+        pub decoration: PtyDecoration,          // Color and icon of the PTY's tab
+        pub elevated: bool,                     // Shell runs as administrator
+        pub output_log: Arc<Mutex<Option<PtyLog>>>, // File the output is logged to
+        pub echo: Arc<EchoProbe>,               // Terminal modes, read without the store lock
+        pub on_exit: OnExit,                    // What happens when the shell exits
+        pub held: bool,                         // Shell exited, PTY kept for its output
+        pub created_at: u64,                    // Unix time in milliseconds
+        pub size: PtySize,                      // Last size the frontend set
+    }
+
+    // Shared state of a PTY, read after the store lock is released
+    pub struct PtyHandles {
+        pub id: String,
+        pub window: String,
+        pub shell: String,
+        pub cwd: String,
+        pub profile: Option<String>,
+        pub decoration: PtyDecoration,
+        pub pid: Option<u32>,
+        pub created_at: u64,
+        pub metrics: PtyMetrics,
+        pub title: Arc<Mutex<Option<String>>>,
+        pub exited: Arc<AtomicBool>,
+    }
+
+    // Output channel of a PTY, swapped when the PTY moves to another window.
+    // Events go out as binary frames, so output bytes reach the frontend as
+    // an ArrayBuffer instead of a JSON array of numbers. They are queued and
+    // sent by a thread of their own, so a slow window never holds up the
+    // reader.
+    #[derive(Clone)]
+    pub struct PtyOutput(Arc<OutputSender>);
+
+    struct OutputSender {
+        channel: Mutex<Channel>,
+        // Unset when the page behind the channel went away, e.g. reloaded,
+        // until another channel is attached
+        attached: AtomicBool,
+        queue: OutputQueue<PtyOutputEvent>,
+        frame_interval: Option<Duration>, // Output is sent at most this often when coalescing frames
+        last_output: Mutex<std::time::Instant>,
+        compress_min_bytes: Option<usize>, // Output at least this large is compressed
+    }
+
+    // How long the sender waits for events before checking if the PTY is gone
+    const SENDER_WAIT: Duration = Duration::from_millis(500);
+
+    impl OutputSender {
+        // Send the queued events, waiting up to `timeout` for some
+        fn send_queued(&self, timeout: Duration) {
+            // Output arriving meanwhile is merged, so only its latest frame is sent
+            if let Some(interval) = self.frame_interval {
+                let due = *self.last_output.lock().unwrap() + interval;
+                if let Some(wait) = due.checked_duration_since(std::time::Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
+            for item in self.queue.pop_all(timeout) {
+                let frame = match item {
+                    Queued::Output(data) => {
+                        *self.last_output.lock().unwrap() = std::time::Instant::now();
+                        output_frame(data, self.compress_min_bytes)
+                    }
+                    Queued::Event(event) => event.encode(),
+                };
+                let result = frame.map_err(tauri::Error::from).and_then(|frame| {
+                    self.channel
+                        .lock()
+                        .unwrap()
+                        .send(InvokeResponseBody::Raw(frame))
+                });
+                if let Err(e) = result {
+                    error!("Failed to send PTY event via channel: {}", e);
+                    self.attached.store(false, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    // Whatever is still queued when the PTY is gone goes out right away
+    impl Drop for OutputSender {
+        fn drop(&mut self) {
+            self.send_queued(Duration::ZERO);
+        }
+    }
+
+    impl PtyOutput {
+        // With a frame interval, output of programs repainting the screen
+        // faster than that is coalesced to the latest complete frame. Output
+        // batches of at least `compress_min_bytes` are sent compressed.
+        pub fn new(
+            channel: Channel,
+            frame_interval: Option<Duration>,
+            compress_min_bytes: Option<usize>,
+        ) -> Self {
+            let sender = Arc::new(OutputSender {
+                channel: Mutex::new(channel),
+                attached: AtomicBool::new(true),
+                queue: OutputQueue::new(MAX_QUEUED_BYTES, frame_interval.is_some()),
+                frame_interval,
+                last_output: Mutex::new(std::time::Instant::now()),
+                compress_min_bytes,
+            });
+            // The thread only holds on to the sender while sending, so it ends
+            // once every handle to the output is dropped
+            let handle = Arc::downgrade(&sender);
+            crash::spawn("pty-sender", move || {
+                while let Some(sender) = handle.upgrade() {
+                    sender.send_queued(SENDER_WAIT);
+                }
+            });
+            Self(sender)
+        }
+
+        pub fn send(&self, event: PtyOutputEvent) {
+            match event {
+                PtyOutputEvent::Output(data) => self.0.queue.push_output(data),
+                event => self.0.queue.push_event(event),
+            }
+        }
+
+        pub fn replace(&self, channel: Channel) {
+            *self.0.channel.lock().unwrap() = channel;
+            self.0.attached.store(true, Ordering::SeqCst);
+        }
+
+        // The channel's page is gone, nothing it gets is shown anymore
+        pub fn detach(&self) {
+            self.0.attached.store(false, Ordering::SeqCst);
+        }
+
+        pub fn is_attached(&self) -> bool {
+            self.0.attached.load(Ordering::SeqCst)
+        }
+
+        // Output bytes dropped because the window didn't keep up
+        pub fn dropped_bytes(&self) -> Arc<AtomicU64> {
+            self.0.queue.dropped_bytes()
+        }
+    }
+
+    // Collects the output read from a PTY until it's time to send it
+    pub struct OutputBatcher {
+        buffer: Vec<u8>,
+        capacity: usize,
+        timeout: Duration,
+        last_send: std::time::Instant,
+    }
+
+    impl OutputBatcher {
+        pub fn new(read_size: usize, timeout: Duration) -> Self {
+            Self {
+                buffer: Vec::with_capacity(read_size * 2),
+                capacity: read_size * 2,
+                timeout,
+                last_send: std::time::Instant::now(),
+            }
+        }
+
+        pub fn buffer_mut(&mut self) -> &mut Vec<u8> {
+            &mut self.buffer
+        }
+
+        // Take the batch if the timeout has passed since the last one, or
+        // right away when forced. A fresh buffer is filled next, so the
+        // output is never copied.
+        pub fn take(&mut self, force: bool) -> Option<Vec<u8>> {
+            let now = std::time::Instant::now();
+            if self.buffer.is_empty() || (!force && now - self.last_send < self.timeout) {
+                return None;
+            }
+            self.last_send = now;
+            Some(std::mem::replace(
+                &mut self.buffer,
+                Vec::with_capacity(self.capacity),
+            ))
+        }
+    }
+
+    // First byte of every frame, telling the frontend how to read the rest
+    pub const FRAME_OUTPUT: u8 = 0; // Raw output bytes follow
+    pub const FRAME_EVENT: u8 = 1; // Any other event follows as JSON
+    pub const FRAME_OUTPUT_LZ4: u8 = 2; // LZ4 compressed output follows, prefixed with its size
+
+    // Compress large output if asked to, unless that doesn't make it smaller
+    pub fn output_frame(
+        data: Vec<u8>,
+        compress_min_bytes: Option<usize>,
+    ) -> serde_json::Result<Vec<u8>> {
+        if compress_min_bytes.is_some_and(|min| data.len() >= min) {
+            let compressed = lz4_flex::compress_prepend_size(&data);
+            if compressed.len() < data.len() {
+                let mut frame = Vec::with_capacity(compressed.len() + 1);
+                frame.push(FRAME_OUTPUT_LZ4);
+                frame.extend_from_slice(&compressed);
+                return Ok(frame);
+            }
+        }
+        PtyOutputEvent::Output(data).encode()
+    }
+
+    // Sent to both windows when a PTY is moved from one to the other
+    #[derive(Clone, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PtyTransfer {
+        pub pty_id: String,
+        pub from_window: String,
+        pub to_window: String,
+        pub profile: Option<String>,
+        pub title: Option<String>,
     }
 
     // Performance metrics for PTY
@@ -36,16 +296,33 @@ mod types {
         pub bytes_read: Arc<AtomicU64>,
         pub bytes_written: Arc<AtomicU64>,
         pub messages_sent: Arc<AtomicU64>,
+        pub dropped_bytes: Arc<AtomicU64>,
         pub created_at: std::time::Instant,
+        pub interval: Option<Duration>, // How often Metrics events are sent, if at all
+        pub next_report: std::time::Instant,
     }
 
     impl PtyMetrics {
-        pub fn new() -> Self {
+        pub fn new(interval: Option<Duration>, dropped_bytes: Arc<AtomicU64>) -> Self {
+            let created_at = std::time::Instant::now();
             Self {
                 bytes_read: Arc::new(AtomicU64::new(0)),
                 bytes_written: Arc::new(AtomicU64::new(0)),
                 messages_sent: Arc::new(AtomicU64::new(0)),
-                created_at: std::time::Instant::now(),
+                dropped_bytes,
+                created_at,
+                interval,
+                next_report: created_at + interval.unwrap_or_default(),
+            }
+        }
+
+        pub fn event(&self) -> PtyOutputEvent {
+            PtyOutputEvent::Metrics {
+                bytes_read: self.bytes_read.load(Ordering::Relaxed),
+                bytes_written: self.bytes_written.load(Ordering::Relaxed),
+                messages_sent: self.messages_sent.load(Ordering::Relaxed),
+                dropped_bytes: self.dropped_bytes.load(Ordering::Relaxed),
+                uptime_ms: self.created_at.elapsed().as_millis() as u64,
             }
         }
     }
@@ -71,12 +348,83 @@ mod types {
             bytes_read: u64,
             bytes_written: u64,
             messages_sent: u64,
+            dropped_bytes: u64,
             uptime_ms: u64,
         },
         Bell,
         Title {
+            // Rendered with `window.title_format` when set
             title: String,
+            // As the shell set it
+            raw: String,
+        },
+        Banner {
+            text: String,
+        },
+        Settings {
+            terminal: TerminalSettings,
+        },
+        // Clickable text found in the output sent so far
+        Annotations {
+            annotations: Vec<Annotation>,
+        },
+        // A program turned echo off to read a password, and back on
+        SecureInputStarted,
+        SecureInputEnded,
+        // Progress a program reported with OSC 9;4
+        Progress {
+            state: ProgressState,
+            percent: Option<u8>,
         },
+        // Color and icon of the PTY's tab changed
+        Decoration(PtyDecoration),
+        // Another program took over the terminal, or the shell got it back
+        ForegroundChanged {
+            name: String,
+            pid: u32,
+        },
+        // The shell was to run as administrator, but elevation was denied
+        ElevationDenied {
+            reason: String,
+        },
+        // The shell of a PTY that holds or restarts on exit ended, sent
+        // instead of `Exit`. Held PTYs stay stored until destroyed.
+        Exited {
+            code: u32,
+            duration_ms: u64,
+            restart: bool,
+        },
+    }
+
+    // A PTY whose shell exited, as the reaper reports it
+    pub struct ExitedPty {
+        pub status: portable_pty::ExitStatus,
+        pub on_exit: OnExit,
+        pub duration_ms: u64,
+        pub output: PtyOutput,
+        pub exit_event_sent: Arc<AtomicBool>,
+        pub hooks: Option<Hooks>,
+        pub elevated: bool,
+        // Dropped once the exit is reported, unset for held PTYs
+        pub removed: Option<PtyInstance>,
+    }
+
+    impl PtyOutputEvent {
+        // Frame the event for the output channel. Output is framed in place,
+        // batches have spare capacity so that doesn't allocate.
+        pub fn encode(self) -> serde_json::Result<Vec<u8>> {
+            match self {
+                PtyOutputEvent::Output(mut data) => {
+                    data.insert(0, FRAME_OUTPUT);
+                    Ok(data)
+                }
+                event => {
+                    let mut frame = vec![FRAME_EVENT];
+                    serde_json::to_writer(&mut frame, &event)?;
+                    Ok(frame)
+                }
+            }
+        }
     }
 
     impl From<PtySizeDto> for PtySize {
@@ -108,7 +456,9 @@ mod store {
     }
 
     // Get a mutable reference to a PTY
-    pub fn get_mut(id: &str) -> Option<std::sync::MutexGuard<HashMap<String, PtyInstance>>> {
+    pub fn get_mut(
+        id: &str,
+    ) -> Option<std::sync::MutexGuard<'static, HashMap<String, PtyInstance>>> {
         let store = PTY_STORE.lock().unwrap();
         if store.contains_key(id) {
             Some(store)
@@ -118,7 +468,7 @@ mod store {
     }
 
     // Get a reference to a PTY
-    pub fn get(id: &str) -> Option<std::sync::MutexGuard<HashMap<String, PtyInstance>>> {
+    pub fn get(id: &str) -> Option<std::sync::MutexGuard<'static, HashMap<String, PtyInstance>>> {
         let store = PTY_STORE.lock().unwrap();
         if store.contains_key(id) {
             Some(store)
@@ -138,28 +488,593 @@ mod store {
         let store = PTY_STORE.lock().unwrap();
         store.keys().cloned().collect()
     }
+
+    // Get the traffic counters of every PTY
+    pub fn all_counters() -> Vec<PtyCounters> {
+        let store = PTY_STORE.lock().unwrap();
+        store
+            .iter()
+            .map(|(id, pty)| PtyCounters {
+                pty_id: id.clone(),
+                window: pty.window.clone(),
+                bytes_read: pty.metrics.bytes_read.load(Ordering::Relaxed),
+                bytes_written: pty.metrics.bytes_written.load(Ordering::Relaxed),
+                messages_sent: pty.metrics.messages_sent.load(Ordering::Relaxed),
+                dropped_bytes: pty.metrics.dropped_bytes.load(Ordering::Relaxed),
+                uptime_ms: pty.metrics.created_at.elapsed().as_millis() as u64,
+            })
+            .collect()
+    }
+
+    // Get the IDs of the other PTYs in the same tab as a PTY
+    pub fn tab_siblings(id: &str) -> Vec<String> {
+        let store = PTY_STORE.lock().unwrap();
+        let Some(tab) = store.get(id).and_then(|pty| pty.tab.as_ref()) else {
+            return Vec::new();
+        };
+        store
+            .iter()
+            .filter(|(other, pty)| *other != id && pty.tab.as_ref() == Some(tab))
+            .map(|(other, _)| other.clone())
+            .collect()
+    }
+
+    // Whether any PTY is a pane of a tab
+    pub fn has_tab(tab: &str) -> bool {
+        let store = PTY_STORE.lock().unwrap();
+        store.values().any(|pty| pty.tab.as_deref() == Some(tab))
+    }
+
+    // Get the IDs of the PTYs shown in a window
+    pub fn get_window_ids(label: &str) -> Vec<String> {
+        let store = PTY_STORE.lock().unwrap();
+        store
+            .iter()
+            .filter(|(_, pty)| pty.window == label)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    // Get the ID and window of the running PTY started for a task
+    pub fn find_task(task: &str) -> Option<(String, String)> {
+        let store = PTY_STORE.lock().unwrap();
+        store
+            .iter()
+            .find(|(_, pty)| {
+                pty.task.as_deref() == Some(task) && !pty.exit_event_sent.load(Ordering::SeqCst)
+            })
+            .map(|(id, pty)| (id.clone(), pty.window.clone()))
+    }
+
+    // Get the metrics events due by `now`, and when the next one is due.
+    // PTYs that have exited aren't reported on anymore.
+    pub fn due_metrics(now: Instant) -> (Vec<(PtyOutput, PtyOutputEvent)>, Option<Instant>) {
+        let mut store = PTY_STORE.lock().unwrap();
+        let mut due = Vec::new();
+        let mut next: Option<Instant> = None;
+        for pty in store.values_mut() {
+            let Some(interval) = pty.metrics.interval else {
+                continue;
+            };
+            if pty.exit_event_sent.load(Ordering::SeqCst) {
+                pty.metrics.interval = None;
+                continue;
+            }
+            if pty.metrics.next_report <= now {
+                due.push((pty.output.clone(), pty.metrics.event()));
+                pty.metrics.next_report = now + interval;
+            }
+            let report = pty.metrics.next_report;
+            next = Some(next.map_or(report, |next| next.min(report)));
+        }
+        (due, next)
+    }
+
+    // Take the PTYs whose shell has exited. PTYs that hold on exit stay
+    // stored, marked as held so they are only taken once.
+    pub fn take_exited() -> Vec<(String, ExitedPty)> {
+        let mut store = PTY_STORE.lock().unwrap();
+        let mut exited = Vec::new();
+        for (id, pty) in store.iter_mut().filter(|(_, pty)| !pty.held) {
+            match pty.child.try_wait() {
+                Ok(Some(status)) => exited.push((id.clone(), status)),
+                Ok(None) => {}
+                Err(e) => error!(pty = %id, "Error checking PTY child status: {}", e),
+            }
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        exited
+            .into_iter()
+            .filter_map(|(id, status)| {
+                let pty = store.get_mut(&id)?;
+                let duration_ms = now.saturating_sub(pty.created_at);
+                let on_exit = match pty.on_exit {
+                    // Don't restart a shell that can't start in a loop
+                    OnExit::Restart if duration_ms < MIN_RESTART_UPTIME_MS => OnExit::Hold,
+                    on_exit => on_exit,
+                };
+                pty.held = on_exit == OnExit::Hold;
+                let mut exited = ExitedPty {
+                    status,
+                    on_exit,
+                    duration_ms,
+                    output: pty.output.clone(),
+                    exit_event_sent: pty.exit_event_sent.clone(),
+                    hooks: pty.hooks.clone(),
+                    elevated: pty.elevated,
+                    removed: None,
+                };
+                if !pty.held {
+                    exited.removed = store.remove(&id);
+                }
+                Some((id, exited))
+            })
+            .collect()
+    }
+
+    // Get the shared state of every PTY, cloning handles only
+    pub fn all_handles() -> Vec<PtyHandles> {
+        let store = PTY_STORE.lock().unwrap();
+        store
+            .iter()
+            .map(|(id, pty)| PtyHandles {
+                id: id.clone(),
+                window: pty.window.clone(),
+                shell: pty.shell.clone(),
+                cwd: pty.cwd.clone(),
+                profile: pty.profile.clone(),
+                decoration: pty.decoration.clone(),
+                pid: pty.child.process_id(),
+                created_at: pty.created_at,
+                metrics: pty.metrics.clone(),
+                title: pty.title.clone(),
+                exited: pty.exit_event_sent.clone(),
+            })
+            .collect()
+    }
+
+    // The process in the foreground of each running PTY, with the output to
+    // report it to. Windows consoles have no foreground process group, the
+    // shell's process stands in for it.
+    pub fn foregrounds() -> Vec<(String, Option<u32>, PtyOutput)> {
+        let store = PTY_STORE.lock().unwrap();
+        store
+            .iter()
+            .filter(|(_, pty)| !pty.held && !pty.exit_event_sent.load(Ordering::SeqCst))
+            .map(|(id, pty)| {
+                #[cfg(unix)]
+                let pid = pty.master.process_group_leader().map(|pid| pid as u32);
+                #[cfg(not(unix))]
+                let pid = pty.child.process_id();
+                (id.clone(), pid, pty.output.clone())
+            })
+            .collect()
+    }
+
+    // Check if any PTY wants metrics events
+    pub fn has_metrics() -> bool {
+        let store = PTY_STORE.lock().unwrap();
+        store.values().any(|pty| pty.metrics.interval.is_some())
+    }
+}
+
+// Module for the metrics reporter, one thread sends the Metrics events of
+// every PTY and stops when none asks for them
+mod reporter {
+    use super::*;
+
+    // Longest the reporter sleeps, so PTYs created meanwhile aren't reported late
+    const MAX_SLEEP: Duration = Duration::from_millis(250);
+
+    static RUNNING: AtomicBool = AtomicBool::new(false);
+
+    // Start the reporter unless it's already running
+    pub fn ensure_running() {
+        if RUNNING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            crash::spawn("pty-metrics", run);
+        }
+    }
+
+    fn run() {
+        loop {
+            let (due, next) = store::due_metrics(Instant::now());
+            for (output, event) in due {
+                output.send(event);
+            }
+
+            if let Some(next) = next {
+                thread::sleep(
+                    next.saturating_duration_since(Instant::now())
+                        .min(MAX_SLEEP),
+                );
+                continue;
+            }
+            // Nothing left to report. A PTY created right now saw the
+            // reporter running, so look again before leaving.
+            RUNNING.store(false, Ordering::SeqCst);
+            if !store::has_metrics()
+                || RUNNING
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_err()
+            {
+                break;
+            }
+        }
+    }
+}
+
+// Module for the foreground watcher, one thread polls which program runs in
+// the foreground of each PTY and reports changes. It stops with the last PTY.
+mod foreground {
+    use super::*;
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    static RUNNING: AtomicBool = AtomicBool::new(false);
+
+    // Start the watcher unless it's already running
+    pub fn ensure_running() {
+        if RUNNING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            crash::spawn("pty-foreground", run);
+        }
+    }
+
+    // Name and ID of the foreground process, from the process group leader
+    // on Unix and the newest process below the shell elsewhere
+    #[cfg(unix)]
+    fn current(pid: u32) -> Option<(u32, String)> {
+        let name = utils::process_name(pid).or_else(|| processes::name(pid))?;
+        Some((pid, name))
+    }
+
+    #[cfg(not(unix))]
+    fn current(pid: u32, tree: &processes::ProcessTree) -> Option<(u32, String)> {
+        tree.newest_below(pid)
+    }
+
+    fn run() {
+        // Last foreground process reported per PTY
+        let mut reported: HashMap<String, u32> = HashMap::new();
+        loop {
+            let ptys = store::foregrounds();
+            if ptys.is_empty() {
+                // A PTY created right now saw the watcher running, so look
+                // again before leaving
+                RUNNING.store(false, Ordering::SeqCst);
+                if store::foregrounds().is_empty()
+                    || RUNNING
+                        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_err()
+                {
+                    break;
+                }
+                continue;
+            }
+            reported.retain(|id, _| ptys.iter().any(|(pty_id, _, _)| pty_id == id));
+
+            #[cfg(not(unix))]
+            let tree = processes::ProcessTree::scan();
+            for (pty_id, pid, output) in ptys {
+                #[cfg(unix)]
+                let foreground = pid.and_then(current);
+                #[cfg(not(unix))]
+                let foreground = pid.and_then(|pid| current(pid, &tree));
+                let Some((pid, name)) = foreground else {
+                    continue;
+                };
+                if reported.insert(pty_id, pid) != Some(pid) {
+                    output.send(PtyOutputEvent::ForegroundChanged { name, pid });
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+// Module for the exit reaper, one thread finishes every PTY whose shell
+// exited. It's woken by child_exit instead of polling each PTY.
+mod reaper {
+    use super::*;
+
+    // How often PTYs are checked when exits can't be waited for
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    // Start the reaper unless it's already running
+    pub fn ensure_running(app: &AppHandle) {
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let app = app.clone();
+        crash::spawn("pty-reaper", move || {
+            let mut signal = ExitSignal::new()
+                .map_err(|e| error!("Failed to wait for PTY exits, polling instead: {}", e))
+                .ok();
+            loop {
+                // Check before waiting, a shell may have exited before the
+                // signal was set up
+                sweep(&app);
+                match signal.as_mut() {
+                    Some(signal) => signal.wait(),
+                    None => thread::sleep(POLL_INTERVAL),
+                }
+            }
+        });
+    }
+
+    fn sweep(app: &AppHandle) {
+        for (pty_id, pty) in store::take_exited() {
+            let _span = info_span!("pty", id = %pty_id).entered();
+            let status = &pty.status;
+            child_exit::unwatch(&pty_id);
+            info!("PTY process exited with status: {:?}", status);
+
+            // Sent even when the reader thread already reported the exit, it
+            // can't tell why the shell ended
+            if let Some(reason) = elevation::denied_reason(status.exit_code(), pty.duration_ms)
+                .filter(|_| pty.elevated)
+            {
+                info!("Elevation denied: {}", reason);
+                pty.output.send(PtyOutputEvent::ElevationDenied {
+                    reason: reason.to_string(),
+                });
+            }
+
+            if pty.on_exit != OnExit::Close {
+                // The frontend keeps the terminal, the reader thread left
+                // the exit to us
+                pty.exit_event_sent.store(true, Ordering::SeqCst);
+                pty.output.send(PtyOutputEvent::Exited {
+                    code: status.exit_code(),
+                    duration_ms: pty.duration_ms,
+                    restart: pty.on_exit == OnExit::Restart,
+                });
+            } else if pty
+                .exit_event_sent
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                // Send exit event with status, but only if not already sent
+                debug!("Sending exit event from reaper via channel");
+                pty.output.send(PtyOutputEvent::Exit {
+                    status: format!("{:?}", status),
+                });
+            }
+
+            // Handlers may take a while, the reaper has other PTYs to sweep
+            let hook_app = app.clone();
+            let hook_args = vec![pty_id.clone().into(), format!("{:?}", status).into()];
+            crash::spawn("script-hook", move || {
+                scripting::dispatch(&hook_app, scripting::ON_PTY_EXITED, hook_args);
+            });
+            if let Some(hooks) = &pty.hooks {
+                hooks.fire(&HookEvent::PtyExited {
+                    status: format!("{:?}", status),
+                });
+            }
+            // The reader thread can't be joined as it might be blocked on
+            // read, it ends on its own once the PTY is dropped
+            drop(pty.removed);
+        }
+    }
 }
 
 // Use our types
 use types::*;
+// For the benchmarks in `benches/`
+pub use types::{OutputBatcher, PtyOutputEvent};
 
-// Create a new PTY and return its ID
+// Create a new PTY and return its ID. Shells of profiles with `elevated`
+// set run as administrator.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_pty(
     window: Window,
-    _app: AppHandle,
-    cwd: String,
+    app: AppHandle,
+    cwd: Option<String>,
+    rows: u16,
+    cols: u16,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    profile: Option<String>,
+    output_channel: Channel,
+    buffer_size: Option<usize>,
+    batch_timeout_ms: Option<u64>,
+    metrics_interval_ms: Option<u64>,
+    coalesce_frames_ms: Option<u64>,
+    compress_min_bytes: Option<usize>,
+    task: Option<String>,
+    tab_id: Option<String>,
+    decoration: Option<PtyDecoration>,
+) -> CommandResult<String> {
+    open_pty(
+        window,
+        app,
+        cwd,
+        rows,
+        cols,
+        command,
+        args,
+        profile,
+        output_channel,
+        buffer_size,
+        batch_timeout_ms,
+        metrics_interval_ms,
+        coalesce_frames_ms,
+        compress_min_bytes,
+        task,
+        tab_id,
+        decoration,
+        false,
+    )
+    .await
+}
+
+// Create a PTY whose shell runs as administrator whatever its profile says,
+// for "Run as Administrator" tabs. If elevation is denied the PTY gets an
+// `elevationDenied` event before it exits.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_elevated_pty(
+    window: Window,
+    app: AppHandle,
+    cwd: Option<String>,
+    rows: u16,
+    cols: u16,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    profile: Option<String>,
+    output_channel: Channel,
+    buffer_size: Option<usize>,
+    batch_timeout_ms: Option<u64>,
+    metrics_interval_ms: Option<u64>,
+    coalesce_frames_ms: Option<u64>,
+    compress_min_bytes: Option<usize>,
+    task: Option<String>,
+    tab_id: Option<String>,
+    decoration: Option<PtyDecoration>,
+) -> CommandResult<String> {
+    open_pty(
+        window,
+        app,
+        cwd,
+        rows,
+        cols,
+        command,
+        args,
+        profile,
+        output_channel,
+        buffer_size,
+        batch_timeout_ms,
+        metrics_interval_ms,
+        coalesce_frames_ms,
+        compress_min_bytes,
+        task,
+        tab_id,
+        decoration,
+        true,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn open_pty(
+    window: Window,
+    app: AppHandle,
+    cwd: Option<String>,
     rows: u16,
     cols: u16,
     command: Option<String>,
     args: Option<Vec<String>>,
-    output_channel: Channel<PtyOutputEvent>,
+    profile: Option<String>,
+    output_channel: Channel,
     buffer_size: Option<usize>,
     batch_timeout_ms: Option<u64>,
     metrics_interval_ms: Option<u64>,
-) -> Result<String, String> {
+    coalesce_frames_ms: Option<u64>,
+    compress_min_bytes: Option<usize>,
+    task: Option<String>,
+    tab_id: Option<String>,
+    decoration: Option<PtyDecoration>,
+    elevated: bool,
+) -> CommandResult<String> {
     // Generate a unique ID for this PTY
     let pty_id = Uuid::new_v4().to_string();
+    // The PTY's threads log inside this span so their messages carry its ID
+    let span = info_span!("pty", id = %pty_id);
+
+    // Look up the profile so its settings can be applied to the new shell
+    let config = Config::load(&app)
+        .map_err(|e| error!("Failed to load config for new PTY: {}", e))
+        .ok()
+        .map(|mut config| {
+            dynamic_profiles::merge(&app, &mut config);
+            config
+        });
+    let default_cwd = config
+        .as_ref()
+        .and_then(|c| c.startup.as_ref())
+        .and_then(|startup| startup.default_cwd.clone());
+    // Without a profile the first matching profile rule picks one, by where
+    // the shell starts
+    let profile = profile.or_else(|| {
+        let rules = config.as_ref()?.profile_rules.as_ref()?;
+        let start = match cwd.clone().or(default_cwd.clone()) {
+            Some(dir) => utils::expand_home(&dir),
+            None => utils::get_home_dir().ok()?,
+        };
+        let picked = profile_rules::select(rules, std::path::Path::new(&start))?;
+        info!("Profile rule picked profile {}", picked);
+        Some(picked.to_string())
+    });
+    let profile_config = profile
+        .as_deref()
+        .and_then(|name| config.as_ref()?.profile(name).cloned());
+    let banner_config = profile_config.as_ref().and_then(|p| p.banner.clone());
+    let terminal_settings = config
+        .as_ref()
+        .map(|c| c.terminal_settings(profile.as_deref()));
+    // Arguments win over the config and the profile's terminal settings
+    let coalesce_frames_ms = coalesce_frames_ms
+        .or_else(|| terminal_settings.as_ref()?.coalesce_frames_ms)
+        .filter(|&ms| ms > 0);
+    let compress_min_bytes = compress_min_bytes
+        .or_else(|| terminal_settings.as_ref()?.compress_min_bytes)
+        .filter(|&bytes| bytes > 0);
+    let output_channel = PtyOutput::new(
+        output_channel,
+        coalesce_frames_ms.map(Duration::from_millis),
+        compress_min_bytes,
+    );
+    let mut activity_scanner = config
+        .as_ref()
+        .and_then(|c| c.notifications.as_ref())
+        .map(ActivityScanner::new);
+    let hooks = config
+        .as_ref()
+        .and_then(|c| c.hooks.as_ref())
+        .map(|h| Hooks::new(h, &pty_id));
+    let mut hook_scanner = hooks.as_ref().and_then(Hooks::scanner);
+    let mut annotation_scanner = config
+        .as_ref()
+        .and_then(|c| c.annotations.as_ref())
+        .and_then(AnnotationScanner::new);
+    let spill_mb = terminal_settings
+        .as_ref()
+        .filter(|t| t.scrollback_spill.unwrap_or(false))
+        .map(|t| t.scrollback_spill_mb.unwrap_or(DEFAULT_SPILL_MB));
+    let history_recorder = config
+        .as_ref()
+        .and_then(|c| HistoryRecorder::new(c.command_history.as_ref()));
+    let on_exit = profile_config
+        .as_ref()
+        .and_then(|p| p.on_exit)
+        .unwrap_or_default();
+    let log_mode = config
+        .as_ref()
+        .and_then(|c| c.logging.as_ref())
+        .and_then(|l| l.pty_output)
+        .unwrap_or_default();
+    let title_format = config.as_ref().and_then(|c| c.window.title_format.clone());
+    let mut startup_commands = profile_config
+        .as_ref()
+        .and_then(|p| p.startup_commands.clone())
+        .filter(|commands| !commands.is_empty());
+    let elevated = elevated
+        || profile_config
+            .as_ref()
+            .and_then(|p| p.elevated)
+            .unwrap_or(false);
 
     // Create PTY system
     let pty_system = native_pty_system();
@@ -173,7 +1088,9 @@ pub async fn create_pty(
     };
 
     // Open a new PTY
-    let pair = pty_system.openpty(size).map_err(|e| e.to_string())?;
+    let pair = pty_system
+        .openpty(size)
+        .map_err(|e| TermillionError::pty("Failed to open PTY", e))?;
 
     // Prepare command
     let mut cmd_builder = if let Some(cmd) = command {
@@ -183,8 +1100,11 @@ pub async fn create_pty(
         utils::get_default_shell()
     };
 
-    // Set working directory
-    cmd_builder.cwd(cwd);
+    // fish and nushell started without arguments load the shell integration
+    let args = args.or_else(|| {
+        let program = cmd_builder.get_argv().first()?.to_string_lossy();
+        shell_integration::default_args(&program)
+    });
 
     // Add arguments if provided
     if let Some(arg_list) = args {
@@ -193,13 +1113,119 @@ pub async fn create_pty(
         }
     }
 
+    // The shell itself, not whatever wraps it below
+    let shell = cmd_builder
+        .get_argv()
+        .first()
+        .map(|program| program.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // Shells of MSYS2, Cygwin and Git Bash need some of the setup their own
+    // launchers do
+    let posix_layer = if cfg!(windows) {
+        msys::PosixLayer::detect(&shell)
+    } else {
+        None
+    };
+    let winpty = posix_layer
+        .as_ref()
+        .filter(|_| {
+            profile_config
+                .as_ref()
+                .and_then(|p| p.winpty)
+                .unwrap_or(false)
+        })
+        .and_then(msys::PosixLayer::winpty);
+
+    // Run the whole command line through winpty, in the profile's sandbox,
+    // then through pkexec, sudo or gsudo. Those reset the environment, so an
+    // elevated shell doesn't see the variables set below.
+    let sandbox = profile_config.as_ref().and_then(|p| p.sandbox.as_ref());
+    if winpty.is_some() || sandbox.is_some() || elevated {
+        let mut argv: Vec<String> = cmd_builder
+            .get_argv()
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        if let Some(winpty) = winpty {
+            argv.insert(0, winpty);
+        }
+        if let Some(sandbox) = sandbox {
+            let tool = sandbox::sandbox_tool(sandbox).map_err(|e| {
+                TermillionError::new(
+                    ErrorKind::Pty,
+                    format!("Failed to sandbox the shell: {}", e),
+                )
+            })?;
+            argv = sandbox::wrap(sandbox, tool, argv);
+        }
+        if elevated {
+            argv = elevation::wrap(argv).map_err(|e| {
+                TermillionError::new(
+                    ErrorKind::PermissionDenied,
+                    format!("Failed to run the shell as administrator: {}", e),
+                )
+            })?;
+        }
+        cmd_builder = CommandBuilder::from_argv(argv.into_iter().map(Into::into).collect());
+    }
+
+    // Set working directory: an explicit directory (e.g. a restored session)
+    // wins over the profile's, then the configured default, then home
+    let cwd = match cwd {
+        Some(cwd) => cwd,
+        None => match profile_config
+            .as_ref()
+            .and_then(|p| p.cwd.clone())
+            .or(default_cwd)
+        {
+            Some(cwd) => utils::expand_home(&cwd),
+            None => utils::get_home_dir()?,
+        },
+    };
+    // Directories may be given as the shell's POSIX paths
+    let cwd = match &posix_layer {
+        Some(layer) => layer.windows_path(&cwd),
+        None => cwd,
+    };
+    cmd_builder.cwd(&cwd);
+
+    // Let `termillion cli` inside the shell find this terminal and this app
+    cmd_builder.env(control::PTY_ID_VAR, &pty_id);
+    cmd_builder.env(control::SOCKET_VAR, control::default_socket_path());
+    if let Some(layer) = &posix_layer {
+        for (key, value) in layer.env() {
+            cmd_builder.env(key, value);
+        }
+    }
+
+    // A UTF-8 locale instead of the `C` desktop launchers tend to pass on,
+    // the profile's env can still set `LC_ALL` and friends
+    let locale = locale::resolve(
+        profile_config.as_ref().and_then(|p| p.locale.as_deref()),
+        |var| std::env::var(var).ok(),
+    );
+    for (key, value) in locale.set {
+        cmd_builder.env(key, value);
+    }
+    for key in locale.remove {
+        cmd_builder.env_remove(key);
+    }
+
+    // Apply profile environment variables, secrets are read from the keyring
+    if let Some(env) = profile_config.as_ref().and_then(|p| p.env.as_ref()) {
+        for (key, value) in env {
+            cmd_builder.env(key, secrets::resolve(value)?);
+        }
+    }
+
     // Important: Drop the slave after spawning the command
     // This is necessary to avoid deadlocks and ensure proper cleanup
     let child = {
         let child = pair
             .slave
             .spawn_command(cmd_builder)
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| TermillionError::pty("Failed to start shell", e).with_context(&shell))?;
         // Explicitly drop the slave handle after spawning
         drop(pair.slave);
         child
@@ -209,30 +1235,92 @@ pub async fn create_pty(
     let exit_event_sent = Arc::new(AtomicBool::new(false));
     let exit_event_sent_clone = exit_event_sent.clone();
 
+    // Tell the frontend which terminal settings apply before any output arrives
+    if let Some(terminal) = terminal_settings {
+        output_channel.send(PtyOutputEvent::Settings { terminal });
+    }
+
+    // A restored tab keeps its decoration, others start with the profile's
+    let decoration = decoration.unwrap_or_else(|| PtyDecoration {
+        color: profile_config.as_ref().and_then(|p| p.color.clone()),
+        icon: profile_config.as_ref().and_then(|p| p.icon.clone()),
+    });
+    if decoration != PtyDecoration::default() {
+        output_channel.send(PtyOutputEvent::Decoration(decoration.clone()));
+    }
+
     // Clone output channel for the reader thread
     let output_channel_clone = output_channel.clone();
 
     // Create a reader for the PTY output
-    let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
-
-    // Take the writer once and store it
-    let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
-
-    // Create metrics
-    let metrics = PtyMetrics::new();
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| TermillionError::pty("Failed to read from PTY", e))?;
+
+    // Take the writer once and store it, the reader thread types the
+    // startup commands through it before the PTY is stored
+    let writer: Arc<Mutex<Box<dyn Write + Send>>> =
+        Arc::new(Mutex::new(pair.master.take_writer().map_err(|e| {
+            TermillionError::pty("Failed to write to PTY", e)
+        })?));
+    let reader_writer = writer.clone();
+
+    // Create metrics, a zero interval means no metrics events
+    let metrics_interval_ms = metrics_interval_ms.filter(|&ms| ms > 0);
+    let metrics = PtyMetrics::new(
+        metrics_interval_ms.map(Duration::from_millis),
+        output_channel.dropped_bytes(),
+    );
     let bytes_read = metrics.bytes_read.clone();
     let messages_sent = metrics.messages_sent.clone();
 
+    let scrollback = match spill_mb.and_then(|mb| open_spill(&app, &pty_id, mb)) {
+        Some(spill) => Scrollback::with_spill(MAX_SCROLLBACK_BYTES, spill),
+        None => Scrollback::new(MAX_SCROLLBACK_BYTES),
+    };
+    let scrollback = Arc::new(Mutex::new(scrollback));
+    let reader_scrollback = scrollback.clone();
+    let blocks = Arc::new(Mutex::new(CommandBlocks::default()));
+    let reader_blocks = blocks.clone();
+    let title = Arc::new(Mutex::new(None));
+    let reader_title = title.clone();
+    let output_log = Arc::new(Mutex::new(open_log(&app, &pty_id, log_mode)));
+    let reader_output_log = output_log.clone();
+    let echo = Arc::new(EchoProbe::new(pair.master.as_ref()));
+    let reader_echo = echo.clone();
+
     // Spawn a thread to read from the PTY and send to channel
-    let reader_thread = thread::spawn(move || {
+    let reader_pty_id = pty_id.clone();
+    let reader_app = app.clone();
+    let reader_hooks = hooks.clone();
+    let reader_thread = crash::spawn("pty-reader", move || {
+        let _span = span.enter();
+        // Send the banner before any shell output so it always appears first
+        if let Some(banner_config) = banner_config {
+            match banner::render(&banner_config) {
+                Ok(text) => output_channel_clone.send(PtyOutputEvent::Banner { text }),
+                Err(e) => error!("Failed to render banner: {}", e),
+            }
+        }
+
         // Use the provided buffer size or default to 8192
         let buffer_size = buffer_size.unwrap_or(8192);
         let mut buffer = vec![0u8; buffer_size];
 
         // Batch processing settings
         let batch_timeout = Duration::from_millis(batch_timeout_ms.unwrap_or(10));
-        let mut batch_buffer = Vec::with_capacity(buffer_size * 2);
-        let mut last_send = std::time::Instant::now();
+        let mut batcher = OutputBatcher::new(buffer_size, batch_timeout);
+
+        // Directories the shell reports, recorded for jump-to-directory
+        let mut osc7_scanner = Osc7Scanner::default();
+
+        // Progress shown on the taskbar, cleared again when the shell exits
+        let mut progress_scanner = ProgressScanner::default();
+        let mut progress_shown = false;
+
+        // Set while the program reads a password
+        let mut secure_input = false;
 
         // Title detection state
         let mut title_sequence = false;
@@ -251,12 +1339,17 @@ pub async fn create_pty(
 
                             // Convert title buffer to string
                             if let Ok(title) = String::from_utf8(title_buf.clone()) {
+                                *reader_title.lock().unwrap() = Some(title.clone());
                                 // Send title event
-                                if let Err(e) =
-                                    output_channel_clone.send(PtyOutputEvent::Title { title })
-                                {
-                                    eprintln!("Failed to send title event: {}", e);
-                                }
+                                output_channel_clone.send(PtyOutputEvent::Title {
+                                    title: render_title(
+                                        &reader_app,
+                                        &reader_pty_id,
+                                        title_format.as_deref(),
+                                        &title,
+                                    ),
+                                    raw: title,
+                                });
                             }
 
                             // Clear title buffer
@@ -287,27 +1380,31 @@ pub async fn create_pty(
             };
 
         // Function to send the current batch
-        let mut send_batch = |buffer: &mut Vec<u8>, force: bool| {
-            let now = std::time::Instant::now();
-            let elapsed = now.duration_since(last_send);
-
+        let mut send_batch = |batcher: &mut OutputBatcher, force: bool| {
             // Send if we have data and either the timeout has elapsed or we're forcing a send
-            if !buffer.is_empty() && (force || elapsed >= batch_timeout) {
-                // Clone the batch buffer to send
-                let output = buffer.clone();
-
-                // Update metrics
-                bytes_read.fetch_add(output.len() as u64, Ordering::Relaxed);
+            if let Some(output) = batcher.take(force) {
+                // Update metrics, the bytes sent before are where this batch starts
+                let offset = bytes_read.fetch_add(output.len() as u64, Ordering::Relaxed);
                 messages_sent.fetch_add(1, Ordering::Relaxed);
 
-                // Send output via channel
-                if let Err(e) = output_channel_clone.send(PtyOutputEvent::Output(output)) {
-                    eprintln!("Failed to send PTY output via channel: {}", e);
+                reader_scrollback.lock().unwrap().push(&output);
+                let finished = reader_blocks.lock().unwrap().scan(&output, offset);
+                if let Some(recorder) = &history_recorder {
+                    for block in &finished {
+                        recorder.record(&reader_app, &reader_pty_id, block);
+                    }
                 }
 
-                // Clear the batch buffer and update the last send time
-                buffer.clear();
-                last_send = now;
+                let annotations = annotation_scanner
+                    .as_mut()
+                    .map(|scanner| scanner.scan(&output, offset))
+                    .unwrap_or_default();
+
+                // Send output via channel
+                output_channel_clone.send(PtyOutputEvent::Output(output));
+                if !annotations.is_empty() {
+                    output_channel_clone.send(PtyOutputEvent::Annotations { annotations });
+                }
             }
         };
 
@@ -315,275 +1412,758 @@ pub async fn create_pty(
             match reader.read(&mut buffer) {
                 Ok(0) => {
                     // End of stream, PTY closed
-                    println!("PTY reader detected EOF, terminal closed");
+                    debug!("PTY reader detected EOF, terminal closed");
 
                     // Send any remaining data in the batch
-                    send_batch(&mut batch_buffer, true);
+                    send_batch(&mut batcher, true);
                     break;
                 }
                 Ok(n) => {
+                    // Logged as read, before title sequences are taken out
+                    if let Some(log) = reader_output_log.lock().unwrap().as_mut() {
+                        if let Err(e) = log.write(&buffer[0..n]) {
+                            error!("Failed to write output log: {}", e);
+                        }
+                    }
+
+                    // The shell has printed something, so it's ready for the startup
+                    // commands. Unless that was a password prompt, like sudo's for an
+                    // elevated shell, which would take them as the password.
+                    if startup_commands.is_some() && reader_echo.ready_for_input() {
+                        if let Some(commands) = startup_commands.take() {
+                            write_startup_commands(&reader_writer, &commands);
+                        }
+                    }
+
                     // Check for bell character (ASCII 7)
                     if buffer[0..n].contains(&7) {
                         // Send bell event
-                        if let Err(e) = output_channel_clone.send(PtyOutputEvent::Bell) {
-                            eprintln!("Failed to send bell event: {}", e);
+                        output_channel_clone.send(PtyOutputEvent::Bell);
+                        if let Some(window) = pty_window(&reader_pty_id) {
+                            tray::notify_bell(&reader_app, &window);
                         }
                     }
 
+                    // Notify about bells, finished commands and trigger matches
+                    // in terminals the user isn't looking at
+                    if let Some(scanner) = activity_scanner.as_mut() {
+                        let activities = scanner.scan(&buffer[0..n]);
+                        if let Some(window) =
+                            pty_window(&reader_pty_id).filter(|_| !activities.is_empty())
+                        {
+                            for activity in &activities {
+                                notifications::notify(&reader_app, &window, activity);
+                            }
+                        }
+                    }
+
+                    if let Some(cwd) = osc7_scanner.scan(&buffer[0..n]) {
+                        frecency::record(&reader_app, &reader_pty_id, &cwd);
+
+                        // Titles showing the directory follow it
+                        if title_format.as_deref().is_some_and(|f| f.contains("{cwd")) {
+                            let raw = reader_title.lock().unwrap().clone().unwrap_or_default();
+                            output_channel_clone.send(PtyOutputEvent::Title {
+                                title: render_title(
+                                    &reader_app,
+                                    &reader_pty_id,
+                                    title_format.as_deref(),
+                                    &raw,
+                                ),
+                                raw,
+                            });
+                        }
+                    }
+
+                    // Password prompts are printed after echo was turned off,
+                    // and echo is back on before the next output
+                    if let Some(reading) = reader_echo
+                        .reading_password()
+                        .filter(|&r| r != secure_input)
+                    {
+                        secure_input = reading;
+                        output_channel_clone.send(if reading {
+                            PtyOutputEvent::SecureInputStarted
+                        } else {
+                            PtyOutputEvent::SecureInputEnded
+                        });
+                    }
+
+                    if let Some(reported) = progress_scanner.scan(&buffer[0..n]) {
+                        output_channel_clone.send(PtyOutputEvent::Progress {
+                            state: reported.state,
+                            percent: reported.percent,
+                        });
+                        if let Some(window) = pty_window(&reader_pty_id) {
+                            progress::show(&reader_app, &window, reported);
+                        }
+                        progress_shown = reported.state != ProgressState::Hidden;
+                    }
+
+                    // Run the hooks configured for bells, failed commands and triggers
+                    if let (Some(scanner), Some(hooks)) =
+                        (hook_scanner.as_mut(), reader_hooks.as_ref())
+                    {
+                        hooks.handle(&scanner.scan(&buffer[0..n]));
+                    }
+
                     // Process for title sequences and add filtered data to batch buffer
                     process_for_title(
                         &buffer[0..n],
-                        &mut batch_buffer,
+                        batcher.buffer_mut(),
                         &mut title_sequence,
                         &mut title_buffer,
                     );
 
                     // Try to send the batch
-                    send_batch(&mut batch_buffer, false);
+                    send_batch(&mut batcher, false);
                 }
                 Err(e) => {
-                    eprintln!("Error reading from PTY: {}", e);
+                    error!("Error reading from PTY: {}", e);
 
                     // Send any remaining data in the batch
-                    send_batch(&mut batch_buffer, true);
+                    send_batch(&mut batcher, true);
                     break;
                 }
             }
         }
 
-        // Send exit event when the reader thread ends, but only if not already sent
-        if !exit_event_sent_clone.load(Ordering::SeqCst) {
-            if exit_event_sent_clone
+        if progress_shown {
+            if let Some(window) = pty_window(&reader_pty_id) {
+                progress::show(&reader_app, &window, progress::Progress::HIDDEN);
+            }
+        }
+
+        // Send exit event when the reader thread ends, but only if not already
+        // sent. Terminals kept on exit hear from the reaper instead.
+        if on_exit == OnExit::Close
+            && exit_event_sent_clone
                 .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
                 .is_ok()
-            {
-                println!("Sending exit event from reader thread via channel");
-                if let Err(e) = output_channel_clone.send(PtyOutputEvent::Exit {
-                    status: "Reader thread ended".to_string(),
-                }) {
-                    eprintln!("Failed to send PTY exit event via channel: {}", e);
-                }
-            }
+        {
+            debug!("Sending exit event from reader thread via channel");
+            output_channel_clone.send(PtyOutputEvent::Exit {
+                status: "Reader thread ended".to_string(),
+            });
         }
     });
 
     // Store the PTY instance first
+    child_exit::watch(&pty_id, child.as_ref());
     store::add(
         pty_id.clone(),
         PtyInstance {
             master: pair.master,
             child,
             reader_thread: Some(reader_thread),
-            exit_watcher: None, // We'll set this after creating the thread
-            writer: Some(writer),
+            writer,
             exit_event_sent,
             metrics,
+            cwd,
+            shell,
+            window: window.label().to_string(),
+            output: output_channel.clone(),
+            scrollback,
+            blocks,
+            hooks: hooks.clone(),
+            title,
+            profile,
+            task,
+            tab: tab_id,
+            decoration,
+            elevated,
+            output_log,
+            echo,
+            on_exit,
+            held: false,
+            size,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
         },
     );
 
-    // Start metrics reporting if requested
-    if let Some(interval) = metrics_interval_ms {
-        let metrics_channel = output_channel.clone();
-        let metrics_pty_id = pty_id.clone();
+    // Metrics events are sent by the shared reporter
+    if metrics_interval_ms.is_some() {
+        reporter::ensure_running();
+    }
 
-        thread::spawn(move || {
-            let interval = Duration::from_millis(interval);
+    // The shared reaper finishes the PTY once its shell exits
+    reaper::ensure_running(&app);
+    foreground::ensure_running();
 
-            loop {
-                thread::sleep(interval);
-
-                // Check if the PTY still exists
-                if let Some(store) = store::get(&metrics_pty_id) {
-                    let pty = match store.get(&metrics_pty_id) {
-                        Some(p) => p,
-                        None => break, // PTY was removed
-                    };
-
-                    // If exit event has been sent, stop reporting metrics
-                    if pty.exit_event_sent.load(Ordering::SeqCst) {
-                        break;
-                    }
+    if let Some(hooks) = &hooks {
+        hooks.fire(&HookEvent::PtyCreated);
+    }
 
-                    // Send metrics
-                    let metrics = PtyOutputEvent::Metrics {
-                        bytes_read: pty.metrics.bytes_read.load(Ordering::Relaxed),
-                        bytes_written: pty.metrics.bytes_written.load(Ordering::Relaxed),
-                        messages_sent: pty.metrics.messages_sent.load(Ordering::Relaxed),
-                        uptime_ms: pty.metrics.created_at.elapsed().as_millis() as u64,
-                    };
-
-                    if let Err(e) = metrics_channel.send(metrics) {
-                        eprintln!("Failed to send PTY metrics: {}", e);
-                        break;
-                    }
-                } else {
-                    // PTY not found, stop reporting metrics
-                    break;
-                }
-            }
-        });
+    // Scripts may type into the new shell, so they run once it is stored
+    let hook_pty_id = pty_id.clone();
+    crash::spawn("script-hook", move || {
+        scripting::dispatch(&app, scripting::ON_PTY_CREATED, vec![hook_pty_id.into()]);
+    });
+
+    Ok(pty_id)
+}
+
+// Type the profile's startup commands into the shell
+fn write_startup_commands(writer: &Mutex<Box<dyn Write + Send>>, commands: &[String]) {
+    let mut writer = writer.lock().unwrap();
+
+    for command in commands {
+        // Shells expect a carriage return, like pressing Enter
+        if let Err(e) = writer.write_all(format!("{}\r", command).as_bytes()) {
+            error!("Failed to write startup command: {}", e);
+            break;
+        }
+    }
+    if let Err(e) = writer.flush() {
+        error!("Failed to flush startup commands: {}", e);
     }
+}
 
-    // Create a thread to watch for process exit
-    let output_channel_exit = output_channel.clone();
-    let pty_id_exit_clone = pty_id.clone();
+// Whether a PTY's program reads a password, where that can be told
+fn pty_reading_password(pty_id: &str) -> Option<bool> {
+    let echo = {
+        let store = store::get(pty_id)?;
+        store.get(pty_id)?.echo.clone()
+    };
+    echo.reading_password()
+}
 
-    let exit_watcher = thread::spawn(move || {
-        // Sleep a bit to ensure the PTY is fully set up
-        thread::sleep(Duration::from_millis(100));
+// Tab a PTY is a pane of
+fn pty_tab(pty_id: &str) -> Option<String> {
+    let store = store::get(pty_id)?;
+    store.get(pty_id)?.tab.clone()
+}
 
-        // Periodically check if the process has exited
-        loop {
-            // Get the PTY from the store
-            if let Some(mut store) = store::get_mut(&pty_id_exit_clone) {
-                let pty = match store.get_mut(&pty_id_exit_clone) {
-                    Some(p) => p,
-                    None => {
-                        println!("PTY was removed from store, exit watcher ending");
-                        break; // PTY was removed, exit the loop
-                    }
-                };
+// Label of the window showing a PTY
+fn pty_window(pty_id: &str) -> Option<String> {
+    let store = store::get(pty_id)?;
+    store.get(pty_id).map(|pty| pty.window.clone())
+}
 
-                // Check if the process has exited
-                match pty.child.try_wait() {
-                    Ok(Some(status)) => {
-                        // Process has exited
-                        println!("PTY process exited with status: {:?}", status);
-
-                        // Send exit event with status, but only if not already sent
-                        if !pty.exit_event_sent.load(Ordering::SeqCst) {
-                            if pty
-                                .exit_event_sent
-                                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-                                .is_ok()
-                            {
-                                println!("Sending exit event from exit watcher via channel");
-                                if let Err(e) = output_channel_exit.send(PtyOutputEvent::Exit {
-                                    status: format!("{:?}", status),
-                                }) {
-                                    eprintln!("Failed to send PTY exit event via channel: {}", e);
-                                }
-                            }
-                        }
+// Write raw bytes to a PTY. The store lock is released first, a program not
+// reading its input can block the write for as long as it likes.
+pub fn write_to_pty(pty_id: &str, data: &[u8]) -> CommandResult<()> {
+    let (writer, bytes_written) = {
+        let store = store::get(pty_id).ok_or_else(|| TermillionError::pty_not_found(pty_id))?;
+        let pty = store.get(pty_id).unwrap();
+        (pty.writer.clone(), pty.metrics.bytes_written.clone())
+    };
 
-                        // Clean up immediately after detecting exit
-                        drop(store); // Release the lock before cleaning up
-
-                        // Try to remove the PTY from the store
-                        if let Some(mut pty) = store::remove(&pty_id_exit_clone) {
-                            println!("Cleaning up PTY resources after exit");
-                            // We don't need to kill the child as it's already exited
-                            // Just clean up the reader thread
-                            if let Some(_thread) = pty.reader_thread.take() {
-                                // We can't really join here as it might be blocked on read
-                                // Just let it drop and clean up naturally
-                            }
-                        }
+    let write_error = |e| TermillionError::io("Failed to write to PTY", e).with_context(pty_id);
+    let mut writer = writer.lock().unwrap();
+    writer.write_all(data).map_err(write_error)?;
+    writer.flush().map_err(write_error)?;
+    drop(writer);
 
-                        break; // Exit the loop
-                    }
-                    Ok(None) => {
-                        // Process is still running
-                        drop(store); // Release the lock before sleeping
-                        thread::sleep(Duration::from_millis(500));
-                    }
-                    Err(e) => {
-                        // Error checking process status
-                        eprintln!("Error checking PTY child status: {}", e);
-                        drop(store); // Release the lock before sleeping
-                        thread::sleep(Duration::from_millis(500));
-                    }
-                }
+    // Update metrics
+    bytes_written.fetch_add(data.len() as u64, Ordering::Relaxed);
+    Ok(())
+}
+
+// Render a tab title from the template, or keep the shell's without one
+fn render_title(app: &AppHandle, pty_id: &str, template: Option<&str>, title: &str) -> String {
+    let Some(template) = template else {
+        return title.to_string();
+    };
+    // Processes are looked up after the store lock is released
+    let (leader, shell_pid, shell, profile, window) = {
+        let Some(store) = store::get(pty_id) else {
+            return title.to_string();
+        };
+        let pty = store.get(pty_id).unwrap();
+        #[cfg(unix)]
+        let leader = pty.master.process_group_leader();
+        #[cfg(not(unix))]
+        let leader = None::<i32>;
+        (
+            leader,
+            pty.child.process_id(),
+            pty.shell.clone(),
+            pty.profile.clone(),
+            pty.window.clone(),
+        )
+    };
+    let vars = TitleVars {
+        index: session::tab_index(app, &window, pty_id),
+        title: Some(title.to_string()).filter(|t| !t.is_empty()),
+        command: leader.and_then(|pid| utils::process_name(pid as u32)),
+        cwd: shell_pid.and_then(utils::process_cwd),
+        shell,
+        profile,
+    };
+    title::render(template, &vars, utils::get_home_dir().ok().as_deref())
+}
+
+// Get the shell's current directory, falling back to the one it started in
+pub fn pty_cwd(pty_id: &str) -> Option<String> {
+    let (pid, cwd) = {
+        let store = store::get(pty_id)?;
+        let pty = store.get(pty_id)?;
+        (pty.child.process_id(), pty.cwd.clone())
+    };
+    pid.and_then(utils::process_cwd).or(Some(cwd))
+}
+
+/// Color and icon of a PTY's tab
+pub fn pty_decoration(pty_id: &str) -> Option<PtyDecoration> {
+    let store = store::get(pty_id)?;
+    store.get(pty_id).map(|pty| pty.decoration.clone())
+}
+
+/// ID and window label of the running PTY started for a task
+pub fn task_pty(task: &str) -> Option<(String, String)> {
+    store::find_task(task)
+}
+
+/// Program a PTY runs, usually its shell
+pub fn pty_shell(pty_id: &str) -> Option<String> {
+    let store = store::get(pty_id)?;
+    store.get(pty_id).map(|pty| pty.shell.clone())
+}
+
+// Directory the scrollback of PTYs is spilled to, one directory per PTY
+fn spill_root(app: &AppHandle) -> Option<std::path::PathBuf> {
+    app.path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| dir.join("scrollback"))
+}
+
+fn open_spill(app: &AppHandle, pty_id: &str, quota_mb: u64) -> Option<Spill> {
+    let dir = spill_root(app)?.join(pty_id);
+    Spill::new(dir, quota_mb * 1024 * 1024)
+        .map_err(|e| error!("Failed to create scrollback spill directory: {}", e))
+        .ok()
+}
+
+/// Remove scrollback spilled by PTYs of an earlier run that didn't exit
+/// cleanly, done at startup
+pub fn remove_stale_spills(app: &AppHandle) {
+    let Some(root) = spill_root(app).filter(|root| root.exists()) else {
+        return;
+    };
+    if let Err(e) = std::fs::remove_dir_all(&root) {
+        error!("Failed to remove old spilled scrollback: {}", e);
+    }
+}
+
+/// Output of a PTY kept in the backend, as it was sent to the frontend,
+/// including what was spilled to disk
+pub fn pty_scrollback(pty_id: &str, max_bytes: u64) -> Option<Vec<u8>> {
+    let scrollback = {
+        let store = store::get(pty_id)?;
+        store.get(pty_id)?.scrollback.clone()
+    };
+    let read = {
+        let scrollback = scrollback.lock().unwrap();
+        scrollback.read_range(scrollback.end().saturating_sub(max_bytes), None)
+    };
+    Some(read.read().1)
+}
+
+/// IDs of all running PTYs
+pub fn active_pty_ids() -> Vec<String> {
+    store::get_all_ids()
+}
+
+/// Traffic counters of a PTY since it started
+#[derive(Debug, Clone, Serialize)]
+pub struct PtyCounters {
+    pub pty_id: String,
+    pub window: String,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub messages_sent: u64,
+    /// Output dropped because the window didn't keep up
+    pub dropped_bytes: u64,
+    pub uptime_ms: u64,
+}
+
+/// Color and icon a PTY's tab is shown with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PtyDecoration {
+    /// `#rrggbb`, `rgb()` or `hsl()`
+    pub color: Option<String>,
+    /// Emoji or image path
+    pub icon: Option<String>,
+}
+
+/// What the frontend needs to rebuild a PTY's tab, e.g. after a reload
+#[derive(Debug, Clone, Serialize)]
+pub struct PtyInfo {
+    pub id: String,
+    /// Last title the shell set
+    pub title: Option<String>,
+    /// The shell's current directory, or the one it started in
+    pub cwd: String,
+    pub profile: Option<String>,
+    /// Color and icon of its tab
+    pub decoration: PtyDecoration,
+    pub shell: String,
+    pub pid: Option<u32>,
+    pub alive: bool,
+    /// Unix time in milliseconds
+    pub created_at: u64,
+    /// Label of the window showing the PTY
+    pub window: String,
+}
+
+/// Size of a PTY in cells and pixels, pixel sizes are 0 when the frontend
+/// didn't report them
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PtyDimensions {
+    pub rows: u16,
+    pub cols: u16,
+    pub pixel_width: u16,
+    pub pixel_height: u16,
+    pub cell_width: f64,
+    pub cell_height: f64,
+}
+
+impl From<PtySize> for PtyDimensions {
+    fn from(size: PtySize) -> Self {
+        let cell = |pixels: u16, cells: u16| {
+            if cells == 0 {
+                0.0
             } else {
-                // PTY not found, exit the loop
-                println!("PTY not found in store, exit watcher ending");
-                break;
+                pixels as f64 / cells as f64
             }
-        }
-    });
-
-    // Update the PTY instance with the exit watcher thread
-    if let Some(mut store) = store::get_mut(&pty_id) {
-        if let Some(pty) = store.get_mut(&pty_id) {
-            pty.exit_watcher = Some(exit_watcher);
+        };
+        Self {
+            rows: size.rows,
+            cols: size.cols,
+            pixel_width: size.pixel_width,
+            pixel_height: size.pixel_height,
+            cell_width: cell(size.pixel_width, size.cols),
+            cell_height: cell(size.pixel_height, size.rows),
         }
     }
+}
 
-    Ok(pty_id)
+/// PTYs out of step with their shell or their window
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PtyAudit {
+    /// Running shells whose window or page is gone, e.g. after a reload
+    /// left their channel behind
+    pub orphans: Vec<String>,
+    /// Exited shells whose PTY is still stored
+    pub zombies: Vec<String>,
+    /// Running shells detached on purpose, never cleaned up
+    pub detached: Vec<String>,
+    /// Exited shells held on purpose for their output, never cleaned up
+    pub held: Vec<String>,
+    /// Whether orphans and zombies were destroyed
+    pub cleaned_up: bool,
 }
 
-// Write data to a PTY
-#[tauri::command]
-pub async fn write_pty(pty_id: String, data: String) -> Result<(), String> {
-    if let Some(mut store) = store::get_mut(&pty_id) {
-        let pty = store.get_mut(&pty_id).unwrap();
+/// Whether a PTY's shell is still running
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PtyState {
+    Running,
+    Exited,
+}
 
-        // Use the stored writer instead of taking it each time
-        if let Some(writer) = &mut pty.writer {
-            writer
-                .write_all(data.as_bytes())
-                .map_err(|e| e.to_string())?;
-            writer.flush().map_err(|e| e.to_string())?;
+/// Metrics, title and state of a PTY at one moment
+#[derive(Debug, Clone, Serialize)]
+pub struct PtySnapshot {
+    pub window: String,
+    pub shell: String,
+    /// Last title the shell set
+    pub title: Option<String>,
+    pub state: PtyState,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub messages_sent: u64,
+    pub dropped_bytes: u64,
+    pub uptime_ms: u64,
+}
 
-            // Update metrics
-            pty.metrics
-                .bytes_written
-                .fetch_add(data.len() as u64, Ordering::Relaxed);
+/// Counters of every running PTY, read under one lock
+pub fn pty_counters() -> Vec<PtyCounters> {
+    store::all_counters()
+}
 
-            Ok(())
-        } else {
-            // If the writer is not available, try to take it again
-            let mut writer = pty.master.take_writer().map_err(|e| e.to_string())?;
-            writer
-                .write_all(data.as_bytes())
-                .map_err(|e| e.to_string())?;
-            writer.flush().map_err(|e| e.to_string())?;
-
-            // Update metrics
-            pty.metrics
-                .bytes_written
-                .fetch_add(data.len() as u64, Ordering::Relaxed);
-
-            // Store the writer for future use
-            pty.writer = Some(writer);
-            Ok(())
+/// Write input to a PTY. In a tab with synchronized panes it goes to the
+/// other panes as well, except those reading a password.
+pub fn write_input(pty_id: &str, data: &[u8]) -> CommandResult<()> {
+    write_to_pty(pty_id, data)?;
+    let synced = pty_tab(pty_id).is_some_and(|tab| pane_sync::is_synced(&tab));
+    if !synced {
+        return Ok(());
+    }
+    for sibling in store::tab_siblings(pty_id) {
+        if pty_reading_password(&sibling) == Some(true) {
+            continue;
+        }
+        if let Err(e) = write_to_pty(&sibling, data) {
+            debug!(pty = %sibling, "Failed to send synchronized input: {}", e);
         }
-    } else {
-        Err(format!("PTY with ID {} not found", pty_id))
     }
+    Ok(())
 }
 
-// Resize a PTY
+// Write what is typed into a terminal to its PTY
 #[tauri::command]
-pub async fn resize_pty(pty_id: String, rows: u16, cols: u16) -> Result<(), String> {
-    if let Some(store) = store::get(&pty_id) {
-        let pty = store.get(&pty_id).unwrap();
+pub async fn write_pty(pty_id: String, data: String) -> CommandResult<()> {
+    write_input(&pty_id, data.as_bytes())
+}
+
+// Resize a PTY. The pixel size of the text area lets programs drawing
+// images (sixel, kitty) scale them to the cells.
+#[tauri::command]
+pub async fn resize_pty(
+    pty_id: String,
+    rows: u16,
+    cols: u16,
+    pixel_width: Option<u16>,
+    pixel_height: Option<u16>,
+) -> CommandResult<()> {
+    if let Some(mut store) = store::get_mut(&pty_id) {
+        let pty = store.get_mut(&pty_id).unwrap();
 
         let size = PtySize {
             rows,
             cols,
-            pixel_width: 0,
-            pixel_height: 0,
+            pixel_width: pixel_width.unwrap_or(0),
+            pixel_height: pixel_height.unwrap_or(0),
         };
 
-        pty.master.resize(size).map_err(|e| e.to_string())?;
+        pty.master
+            .resize(size)
+            .map_err(|e| TermillionError::pty("Failed to resize PTY", e).with_context(&pty_id))?;
+        pty.size = size;
 
         Ok(())
     } else {
-        Err(format!("PTY with ID {} not found", pty_id))
+        Err(TermillionError::pty_not_found(&pty_id))
+    }
+}
+
+// Check every stored PTY against its shell and its window. With `cleanup`
+// the orphans and zombies found are destroyed.
+#[tauri::command]
+pub async fn audit_ptys(app: AppHandle, cleanup: bool) -> CommandResult<PtyAudit> {
+    let mut audit = PtyAudit::default();
+    for pty_id in store::get_all_ids() {
+        let Some(mut store) = store::get_mut(&pty_id) else {
+            continue;
+        };
+        let pty = store.get_mut(&pty_id).unwrap();
+        let exited = match pty.child.try_wait() {
+            Ok(status) => status.is_some(),
+            Err(e) => {
+                error!(pty = %pty_id, "Error checking PTY child status: {}", e);
+                false
+            }
+        };
+        if pty.held {
+            audit.held.push(pty_id);
+        } else if exited {
+            audit.zombies.push(pty_id);
+        } else if pty.window == DETACHED_WINDOW {
+            audit.detached.push(pty_id);
+        } else if !pty.output.is_attached() || app.get_webview_window(&pty.window).is_none() {
+            audit.orphans.push(pty_id);
+        }
+    }
+
+    if cleanup {
+        for pty_id in audit.orphans.iter().chain(&audit.zombies) {
+            destroy(pty_id);
+        }
+        audit.cleaned_up = true;
+    }
+    if !audit.orphans.is_empty() || !audit.zombies.is_empty() {
+        info!(
+            orphans = audit.orphans.len(),
+            zombies = audit.zombies.len(),
+            cleanup,
+            "PTY audit found stale PTYs"
+        );
     }
+    Ok(audit)
+}
+
+// Get the size a PTY was last given, with the size of its cells
+#[tauri::command]
+pub async fn get_pty_size(pty_id: String) -> CommandResult<PtyDimensions> {
+    let store = store::get(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    Ok(store.get(&pty_id).unwrap().size.into())
+}
+
+// Get the commands run in a PTY, found through the shell integration marks.
+// Shells without them have none.
+#[tauri::command]
+pub async fn get_command_blocks(pty_id: String) -> CommandResult<Vec<CommandBlock>> {
+    let store = store::get(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let blocks = store.get(&pty_id).unwrap().blocks.clone();
+    drop(store);
+    let list = blocks.lock().unwrap().list();
+    Ok(list)
+}
+
+/// Output of one command, as it was sent to the frontend
+#[derive(Debug, Serialize)]
+pub struct CommandOutput {
+    pub block: CommandBlock,
+    pub output: String,
+    /// The start of the output was already dropped from the scrollback
+    pub truncated: bool,
+}
+
+/// Part of a PTY's output, offsets as in command blocks
+#[derive(Debug, Serialize)]
+pub struct ScrollbackRange {
+    pub start: u64,
+    pub end: u64,
+    pub data: String,
+}
+
+// Get the output kept for a PTY between two stream offsets, from memory or
+// the spill files. Without offsets all of it, which can be large.
+#[tauri::command]
+pub async fn get_scrollback(
+    pty_id: String,
+    start: Option<u64>,
+    end: Option<u64>,
+) -> CommandResult<ScrollbackRange> {
+    let scrollback = {
+        let store = store::get(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+        store.get(&pty_id).unwrap().scrollback.clone()
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let read = scrollback
+            .lock()
+            .unwrap()
+            .read_range(start.unwrap_or(0), end);
+        let (start, data) = read.read();
+        ScrollbackRange {
+            start,
+            end: start + data.len() as u64,
+            data: String::from_utf8_lossy(&data).into_owned(),
+        }
+    })
+    .await
+    .map_err(|e| {
+        TermillionError::new(
+            ErrorKind::Internal,
+            format!("Failed to read scrollback: {}", e),
+        )
+    })
+}
+
+// Get what a command printed, for copying the output of the last command.
+// Commands still running return their output so far.
+#[tauri::command]
+pub async fn get_command_output(pty_id: String, block_id: u64) -> CommandResult<CommandOutput> {
+    let store = store::get(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let pty = store.get(&pty_id).unwrap();
+    let (blocks, scrollback) = (pty.blocks.clone(), pty.scrollback.clone());
+    drop(store);
+
+    let block = blocks.lock().unwrap().get(block_id).ok_or_else(|| {
+        TermillionError::new(
+            ErrorKind::NotFound,
+            format!("Command block {} not found", block_id),
+        )
+        .with_context(&pty_id)
+    })?;
+    let Some(output_start) = block.output_start else {
+        return Err(TermillionError::new(
+            ErrorKind::InvalidInput,
+            format!("Command block {} hasn't run a command", block_id),
+        )
+        .with_context(&pty_id));
+    };
+    let read = scrollback
+        .lock()
+        .unwrap()
+        .read_range(output_start, block.end);
+    let (start, output) = read.read();
+    let truncated = output_start < start;
+    Ok(CommandOutput {
+        block,
+        output: String::from_utf8_lossy(&output).into_owned(),
+        truncated,
+    })
 }
 
 // Destroy a PTY
 #[tauri::command]
-pub async fn destroy_pty(pty_id: String) -> Result<(), String> {
-    if let Some(mut pty) = store::remove(&pty_id) {
+pub async fn destroy_pty(pty_id: String) -> CommandResult<()> {
+    destroy(&pty_id);
+    Ok(())
+}
+
+// Destroy every PTY shown in a window, used when the window closes
+pub fn destroy_window_ptys(label: &str) {
+    for pty_id in store::get_window_ids(label) {
+        destroy(&pty_id);
+    }
+}
+
+/// Window label of PTYs whose window closed while they kept running
+pub const DETACHED_WINDOW: &str = "";
+
+// Keep the PTYs shown in a closed window running without a window. Their
+// output only goes to the scrollback until a window takes them over with
+// `transfer_pty`.
+pub fn detach_window_ptys(label: &str) {
+    let mut tabs = Vec::new();
+    for pty_id in store::get_window_ids(label) {
+        let Some(mut store) = store::get_mut(&pty_id) else {
+            continue;
+        };
+        let pty = store.get_mut(&pty_id).unwrap();
+        pty.window = DETACHED_WINDOW.to_string();
+        pty.output.replace(Channel::new(|_| Ok(())));
+        tabs.push(pty.tab.take());
+        info!(pty = %pty_id, "Detached PTY from closed window {}", label);
+    }
+    for tab in tabs {
+        forget_tab(tab.as_deref());
+    }
+}
+
+// A tab no PTY is a pane of anymore starts out unsynchronized when a tab
+// with its ID is opened again. Call without holding the store lock.
+fn forget_tab(tab: Option<&str>) {
+    if let Some(tab) = tab.filter(|tab| !store::has_tab(tab)) {
+        pane_sync::set(tab, false);
+    }
+}
+
+// The page of a window is loading again, e.g. after a reload. Its PTYs keep
+// running, but their channels lead nowhere until a new page attaches them,
+// and the pastes it was streaming won't be ended.
+pub fn window_reloaded(label: &str) {
+    for pty_id in store::get_window_ids(label) {
+        if let Some(store) = store::get(&pty_id) {
+            store.get(&pty_id).unwrap().output.detach();
+        }
+        paste::drop_streams(&pty_id);
+    }
+}
+
+// Kill a PTY's shell and release its resources
+#[instrument(name = "pty", skip_all, fields(id = %pty_id))]
+fn destroy(pty_id: &str) {
+    if let Some(mut pty) = store::remove(pty_id) {
+        child_exit::unwatch(pty_id);
+
         // Mark as exited to prevent further exit events
         pty.exit_event_sent.store(true, Ordering::SeqCst);
 
-        // First try to gracefully kill the child process
-        if let Err(e) = pty.child.kill() {
-            eprintln!("Failed to kill PTY child process: {}", e);
+        // First try to gracefully kill the child process, held ones are gone
+        if pty.held {
+            debug!("Destroying held PTY");
+        } else if let Err(e) = pty.child.kill() {
+            error!("Failed to kill PTY child process: {}", e);
             // Continue anyway, as the process might have already exited
         }
 
@@ -591,10 +2171,10 @@ pub async fn destroy_pty(pty_id: String) -> Result<(), String> {
         let wait_result = pty.child.wait();
         match wait_result {
             Ok(status) => {
-                println!("PTY child exited with status: {:?}", status);
+                info!("PTY child exited with status: {:?}", status);
             }
             Err(e) => {
-                eprintln!("Failed to wait for PTY child: {}", e);
+                error!("Failed to wait for PTY child: {}", e);
                 // Continue anyway, we're cleaning up
             }
         }
@@ -605,24 +2185,95 @@ pub async fn destroy_pty(pty_id: String) -> Result<(), String> {
             // Just let it drop and clean up naturally
         }
 
-        if let Some(_thread) = pty.exit_watcher.take() {
-            // Same for the exit watcher
-        }
+        // Streamed pastes stop waiting for more chunks
+        paste::drop_streams(pty_id);
 
-        // Drop the writer explicitly
-        drop(pty.writer.take());
+        forget_tab(pty.tab.as_deref());
+    }
+    // If the PTY is not found, it might have already been cleaned up
+}
 
-        Ok(())
-    } else {
-        // If the PTY is not found, it might have already been cleaned up
-        // Just return success
-        Ok(())
+// Move a PTY to another window. Both windows are told with a
+// `pty-transferred` event, the target attaches its own output channel and
+// the source drops its tab.
+#[tauri::command]
+pub async fn transfer_pty(
+    app: AppHandle,
+    pty_id: String,
+    window_label: String,
+) -> CommandResult<()> {
+    if app.get_webview_window(&window_label).is_none() {
+        return Err(TermillionError::new(
+            ErrorKind::NotFound,
+            format!("Window {} not found", window_label),
+        )
+        .with_context(&window_label));
+    }
+
+    // The PTY leaves its tab until the target window attaches it to one
+    let (transfer, tab) = {
+        let mut store =
+            store::get_mut(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+        let pty = store.get_mut(&pty_id).unwrap();
+        if pty.window == window_label {
+            return Ok(());
+        }
+        let title = pty.title.lock().unwrap().clone();
+        let transfer = PtyTransfer {
+            pty_id: pty_id.clone(),
+            from_window: std::mem::replace(&mut pty.window, window_label.clone()),
+            to_window: window_label,
+            profile: pty.profile.clone(),
+            title,
+        };
+        (transfer, pty.tab.take())
+    };
+    forget_tab(tab.as_deref());
+
+    // Detached PTYs have no window left to tell
+    let windows = [&transfer.to_window, &transfer.from_window];
+    for label in windows
+        .into_iter()
+        .filter(|l| l.as_str() != DETACHED_WINDOW)
+    {
+        app.emit_to(label.as_str(), "pty-transferred", transfer.clone())
+            .map_err(|e| {
+                TermillionError::new(
+                    ErrorKind::Internal,
+                    format!("Failed to notify window: {}", e),
+                )
+            })?;
     }
+    Ok(())
+}
+
+// Send a PTY's output to a new channel, after it was moved to this window
+// and into one of its tabs
+#[tauri::command]
+pub async fn attach_pty(
+    window: Window,
+    pty_id: String,
+    output_channel: Channel,
+    tab_id: Option<String>,
+) -> CommandResult<()> {
+    let mut store =
+        store::get_mut(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let pty = store.get_mut(&pty_id).unwrap();
+    if pty.window != window.label() {
+        return Err(TermillionError::new(
+            ErrorKind::InvalidInput,
+            format!("PTY with ID {} belongs to window {}", pty_id, pty.window),
+        )
+        .with_context(&pty_id));
+    }
+    pty.output.replace(output_channel);
+    pty.tab = tab_id;
+    Ok(())
 }
 
 // Check if a PTY is alive
 #[tauri::command]
-pub async fn is_pty_alive(pty_id: String) -> Result<bool, String> {
+pub async fn is_pty_alive(pty_id: String) -> CommandResult<bool> {
     if let Some(mut store) = store::get_mut(&pty_id) {
         let pty = store.get_mut(&pty_id).unwrap();
 
@@ -639,7 +2290,9 @@ pub async fn is_pty_alive(pty_id: String) -> Result<bool, String> {
                 Ok(false) // Process has exited
             }
             Ok(None) => Ok(true), // Process is still running
-            Err(e) => Err(e.to_string()),
+            Err(e) => {
+                Err(TermillionError::io("Failed to check PTY process", e).with_context(&pty_id))
+            }
         }
     } else {
         // If the PTY is not found, it's not alive
@@ -647,15 +2300,158 @@ pub async fn is_pty_alive(pty_id: String) -> Result<bool, String> {
     }
 }
 
-// Get all active PTY IDs
+// Programs from `window.confirm_close_processes` running in a PTY, so the
+// tab only asks before closing when something would be lost
+#[tauri::command]
+pub async fn get_blocking_processes(app: AppHandle, pty_id: String) -> CommandResult<Vec<String>> {
+    let pid = {
+        let store = store::get(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+        let pty = store.get(&pty_id).unwrap();
+        if pty.held {
+            return Ok(Vec::new());
+        }
+        pty.child.process_id()
+    };
+    let Some(pid) = pid else {
+        return Ok(Vec::new());
+    };
+    let listed = Config::load(&app)?
+        .window
+        .confirm_close_processes
+        .unwrap_or_else(|| processes::DEFAULT_CONFIRM_CLOSE.map(String::from).to_vec());
+    if listed.is_empty() {
+        return Ok(Vec::new());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        processes::ProcessTree::scan().running_below(pid, &listed)
+    })
+    .await
+    .map_err(|e| {
+        TermillionError::new(
+            ErrorKind::Internal,
+            format!("Failed to list processes: {}", e),
+        )
+        .with_context(&pty_id)
+    })
+}
+
+// Get every PTY with what its tab shows. Directories are looked up after the
+// store lock is released.
+#[tauri::command]
+pub async fn get_active_ptys() -> CommandResult<Vec<PtyInfo>> {
+    let mut ptys: Vec<PtyInfo> = store::all_handles()
+        .into_iter()
+        .map(|pty| PtyInfo {
+            title: pty.title.lock().unwrap().clone(),
+            cwd: pty.pid.and_then(utils::process_cwd).unwrap_or(pty.cwd),
+            alive: !pty.exited.load(Ordering::SeqCst),
+            id: pty.id,
+            profile: pty.profile,
+            decoration: pty.decoration,
+            shell: pty.shell,
+            pid: pty.pid,
+            created_at: pty.created_at,
+            window: pty.window,
+        })
+        .collect();
+    ptys.sort_by_key(|pty| pty.created_at);
+    Ok(ptys)
+}
+
+// Get the PTYs whose window closed while they kept running, for a window to
+// take over with `transfer_pty`
+#[tauri::command]
+pub async fn list_detached_ptys() -> CommandResult<Vec<PtyInfo>> {
+    let mut ptys = get_active_ptys().await?;
+    ptys.retain(|pty| pty.window == DETACHED_WINDOW);
+    Ok(ptys)
+}
+
+// Open the output log of a new PTY, a log that can't be opened is left out
+fn open_log(app: &AppHandle, pty_id: &str, mode: PtyLogMode) -> Option<PtyLog> {
+    let path = output_log::log_path(app, pty_id)?;
+    PtyLog::open(&path, mode)
+        .map_err(|e| error!("Failed to open output log {}: {}", path.display(), e))
+        .ok()
+        .flatten()
+}
+
+// Start or stop logging a PTY's output to a file of its own, in the mode of
+// `logging.pty_output`. Returns the file's path while logging.
+#[tauri::command]
+pub async fn set_pty_logging(
+    app: AppHandle,
+    pty_id: String,
+    mode: PtyLogMode,
+) -> CommandResult<Option<String>> {
+    let path = output_log::log_path(&app, &pty_id)
+        .ok_or_else(|| TermillionError::new(ErrorKind::Io, "No log directory"))?;
+    let log = PtyLog::open(&path, mode)
+        .map_err(|e| TermillionError::io("Failed to open output log", e).with_context(&pty_id))?;
+    let logging = log.is_some();
+
+    let mut store =
+        store::get_mut(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let pty = store.get_mut(&pty_id).unwrap();
+    *pty.output_log.lock().unwrap() = log;
+    Ok(logging.then(|| path.to_string_lossy().into_owned()))
+}
+
+// Set the color and icon of a PTY's tab. They are saved with the session, so
+// restored tabs look the same. Unset fields remove the color or icon.
+#[tauri::command]
+pub async fn set_pty_decoration(
+    pty_id: String,
+    color: Option<String>,
+    icon: Option<String>,
+) -> CommandResult<()> {
+    if let Some(value) = color.as_deref().filter(|c| color::parse(c).is_none()) {
+        return Err(TermillionError::new(
+            ErrorKind::InvalidInput,
+            format!("'{}' is not a valid color", value),
+        )
+        .with_context(&pty_id));
+    }
+    let mut store =
+        store::get_mut(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let pty = store.get_mut(&pty_id).unwrap();
+    pty.decoration = PtyDecoration { color, icon };
+    pty.output
+        .send(PtyOutputEvent::Decoration(pty.decoration.clone()));
+    Ok(())
+}
+
+// Get the metrics, titles and states of every PTY in one call. The store is
+// only locked to clone the PTYs' shared counters, which are read after.
 #[tauri::command]
-pub async fn get_active_ptys() -> Result<Vec<String>, String> {
-    Ok(store::get_all_ids())
+pub async fn get_all_pty_metrics() -> CommandResult<HashMap<String, PtySnapshot>> {
+    Ok(store::all_handles()
+        .into_iter()
+        .map(|pty| {
+            let state = if pty.exited.load(Ordering::SeqCst) {
+                PtyState::Exited
+            } else {
+                PtyState::Running
+            };
+            let snapshot = PtySnapshot {
+                window: pty.window,
+                shell: pty.shell,
+                title: pty.title.lock().unwrap().clone(),
+                state,
+                bytes_read: pty.metrics.bytes_read.load(Ordering::Relaxed),
+                bytes_written: pty.metrics.bytes_written.load(Ordering::Relaxed),
+                messages_sent: pty.metrics.messages_sent.load(Ordering::Relaxed),
+                dropped_bytes: pty.metrics.dropped_bytes.load(Ordering::Relaxed),
+                uptime_ms: pty.metrics.created_at.elapsed().as_millis() as u64,
+            };
+            (pty.id, snapshot)
+        })
+        .collect())
 }
 
 // Add a new command to get metrics
 #[tauri::command]
-pub async fn get_pty_metrics(pty_id: String) -> Result<serde_json::Value, String> {
+pub async fn get_pty_metrics(pty_id: String) -> CommandResult<serde_json::Value> {
     if let Some(store) = store::get(&pty_id) {
         let pty = store.get(&pty_id).unwrap();
 
@@ -663,11 +2459,60 @@ pub async fn get_pty_metrics(pty_id: String) -> Result<serde_json::Value, String
             "bytes_read": pty.metrics.bytes_read.load(Ordering::Relaxed),
             "bytes_written": pty.metrics.bytes_written.load(Ordering::Relaxed),
             "messages_sent": pty.metrics.messages_sent.load(Ordering::Relaxed),
+            "dropped_bytes": pty.metrics.dropped_bytes.load(Ordering::Relaxed),
             "uptime_ms": pty.metrics.created_at.elapsed().as_millis(),
         });
 
         Ok(metrics)
     } else {
-        Err(format!("PTY with ID {} not found", pty_id))
+        Err(TermillionError::pty_not_found(&pty_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::types::*;
+
+    #[test]
+    fn test_lz4_output_frame() {
+        let data = b"\x1b[32mok\x1b[0m line\r\n".repeat(8);
+        // The same frame is decoded in srcjs/terminal/lz4.test.ts
+        let expected: &[u8] = &[
+            2, 144, 0, 0, 0, 255, 3, 27, 91, 51, 50, 109, 111, 107, 27, 91, 48, 109, 32, 108, 105,
+            110, 101, 13, 10, 18, 0, 101, 96, 108, 105, 110, 101, 13, 10,
+        ];
+        assert_eq!(output_frame(data.clone(), Some(64)).unwrap(), expected);
+        // Smaller output is left as it is
+        assert_eq!(output_frame(data, Some(1024)).unwrap()[0], FRAME_OUTPUT);
+    }
+
+    #[test]
+    fn test_output_frame_layout() {
+        // Output is the tag followed by the bytes as they were read
+        let frame = PtyOutputEvent::Output(b"ls\r\n".to_vec()).encode().unwrap();
+        assert_eq!(frame, [FRAME_OUTPUT, b'l', b's', b'\r', b'\n']);
+        assert_eq!(output_frame(b"ls\r\n".to_vec(), None).unwrap(), frame);
+
+        // Other events are the tag followed by the event as JSON
+        let frame = PtyOutputEvent::Exit { status: "0".into() }
+            .encode()
+            .unwrap();
+        assert_eq!(frame[0], FRAME_EVENT);
+        assert_eq!(&frame[1..], br#"{"event":"exit","data":{"status":"0"}}"#);
+        let frame = PtyOutputEvent::Bell.encode().unwrap();
+        assert_eq!(frame, [&[FRAME_EVENT][..], br#"{"event":"bell"}"#].concat());
+
+        // Compressed output is the tag, its size as little endian u32 and an
+        // LZ4 block
+        let data = b"0123456789".repeat(20);
+        let frame = output_frame(data.clone(), Some(100)).unwrap();
+        assert_eq!(frame[0], FRAME_OUTPUT_LZ4);
+        assert_eq!(frame[1..5], 200u32.to_le_bytes());
+        assert_eq!(lz4_flex::decompress(&frame[5..], 200).unwrap(), data);
+
+        // Output that doesn't get smaller is sent as it is
+        let data: Vec<u8> = (0..=255).collect();
+        let frame = output_frame(data.clone(), Some(100)).unwrap();
+        assert_eq!(frame, [&[FRAME_OUTPUT][..], &data].concat());
     }
 }
@@ -0,0 +1,90 @@
+use portable_pty::MasterPty;
+
+/// Reads the terminal modes of a PTY without its master, so the reader
+/// thread can check them without the store lock. Holds its own duplicate
+/// of the master's descriptor.
+pub struct EchoProbe {
+    #[cfg(unix)]
+    fd: Option<std::os::fd::OwnedFd>,
+}
+
+impl EchoProbe {
+    #[cfg(unix)]
+    pub fn new(master: &dyn MasterPty) -> Self {
+        use std::os::fd::FromRawFd;
+        let fd = master
+            .as_raw_fd()
+            .map(|fd| unsafe { libc::dup(fd) })
+            .filter(|&fd| fd >= 0)
+            // A descriptor from dup is ours to own and close
+            .map(|fd| unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) });
+        Self { fd }
+    }
+
+    #[cfg(not(unix))]
+    pub fn new(_master: &dyn MasterPty) -> Self {
+        Self {}
+    }
+
+    /// Whether the program in the PTY turned echo off to read a password.
+    /// Echo off in raw mode is an editor or pager drawing the screen, so
+    /// only line-buffered input counts. `None` where the terminal modes
+    /// can't be read, like ConPTY on Windows.
+    #[cfg(unix)]
+    pub fn reading_password(&self) -> Option<bool> {
+        use std::os::fd::AsRawFd;
+        let fd = self.fd.as_ref()?.as_raw_fd();
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // The master side reports the modes the program set on its terminal
+        if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let flags = unsafe { termios.assume_init() }.c_lflag;
+        Some(flags & libc::ECHO == 0 && flags & libc::ICANON != 0)
+    }
+
+    #[cfg(not(unix))]
+    pub fn reading_password(&self) -> Option<bool> {
+        None
+    }
+
+    /// Whether the PTY can be typed into, not while a password is read, like
+    /// sudo's prompt before an elevated shell starts
+    pub fn ready_for_input(&self) -> bool {
+        self.reading_password() != Some(true)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use portable_pty::{native_pty_system, PtySize};
+    use std::os::fd::RawFd;
+
+    // Set the local modes of the PTY's terminal, as a program in it would
+    fn set_modes(fd: RawFd, on: libc::tcflag_t, off: libc::tcflag_t) {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        unsafe {
+            assert_eq!(libc::tcgetattr(fd, termios.as_mut_ptr()), 0);
+            let mut termios = termios.assume_init();
+            termios.c_lflag = (termios.c_lflag | on) & !off;
+            assert_eq!(libc::tcsetattr(fd, libc::TCSANOW, &termios), 0);
+        }
+    }
+
+    #[test]
+    fn test_ready_for_input() {
+        let pair = native_pty_system().openpty(PtySize::default()).unwrap();
+        let fd = pair.master.as_raw_fd().unwrap();
+        let probe = EchoProbe::new(pair.master.as_ref());
+
+        // A password prompt turns echo off but still reads whole lines
+        set_modes(fd, libc::ICANON, libc::ECHO);
+        assert!(!probe.ready_for_input());
+        // A shell's line editor in raw mode, or a plain cooked terminal
+        set_modes(fd, 0, libc::ECHO | libc::ICANON);
+        assert!(probe.ready_for_input());
+        set_modes(fd, libc::ECHO | libc::ICANON, 0);
+        assert!(probe.ready_for_input());
+    }
+}
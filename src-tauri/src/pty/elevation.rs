@@ -0,0 +1,77 @@
+use super::utils;
+
+/// Exit code of the sudo wrapper when no password was given, pkexec exits
+/// with it too when its dialog is dismissed
+#[cfg(unix)]
+const DENIED: u32 = 126;
+/// Exit code of pkexec when the user isn't allowed to run the shell as root
+#[cfg(unix)]
+const NOT_AUTHORIZED: u32 = 127;
+/// `ERROR_CANCELLED`, returned by sudo when its UAC prompt is declined
+#[cfg(windows)]
+const DENIED: u32 = 1223;
+/// Shells exiting later than this got past the prompt, so their exit codes
+/// are their own even when they match the ones above
+const MAX_PROMPT_MS: u64 = 60_000;
+
+/// The command line that runs `argv` elevated. On Unix that is pkexec in a
+/// graphical session and sudo otherwise, on Windows the `sudo` built into
+/// Windows 11 or gsudo, which both show the UAC prompt.
+pub fn wrap(argv: Vec<String>) -> Result<Vec<String>, String> {
+    #[cfg(unix)]
+    {
+        let graphical = cfg!(target_os = "linux")
+            && (std::env::var_os("DISPLAY").is_some()
+                || std::env::var_os("WAYLAND_DISPLAY").is_some());
+        if graphical && utils::find_executable("pkexec").is_some() {
+            return Ok(["pkexec".to_string()].into_iter().chain(argv).collect());
+        }
+        if utils::find_executable("sudo").is_none() {
+            return Err("Neither pkexec nor sudo is installed".to_string());
+        }
+        // Ask for the password first, so giving up exits with a code of our
+        // own rather than whatever the shell would have returned
+        let script = format!("sudo -v || exit {}; exec sudo -- \"$0\" \"$@\"", DENIED);
+        Ok(["sh".to_string(), "-c".to_string(), script]
+            .into_iter()
+            .chain(argv)
+            .collect())
+    }
+
+    #[cfg(windows)]
+    {
+        let sudo = ["sudo.exe", "gsudo.exe"]
+            .into_iter()
+            .find(|program| utils::find_executable(program).is_some())
+            .ok_or("Running as administrator needs sudo (Windows 11) or gsudo")?;
+        Ok([sudo.to_string()].into_iter().chain(argv).collect())
+    }
+}
+
+/// Why elevation failed, for the exit code of an elevated shell and how long
+/// it ran. Only shells exiting while the prompt could still be up count.
+pub fn denied_reason(exit_code: u32, duration_ms: u64) -> Option<&'static str> {
+    if duration_ms > MAX_PROMPT_MS {
+        return None;
+    }
+    match exit_code {
+        DENIED => Some("Elevation was cancelled or the password was wrong"),
+        #[cfg(unix)]
+        NOT_AUTHORIZED => Some("Not authorized to run the shell as administrator"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_denied_reason() {
+        assert!(denied_reason(DENIED, 5_000).is_some());
+        assert_eq!(denied_reason(0, 5_000), None);
+        assert_eq!(denied_reason(1, 5_000), None);
+        // A shell that ran for a while exited with the code itself
+        assert_eq!(denied_reason(DENIED, MAX_PROMPT_MS + 1), None);
+    }
+}
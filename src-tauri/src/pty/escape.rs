@@ -0,0 +1,105 @@
+// Position inside an escape sequence
+#[derive(Clone, Copy, PartialEq, Default)]
+enum State {
+    #[default]
+    None,
+    Started,
+    Csi,
+    Osc,
+    OscEnd,
+    /// Character set selection, which takes one more byte
+    Charset,
+}
+
+/// What a scanned byte of output turned out to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scanned {
+    /// Text or a control character outside escape sequences
+    Text,
+    /// The escape character starting a sequence
+    EscapeStart,
+    /// Any other part of an escape sequence
+    Escape,
+}
+
+/// Follows the escape sequences in PTY output a byte at a time, so scanners
+/// can tell text from escapes and get the payloads of OSC sequences
+#[derive(Default)]
+pub struct EscapeScanner {
+    state: State,
+    osc: Vec<u8>,
+    /// Longest OSC payload kept, the rest is dropped
+    max_osc_length: usize,
+}
+
+impl EscapeScanner {
+    pub fn new(max_osc_length: usize) -> Self {
+        Self {
+            max_osc_length,
+            ..Self::default()
+        }
+    }
+
+    /// Scan the next byte, `on_osc` gets the payload of an OSC sequence it ends
+    pub fn advance(&mut self, byte: u8, on_osc: impl FnOnce(&[u8])) -> Scanned {
+        let (state, scanned) = match (self.state, byte) {
+            (State::Osc | State::OscEnd, 0x07) | (State::OscEnd, b'\\') => {
+                on_osc(&self.osc);
+                (State::None, Scanned::Escape)
+            }
+            (State::Osc | State::OscEnd, 0x1b) => (State::OscEnd, Scanned::Escape),
+            (State::Osc | State::OscEnd, _) => {
+                if self.osc.len() < self.max_osc_length {
+                    self.osc.push(byte);
+                }
+                (State::Osc, Scanned::Escape)
+            }
+            (State::Started, b']') => {
+                self.osc.clear();
+                (State::Osc, Scanned::Escape)
+            }
+            (State::Started, b'[') => (State::Csi, Scanned::Escape),
+            (State::Started, b'(' | b')' | b'*' | b'+') => (State::Charset, Scanned::Escape),
+            (State::Started, 0x1b) => (State::Started, Scanned::EscapeStart),
+            (State::Started | State::Charset, _) => (State::None, Scanned::Escape),
+            // CSI sequences end with a byte in @..~
+            (State::Csi, 0x40..=0x7e) => (State::None, Scanned::Escape),
+            (State::Csi, _) => (State::Csi, Scanned::Escape),
+            (State::None, 0x1b) => (State::Started, Scanned::EscapeStart),
+            (State::None, _) => (State::None, Scanned::Text),
+        };
+        self.state = state;
+        scanned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_scanner() {
+        let mut scanner = EscapeScanner::new(8);
+        let mut text = Vec::new();
+        let mut payloads = Vec::new();
+        let output = b"a\x1b[1;31mb\x1b(Bc\x1b]0;title\x07d\x1b]7;file:///tmp\x1b\\e";
+        // Fed in pieces, sequences go on in the next piece
+        for chunk in output.chunks(12) {
+            for &byte in chunk {
+                let scanned = scanner.advance(byte, |osc| {
+                    payloads.push(String::from_utf8_lossy(osc).into_owned())
+                });
+                if scanned == Scanned::Text {
+                    text.push(byte);
+                }
+            }
+        }
+        assert_eq!(text, b"abcde");
+        // Longer payloads are cut short
+        assert_eq!(payloads, ["0;title", "7;file:/"]);
+
+        assert_eq!(scanner.advance(0x1b, |_| {}), Scanned::EscapeStart);
+        assert_eq!(scanner.advance(0x1b, |_| {}), Scanned::EscapeStart);
+        assert_eq!(scanner.advance(b'[', |_| {}), Scanned::Escape);
+    }
+}
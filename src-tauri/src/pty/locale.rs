@@ -0,0 +1,116 @@
+/// Locale variables that pick the character set, `LC_ALL` winning over
+/// `LC_CTYPE` winning over `LANG`
+const CHARSET_VARS: [&str; 2] = ["LC_ALL", "LC_CTYPE"];
+
+/// Changes to the environment a shell inherits
+#[derive(Debug, Default, PartialEq)]
+pub struct LocaleEnv {
+    pub set: Vec<(&'static str, String)>,
+    pub remove: Vec<&'static str>,
+}
+
+/// Whether a locale like `en_US.UTF-8` uses UTF-8
+pub fn is_utf8(locale: &str) -> bool {
+    let locale = locale.to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+/// Locale for shells when neither the profile nor the app's environment
+/// has a UTF-8 one
+#[cfg(target_os = "macos")]
+fn default_locale() -> Option<String> {
+    static LOCALE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    let locale = LOCALE.get_or_init(|| {
+        // The system language, like `en_US` or `de_DE@rg=chzzzz`
+        std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleLocale"])
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|locale| {
+                locale
+                    .trim()
+                    .split('@')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .filter(|locale| !locale.is_empty())
+            .map(|locale| format!("{}.UTF-8", locale))
+            .unwrap_or_else(|| "en_US.UTF-8".to_string())
+    });
+    Some(locale.clone())
+}
+
+#[cfg(target_os = "linux")]
+fn default_locale() -> Option<String> {
+    Some("C.UTF-8".to_string())
+}
+
+// Windows programs don't read LANG, profiles that need it set it
+#[cfg(target_os = "windows")]
+fn default_locale() -> Option<String> {
+    None
+}
+
+/// How a shell's locale differs from the app's. Apps started from a desktop
+/// launcher often inherit `C`, which breaks Unicode in the shell, so a
+/// locale without UTF-8 is replaced by the profile's or the platform
+/// default.
+pub fn resolve(configured: Option<&str>, inherited: impl Fn(&str) -> Option<String>) -> LocaleEnv {
+    let mut env = LocaleEnv::default();
+    let utf8_lang = inherited("LANG").is_some_and(|lang| is_utf8(&lang));
+    match configured {
+        Some(locale) => env.set.push(("LANG", locale.to_string())),
+        None if utf8_lang => {}
+        None => {
+            if let Some(locale) = default_locale() {
+                env.set.push(("LANG", locale));
+            }
+        }
+    }
+    // These would win over LANG
+    if utf8_lang || !env.set.is_empty() {
+        env.remove.extend(
+            CHARSET_VARS
+                .into_iter()
+                .filter(|var| inherited(var).is_some_and(|value| !is_utf8(&value))),
+        );
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_locale() {
+        let inherited = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        let env = resolve(Some("de_DE.UTF-8"), inherited(&[("LC_ALL", "C")]));
+        assert_eq!(env.set, [("LANG", "de_DE.UTF-8".to_string())]);
+        assert_eq!(env.remove, ["LC_ALL"]);
+
+        // A UTF-8 locale from the app's environment is kept
+        let env = resolve(None, inherited(&[("LANG", "en_GB.UTF-8")]));
+        assert_eq!(env, LocaleEnv::default());
+        let env = resolve(None, inherited(&[("LANG", "en_GB.UTF-8"), ("LC_ALL", "C")]));
+        assert!(env.set.is_empty());
+        assert_eq!(env.remove, ["LC_ALL"]);
+
+        let env = resolve(None, inherited(&[("LANG", "C"), ("LC_CTYPE", "POSIX")]));
+        if cfg!(windows) {
+            assert_eq!(env, LocaleEnv::default());
+        } else {
+            assert!(env.set[0].1.ends_with(".UTF-8"));
+            assert_eq!(env.remove, ["LC_CTYPE"]);
+        }
+    }
+}
@@ -1,5 +1,28 @@
+mod banner;
+mod benchmark;
+mod blocks;
+mod child_exit;
 mod core;
+mod echo;
+mod elevation;
+mod escape;
+mod locale;
+mod msys;
+mod output_log;
+mod pane_sync;
+mod processes;
+mod queue;
+mod sandbox;
+mod scrollback;
+mod title;
 mod utils;
 
 // Re-export the public API
+pub use benchmark::*;
+pub use blocks::CommandBlock;
 pub use core::*;
+pub use escape::{EscapeScanner, Scanned};
+pub use locale::is_utf8 as is_utf8_locale;
+pub use pane_sync::*;
+pub use sandbox::sandbox_tool;
+pub use utils::{expand_home, find_executable, path_exists};
@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::utils;
+
+/// Kind of POSIX layer a Windows shell comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flavor {
+    Msys2,
+    GitBash,
+    Cygwin,
+}
+
+/// MSYS2, Git for Windows or Cygwin install a shell belongs to
+#[derive(Debug)]
+pub struct PosixLayer {
+    pub flavor: Flavor,
+    root: PathBuf,
+}
+
+impl PosixLayer {
+    /// Find the install the program is part of, by the runtime DLL next to
+    /// it. Git for Windows is MSYS2 with Git's own launchers at its root.
+    pub fn detect(program: &str) -> Option<Self> {
+        let program = utils::find_executable(program)?;
+        program.ancestors().skip(1).find_map(|root| {
+            let flavor = if root.join("usr/bin/msys-2.0.dll").exists() {
+                if root.join("git-bash.exe").exists() || root.join("cmd/git.exe").exists() {
+                    Flavor::GitBash
+                } else {
+                    Flavor::Msys2
+                }
+            } else if root.join("bin/cygwin1.dll").exists() {
+                Flavor::Cygwin
+            } else {
+                return None;
+            };
+            Some(Self {
+                flavor,
+                root: root.to_path_buf(),
+            })
+        })
+    }
+
+    /// Variables the shell needs to start like its own launcher starts it.
+    /// `CHERE_INVOKING` keeps login shells from changing to the home
+    /// directory. Profile variables are set later and win, e.g. to pick
+    /// another `MSYSTEM`.
+    pub fn env(&self) -> Vec<(&'static str, &'static str)> {
+        let mut env = vec![("CHERE_INVOKING", "1")];
+        match self.flavor {
+            Flavor::Msys2 => env.push(("MSYSTEM", "UCRT64")),
+            Flavor::GitBash => env.push(("MSYSTEM", "MINGW64")),
+            Flavor::Cygwin => {}
+        }
+        env
+    }
+
+    fn bin(&self, name: &str) -> Option<PathBuf> {
+        ["usr/bin", "bin"]
+            .iter()
+            .map(|dir| self.root.join(dir).join(name))
+            .find(|path| path.exists())
+    }
+
+    /// `winpty.exe` of the install, for runtimes too old to render under
+    /// ConPTY
+    pub fn winpty(&self) -> Option<String> {
+        self.bin("winpty.exe")
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    /// A directory as Windows sees it. Profiles of these shells tend to
+    /// use POSIX paths like `/c/src` or `/home/me`.
+    pub fn windows_path(&self, path: &str) -> String {
+        if !path.starts_with('/') {
+            return path.to_string();
+        }
+        if let Some(converted) = drive_path(self.flavor, path) {
+            return converted;
+        }
+        let converted = self.bin("cygpath.exe").and_then(|cygpath| {
+            let output = Command::new(cygpath).args(["-w", path]).output().ok()?;
+            let converted = String::from_utf8(output.stdout).ok()?;
+            Some(converted.trim().to_string()).filter(|path| !path.is_empty())
+        });
+        converted.unwrap_or_else(|| path.to_string())
+    }
+}
+
+// `/c/src` (MSYS2) or `/cygdrive/c/src` (Cygwin) as `C:\src`, without
+// asking cygpath
+fn drive_path(flavor: Flavor, path: &str) -> Option<String> {
+    let rest = match flavor {
+        Flavor::Cygwin => path.strip_prefix("/cygdrive/")?,
+        Flavor::Msys2 | Flavor::GitBash => path.strip_prefix('/')?,
+    };
+    let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    let mut chars = drive.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Some(format!(
+            "{}:\\{}",
+            letter.to_ascii_uppercase(),
+            rest.replace('/', "\\")
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drive_path() {
+        assert_eq!(
+            drive_path(Flavor::Msys2, "/c/src/app").as_deref(),
+            Some("C:\\src\\app")
+        );
+        assert_eq!(drive_path(Flavor::GitBash, "/d").as_deref(), Some("D:\\"));
+        assert_eq!(
+            drive_path(Flavor::Cygwin, "/cygdrive/e/data").as_deref(),
+            Some("E:\\data")
+        );
+        // Paths inside the install are left to cygpath
+        assert_eq!(drive_path(Flavor::Msys2, "/home/me"), None);
+        assert_eq!(drive_path(Flavor::Cygwin, "/c/src"), None);
+    }
+}
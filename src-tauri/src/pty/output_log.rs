@@ -0,0 +1,84 @@
+use chrono::SecondsFormat;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::config::PtyLogMode;
+
+/// Output of one PTY written to a file as it arrives
+pub struct PtyLog {
+    file: File,
+    timestamps: bool,
+    // Whether the next byte starts a line, so lines split across reads get
+    // a single timestamp
+    at_line_start: bool,
+    buffer: Vec<u8>,
+}
+
+/// Where the log file of a PTY goes, in the app's log directory
+pub fn log_path(app: &AppHandle, pty_id: &str) -> Option<PathBuf> {
+    let dir = app.path().app_log_dir().ok()?;
+    Some(dir.join("pty").join(format!("{}.log", pty_id)))
+}
+
+impl PtyLog {
+    /// Start logging to `path`, appending to an earlier log of the PTY.
+    /// Nothing is logged when the mode is off.
+    pub fn open(path: &Path, mode: PtyLogMode) -> io::Result<Option<Self>> {
+        if mode == PtyLogMode::Off {
+            return Ok(None);
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Some(Self {
+            file,
+            timestamps: mode == PtyLogMode::Timestamped,
+            at_line_start: true,
+            buffer: Vec::new(),
+        }))
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        if !self.timestamps {
+            return self.file.write_all(data);
+        }
+        let stamp = chrono::Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+        self.buffer.clear();
+        timestamp_lines(data, &stamp, &mut self.at_line_start, &mut self.buffer);
+        self.file.write_all(&self.buffer)
+    }
+}
+
+// Copy `data` to `out` with `stamp` and a space before each line. Lines end
+// at `\n`, a `\r` alone redraws the line and isn't a new one.
+fn timestamp_lines(data: &[u8], stamp: &str, at_line_start: &mut bool, out: &mut Vec<u8>) {
+    for line in data.split_inclusive(|&byte| byte == b'\n') {
+        if *at_line_start {
+            out.extend_from_slice(stamp.as_bytes());
+            out.push(b' ');
+        }
+        out.extend_from_slice(line);
+        *at_line_start = line.ends_with(b"\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_lines() {
+        let mut at_line_start = true;
+        let mut out = Vec::new();
+        timestamp_lines(b"$ make\r\nbuil", "T1", &mut at_line_start, &mut out);
+        timestamp_lines(b"ding\ndone\n", "T2", &mut at_line_start, &mut out);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "T1 $ make\r\nT1 building\nT2 done\n"
+        );
+        assert!(at_line_start);
+    }
+}
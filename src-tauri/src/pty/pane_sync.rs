@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::error::CommandResult;
+
+lazy_static::lazy_static! {
+    // Tabs whose panes all get the input typed into any of them
+    static ref SYNCED_TABS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+pub fn set(tab_id: &str, enabled: bool) {
+    let mut synced = SYNCED_TABS.lock().unwrap();
+    if enabled {
+        synced.insert(tab_id.to_string());
+    } else {
+        synced.remove(tab_id);
+    }
+}
+
+pub fn is_synced(tab_id: &str) -> bool {
+    SYNCED_TABS.lock().unwrap().contains(tab_id)
+}
+
+// Send input typed into one pane of a tab to its other panes too. Kept in
+// the backend so it survives the webview reloading.
+#[tauri::command]
+pub async fn set_pane_sync(tab_id: String, enabled: bool) -> CommandResult<()> {
+    set(&tab_id, enabled);
+    Ok(())
+}
+
+// Whether the panes of a tab get each other's input
+#[tauri::command]
+pub async fn get_pane_sync(tab_id: String) -> CommandResult<bool> {
+    Ok(is_synced(&tab_id))
+}
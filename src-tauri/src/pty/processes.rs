@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Programs that make closing their terminal ask first when the config
+/// doesn't list any
+pub const DEFAULT_CONFIRM_CLOSE: [&str; 10] = [
+    "vim", "nvim", "vi", "nano", "emacs", "ssh", "mosh", "tmux", "screen", "less",
+];
+
+#[derive(Debug)]
+struct Process {
+    pid: u32,
+    parent: Option<u32>,
+    name: String,
+    // Seconds since the epoch
+    #[cfg_attr(unix, allow(dead_code))]
+    started: u64,
+}
+
+/// The processes running at one moment, to look up many PTYs in one scan
+pub struct ProcessTree {
+    processes: Vec<Process>,
+}
+
+// A program name without its path, Windows extension or case, so `vim`
+// in the config matches `vim.exe`
+fn program_name(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let file = file.to_lowercase();
+    file.strip_suffix(".exe").unwrap_or(&file).to_string()
+}
+
+/// Name of a single process, without scanning every other one
+pub fn name(pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing(),
+    );
+    system
+        .process(pid)
+        .map(|process| program_name(&process.name().to_string_lossy()))
+}
+
+impl ProcessTree {
+    pub fn scan() -> Self {
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let processes = system
+            .processes()
+            .iter()
+            .map(|(pid, process)| Process {
+                pid: pid.as_u32(),
+                parent: process.parent().map(|parent| parent.as_u32()),
+                name: program_name(&process.name().to_string_lossy()),
+                started: process.start_time(),
+            })
+            .collect();
+        Self { processes }
+    }
+
+    // Every process below `root`, parents before their children
+    fn descendants(&self, root: u32) -> Vec<&Process> {
+        let mut children: HashMap<u32, Vec<&Process>> = HashMap::new();
+        for process in &self.processes {
+            if let Some(parent) = process.parent {
+                children.entry(parent).or_default().push(process);
+            }
+        }
+        let mut found = Vec::new();
+        let mut pending = vec![root];
+        while let Some(pid) = pending.pop() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                found.push(child);
+                pending.push(child.pid);
+            }
+        }
+        found
+    }
+
+    /// Names of the listed programs running below the process `root`, each
+    /// once and in the order they were found
+    pub fn running_below(&self, root: u32, listed: &[String]) -> Vec<String> {
+        let listed: Vec<String> = listed.iter().map(|name| program_name(name)).collect();
+        let mut found: Vec<String> = Vec::new();
+        for process in self.descendants(root) {
+            if listed.contains(&process.name) && !found.contains(&process.name) {
+                found.push(process.name.clone());
+            }
+        }
+        found
+    }
+
+    /// The process started last below `root`, or `root` itself when it has
+    /// none. Stands in for the foreground process where the console has no
+    /// process groups, like on Windows.
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn newest_below(&self, root: u32) -> Option<(u32, String)> {
+        self.descendants(root)
+            .into_iter()
+            .max_by_key(|process| process.started)
+            .or_else(|| self.processes.iter().find(|process| process.pid == root))
+            .map(|process| (process.pid, process.name.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_tree() {
+        let process = |pid, parent, name: &str, started| Process {
+            pid,
+            parent: Some(parent),
+            name: program_name(name),
+            started,
+        };
+        let tree = ProcessTree {
+            processes: vec![
+                process(10, 1, "bash", 100),
+                process(11, 10, "make", 101),
+                process(12, 11, "Vim.exe", 105),
+                process(13, 10, "ssh", 102),
+                process(14, 13, "ssh", 103),
+                process(20, 1, "nvim", 200),
+            ],
+        };
+        let listed = ["vim".to_string(), "nvim".to_string(), "ssh".to_string()];
+        let mut found = tree.running_below(10, &listed);
+        found.sort();
+        assert_eq!(found, ["ssh", "vim"]);
+        assert!(tree.running_below(12, &listed).is_empty());
+
+        assert_eq!(tree.newest_below(10), Some((12, "vim".to_string())));
+        assert_eq!(tree.newest_below(20), Some((20, "nvim".to_string())));
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Output waiting to be sent to the frontend, older output is dropped past
+/// this many bytes
+pub const MAX_QUEUED_BYTES: usize = 4 * 1024 * 1024;
+
+/// Ways programs move the cursor home before repainting the screen
+const CURSOR_HOME: [&[u8]; 4] = [b"\x1b[H", b"\x1b[1;1H", b"\x1b[;H", b"\x1b[1H"];
+
+pub enum Queued<E> {
+    Output(Vec<u8>),
+    Event(E),
+}
+
+struct State<E> {
+    items: VecDeque<Queued<E>>,
+    output_bytes: usize,
+}
+
+/// Events on their way from a PTY's threads to the one sending them to the
+/// frontend. Output arriving while the sender is behind is merged into one
+/// message, and once more than the limit is waiting the oldest output is
+/// dropped, so a stalled window can't make the backend grow without bound.
+/// Other events are small and always kept.
+pub struct OutputQueue<E> {
+    state: Mutex<State<E>>,
+    ready: Condvar,
+    limit: usize,
+    /// Only send the latest complete frame of programs repainting the screen
+    coalesce_frames: bool,
+    dropped_bytes: Arc<AtomicU64>,
+}
+
+/// Start of the last complete frame in output that repaints the screen more
+/// than once, frames being what is between two cursor-home sequences.
+/// Everything before it was painted over, 0 if there is no such frame.
+pub fn latest_frame_start(data: &[u8]) -> usize {
+    let homes: Vec<usize> = (0..data.len())
+        .filter(|&i| data[i] == 0x1b && CURSOR_HOME.iter().any(|home| data[i..].starts_with(home)))
+        .collect();
+    homes.len().checked_sub(2).map_or(0, |i| homes[i])
+}
+
+/// Drop the frames painted over in `data`, keeping the escape sequences in
+/// them that change terminal state rather than draw, like mode switches,
+/// colors and scroll regions, so the kept frame is shown as it would have been
+pub fn coalesce_frames(data: &mut Vec<u8>) {
+    let start = latest_frame_start(data);
+    if start > 0 {
+        let state = state_sequences(&data[..start]);
+        data.splice(..start, state);
+    }
+}
+
+// The escape sequences in `data` that don't only move the cursor, erase or
+// draw. Colors set before the last reset are left out since it undoes them.
+fn state_sequences(data: &[u8]) -> Vec<u8> {
+    let mut modes = Vec::new();
+    let mut colors = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != 0x1b {
+            i += 1;
+            continue;
+        }
+        let Some(&kind) = data.get(i + 1) else {
+            break;
+        };
+        let (end, keep) = match kind {
+            b'[' => {
+                let Some(len) = data[i + 2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+                    break;
+                };
+                let end = i + 2 + len + 1;
+                let params = &data[i + 2..end - 1];
+                match data[end - 1] {
+                    b'm' if params.is_empty() || params == b"0" => {
+                        colors.clear();
+                        colors.extend_from_slice(&data[i..end]);
+                        (end, false)
+                    }
+                    b'm' => {
+                        colors.extend_from_slice(&data[i..end]);
+                        (end, false)
+                    }
+                    // Cursor movement, erasing, inserting and deleting
+                    b'@'
+                    | b'A'..=b'H'
+                    | b'J'
+                    | b'K'
+                    | b'L'
+                    | b'M'
+                    | b'P'
+                    | b'S'
+                    | b'T'
+                    | b'X'
+                    | b'`'
+                    | b'a'
+                    | b'b'
+                    | b'd'
+                    | b'e'
+                    | b'f' => (end, false),
+                    _ => (end, true),
+                }
+            }
+            // Titles, working directory and hyperlinks, ended by BEL or ST
+            b']' => {
+                let Some(stop) = (i + 2..data.len())
+                    .find(|&j| data[j] == 0x07 || data[j..].starts_with(b"\x1b\\"))
+                else {
+                    break;
+                };
+                (stop + if data[stop] == 0x07 { 1 } else { 2 }, true)
+            }
+            // Device control strings draw, like sixel images
+            b'P' | b'X' | b'^' | b'_' => {
+                let Some(stop) = (i + 2..data.len()).find(|&j| data[j..].starts_with(b"\x1b\\"))
+                else {
+                    break;
+                };
+                (stop + 2, false)
+            }
+            // Charset designations
+            0x20..=0x2f => (i + 3, true),
+            // Index, next line and reverse index move the cursor
+            b'D' | b'E' | b'M' => (i + 2, false),
+            _ => (i + 2, true),
+        };
+        let end = end.min(data.len());
+        if keep {
+            modes.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+    }
+    modes.extend_from_slice(&colors);
+    modes
+}
+
+impl<E> OutputQueue<E> {
+    pub fn new(limit: usize, coalesce_frames: bool) -> Self {
+        Self {
+            state: Mutex::new(State {
+                items: VecDeque::new(),
+                output_bytes: 0,
+            }),
+            ready: Condvar::new(),
+            limit,
+            coalesce_frames,
+            dropped_bytes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Output bytes dropped so far
+    pub fn dropped_bytes(&self) -> Arc<AtomicU64> {
+        self.dropped_bytes.clone()
+    }
+
+    pub fn push_output(&self, data: Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.output_bytes += data.len();
+        match state.items.back_mut() {
+            Some(Queued::Output(last)) => last.extend_from_slice(&data),
+            _ => state.items.push_back(Queued::Output(data)),
+        }
+        let dropped = state.trim(self.limit);
+        if dropped > 0 {
+            self.dropped_bytes
+                .fetch_add(dropped as u64, Ordering::Relaxed);
+        }
+        drop(state);
+        self.ready.notify_one();
+    }
+
+    pub fn push_event(&self, event: E) {
+        self.state
+            .lock()
+            .unwrap()
+            .items
+            .push_back(Queued::Event(event));
+        self.ready.notify_one();
+    }
+
+    /// Wait up to `timeout` for something to send and take all of it
+    pub fn pop_all(&self, timeout: Duration) -> Vec<Queued<E>> {
+        let state = self.state.lock().unwrap();
+        let (mut state, _) = self
+            .ready
+            .wait_timeout_while(state, timeout, |state| state.items.is_empty())
+            .unwrap();
+        state.output_bytes = 0;
+        let mut items: Vec<Queued<E>> = state.items.drain(..).collect();
+        drop(state);
+        if self.coalesce_frames {
+            for item in &mut items {
+                if let Queued::Output(data) = item {
+                    coalesce_frames(data);
+                }
+            }
+        }
+        items
+    }
+}
+
+impl<E> State<E> {
+    // Drop the oldest output until at most `limit` bytes of it are queued,
+    // returns how many bytes were dropped
+    fn trim(&mut self, limit: usize) -> usize {
+        let mut dropped = 0;
+        let mut index = 0;
+        while self.output_bytes > limit && index < self.items.len() {
+            let excess = self.output_bytes - limit;
+            let Queued::Output(data) = &mut self.items[index] else {
+                index += 1;
+                continue;
+            };
+            // Resume at a line so the output isn't cut inside an escape sequence
+            let cut = if data.len() <= excess || data[excess - 1] == b'\n' {
+                excess.min(data.len())
+            } else {
+                data[excess..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(data.len(), |newline| excess + newline + 1)
+            };
+            if cut == data.len() {
+                self.items.remove(index);
+            } else {
+                data.drain(..cut);
+            }
+            self.output_bytes -= cut;
+            dropped += cut;
+        }
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_queue_limit() {
+        let queue = OutputQueue::new(10, false);
+        queue.push_output(b"one\n".to_vec());
+        queue.push_event("title");
+        queue.push_output(b"two\n".to_vec());
+        // Merged with the output before it, then the oldest line is dropped
+        queue.push_output(b"three\n".to_vec());
+        assert_eq!(queue.dropped_bytes().load(Ordering::Relaxed), 4);
+
+        let items = queue.pop_all(Duration::ZERO);
+        let items: Vec<_> = items
+            .iter()
+            .map(|item| match item {
+                Queued::Output(data) => String::from_utf8_lossy(data).into_owned(),
+                Queued::Event(event) => event.to_string(),
+            })
+            .collect();
+        assert_eq!(items, ["title", "two\nthree\n"]);
+        assert!(queue.pop_all(Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_latest_frame_start() {
+        assert_eq!(latest_frame_start(b"plain output\r\n"), 0);
+        assert_eq!(latest_frame_start(b"\x1b[Hone"), 0);
+        // The first frame was repainted by the second, the third isn't done
+        let data = b"\x1b[?25l\x1b[Hone\x1b[1;1Htwo\x1b[Hthr";
+        assert_eq!(&data[latest_frame_start(data)..], b"\x1b[1;1Htwo\x1b[Hthr");
+
+        // Modes and colors set in the dropped frame still apply to the kept one
+        let mut data =
+            b"\x1b[?1049h\x1b[?25l\x1b[H\x1b[1mone\x1b[0m\x1b[31m\x1b[2K\x1b[Htwo\x1b[Hthr"
+                .to_vec();
+        coalesce_frames(&mut data);
+        assert_eq!(
+            data,
+            b"\x1b[?1049h\x1b[?25l\x1b[0m\x1b[31m\x1b[Htwo\x1b[Hthr"
+        );
+    }
+}
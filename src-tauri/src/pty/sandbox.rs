@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use super::utils;
+use crate::config::{SandboxConfig, SandboxTool};
+
+fn program(tool: SandboxTool) -> &'static str {
+    match tool {
+        SandboxTool::Bubblewrap => "bwrap",
+        SandboxTool::Firejail => "firejail",
+        SandboxTool::SandboxExec => "sandbox-exec",
+    }
+}
+
+/// Sandbox program a profile's shell runs in, the configured one or else
+/// the first one installed
+pub fn sandbox_tool(config: &SandboxConfig) -> Result<SandboxTool, String> {
+    let candidates: &[SandboxTool] = match &config.tool {
+        Some(tool) => std::slice::from_ref(tool),
+        None if cfg!(windows) => return Err("Sandboxing isn't supported on Windows".into()),
+        None if cfg!(target_os = "macos") => &[SandboxTool::SandboxExec],
+        None => &[SandboxTool::Bubblewrap, SandboxTool::Firejail],
+    };
+    candidates
+        .iter()
+        .copied()
+        .find(|&tool| utils::find_executable(program(tool)).is_some())
+        .ok_or_else(|| {
+            let names: Vec<&str> = candidates.iter().map(|&tool| program(tool)).collect();
+            format!("Sandboxing needs {} to be installed", names.join(" or "))
+        })
+}
+
+// Paths as the sandbox sees them, symlinks like macOS' /tmp resolved.
+// Paths that don't exist can't be bound or hidden, they are left out.
+fn resolve(paths: &Option<Vec<String>>) -> Vec<String> {
+    paths
+        .iter()
+        .flatten()
+        .filter_map(|path| std::fs::canonicalize(utils::expand_home(path)).ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The command line that runs `argv` in the sandbox
+pub fn wrap(config: &SandboxConfig, tool: SandboxTool, argv: Vec<String>) -> Vec<String> {
+    let writable = resolve(&config.writable);
+    let hidden = resolve(&config.hidden);
+    let network = config.network.unwrap_or(false);
+
+    let mut args = vec![program(tool).to_string()];
+    match tool {
+        SandboxTool::Bubblewrap => {
+            args.extend(
+                ["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"].map(String::from),
+            );
+            args.extend(["--tmpfs", "/tmp"].map(String::from));
+            for path in writable {
+                args.extend(["--bind".to_string(), path.clone(), path]);
+            }
+            // An empty directory over hidden directories, /dev/null over files
+            for path in hidden {
+                if Path::new(&path).is_dir() {
+                    args.extend(["--tmpfs".to_string(), path]);
+                } else {
+                    args.extend(["--ro-bind".to_string(), "/dev/null".to_string(), path]);
+                }
+            }
+            if !network {
+                args.push("--unshare-net".to_string());
+            }
+            args.extend(["--unshare-pid", "--die-with-parent", "--"].map(String::from));
+        }
+        SandboxTool::Firejail => {
+            args.extend(
+                ["--quiet", "--noprofile", "--read-only=/", "--private-tmp"].map(String::from),
+            );
+            args.extend(
+                writable
+                    .into_iter()
+                    .map(|path| format!("--read-write={}", path)),
+            );
+            args.extend(
+                hidden
+                    .into_iter()
+                    .map(|path| format!("--blacklist={}", path)),
+            );
+            if !network {
+                args.push("--net=none".to_string());
+            }
+        }
+        SandboxTool::SandboxExec => {
+            args.extend([
+                "-p".to_string(),
+                seatbelt_profile(&writable, &hidden, network),
+            ]);
+        }
+    }
+    args.extend(argv);
+    args
+}
+
+// Profile for `sandbox-exec`, in Apple's Scheme-like sandbox language
+fn seatbelt_profile(writable: &[String], hidden: &[String], network: bool) -> String {
+    let quote = |path: &str| format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut profile = String::from("(version 1)\n(allow default)\n");
+    if !network {
+        profile.push_str("(deny network*)\n");
+    }
+    // Terminals and temporary files stay writable, or the shell can't work
+    profile.push_str("(deny file-write*)\n(allow file-write* (regex #\"^/dev/\")");
+    profile.push_str(" (subpath \"/private/tmp\") (subpath \"/private/var/folders\")");
+    for path in writable {
+        profile.push_str(&format!(" (subpath {})", quote(path)));
+    }
+    profile.push_str(")\n");
+    for path in hidden {
+        profile.push_str(&format!("(deny file-read* (subpath {}))\n", quote(path)));
+    }
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_sandbox() {
+        let temp = std::fs::canonicalize(std::env::temp_dir())
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let config = SandboxConfig {
+            tool: None,
+            writable: Some(vec![temp.clone(), "/does/not/exist".into()]),
+            hidden: None,
+            network: None,
+        };
+        let argv = vec!["bash".to_string(), "-l".to_string()];
+
+        let args = wrap(&config, SandboxTool::Bubblewrap, argv.clone());
+        assert_eq!(args[0], "bwrap");
+        assert!(args.windows(3).any(|w| w == ["--bind", &temp, &temp]));
+        assert!(!args.iter().any(|arg| arg == "/does/not/exist"));
+        assert!(args.contains(&"--unshare-net".to_string()));
+        assert_eq!(args[args.len() - 3..], ["--", "bash", "-l"]);
+
+        let args = wrap(&config, SandboxTool::Firejail, argv.clone());
+        assert!(args.contains(&format!("--read-write={}", temp)));
+        assert!(args.contains(&"--net=none".to_string()));
+
+        let args = wrap(&config, SandboxTool::SandboxExec, argv);
+        assert!(args[2].contains("(deny network*)"));
+        assert!(args[2].contains(&format!("(subpath \"{}\")", temp)));
+    }
+}
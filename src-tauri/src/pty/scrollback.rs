@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Bytes of output kept per PTY for exports
+pub const MAX_SCROLLBACK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Disk space the spilled output of a PTY may take when not configured
+pub const DEFAULT_SPILL_MB: u64 = 256;
+
+/// Uncompressed bytes of output per spill file
+const SPILL_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Raw output of a PTY as sent to the frontend, the oldest lines are dropped
+/// once it is full, or spilled to disk if that is turned on
+pub struct Scrollback {
+    data: VecDeque<u8>,
+    limit: usize,
+    /// Stream offset of the first byte kept in memory
+    start: u64,
+    spill: Option<Spill>,
+}
+
+// A spill file and how much output it holds
+struct SpillChunk {
+    path: PathBuf,
+    len: u64,
+    disk_bytes: u64,
+}
+
+/// Output dropped from memory, kept in lz4 compressed files of
+/// `SPILL_CHUNK_BYTES` each. The directory is removed with it.
+pub struct Spill {
+    dir: PathBuf,
+    chunks: VecDeque<SpillChunk>,
+    /// Output not making up a whole file yet
+    pending: Vec<u8>,
+    /// Stream offset of the first spilled byte still kept
+    start: u64,
+    disk_bytes: u64,
+    /// Most disk space the files may take, the oldest are removed beyond it
+    quota: u64,
+    next_file: u64,
+}
+
+impl Spill {
+    pub fn new(dir: PathBuf, quota: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            chunks: VecDeque::new(),
+            pending: Vec::new(),
+            start: 0,
+            disk_bytes: 0,
+            quota,
+            next_file: 0,
+        })
+    }
+
+    fn push(&mut self, bytes: impl Iterator<Item = u8>) -> io::Result<()> {
+        self.pending.extend(bytes);
+        while self.pending.len() >= SPILL_CHUNK_BYTES {
+            let chunk: Vec<u8> = self.pending.drain(..SPILL_CHUNK_BYTES).collect();
+            let compressed = lz4_flex::compress_prepend_size(&chunk);
+            let path = self.dir.join(format!("{}.lz4", self.next_file));
+            fs::write(&path, &compressed)?;
+            self.next_file += 1;
+            self.disk_bytes += compressed.len() as u64;
+            self.chunks.push_back(SpillChunk {
+                path,
+                len: chunk.len() as u64,
+                disk_bytes: compressed.len() as u64,
+            });
+        }
+        while self.disk_bytes > self.quota {
+            let Some(oldest) = self.chunks.pop_front() else {
+                break;
+            };
+            self.start += oldest.len;
+            self.disk_bytes -= oldest.disk_bytes;
+            fs::remove_file(&oldest.path)?;
+        }
+        Ok(())
+    }
+
+    // The files and pending output holding the spilled output between two
+    // stream offsets, to read without the scrollback
+    fn plan(&self, start: u64, end: u64) -> SpillRead {
+        let mut read = SpillRead::default();
+        let mut chunk_start = self.start;
+        for chunk in &self.chunks {
+            let chunk_end = chunk_start + chunk.len;
+            if chunk_end > start && chunk_start < end {
+                let from = start.saturating_sub(chunk_start) as usize;
+                let to = (end.min(chunk_end) - chunk_start) as usize;
+                read.files.push((chunk.path.clone(), from..to));
+            }
+            chunk_start = chunk_end;
+        }
+        if end > chunk_start {
+            let from = start.saturating_sub(chunk_start) as usize;
+            let to = (end - chunk_start) as usize;
+            read.pending_start = start.max(chunk_start);
+            read.pending = self.pending[from..to.min(self.pending.len())].to_vec();
+        }
+        read
+    }
+}
+
+// Spilled output to read, byte ranges of files and then pending output
+#[derive(Default)]
+struct SpillRead {
+    files: Vec<(PathBuf, Range<usize>)>,
+    pending_start: u64,
+    pending: Vec<u8>,
+}
+
+impl SpillRead {
+    // Fails when a file is gone, e.g. removed for the quota meanwhile
+    fn read(&self) -> io::Result<Vec<u8>> {
+        let mut range = Vec::new();
+        for (path, bytes) in &self.files {
+            let compressed = fs::read(path)?;
+            let data = lz4_flex::decompress_size_prepended(&compressed)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            range.extend_from_slice(&data[bytes.start..bytes.end.min(data.len())]);
+        }
+        range.extend_from_slice(&self.pending);
+        Ok(range)
+    }
+}
+
+/// Output between two stream offsets taken from a scrollback. Spilled output
+/// is read by `read`, after the scrollback's lock is released, so a long
+/// read doesn't hold up the PTY's output.
+pub struct ScrollbackRead {
+    start: u64,
+    spill: Option<SpillRead>,
+    memory_start: u64,
+    memory: Vec<u8>,
+}
+
+impl ScrollbackRead {
+    /// The output and the stream offset it starts at. When spilled files
+    /// can't be read it starts after them.
+    pub fn read(self) -> (u64, Vec<u8>) {
+        let Some(spill) = self.spill else {
+            return (self.start, self.memory);
+        };
+        let (start, mut range) = match spill.read() {
+            Ok(range) => (self.start, range),
+            Err(e) => {
+                tracing::warn!("Failed to read spilled scrollback: {}", e);
+                if spill.pending.is_empty() {
+                    (self.memory_start, Vec::new())
+                } else {
+                    (spill.pending_start, spill.pending)
+                }
+            }
+        };
+        range.extend(self.memory);
+        (start, range)
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            tracing::warn!("Failed to remove spilled scrollback: {}", e);
+        }
+    }
+}
+
+impl Scrollback {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            data: VecDeque::new(),
+            limit,
+            start: 0,
+            spill: None,
+        }
+    }
+
+    /// Keep output dropped from memory in `spill`
+    pub fn with_spill(limit: usize, spill: Spill) -> Self {
+        Self {
+            spill: Some(spill),
+            ..Self::new(limit)
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes);
+        if self.data.len() <= self.limit {
+            return;
+        }
+        let excess = self.data.len() - self.limit;
+        let cut_at_line = self.data[excess - 1] == b'\n';
+        self.drop_front(excess);
+        // Start at a line so the first one isn't cut inside an escape sequence
+        if cut_at_line {
+            return;
+        }
+        if let Some(newline) = self.data.iter().position(|&b| b == b'\n') {
+            self.drop_front(newline + 1);
+        }
+    }
+
+    fn drop_front(&mut self, count: usize) {
+        self.start += count as u64;
+        let dropped = self.data.drain(..count);
+        let Some(spill) = self.spill.as_mut() else {
+            return;
+        };
+        // Without the rest of the spill its offsets are wrong, so it goes
+        if let Err(e) = spill.push(dropped) {
+            tracing::warn!("Failed to spill scrollback to disk: {}", e);
+            self.spill = None;
+        }
+    }
+
+    /// Stream offset of the oldest byte still kept, in memory or on disk
+    pub fn start(&self) -> u64 {
+        self.spill.as_ref().map_or(self.start, |spill| spill.start)
+    }
+
+    /// Stream offset right after the latest output
+    pub fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+
+    /// Take the output between two stream offsets to read later, as far as
+    /// it is still kept. No end means up to the latest output.
+    pub fn read_range(&self, start: u64, end: Option<u64>) -> ScrollbackRead {
+        let end = end.unwrap_or(self.end()).clamp(self.start(), self.end());
+        let start = start.clamp(self.start(), end);
+        let memory_start = start.max(self.start);
+        let memory_end = end.max(memory_start);
+        ScrollbackRead {
+            start,
+            spill: self
+                .spill
+                .as_ref()
+                .filter(|_| start < self.start)
+                .map(|spill| spill.plan(start, end.min(self.start))),
+            memory_start,
+            memory: self
+                .data
+                .range((memory_start - self.start) as usize..(memory_end - self.start) as usize)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(scrollback: &Scrollback, start: u64, end: Option<u64>) -> Vec<u8> {
+        scrollback.read_range(start, end).read().1
+    }
+
+    #[test]
+    fn test_scrollback_limit() {
+        let mut scrollback = Scrollback::new(10);
+        scrollback.push(b"one\ntwo\n");
+        assert_eq!(range(&scrollback, 0, None), b"one\ntwo\n");
+        scrollback.push(b"three\n");
+        assert_eq!(range(&scrollback, 0, None), b"two\nthree\n");
+        // Partly dropped lines are dropped whole
+        scrollback.push(b"four\n");
+        assert_eq!(range(&scrollback, 0, None), b"four\n");
+        assert_eq!(scrollback.start(), 14);
+        assert_eq!(range(&scrollback, 15, Some(17)), b"ou");
+        assert_eq!(range(&scrollback, 0, None), b"four\n");
+
+        let dir = std::env::temp_dir().join(format!("termillion-spill-{}", std::process::id()));
+        let spill = Spill::new(dir.clone(), 2 * SPILL_CHUNK_BYTES as u64).unwrap();
+        let mut scrollback = Scrollback::with_spill(10, spill);
+        let line: Vec<u8> = (0..SPILL_CHUNK_BYTES / 4)
+            .flat_map(|i| format!("{:07}\n", i).into_bytes())
+            .collect();
+        scrollback.push(&line);
+        scrollback.push(b"last\n");
+        // Everything is still there, read back across the file and memory
+        assert_eq!(scrollback.start(), 0);
+        let all = range(&scrollback, 0, None);
+        assert_eq!(all.len(), line.len() + 5);
+        assert_eq!(&all[..8], b"0000000\n");
+        assert_eq!(
+            range(
+                &scrollback,
+                SPILL_CHUNK_BYTES as u64 - 8,
+                Some(SPILL_CHUNK_BYTES as u64)
+            ),
+            b"0131071\n"
+        );
+        assert!(dir.join("0.lz4").exists());
+
+        // Output whose file is gone is left out, and the read says so
+        let read = scrollback.read_range(4, None);
+        fs::remove_file(dir.join("0.lz4")).unwrap();
+        let (start, data) = read.read();
+        assert!(start >= SPILL_CHUNK_BYTES as u64);
+        assert_eq!(start + data.len() as u64, all.len() as u64);
+        assert!(data.ends_with(b"last\n"));
+        drop(scrollback);
+        assert!(!dir.exists());
+    }
+}
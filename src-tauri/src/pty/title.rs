@@ -0,0 +1,91 @@
+/// Values a tab title template can show, missing ones render empty
+#[derive(Debug, Default)]
+pub struct TitleVars {
+    /// Position of the tab in its window, starting at 1
+    pub index: Option<usize>,
+    /// Title the shell set
+    pub title: Option<String>,
+    /// Program running in the foreground, the shell itself at the prompt
+    pub command: Option<String>,
+    pub cwd: Option<String>,
+    pub shell: String,
+    pub profile: Option<String>,
+}
+
+/// The last directory of a path, `~` for the home directory itself
+pub fn short_cwd(cwd: &str, home: Option<&str>) -> String {
+    let trimmed = cwd.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        return cwd.to_string();
+    }
+    if home.is_some_and(|home| home.trim_end_matches(['/', '\\']) == trimmed) {
+        return "~".to_string();
+    }
+    // Split on both separators, Windows paths may use either
+    trimmed
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+// Name of a program without its directory and `.exe`
+fn program_name(program: &str) -> String {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    name.strip_suffix(".exe").unwrap_or(name).to_string()
+}
+
+/// Fill in `{index}`, `{title}`, `{command}`, `{cwd}`, `{cwd_short}`,
+/// `{shell}` and `{profile}`. Unknown placeholders are left as they are.
+pub fn render(template: &str, vars: &TitleVars, home: Option<&str>) -> String {
+    let cwd = vars.cwd.as_deref().unwrap_or_default();
+    let values = [
+        (
+            "index",
+            vars.index.map(|i| i.to_string()).unwrap_or_default(),
+        ),
+        ("title", vars.title.clone().unwrap_or_default()),
+        (
+            "command",
+            program_name(vars.command.as_deref().unwrap_or(&vars.shell)),
+        ),
+        ("cwd_short", short_cwd(cwd, home)),
+        ("cwd", cwd.to_string()),
+        ("shell", program_name(&vars.shell)),
+        ("profile", vars.profile.clone().unwrap_or_default()),
+    ];
+    let mut rendered = template.to_string();
+    for (name, value) in values {
+        rendered = rendered.replace(&format!("{{{}}}", name), &value);
+    }
+    rendered.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_title() {
+        let vars = TitleVars {
+            index: Some(2),
+            title: Some("vim notes.md".into()),
+            command: Some("/usr/bin/vim".into()),
+            cwd: Some("/home/me/src/termillion".into()),
+            shell: "/bin/zsh".into(),
+            profile: None,
+        };
+        assert_eq!(
+            render("{index}: {command} — {cwd_short}", &vars, Some("/home/me")),
+            "2: vim — termillion"
+        );
+        assert_eq!(
+            render("{profile} {shell} {other}", &vars, None),
+            "zsh {other}"
+        );
+
+        assert_eq!(short_cwd("/home/me/", Some("/home/me")), "~");
+        assert_eq!(short_cwd(r"C:\Users\me\code", None), "code");
+        assert_eq!(short_cwd("/", None), "/");
+    }
+}
@@ -85,6 +85,55 @@ pub fn get_home_dir() -> Result<String, String> {
         .ok_or_else(|| "Could not determine home directory".to_string())
 }
 
+/// Expand a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match get_home_dir() {
+            Ok(home) => format!("{}{}", home, rest),
+            Err(_) => path.to_string(),
+        },
+        _ => path.to_string(),
+    }
+}
+
+/// Resolve a command to an executable on the PATH, or check it exists when
+/// it is a path
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    which::which(expand_home(command)).ok()
+}
+
+/// Get the current working directory of a process
+pub fn process_cwd(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_link(format!("/proc/{}/cwd", pid))
+            .ok()
+            .map(|path| path.to_string_lossy().to_string())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Get the name of a process's program
+pub fn process_name(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|name| name.trim_end().to_string())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
 /// Normalize a path for the current platform
 pub fn normalize_path(path: &str) -> String {
     #[cfg(target_os = "windows")]
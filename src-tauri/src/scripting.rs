@@ -0,0 +1,312 @@
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::actions;
+use crate::config::{self, Config};
+use crate::notifications;
+use crate::pty;
+use crate::session::SessionTab;
+use crate::window;
+
+/// Directory next to the config file that user scripts are loaded from
+const SCRIPTS_DIR: &str = "scripts";
+
+/// Extension of script files, other files in the directory are ignored
+const SCRIPT_EXTENSION: &str = "rhai";
+
+/// Calls a single hook may make before it is stopped, so a script stuck in a
+/// loop can't hang the PTY that triggered it
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Events scripts can handle by defining a function of the same name
+pub const ON_PTY_CREATED: &str = "on_pty_created";
+pub const ON_PTY_EXITED: &str = "on_pty_exited";
+
+/// A script that was found in the scripts directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptInfo {
+    pub name: String,
+    /// Events the script has a handler for
+    pub handlers: Vec<String>,
+    /// Why the script couldn't be loaded, it is skipped when set
+    pub error: Option<String>,
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+}
+
+/// Compiled user scripts and the engine that runs them
+pub struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<Script>,
+    info: Vec<ScriptInfo>,
+}
+
+impl ScriptHost {
+    /// Compile scripts given as `(name, source)` and run their top-level code
+    pub fn from_sources(engine: Engine, sources: Vec<(String, String)>) -> Self {
+        let mut scripts = Vec::new();
+        let mut info = Vec::new();
+        for (name, source) in sources {
+            let loaded = engine
+                .compile(&source)
+                .map_err(|e| e.to_string())
+                .and_then(|ast| {
+                    engine
+                        .run_ast_with_scope(&mut Scope::new(), &ast)
+                        .map(|_| ast)
+                        .map_err(|e| e.to_string())
+                });
+            match loaded {
+                Ok(ast) => {
+                    info.push(ScriptInfo {
+                        name: name.clone(),
+                        handlers: ast.iter_functions().map(|f| f.name.to_string()).collect(),
+                        error: None,
+                    });
+                    scripts.push(Script { name, ast });
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to load script {}: {}", name, e);
+                    info.push(ScriptInfo {
+                        name,
+                        handlers: Vec::new(),
+                        error: Some(e),
+                    });
+                }
+            }
+        }
+        Self {
+            engine,
+            scripts,
+            info,
+        }
+    }
+
+    /// Call the handler of an event in every script that defines one
+    pub fn dispatch(&self, event: &str, args: Vec<Dynamic>) {
+        for script in &self.scripts {
+            let has_handler = script
+                .ast
+                .iter_functions()
+                .any(|f| f.name == event && f.params.len() == args.len());
+            if !has_handler {
+                continue;
+            }
+            let result =
+                self.engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, event, args.clone());
+            if let Err(e) = result {
+                tracing::warn!("Script {} failed in {}: {}", script.name, event, e);
+            }
+        }
+    }
+}
+
+/// Scripts of the running app, swapped as a whole on reload
+#[derive(Default)]
+pub struct ScriptState(Mutex<Option<Arc<ScriptHost>>>);
+
+fn scripts_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_path = config::get_config_path(app)?;
+    Ok(config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(SCRIPTS_DIR))
+}
+
+// Script files sorted by name, so scripts can rely on the order they run in
+fn read_sources(dir: &Path) -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| match fs::read_to_string(&path) {
+            Ok(source) => Some((path.file_name()?.to_string_lossy().into_owned(), source)),
+            Err(e) => {
+                tracing::warn!("Failed to read script {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn script_error(e: impl Into<String>) -> Box<EvalAltResult> {
+    let message: String = e.into();
+    message.into()
+}
+
+// Open a tab in the focused window, like `termillion cli spawn`
+fn spawn_tab(
+    app: &AppHandle,
+    profile: Option<String>,
+    command: Option<String>,
+) -> Result<(), String> {
+    let window =
+        window::target_window(app).ok_or_else(|| "No window to open the tab in".to_string())?;
+    let tab = SessionTab {
+        profile,
+        cwd: None,
+        title: None,
+        command,
+        args: None,
+        pty_id: None,
+        task: None,
+        decoration: None,
+    };
+    app.emit_to(window.label(), "open-session-tab", tab)
+        .map_err(|e| format!("Failed to open tab: {}", e))
+}
+
+/// Engine with the automation API scripts can call
+fn create_engine(app: &AppHandle) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| tracing::info!(target: "script", "{}", text));
+    engine.on_debug(|text, source, _| {
+        tracing::debug!(target: "script", "{}: {}", source.unwrap_or("script"), text)
+    });
+
+    let handle = app.clone();
+    engine.register_fn("spawn_tab", move || {
+        spawn_tab(&handle, None, None).map_err(script_error)
+    });
+    let handle = app.clone();
+    engine.register_fn("spawn_tab", move |profile: &str| {
+        spawn_tab(&handle, Some(profile.to_string()), None).map_err(script_error)
+    });
+    let handle = app.clone();
+    engine.register_fn("spawn_tab", move |profile: &str, command: &str| {
+        let profile = Some(profile).filter(|p| !p.is_empty()).map(str::to_string);
+        spawn_tab(&handle, profile, Some(command.to_string())).map_err(script_error)
+    });
+    engine.register_fn("send_text", |pty_id: &str, text: &str| {
+        pty::write_to_pty(pty_id, text.as_bytes()).map_err(script_error)
+    });
+    engine.register_fn("notify", |body: &str| {
+        notifications::show(body).map_err(script_error)
+    });
+    let handle = app.clone();
+    engine.register_fn("run_action", move |name: &str, pty_id: &str| {
+        actions::run_action(&handle, name, pty_id).map_err(script_error)
+    });
+    let handle = app.clone();
+    engine.register_fn("config", move || {
+        let config = Config::load(&handle).map_err(script_error)?;
+        rhai::serde::to_dynamic(&config)
+    });
+    engine.register_fn("active_ptys", || -> rhai::Array {
+        pty::active_pty_ids()
+            .into_iter()
+            .map(Dynamic::from)
+            .collect()
+    });
+    engine.register_fn("pty_cwd", |pty_id: &str| -> Dynamic {
+        pty::pty_cwd(pty_id).map_or(Dynamic::UNIT, Dynamic::from)
+    });
+
+    engine
+}
+
+fn load(app: &AppHandle) -> ScriptHost {
+    let sources = scripts_dir(app)
+        .map(|dir| read_sources(&dir))
+        .unwrap_or_default();
+    ScriptHost::from_sources(create_engine(app), sources)
+}
+
+/// Load the user's scripts, called once at startup
+pub fn init(app: &AppHandle) {
+    let host = load(app);
+    *app.state::<ScriptState>().0.lock().unwrap() = Some(Arc::new(host));
+}
+
+/// Run the scripts' handlers for an event on the calling thread, which
+/// waits for all of them. Callers on PTY threads spawn a thread for it.
+pub fn dispatch(app: &AppHandle, event: &str, args: Vec<Dynamic>) {
+    let Some(state) = app.try_state::<ScriptState>() else {
+        return;
+    };
+    // Handlers may take a while, so they run outside the lock
+    let host = state.0.lock().unwrap().clone();
+    if let Some(host) = host {
+        host.dispatch(event, args);
+    }
+}
+
+// Load the scripts directory again, returning what was found
+#[tauri::command]
+pub async fn reload_scripts(
+    app: AppHandle,
+    state: State<'_, ScriptState>,
+) -> Result<Vec<ScriptInfo>, String> {
+    let host = load(&app);
+    let info = host.info.clone();
+    *state.0.lock().unwrap() = Some(Arc::new(host));
+    Ok(info)
+}
+
+// List the loaded scripts and their handlers
+#[tauri::command]
+pub async fn list_scripts(state: State<'_, ScriptState>) -> Result<Vec<ScriptInfo>, String> {
+    Ok(state
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|host| host.info.clone())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch_to_handlers() {
+        let created = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        let recorded = created.clone();
+        engine.register_fn("send_text", move |pty_id: &str, text: &str| {
+            recorded
+                .lock()
+                .unwrap()
+                .push(format!("{}:{}", pty_id, text));
+        });
+
+        let host = ScriptHost::from_sources(
+            engine,
+            vec![
+                (
+                    "greet.rhai".into(),
+                    r#"fn on_pty_created(id) { send_text(id, "echo hi\r"); }"#.into(),
+                ),
+                ("broken.rhai".into(), "fn on_pty_created(id) {".into()),
+                (
+                    "other.rhai".into(),
+                    "fn on_pty_exited(id, status) {}".into(),
+                ),
+            ],
+        );
+
+        host.dispatch(ON_PTY_CREATED, vec![Dynamic::from("abc".to_string())]);
+        assert_eq!(*created.lock().unwrap(), ["abc:echo hi\r"]);
+
+        assert_eq!(host.info.len(), 3);
+        assert_eq!(host.info[0].handlers, [ON_PTY_CREATED]);
+        assert!(host.info[1].error.is_some());
+        assert_eq!(host.info[2].handlers, [ON_PTY_EXITED]);
+    }
+}
@@ -0,0 +1,328 @@
+use grep::matcher::Matcher;
+use grep::regex::{RegexMatcher, RegexMatcherBuilder};
+use grep::searcher::{sinks, BinaryDetection, SearcherBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{ipc::Channel, AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::pty;
+
+/// Matches reported before the search stops when no limit is given
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+/// Longest preview sent for a matching line, minified files can have
+/// megabyte-long lines
+const MAX_PREVIEW_CHARS: usize = 300;
+
+/// Matches found are sent at most this often
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat the pattern as a regular expression instead of plain text
+    pub regex: bool,
+    /// Match case exactly, by default the search ignores case unless the
+    /// pattern has an uppercase letter
+    pub case_sensitive: Option<bool>,
+    pub whole_word: bool,
+    /// Also search hidden and ignored files
+    pub include_ignored: bool,
+    /// Globs files have to match, `!` excludes, e.g. `["*.rs", "!target/"]`
+    pub globs: Vec<String>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentMatch {
+    /// Path relative to the search root, with `/` separators
+    pub path: String,
+    /// Line number, starting at 1
+    pub line: u64,
+    /// The line, cut short when it is long
+    pub preview: String,
+    /// Character range of the first match in the preview
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum ContentSearchEvent {
+    /// Matches found since the last batch
+    Matches(Vec<ContentMatch>),
+    /// Sent last, after the search finished or was cancelled
+    Done {
+        files_searched: usize,
+        matches: usize,
+        cancelled: bool,
+        /// The search stopped at the result limit
+        truncated: bool,
+    },
+}
+
+/// Cancellation flags of the running searches by id
+#[derive(Default)]
+pub struct SearchState(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+fn build_matcher(pattern: &str, options: &SearchOptions) -> Result<RegexMatcher, String> {
+    let mut builder = RegexMatcherBuilder::new();
+    match options.case_sensitive {
+        Some(sensitive) => builder.case_insensitive(!sensitive),
+        None => builder.case_smart(true),
+    };
+    builder
+        .fixed_strings(!options.regex)
+        .word(options.whole_word)
+        .build(pattern)
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Build the reported match for a line the matcher found something in
+fn line_match(
+    matcher: &RegexMatcher,
+    path: &str,
+    line_number: u64,
+    line: &str,
+) -> Option<ContentMatch> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let found = matcher.find(line.as_bytes()).ok().flatten()?;
+    let start = line[..found.start()].chars().count();
+    let end = start + line[found.start()..found.end()].chars().count();
+
+    // Keep the match in view when the line has to be cut
+    let skip = if end > MAX_PREVIEW_CHARS {
+        start.saturating_sub(MAX_PREVIEW_CHARS / 4)
+    } else {
+        0
+    };
+    let preview: String = line.chars().skip(skip).take(MAX_PREVIEW_CHARS).collect();
+    let length = preview.chars().count();
+    Some(ContentMatch {
+        path: path.to_string(),
+        line: line_number,
+        preview,
+        start: (start - skip).min(length),
+        end: (end - skip).min(length),
+    })
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+    if cfg!(windows) {
+        relative.replace('\\', "/")
+    } else {
+        relative.into_owned()
+    }
+}
+
+struct SearchRun {
+    root: PathBuf,
+    matcher: RegexMatcher,
+    overrides: Override,
+    /// Skip hidden and ignored files
+    ignore: bool,
+    cancelled: Arc<AtomicBool>,
+    files_searched: Arc<AtomicUsize>,
+    found: Arc<AtomicUsize>,
+    max_results: usize,
+}
+
+// Search files on all cores, skipping what .gitignore and friends exclude
+// unless asked not to
+fn walk(run: &SearchRun, sender: mpsc::Sender<ContentMatch>) {
+    let ignore = run.ignore;
+    let walker = WalkBuilder::new(&run.root)
+        .hidden(ignore)
+        .git_ignore(ignore)
+        .git_global(ignore)
+        .git_exclude(ignore)
+        .ignore(ignore)
+        .require_git(false)
+        .overrides(run.overrides.clone())
+        .build_parallel();
+
+    walker.run(|| {
+        let sender = sender.clone();
+        let mut searcher = SearcherBuilder::new()
+            .binary_detection(BinaryDetection::quit(b'\x00'))
+            .line_number(true)
+            .build();
+        Box::new(move |entry| {
+            if run.cancelled.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                return WalkState::Continue;
+            }
+            run.files_searched.fetch_add(1, Ordering::Relaxed);
+            let path = relative_path(&run.root, entry.path());
+            let sink = sinks::Lossy(|line_number, line| {
+                let Some(found) = line_match(&run.matcher, &path, line_number, line) else {
+                    return Ok(true);
+                };
+                if run.found.fetch_add(1, Ordering::Relaxed) >= run.max_results
+                    || sender.send(found).is_err()
+                {
+                    run.cancelled.store(true, Ordering::Relaxed);
+                    return Ok(false);
+                }
+                Ok(!run.cancelled.load(Ordering::Relaxed))
+            });
+            // Unreadable files are skipped like ripgrep does
+            let _ = searcher.search_path(&run.matcher, entry.path(), sink);
+            WalkState::Continue
+        })
+    });
+}
+
+fn build_overrides(root: &Path, globs: &[String]) -> Result<Override, String> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in globs {
+        overrides
+            .add(glob)
+            .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?;
+    }
+    overrides
+        .build()
+        .map_err(|e| format!("Invalid globs: {}", e))
+}
+
+// Search the contents of the files under a directory. Matches are streamed
+// in batches and `done` is sent last. Returns the id to cancel the search with.
+#[tauri::command]
+pub async fn search_in_directory(
+    app: AppHandle,
+    state: State<'_, SearchState>,
+    root: String,
+    pattern: String,
+    opts: Option<SearchOptions>,
+    on_event: Channel<ContentSearchEvent>,
+) -> Result<String, String> {
+    let root = PathBuf::from(pty::expand_home(&root));
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+    let options = opts.unwrap_or_default();
+    let matcher = build_matcher(&pattern, &options)?;
+    let overrides = build_overrides(&root, &options.globs)?;
+
+    let search_id = Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state
+        .0
+        .lock()
+        .unwrap()
+        .insert(search_id.clone(), cancelled.clone());
+
+    let run = Arc::new(SearchRun {
+        root,
+        matcher,
+        overrides,
+        ignore: !options.include_ignored,
+        max_results: options.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+        cancelled,
+        files_searched: Arc::new(AtomicUsize::new(0)),
+        found: Arc::new(AtomicUsize::new(0)),
+    });
+
+    let (sender, receiver) = mpsc::channel();
+    let walker_run = run.clone();
+    thread::spawn(move || walk(&walker_run, sender));
+
+    let id = search_id.clone();
+    thread::spawn(move || {
+        let mut batch = Vec::new();
+        let mut last_sent = Instant::now();
+        let mut listening = true;
+        // Ends once every walker thread dropped its sender
+        for found in receiver {
+            batch.push(found);
+            if last_sent.elapsed() >= BATCH_INTERVAL {
+                if on_event
+                    .send(ContentSearchEvent::Matches(std::mem::take(&mut batch)))
+                    .is_err()
+                {
+                    // Nobody is listening anymore, stop the walkers
+                    run.cancelled.store(true, Ordering::Relaxed);
+                    listening = false;
+                    break;
+                }
+                last_sent = Instant::now();
+            }
+        }
+        app.state::<SearchState>().0.lock().unwrap().remove(&id);
+        if !listening {
+            return;
+        }
+        if !batch.is_empty() {
+            let _ = on_event.send(ContentSearchEvent::Matches(batch));
+        }
+        let found = run.found.load(Ordering::Relaxed);
+        let truncated = found > run.max_results;
+        let _ = on_event.send(ContentSearchEvent::Done {
+            files_searched: run.files_searched.load(Ordering::Relaxed),
+            matches: found.min(run.max_results),
+            cancelled: run.cancelled.load(Ordering::Relaxed) && !truncated,
+            truncated,
+        });
+    });
+
+    Ok(search_id)
+}
+
+// Stop a running search, it still sends `done`
+#[tauri::command]
+pub async fn cancel_search(state: State<'_, SearchState>, search_id: String) -> Result<(), String> {
+    let searches = state.0.lock().unwrap();
+    let cancelled = searches
+        .get(&search_id)
+        .ok_or_else(|| format!("No search with id {}", search_id))?;
+    cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_match() {
+        let literal = build_matcher("a.b", &SearchOptions::default()).unwrap();
+        assert!(line_match(&literal, "x.txt", 1, "axb").is_none());
+        let found = line_match(&literal, "x.txt", 3, "  see A.B\r\n").unwrap();
+        assert_eq!((found.line, found.start, found.end), (3, 6, 9));
+        assert_eq!(found.preview, "  see A.B");
+
+        // An uppercase letter makes the search case sensitive
+        let smart = build_matcher("Error", &SearchOptions::default()).unwrap();
+        assert!(line_match(&smart, "x.txt", 1, "error").is_none());
+
+        let regex = SearchOptions {
+            regex: true,
+            whole_word: true,
+            ..Default::default()
+        };
+        let words = build_matcher(r"fn \w+", &regex).unwrap();
+        assert!(line_match(&words, "x.rs", 1, "pub fn main() {}").is_some());
+        assert!(build_matcher("(", &regex).is_err());
+
+        // Long lines are cut around the match
+        let long = format!("{}needle{}", "x".repeat(1000), "y".repeat(1000));
+        let needle = build_matcher("needle", &SearchOptions::default()).unwrap();
+        let found = line_match(&needle, "min.js", 1, &long).unwrap();
+        assert_eq!(found.preview.chars().count(), MAX_PREVIEW_CHARS);
+        assert_eq!(&found.preview[found.start..found.end], "needle");
+    }
+}
@@ -0,0 +1,68 @@
+use keyring::Entry;
+
+/// Prefix of config values that are looked up in the OS keyring
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// Split a `keyring:service/account` reference into service and account
+pub fn parse_reference(value: &str) -> Option<(&str, &str)> {
+    let reference = value.strip_prefix(KEYRING_PREFIX)?;
+    let (service, account) = reference.split_once('/')?;
+    if service.is_empty() || account.is_empty() {
+        return None;
+    }
+    Some((service, account))
+}
+
+fn entry(service: &str, account: &str) -> Result<Entry, String> {
+    Entry::new(service, account).map_err(|e| format!("Failed to open keyring entry: {}", e))
+}
+
+/// Resolve a config value, fetching `keyring:` references from the OS
+/// keyring. Other values are returned as they are.
+pub fn resolve(value: &str) -> Result<String, String> {
+    if !value.starts_with(KEYRING_PREFIX) {
+        return Ok(value.to_string());
+    }
+    let (service, account) = parse_reference(value).ok_or_else(|| {
+        format!(
+            "Invalid keyring reference {}, expected keyring:service/account",
+            value
+        )
+    })?;
+    entry(service, account)?
+        .get_password()
+        .map_err(|e| format!("Failed to read secret {}/{}: {}", service, account, e))
+}
+
+// Store a secret in the OS keyring so the config can reference it
+#[tauri::command]
+pub async fn set_secret(service: String, account: String, secret: String) -> Result<(), String> {
+    entry(&service, &account)?
+        .set_password(&secret)
+        .map_err(|e| format!("Failed to store secret {}/{}: {}", service, account, e))
+}
+
+// Remove a secret from the OS keyring
+#[tauri::command]
+pub async fn delete_secret(service: String, account: String) -> Result<(), String> {
+    entry(&service, &account)?
+        .delete_credential()
+        .map_err(|e| format!("Failed to delete secret {}/{}: {}", service, account, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            parse_reference("keyring:github/octocat"),
+            Some(("github", "octocat"))
+        );
+        assert_eq!(parse_reference("keyring:github"), None);
+        assert_eq!(parse_reference("keyring:/octocat"), None);
+        assert_eq!(parse_reference("plain value"), None);
+        assert_eq!(resolve("plain value").unwrap(), "plain value");
+    }
+}
@@ -0,0 +1,136 @@
+use tauri::AppHandle;
+
+use crate::config::{Config, SelectionConfig};
+use crate::error::CommandResult;
+
+/// Apply the copy rules from the selection config to selected text
+pub fn process(text: &str, config: &SelectionConfig) -> String {
+    #[cfg(target_os = "windows")]
+    let line_ending = "\r\n";
+    #[cfg(not(target_os = "windows"))]
+    let line_ending = "\n";
+
+    let mut lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .map(|line| {
+            if config.trim_trailing_whitespace_on_copy {
+                line.trim_end()
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    // Selections spanning the end of the buffer pick up empty rows
+    if config.trim_trailing_whitespace_on_copy {
+        while lines.len() > 1 && lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+    }
+
+    lines.join(line_ending).replace('\0', "")
+}
+
+// Normalize selected text before the frontend copies it to the clipboard
+#[tauri::command]
+pub async fn process_selection(app: AppHandle, text: String) -> CommandResult<String> {
+    let config = Config::load(&app)?;
+    let selection = config.terminal.selection.unwrap_or_default();
+    Ok(process(&text, &selection))
+}
+
+fn primary_selection_enabled(app: &AppHandle) -> CommandResult<bool> {
+    let config = Config::load(app)?;
+    Ok(config
+        .terminal
+        .selection
+        .and_then(|selection| selection.primary_selection)
+        .unwrap_or(false))
+}
+
+#[cfg(target_os = "linux")]
+mod primary {
+    use arboard::{Clipboard, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+    use crate::error::{CommandResult, ErrorKind, TermillionError};
+
+    pub fn set(text: String) -> CommandResult<()> {
+        Clipboard::new()
+            .and_then(|mut clipboard| {
+                clipboard
+                    .set()
+                    .clipboard(LinuxClipboardKind::Primary)
+                    .text(text)
+            })
+            .map_err(|e| {
+                TermillionError::new(
+                    ErrorKind::Io,
+                    format!("Failed to set primary selection: {}", e),
+                )
+            })
+    }
+
+    pub fn get() -> CommandResult<Option<String>> {
+        let result = Clipboard::new().and_then(|mut clipboard| {
+            clipboard
+                .get()
+                .clipboard(LinuxClipboardKind::Primary)
+                .text()
+        });
+        match result {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(TermillionError::new(
+                ErrorKind::Io,
+                format!("Failed to read primary selection: {}", e),
+            )),
+        }
+    }
+}
+
+// Other platforms have no primary selection
+#[cfg(not(target_os = "linux"))]
+mod primary {
+    use crate::error::CommandResult;
+
+    pub fn set(_text: String) -> CommandResult<()> {
+        Ok(())
+    }
+
+    pub fn get() -> CommandResult<Option<String>> {
+        Ok(None)
+    }
+}
+
+// Put selected text in the X11/Wayland primary selection, does nothing
+// unless `terminal.selection.primary_selection` is on
+#[tauri::command]
+pub async fn set_primary_selection(app: AppHandle, text: String) -> CommandResult<()> {
+    if !primary_selection_enabled(&app)? {
+        return Ok(());
+    }
+    primary::set(text)
+}
+
+// Text in the primary selection for a middle-click paste, none when it is
+// empty, turned off or not supported
+#[tauri::command]
+pub async fn get_primary_selection(app: AppHandle) -> CommandResult<Option<String>> {
+    if !primary_selection_enabled(&app)? {
+        return Ok(None);
+    }
+    primary::get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trims_trailing_whitespace() {
+        let config = SelectionConfig::default();
+        let processed = process("ls -la   \r\nfoo\t\n\n  \n", &config);
+        assert_eq!(processed.lines().collect::<Vec<_>>(), vec!["ls -la", "foo"]);
+    }
+}
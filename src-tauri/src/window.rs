@@ -0,0 +1,114 @@
+use tauri::{
+    AppHandle, LogicalPosition, LogicalSize, Manager, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder,
+};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::dropdown::DROPDOWN_LABEL;
+
+// Whether the window-state plugin has a saved size and position to restore
+fn has_saved_state(app: &AppHandle) -> bool {
+    app.path()
+        .app_config_dir()
+        .map(|dir| {
+            dir.join(tauri_plugin_window_state::DEFAULT_FILENAME)
+                .exists()
+        })
+        .unwrap_or(false)
+}
+
+/// Apply the configured geometry and startup state to a newly opened window.
+/// The window-state plugin restores the last size first, the configured size
+/// only replaces it on first launch or when `remember_size` is off.
+pub fn apply_startup_config(window: &WebviewWindow, config: &Config) -> tauri::Result<()> {
+    let window_config = &config.window;
+    let remember_size = window_config.remember_size.unwrap_or(true);
+
+    if !remember_size || !has_saved_state(window.app_handle()) {
+        if let (Some(width), Some(height)) = (window_config.width, window_config.height) {
+            window.set_size(LogicalSize::new(width, height))?;
+        }
+        match window_config.position {
+            Some(position) => window.set_position(LogicalPosition::new(position.x, position.y))?,
+            None => window.center()?,
+        }
+    }
+
+    if let Some(always_on_top) = window_config.always_on_top {
+        window.set_always_on_top(always_on_top)?;
+    }
+
+    let startup = config.startup.clone().unwrap_or_default();
+    if startup.start_fullscreen {
+        window.set_fullscreen(true)?;
+    } else if startup.start_maximized {
+        window.maximize()?;
+    }
+
+    Ok(())
+}
+
+/// Windows opened after the main one are labelled `terminal-<id>`
+const WINDOW_LABEL_PREFIX: &str = "terminal-";
+
+/// The focused terminal window, or the main one when the app is in the background
+pub fn target_window(app: &AppHandle) -> Option<WebviewWindow> {
+    app.webview_windows()
+        .into_values()
+        .find(|w| w.label() != DROPDOWN_LABEL && w.is_focused().unwrap_or(false))
+        .or_else(|| app.get_webview_window("main"))
+}
+
+/// Unminimize, show and focus a window
+pub fn bring_to_front(window: &WebviewWindow) -> Result<(), String> {
+    window
+        .unminimize()
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show window: {}", e))
+}
+
+/// A fresh label for a terminal window
+pub fn new_window_label() -> String {
+    format!("{}{}", WINDOW_LABEL_PREFIX, Uuid::new_v4().simple())
+}
+
+/// Open another terminal window and return its label
+pub fn open_window(app: &AppHandle) -> Result<String, String> {
+    let label = new_window_label();
+    open_window_with_label(app, &label)?;
+    Ok(label)
+}
+
+/// Open a terminal window under a label from `new_window_label`
+pub fn open_window_with_label(app: &AppHandle, label: &str) -> Result<WebviewWindow, String> {
+    let window = WebviewWindowBuilder::new(app, label, WebviewUrl::default())
+        .title("termillion")
+        .inner_size(800.0, 600.0)
+        .decorations(false)
+        .build()
+        .map_err(|e| format!("Failed to create window: {}", e))?;
+
+    let config = Config::load(app)?;
+    apply_startup_config(&window, &config)
+        .map_err(|e| format!("Failed to apply window config: {}", e))?;
+    Ok(window)
+}
+
+// Open another terminal window and return its label
+#[tauri::command]
+pub async fn create_window(app: AppHandle) -> Result<String, String> {
+    open_window(&app)
+}
+
+// Close a window, its PTYs are destroyed once the window is gone
+#[tauri::command]
+pub async fn close_window(app: AppHandle, label: String) -> Result<(), String> {
+    let window = app
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("Window {} not found", label))?;
+    window
+        .close()
+        .map_err(|e| format!("Failed to close window: {}", e))
+}
@@ -3,6 +3,11 @@ export interface FontConfig {
   fallback_family: string;
   size: number;
   line_height: number;
+  ligatures?: boolean;
+  font_weight?: number;
+  bold_font_family?: string;
+  italic_font_family?: string;
+  features?: Record<string, boolean>;
 }
 
 export interface ThemeConfig {
@@ -39,17 +44,41 @@ export interface PaddingConfig {
   y: number;
 }
 
+export interface CursorConfig {
+  style: "block" | "bar" | "underline";
+  blink: boolean;
+  blink_interval_ms?: number;
+  unfocused_style?: "outline" | "block" | "bar" | "underline" | "none";
+}
+
+export interface SelectionConfig {
+  word_separators: string;
+  copy_on_select: boolean;
+  trim_trailing_whitespace_on_copy: boolean;
+  primary_selection?: boolean | null;
+}
+
 export interface TerminalSettings {
   scrollback?: number;
+  scrollback_spill?: boolean | null;
+  scrollback_spill_mb?: number | null;
+  coalesce_frames_ms?: number | null;
+  compress_min_bytes?: number | null;
   padding?: PaddingConfig;
+  cursor?: CursorConfig;
+  selection?: SelectionConfig;
 }
 
 export interface Profile {
   name: string;
   command: string;
   args?: string[];
+  // Provider that generated the profile, unset for the config file's own
+  source?: string | null;
+  elevated?: boolean | null;
   font?: FontConfig;
   theme?: ThemeConfig;
+  terminal?: TerminalSettings;
 }
 
 export interface Profiles {
@@ -63,19 +92,11 @@ export interface Shortcut {
   shift: boolean;
   alt: boolean;
   meta: boolean;
+  send_text?: string;
 }
 
-export interface KeyboardShortcuts {
-  new_tab: Shortcut;
-  close_tab: Shortcut;
-  split_vertical: Shortcut;
-  split_horizontal: Shortcut;
-  focus_next_pane: Shortcut;
-  focus_previous_pane: Shortcut;
-  close_pane: Shortcut;
-  reload_config: Shortcut;
-  show_profiles: Shortcut;
-}
+// Maps action names (new_tab, split_vertical, ...) to their shortcut
+export type KeyboardShortcuts = Record<string, Shortcut>;
 
 export interface TabStyle {
   background_color: string;
@@ -112,6 +133,51 @@ export interface WindowConfig {
   titlebar_background: string;
   interactive: InteractiveElementStyle;
   tabs: WindowTabsStyle;
+  width?: number | null;
+  height?: number | null;
+  position?: { x: number; y: number } | null;
+  always_on_top?: boolean | null;
+  remember_size?: boolean | null;
+  close_ptys?: "destroy" | "detach" | null;
+  confirm_close_processes?: string[] | null;
+  title_format?: string | null;
+}
+
+export interface StartupConfig {
+  restore_previous_session: boolean;
+  default_profile?: string | null;
+  default_cwd?: string | null;
+  start_maximized: boolean;
+  start_fullscreen: boolean;
+}
+
+export interface PtyDecoration {
+  color?: string | null;
+  icon?: string | null;
+}
+
+export interface SessionTab {
+  profile?: string | null;
+  cwd?: string | null;
+  title?: string | null;
+  command?: string | null;
+  args?: string[] | null;
+  task?: string | null;
+  decoration?: PtyDecoration | null;
+  pty_id?: string;
+}
+
+export interface WindowBounds {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface Session {
+  tabs: SessionTab[];
+  active: number;
+  bounds?: WindowBounds | null;
 }
 
 export interface Config {
@@ -123,4 +189,37 @@ export interface Config {
   profiles: Profiles;
   shortcuts: KeyboardShortcuts;
   window: WindowConfig;
+  startup?: StartupConfig | null;
+}
+
+// Suggested change, a null value removes the key
+export interface ValidationFix {
+  path: string;
+  value: unknown | null;
+}
+
+// Findings of validate_config, errors should block saving
+export interface ValidationError {
+  severity: "error" | "warning" | "info";
+  code: string;
+  component: string;
+  path: string;
+  message: string;
+  fix: ValidationFix | null;
+}
+
+// What config and PTY commands reject with
+export interface TermillionError {
+  kind:
+    | "not_found"
+    | "permission_denied"
+    | "invalid_input"
+    | "config_parse"
+    | "io"
+    | "pty"
+    | "internal";
+  message: string;
+  // What the error is about, like a PTY ID or a file path
+  context: string | null;
+  recoverable: boolean;
 }
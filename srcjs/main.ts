@@ -1,109 +1,222 @@
-import { getCurrentWindow } from "@tauri-apps/api/window";
-import { invoke } from "@tauri-apps/api/core";
-import "./styles/window-controls.css";
-import "./styles/terminal.css";
-import "./styles/tabs.css";
-import "@xterm/xterm/css/xterm.css";
-import { TabManager } from "./terminal/TabManager";
-import type { Config } from "./config";
-import { ShortcutManager } from "./utils/ShortcutManager";
-
-class App {
-  private tabManager: TabManager | null = null;
-  private appWindow = getCurrentWindow();
-  private shortcutManager!: ShortcutManager;
-
-  constructor() {
-    this.initializeApp();
-  }
-
-  private async initializeApp() {
-    // Initialize window controls
-    new WindowControls();
-
-    // Load config
-    const config = await invoke<Config>("get_config");
-    window.__config = config; // Make config available globally
-
-    // Initialize shortcut manager
-    this.shortcutManager = ShortcutManager.getInstance(config);
-
-    // Initialize tab manager
-    this.tabManager = new TabManager(config);
-
-    // Register shortcuts
-    this.registerShortcuts();
-
-    // Create first tab
-    await this.tabManager.createFirstTab();
-
-    // Focus window after initialization
-    await this.appWindow.setFocus();
-  }
-
-  private registerShortcuts(): void {
-    // Get the new tab shortcut from config
-    const newTabShortcut = this.shortcutManager.getShortcutConfig("new_tab");
-
-    const closeTabShortcut =
-      this.shortcutManager.getShortcutConfig("close_tab");
-
-    // Register the new tab shortcut
-    this.shortcutManager.registerShortcut(newTabShortcut, () => {
-      this.tabManager?.createTab();
-    });
-
-    // Register the close tab shortcut
-    this.shortcutManager.registerShortcut(closeTabShortcut, () => {
-      const activeTab = this.tabManager?.getActiveTab();
-      const totalTabs = this.tabManager?.getTotalTabs();
-      console.log("Closing tab", activeTab);
-      if (activeTab && totalTabs && totalTabs > 1) {
-        this.tabManager?.closeTab(activeTab.id);
-      }
-    });
-  }
-}
-
-class WindowControls {
-  private appWindow = getCurrentWindow();
-
-  constructor() {
-    this.initializeControls();
-  }
-
-  private initializeControls() {
-    const minimizeBtn = document.querySelector(
-      '[data-window-control="minimize"]'
-    );
-    const maximizeBtn = document.querySelector(
-      '[data-window-control="maximize"]'
-    );
-    const closeBtn = document.querySelector('[data-window-control="close"]');
-
-    minimizeBtn?.addEventListener("click", () => this.appWindow.minimize());
-    maximizeBtn?.addEventListener("click", this.handleMaximize.bind(this));
-    closeBtn?.addEventListener("click", () => this.appWindow.close());
-  }
-
-  private async handleMaximize() {
-    const isMaximized = await this.appWindow.isMaximized();
-    if (isMaximized) {
-      this.appWindow.unmaximize();
-    } else {
-      this.appWindow.maximize();
-    }
-  }
-}
-
-// Add config to window object for global access
-declare global {
-  interface Window {
-    __config: Config;
-  }
-}
-
-// Initialize app
-document.addEventListener("DOMContentLoaded", () => {
-  new App();
-});
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { invoke } from "@tauri-apps/api/core";
+import { openUrl } from "@tauri-apps/plugin-opener";
+import "./styles/window-controls.css";
+import "./styles/terminal.css";
+import "./styles/tabs.css";
+import "@xterm/xterm/css/xterm.css";
+import { TabManager } from "./terminal/TabManager";
+import type { Config, Session, SessionTab } from "./config";
+import { ShortcutManager } from "./utils/ShortcutManager";
+import { handleTransfer, type PtyTransfer } from "./terminal/transfer";
+
+const ISSUES_URL = "https://github.com/fosslife/termillion/issues/new";
+
+interface CrashReport {
+  time: string;
+  app_version: string;
+  config_version: number | null;
+  thread: string | null;
+  message: string;
+  location: string | null;
+  backtrace: string;
+  recent_log: string[];
+}
+
+class App {
+  private tabManager: TabManager | null = null;
+  private appWindow = getCurrentWindow();
+  private shortcutManager!: ShortcutManager;
+
+  constructor() {
+    this.initializeApp();
+  }
+
+  private async initializeApp() {
+    // Initialize window controls
+    new WindowControls();
+
+    // Load config
+    const config = await invoke<Config>("get_config");
+    window.__config = config; // Make config available globally
+
+    // Initialize shortcut manager
+    this.shortcutManager = ShortcutManager.getInstance(config);
+
+    // Initialize tab manager
+    this.tabManager = new TabManager(config);
+
+    // Register shortcuts
+    this.registerShortcuts();
+
+    // Tabs opened from the tray menu
+    this.appWindow.listen<string>("open-tab", (event) => {
+      this.tabManager?.createTab(event.payload);
+    });
+
+    // Tabs for ssh:// and sftp:// links opened while the app runs
+    this.appWindow.listen<SessionTab>("open-session-tab", (event) => {
+      const tab = event.payload;
+      this.tabManager?.createTab(
+        tab.profile ?? undefined,
+        tab.cwd ?? undefined,
+        tab.title ?? undefined,
+        tab.command
+          ? { command: tab.command, args: tab.args ?? undefined }
+          : undefined,
+        tab.task ?? undefined
+      );
+    });
+
+    // Tabs of tasks run again, focused or closed to be restarted
+    this.appWindow.listen<string>("focus-pty", (event) => {
+      this.tabManager?.focusTerminal(event.payload);
+    });
+    this.appWindow.listen<string>("close-pty", (event) => {
+      this.tabManager?.closeTerminal(event.payload);
+    });
+
+    // Tabs moved between windows
+    this.appWindow.listen<PtyTransfer>("pty-transferred", (event) => {
+      if (this.tabManager) {
+        handleTransfer(event.payload, this.appWindow.label, this.tabManager);
+      }
+    });
+
+    // Named sessions restored into this window
+    this.appWindow.listen<Session>("restore-session", (event) => {
+      this.tabManager?.restoreSession(event.payload);
+    });
+
+    // Create first tab
+    await this.tabManager.createFirstTab();
+
+    // Focus window after initialization
+    await this.appWindow.setFocus();
+
+    if (this.appWindow.label === "main") {
+      this.offerCrashReport();
+    }
+  }
+
+  // After a crash, offer to open an issue with the report filled in
+  private async offerCrashReport(): Promise<void> {
+    const report = await invoke<CrashReport | null>("get_last_crash_report");
+    if (!report) {
+      return;
+    }
+    const where = report.location ? ` at ${report.location}` : "";
+    if (
+      !confirm(
+        `termillion crashed last time: ${report.message}${where}\n\nReport this issue?`
+      )
+    ) {
+      return;
+    }
+    const body = [
+      `**Version:** ${report.app_version} (config version ${report.config_version ?? "unknown"})`,
+      `**Thread:** ${report.thread ?? "unknown"}`,
+      `**Panic:** ${report.message}${where}`,
+      "",
+      "<details><summary>Backtrace</summary>",
+      "",
+      "```",
+      report.backtrace,
+      "```",
+      "</details>",
+      "",
+      "<details><summary>Recent log</summary>",
+      "",
+      "```",
+      ...report.recent_log,
+      "```",
+      "</details>",
+    ].join("\n");
+    const url = new URL(ISSUES_URL);
+    url.searchParams.set("title", `Crash: ${report.message}`);
+    // Long backtraces make the URL too long for GitHub
+    url.searchParams.set("body", body.slice(0, 6000));
+    await openUrl(url.toString());
+  }
+
+  private registerShortcuts(): void {
+    // Get the new tab shortcut from config
+    const newTabShortcut = this.shortcutManager.getShortcutConfig("new_tab");
+
+    const closeTabShortcut =
+      this.shortcutManager.getShortcutConfig("close_tab");
+
+    // Register the new tab shortcut
+    this.shortcutManager.registerShortcut(newTabShortcut, () => {
+      this.tabManager?.createTab();
+    });
+
+    // Register the close tab shortcut
+    this.shortcutManager.registerShortcut(closeTabShortcut, () => {
+      const activeTab = this.tabManager?.getActiveTab();
+      const totalTabs = this.tabManager?.getTotalTabs();
+      console.log("Closing tab", activeTab);
+      if (activeTab && totalTabs && totalTabs > 1) {
+        this.tabManager?.closeTab(activeTab.id);
+      }
+    });
+
+    // Send-text macros are expanded and written by the backend
+    const shortcuts = window.__config.shortcuts;
+    for (const [action, shortcut] of Object.entries(shortcuts)) {
+      if (!shortcut.send_text) continue;
+
+      const combo = this.shortcutManager.getShortcutConfig(action);
+      this.shortcutManager.registerShortcut(combo, () => {
+        const ptyId = this.tabManager?.getActiveTab()?.terminalId;
+        if (!ptyId) return;
+        invoke("execute_action", { actionName: action, ptyId }).catch((e) =>
+          console.error(`Failed to run action ${action}:`, e)
+        );
+      });
+    }
+  }
+}
+
+class WindowControls {
+  private appWindow = getCurrentWindow();
+
+  constructor() {
+    this.initializeControls();
+  }
+
+  private initializeControls() {
+    const minimizeBtn = document.querySelector(
+      '[data-window-control="minimize"]'
+    );
+    const maximizeBtn = document.querySelector(
+      '[data-window-control="maximize"]'
+    );
+    const closeBtn = document.querySelector('[data-window-control="close"]');
+
+    minimizeBtn?.addEventListener("click", () => this.appWindow.minimize());
+    maximizeBtn?.addEventListener("click", this.handleMaximize.bind(this));
+    closeBtn?.addEventListener("click", () => this.appWindow.close());
+  }
+
+  private async handleMaximize() {
+    const isMaximized = await this.appWindow.isMaximized();
+    if (isMaximized) {
+      this.appWindow.unmaximize();
+    } else {
+      this.appWindow.maximize();
+    }
+  }
+}
+
+// Add config to window object for global access
+declare global {
+  interface Window {
+    __config: Config;
+  }
+}
+
+// Initialize app
+document.addEventListener("DOMContentLoaded", () => {
+  new App();
+});
@@ -1,18 +1,27 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Config, Profile } from "../config";
+import type { Config, Profile, PtyDecoration, Session } from "../config";
 import { TerminalManager } from "./TerminalManager";
 import { EventBus } from "../utils/EventBus";
 import { ProfileManager } from "./ProfileManager";
 import { platform } from "@tauri-apps/plugin-os";
+import {
+  listDetachedPtys,
+  transferPty,
+  type PtyTransfer,
+  type TransferTarget,
+} from "./transfer";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 
 export interface Tab {
   id: string;
   title: string;
   terminalId: string;
   active: boolean;
+  profile?: string;
+  decoration?: PtyDecoration;
 }
 
-export class TabManager {
+export class TabManager implements TransferTarget {
   private tabs: Tab[] = [];
   private terminalManager: TerminalManager;
   private tabsList: HTMLElement | null = null;
@@ -33,6 +42,29 @@ export class TabManager {
       this.closeProfileMenu();
     });
 
+    // Tab colors and icons, from the profile or set later
+    EventBus.getInstance().on(
+      "terminal:decoration",
+      (event: { tabId: string; decoration: PtyDecoration }) => {
+        const tab = this.tabs.find((t) => t.id === event.tabId);
+        if (tab) {
+          tab.decoration = event.decoration;
+          this.updateTabsUI();
+        }
+      }
+    );
+
+    // Profiles that restart on exit give the tab a new PTY
+    EventBus.getInstance().on(
+      "terminal:restarted",
+      (event: { tabId: string; ptyId: string }) => {
+        const tab = this.tabs.find((t) => t.id === event.tabId);
+        if (tab) {
+          tab.terminalId = event.ptyId;
+        }
+      }
+    );
+
     // Listen for number key events
     EventBus.getInstance().on("numberKeyPressed", (index: number) => {
       this.handleNumberKey(index);
@@ -200,6 +232,10 @@ export class TabManager {
       const tabElement = document.createElement("div");
       tabElement.className = `tab ${tab.active ? "active" : ""}`;
       tabElement.dataset.tabId = tab.id;
+      if (tab.decoration?.color) {
+        tabElement.classList.add("decorated");
+        tabElement.style.setProperty("--tab-color", tab.decoration.color);
+      }
 
       // Add click handler for tab switching
       tabElement.addEventListener("click", (e) => {
@@ -216,6 +252,13 @@ export class TabManager {
         }
       });
 
+      if (tab.decoration?.icon) {
+        const iconElement = document.createElement("span");
+        iconElement.className = "tab-icon";
+        iconElement.textContent = tab.decoration.icon;
+        tabElement.appendChild(iconElement);
+      }
+
       // Title part
       const titleElement = document.createElement("span");
       titleElement.textContent = tab.title;
@@ -243,6 +286,26 @@ export class TabManager {
     });
 
     console.log("Tabs UI update complete");
+
+    // Every tab change ends up here, keep the backend's session in sync
+    this.syncSession();
+  }
+
+  private syncSession(): void {
+    const session: Session = {
+      tabs: this.tabs.map((tab) => ({
+        profile: tab.profile,
+        title: tab.title,
+        pty_id: tab.terminalId,
+      })),
+      active: Math.max(
+        this.tabs.findIndex((tab) => tab.active),
+        0
+      ),
+    };
+    invoke("update_session", { session }).catch((e) =>
+      console.error("Failed to update session:", e)
+    );
   }
 
   async createFirstTab(): Promise<void> {
@@ -256,24 +319,45 @@ export class TabManager {
       let command: string | undefined;
       let args: string[] | undefined;
 
-      // Check if we have a default profile
+      // The backend decides which tabs to open: the previous session or
+      // the startup profile
+      const session = await invoke<Session>("get_startup_session").catch(
+        (e) => {
+          console.error("Failed to get startup session:", e);
+          return null;
+        }
+      );
+      const [firstTab, ...restoredTabs] = session?.tabs ?? [];
+      const profileName =
+        firstTab?.profile ?? this.config.profiles?.default ?? undefined;
+
+      // Check if we have a startup profile
+      let selectedProfile: string | undefined;
       if (this.config.profiles) {
         const defaultProfile = this.config.profiles.list.find(
-          (p) => p.name === this.config.profiles?.default
+          (p) => p.name === profileName
         );
         if (defaultProfile) {
           shellName = defaultProfile.name;
           command = defaultProfile.command;
           args = defaultProfile.args ?? undefined;
+          selectedProfile = defaultProfile.name;
         }
       }
 
+      // A command given with --execute replaces the profile's shell
+      if (firstTab?.command) {
+        command = firstTab.command;
+        args = firstTab.args ?? undefined;
+      }
+
       // Create a new tab object
       const tab: Tab = {
         id,
-        title: shellName,
+        title: firstTab?.title ?? shellName,
         terminalId: id, // Initially use the tab ID as the terminal ID
         active: true, // First tab is active by default
+        profile: selectedProfile,
       };
 
       // Add the tab to our list
@@ -301,7 +385,11 @@ export class TabManager {
       console.log(`Created terminal for first tab ${id}`);
 
       // Mount terminal with profile
-      await terminal.mount(terminalContainer, command, args);
+      await terminal.mount(terminalContainer, command, args, {
+        profile: selectedProfile,
+        cwd: firstTab?.cwd ?? undefined,
+        decoration: firstTab?.decoration ?? undefined,
+      });
       console.log(`Mounted terminal for first tab ${id}`);
 
       // Update the tab with the actual PTY ID
@@ -322,6 +410,22 @@ export class TabManager {
       // Update the UI
       this.updateTabsUI();
       console.log(`First tab ${id} creation complete`);
+
+      // Reopen the rest of a restored session
+      for (const restored of restoredTabs) {
+        await this.createTab(
+          restored.profile ?? undefined,
+          restored.cwd ?? undefined,
+          restored.title ?? undefined,
+          undefined,
+          undefined,
+          restored.decoration ?? undefined
+        );
+      }
+      const activeTab = this.tabs[session?.active ?? 0];
+      if (restoredTabs.length > 0 && activeTab) {
+        this.switchTab(activeTab.id);
+      }
     } catch (error) {
       console.error("Error creating first tab:", error);
       // If there was an error, try to create a simple tab as a fallback
@@ -397,7 +501,15 @@ export class TabManager {
     return true;
   }
 
-  async createTab(profileName?: string): Promise<void> {
+  async createTab(
+    profileName?: string,
+    cwd?: string,
+    title?: string,
+    program?: { command: string; args?: string[] },
+    task?: string,
+    decoration?: PtyDecoration,
+    ptyId?: string
+  ): Promise<void> {
     try {
       if (profileName && !this.validateProfile(profileName)) {
         console.warn(`Invalid profile: ${profileName}`);
@@ -412,6 +524,7 @@ export class TabManager {
       let shellName: string;
       let command: string | undefined;
       let args: string[] | undefined;
+      let selectedProfile: string | undefined;
 
       if (profileName && this.config.profiles) {
         const profile = this.config.profiles.list.find(
@@ -421,6 +534,7 @@ export class TabManager {
           shellName = profile.name;
           command = profile.command;
           args = profile.args ?? undefined;
+          selectedProfile = profileName;
         } else {
           shellName = await this.getDefaultShellName();
         }
@@ -428,12 +542,20 @@ export class TabManager {
         shellName = await this.getDefaultShellName();
       }
 
+      // A program to run instead of the shell, like ssh for an ssh:// link
+      if (program) {
+        shellName = program.command;
+        command = program.command;
+        args = program.args;
+      }
+
       // Create a new tab object
       const tab: Tab = {
         id,
-        title: shellName,
+        title: title ?? shellName,
         terminalId: id, // Initially use the tab ID as the terminal ID
         active: false, // Will be set to active when we switch to it
+        profile: selectedProfile,
       };
 
       // Add the tab to our list
@@ -457,7 +579,13 @@ export class TabManager {
       console.log(`Created terminal for tab ${id}`);
 
       // Mount the terminal
-      await terminal.mount(terminalContainer, command, args);
+      await terminal.mount(terminalContainer, command, args, {
+        profile: selectedProfile,
+        cwd,
+        task,
+        decoration,
+        ptyId,
+      });
       console.log(`Mounted terminal for tab ${id}`);
 
       // Update the tab with the actual PTY ID
@@ -476,6 +604,96 @@ export class TabManager {
     }
   }
 
+  // Switch to the tab showing a PTY
+  focusTerminal(ptyId: string): void {
+    const tab = this.tabs.find((t) => t.terminalId === ptyId);
+    if (tab) {
+      this.switchTab(tab.id);
+    }
+  }
+
+  // Close the tab showing a PTY, unless it's the last one
+  async closeTerminal(ptyId: string): Promise<void> {
+    const tab = this.tabs.find((t) => t.terminalId === ptyId);
+    if (tab) {
+      await this.closeTab(tab.id);
+    }
+  }
+
+  // Show a PTY moved here from another window in a new tab
+  async adoptTerminal(transfer: PtyTransfer): Promise<void> {
+    const profile = this.config.profiles?.list.some(
+      (p) => p.name === transfer.profile
+    )
+      ? transfer.profile ?? undefined
+      : undefined;
+    await this.createTab(
+      profile,
+      undefined,
+      transfer.title ?? undefined,
+      undefined,
+      undefined,
+      undefined,
+      transfer.ptyId
+    );
+  }
+
+  // Drop the tab of a PTY moved to another window, the PTY lives on there
+  async releaseTerminal(ptyId: string): Promise<void> {
+    const tab = this.tabs.find((t) => t.terminalId === ptyId);
+    if (tab) {
+      await this.terminalManager.releaseTerminal(tab.id);
+      await this.closeTab(tab.id, true);
+    }
+  }
+
+  // Replace the open tabs with the ones of a restored session
+  async restoreSession(session: Session): Promise<void> {
+    const previousTabs = this.tabs.map((tab) => tab.id);
+    for (const restored of session.tabs) {
+      await this.createTab(
+        restored.profile ?? undefined,
+        restored.cwd ?? undefined,
+        restored.title ?? undefined,
+        undefined,
+        undefined,
+        restored.decoration ?? undefined
+      );
+    }
+    if (this.tabs.length === previousTabs.length) {
+      console.warn("Restored session has no tabs, keeping the current ones");
+      return;
+    }
+    for (const id of previousTabs) {
+      await this.closeTab(id, true);
+    }
+    const activeTab = this.tabs[session.active];
+    if (activeTab) {
+      this.switchTab(activeTab.id);
+    }
+  }
+
+  // Whether a tab may close, asking the user when programs from
+  // `window.confirm_close_processes` run in it
+  private async confirmClose(tab: Tab): Promise<boolean> {
+    let running: string[];
+    try {
+      running = await invoke<string[]>("get_blocking_processes", {
+        ptyId: tab.terminalId,
+      });
+    } catch (error) {
+      // The PTY may be gone already, nothing to lose then
+      console.warn(`Could not list processes of tab ${tab.id}:`, error);
+      return true;
+    }
+    if (running.length === 0) {
+      return true;
+    }
+    return confirm(
+      `${running.join(", ")} still running in "${tab.title}".\n\nClose the tab anyway?`
+    );
+  }
+
   async closeTab(tabId: string, forceClose: boolean = false): Promise<void> {
     console.log(
       `Closing tab ${tabId}, forceClose=${forceClose}, tabCount=${this.tabs.length}`
@@ -499,6 +717,11 @@ export class TabManager {
       `Found tab at index ${tabIndex}, id=${tab.id}, terminalId=${tab.terminalId}`
     );
 
+    // Ask first when an editor, ssh session or the like still runs in it
+    if (!forceClose && !(await this.confirmClose(tab))) {
+      return;
+    }
+
     // Store whether this tab was active before removing it
     const wasActive = tab.active;
 
@@ -705,6 +928,28 @@ export class TabManager {
       menu.appendChild(item);
     });
 
+    // Shells of closed windows, still running until a window takes them over
+    const detached = await listDetachedPtys().catch((error) => {
+      console.error("Failed to list detached PTYs:", error);
+      return [];
+    });
+    for (const pty of detached) {
+      const item = document.createElement("div");
+      item.className = "profile-item";
+      item.textContent = `Reattach ${pty.title ?? pty.shell}`;
+      item.addEventListener("click", () => {
+        transferPty(pty.id, getCurrentWindow().label).catch(console.error);
+        this.closeProfileMenu();
+      });
+      item.addEventListener("mouseenter", () => {
+        item.style.backgroundColor = "rgba(255, 255, 255, 0.05)";
+      });
+      item.addEventListener("mouseleave", () => {
+        item.style.backgroundColor = "";
+      });
+      menu.appendChild(item);
+    }
+
     // Add management button
     const manageItem = document.createElement("div");
     manageItem.className = "profile-item manage";
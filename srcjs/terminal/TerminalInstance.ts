@@ -8,18 +8,28 @@ import { WebLinksAddon } from "@xterm/addon-web-links";
 import { WebglAddon } from "@xterm/addon-webgl";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { homeDir } from "@tauri-apps/api/path";
-import type { Config } from "../config";
+import type { Config, PtyDecoration, TerminalSettings } from "../config";
 import { EventBus } from "../utils/EventBus";
+import { applyFontConfig, needsDomRenderer } from "./fonts";
+import { decompressLz4 } from "./lz4";
+import { attachPty } from "./transfer";
 import { Channel } from "@tauri-apps/api/core";
 import { writeText, readText } from "@tauri-apps/plugin-clipboard-manager";
 
-// Define the PTY output event types to match the Rust backend
+// First byte of every frame on the output channel: raw output bytes, a
+// JSON encoded event or LZ4 compressed output follow
+const FRAME_OUTPUT = 0;
+const FRAME_EVENT = 1;
+const FRAME_OUTPUT_LZ4 = 2;
+const frameDecoder = new TextDecoder();
+
+// Pastes larger than this are streamed to the shell in chunks of this size
+// instead of sent in one IPC message
+const PASTE_STREAM_CHUNK = 64 * 1024;
+
+// Define the PTY output event types to match the Rust backend, output
+// arrives as raw frames instead
 type PtyOutputEvent =
-  | {
-      event: "output";
-      data: number[];
-    }
   | {
       event: "exit";
       data: {
@@ -32,16 +42,65 @@ type PtyOutputEvent =
         bytes_read: number;
         bytes_written: number;
         messages_sent: number;
+        dropped_bytes: number;
         uptime_ms: number;
       };
     }
   | {
       event: "bell";
     }
+  | {
+      event: "secureInputStarted" | "secureInputEnded";
+    }
+  | {
+      event: "progress";
+      data: {
+        state: "hidden" | "normal" | "error" | "indeterminate" | "paused";
+        percent: number | null;
+      };
+    }
   | {
       event: "title";
       data: {
         title: string;
+        raw: string;
+      };
+    }
+  | {
+      event: "banner";
+      data: {
+        text: string;
+      };
+    }
+  | {
+      event: "settings";
+      data: {
+        terminal: TerminalSettings;
+      };
+    }
+  | {
+      event: "decoration";
+      data: PtyDecoration;
+    }
+  | {
+      event: "foregroundChanged";
+      data: {
+        name: string;
+        pid: number;
+      };
+    }
+  | {
+      event: "elevationDenied";
+      data: {
+        reason: string;
+      };
+    }
+  | {
+      event: "exited";
+      data: {
+        code: number;
+        duration_ms: number;
+        restart: boolean;
       };
     };
 
@@ -54,6 +113,20 @@ interface PtyMetrics {
   throughputBps: number;
 }
 
+// A command found through the shell integration marks, offsets count bytes
+// of output
+interface CommandBlock {
+  id: number;
+  prompt_start: number;
+  command_start: number | null;
+  output_start: number | null;
+  end: number | null;
+  command: string | null;
+  exit_code: number | null;
+  started_at: number | null;
+  finished_at: number | null;
+}
+
 export class TerminalInstance {
   private xterm: XTerm | null = null;
   private fitAddon: FitAddon | null = null;
@@ -61,13 +134,24 @@ export class TerminalInstance {
   private tabId: string | null = null;
   private container: HTMLElement | null = null;
   private resizeObserver: ResizeObserver | null = null;
-  private outputChannel: Channel<PtyOutputEvent> | null = null;
+  private outputChannel: Channel<ArrayBuffer> | null = null;
   private focused: boolean = false;
   private isBeingDestroyed: boolean = false;
   private metrics: PtyMetrics | null = null;
   private lastMetricsUpdate: number = 0;
   private bellEnabled: boolean = true;
   private currentTitle: string = "";
+  private copyOnSelect: boolean = false;
+  private secureInput: boolean = false;
+  // Program in the foreground, the shell when nothing else runs
+  private foregroundProcess: string | null = null;
+  // Set once the shell exited and the terminal is kept for its output
+  private held: boolean = false;
+  private spawnArgs: {
+    command?: string;
+    args?: string[];
+    options?: Parameters<TerminalInstance["mount"]>[3];
+  } | null = null;
 
   constructor(
     private readonly config: Config,
@@ -85,7 +169,17 @@ export class TerminalInstance {
       bufferSize?: number;
       batchTimeoutMs?: number;
       metricsIntervalMs?: number;
+      coalesceFramesMs?: number;
+      compressMinBytes?: number;
       bellEnabled?: boolean;
+      profile?: string;
+      cwd?: string;
+      task?: string;
+      decoration?: PtyDecoration;
+      // Run the shell as administrator even if its profile doesn't
+      elevated?: boolean;
+      // A PTY moved here from another window, shown instead of starting one
+      ptyId?: string;
     }
   ): Promise<void> {
     if (this.xterm) return;
@@ -158,13 +252,13 @@ export class TerminalInstance {
     );
 
     const terminalOptions: ITerminalOptions & ITerminalInitOnlyOptions = {
-      cursorBlink: true,
-      cursorStyle: "block",
+      cursorBlink: this.config.terminal?.cursor?.blink ?? true,
+      cursorStyle: this.config.terminal?.cursor?.style ?? "block",
+      cursorInactiveStyle:
+        this.config.terminal?.cursor?.unfocused_style ?? "outline",
       fontSize: this.config.font.size ?? 14,
-      fontFamily:
-        this.config.font.family !== "monospace"
-          ? `${this.config.font.family}, ${this.config.font.fallback_family}`
-          : "monospace",
+      fontFamily: applyFontConfig(this.config.font),
+      fontWeight: this.config.font.font_weight ?? "normal",
       lineHeight: this.config.font.line_height ?? 1.2,
       theme: {
         background: this.config.theme.background ?? "#1a1b26",
@@ -178,10 +272,28 @@ export class TerminalInstance {
       cols: 100,
       allowTransparency: true,
       rightClickSelectsWord: true,
+      wordSeparator: this.config.terminal?.selection?.word_separators,
     };
 
     this.xterm = new XTerm(terminalOptions);
 
+    // Profiles can turn copy on select on or off once the PTY starts
+    this.copyOnSelect = this.config.terminal?.selection?.copy_on_select ?? false;
+    const primarySelection =
+      this.config.terminal?.selection?.primary_selection ?? false;
+    this.xterm.onSelectionChange(() => {
+      const selection = this.xterm?.getSelection();
+      if (!selection) return;
+      if (primarySelection) {
+        invoke("set_primary_selection", { text: selection }).catch(
+          console.error
+        );
+      }
+      if (this.copyOnSelect) {
+        this.copySelection(selection);
+      }
+    });
+
     // Add addons
     this.fitAddon = new FitAddon();
     this.xterm.loadAddon(this.fitAddon);
@@ -190,33 +302,47 @@ export class TerminalInstance {
     // Open terminal in container
     this.xterm.open(container);
 
-    // Try WebGL addon
-    try {
-      this.xterm.loadAddon(new WebglAddon());
-    } catch (e) {
-      console.warn("WebGL addon failed to load:", e);
+    // Try WebGL addon, unless the font settings need the DOM renderer
+    if (!needsDomRenderer(this.config.font)) {
+      try {
+        this.xterm.loadAddon(new WebglAddon());
+      } catch (e) {
+        console.warn("WebGL addon failed to load:", e);
+      }
     }
 
     // Ensure container is visible and sized before fitting
     await new Promise((resolve) => setTimeout(resolve, 0));
     this.fit();
 
-    // Create PTY with optional command and args
-    const cwd = await homeDir();
+    // Create PTY with optional command and args, the backend picks the
+    // directory unless one is given (e.g. when restoring a session)
+    const cwd = options?.cwd;
     console.log("Creating PTY with:", { cwd, command, args });
 
     // Create a channel for PTY output and exit events
-    this.outputChannel = new Channel<PtyOutputEvent>();
+    this.outputChannel = new Channel<ArrayBuffer>();
 
     // Set up channel message handler
-    this.outputChannel.onmessage = async (message: PtyOutputEvent) => {
+    this.outputChannel.onmessage = async (frame: ArrayBuffer) => {
       if (this.isBeingDestroyed) return;
 
-      if (message.event === "output") {
-        // Convert byte array to Uint8Array and write to terminal
-        const uint8Array = new Uint8Array(message.data);
-        this.xterm?.write(uint8Array);
-      } else if (message.event === "exit") {
+      const bytes = new Uint8Array(frame);
+      if (bytes[0] === FRAME_OUTPUT) {
+        // Write the output bytes straight to the terminal
+        this.xterm?.write(bytes.subarray(1));
+        return;
+      }
+      if (bytes[0] === FRAME_OUTPUT_LZ4) {
+        this.xterm?.write(decompressLz4(bytes.subarray(1)));
+        return;
+      }
+      if (bytes[0] !== FRAME_EVENT) return;
+      const message: PtyOutputEvent = JSON.parse(
+        frameDecoder.decode(bytes.subarray(1))
+      );
+
+      if (message.event === "exit") {
         console.log(
           `Terminal process exited with status: ${JSON.stringify(
             message.data.status
@@ -301,6 +427,11 @@ export class TerminalInstance {
             ptyId: this.ptyId,
           });
         }
+      } else if (message.event === "banner") {
+        // Banners are plain text, normalize newlines for xterm
+        this.xterm?.write(message.data.text.replace(/\r?\n/g, "\r\n") + "\r\n");
+      } else if (message.event === "settings") {
+        this.applyTerminalSettings(message.data.terminal);
       } else if (message.event === "title") {
         // Update the terminal title
         this.currentTitle = message.data.title;
@@ -310,32 +441,77 @@ export class TerminalInstance {
           ptyId: this.ptyId,
           title: this.currentTitle,
         });
+      } else if (
+        message.event === "secureInputStarted" ||
+        message.event === "secureInputEnded"
+      ) {
+        // Lets the tab show a lock and broadcast input skip this terminal
+        this.secureInput = message.event === "secureInputStarted";
+        this.container?.classList.toggle("secure-input", this.secureInput);
+        EventBus.getInstance().emit("terminal:secure-input", {
+          ptyId: this.ptyId,
+          active: this.secureInput,
+        });
+      } else if (message.event === "progress") {
+        // For tab progress bars, the taskbar is updated by the backend
+        EventBus.getInstance().emit("terminal:progress", {
+          ptyId: this.ptyId,
+          ...message.data,
+        });
+      } else if (message.event === "decoration") {
+        // Sent by tab since it can arrive before the tab knows the PTY's ID
+        EventBus.getInstance().emit("terminal:decoration", {
+          tabId: this.tabId,
+          decoration: message.data,
+        });
+      } else if (message.event === "foregroundChanged") {
+        // Lets tabs show what runs in them, like vim or ssh
+        this.foregroundProcess = message.data.name;
+        EventBus.getInstance().emit("terminal:foreground", {
+          ptyId: this.ptyId,
+          ...message.data,
+        });
+      } else if (message.event === "elevationDenied") {
+        // The exit follows, leave the reason in the terminal for the user
+        this.xterm?.write(`\r\n\x1b[31m${message.data.reason}\x1b[0m\r\n`);
+        EventBus.getInstance().emit("terminal:elevation-denied", {
+          ptyId: this.ptyId,
+          reason: message.data.reason,
+        });
+      } else if (message.event === "exited") {
+        // The profile holds or restarts the shell, its output stays
+        const { code, duration_ms, restart } = message.data;
+        const seconds = (duration_ms / 1000).toFixed(1);
+        this.xterm?.write(
+          `\r\n\x1b[2m[Process exited with code ${code} after ${seconds}s]\x1b[0m\r\n`
+        );
+        if (!restart) {
+          this.held = true;
+          return;
+        }
+        try {
+          await this.spawn();
+          EventBus.getInstance().emit("terminal:restarted", {
+            tabId: this.tabId,
+            ptyId: this.ptyId,
+          });
+        } catch (error) {
+          console.error("Failed to restart shell:", error);
+          this.held = true;
+        }
       }
     };
 
-    this.ptyId = await invoke<string>("create_pty", {
-      cwd,
-      rows: this.xterm.rows,
-      cols: this.xterm.cols,
-      command,
-      args,
-      outputChannel: this.outputChannel,
-      buffer_size: options?.bufferSize || 32768, // Default to 32KB for better performance
-      batch_timeout_ms: options?.batchTimeoutMs || 10, // Default to 10ms batch timeout
-      metrics_interval_ms: options?.metricsIntervalMs || 1000, // Default to 1 second metrics interval
-    });
-
-    console.log(
-      `Terminal created with ptyId=${this.ptyId}, tabId=${this.tabId}`
-    );
-
-    // Set the terminal ID and tab ID as data attributes on the container for debugging
-    container.dataset.ptyId = this.ptyId || "";
-    container.dataset.tabId = this.tabId || "";
+    this.spawnArgs = { command, args, options };
+    if (options?.ptyId) {
+      await this.attach(options.ptyId);
+    } else {
+      await this.spawn();
+    }
 
     // Set up event listeners
     this.xterm.onData((data) => {
-      if (this.ptyId && !this.isBeingDestroyed) {
+      if (this.ptyId && !this.isBeingDestroyed && !this.held) {
         invoke("write_pty", {
           ptyId: this.ptyId,
           data,
@@ -346,6 +522,21 @@ export class TerminalInstance {
     // Handle container clicks for focus
     container.addEventListener("click", this.handleClick);
 
+    // Middle click pastes the primary selection, like other Linux terminals
+    if (primarySelection) {
+      container.addEventListener("mousedown", (e: MouseEvent) => {
+        if (e.button !== 1) return;
+        e.preventDefault();
+        invoke<string | null>("get_primary_selection")
+          .then((text) => {
+            if (text && this.ptyId && !this.isBeingDestroyed) {
+              return this.paste(text);
+            }
+          })
+          .catch(console.error);
+      });
+    }
+
     // Set up resize observer
     this.resizeObserver = new ResizeObserver(() => {
       // Debounce resize events
@@ -373,11 +564,7 @@ export class TerminalInstance {
         event.key === "c" &&
         this.xterm?.hasSelection()
       ) {
-        const selection = this.xterm.getSelection();
-        // Use Tauri's clipboard plugin
-        writeText(selection).catch((err: Error) => {
-          console.error("Failed to copy to clipboard:", err);
-        });
+        this.copySelection(this.xterm.getSelection());
         return false; // Prevent default handling
       }
 
@@ -387,10 +574,7 @@ export class TerminalInstance {
         readText()
           .then((text: string) => {
             if (text && this.ptyId && !this.isBeingDestroyed) {
-              invoke("write_pty", {
-                ptyId: this.ptyId,
-                data: text,
-              }).catch(console.error);
+              this.paste(text).catch(console.error);
             }
           })
           .catch((err: Error) => {
@@ -448,10 +632,7 @@ export class TerminalInstance {
       addMenuItem("Copy", () => {
         const selection = this.xterm?.getSelection();
         if (selection) {
-          // Use Tauri's clipboard plugin
-          writeText(selection).catch((err: Error) => {
-            console.error("Failed to copy to clipboard:", err);
-          });
+          this.copySelection(selection);
         }
       });
 
@@ -461,10 +642,7 @@ export class TerminalInstance {
         readText()
           .then((text: string) => {
             if (text && this.ptyId && !this.isBeingDestroyed) {
-              invoke("write_pty", {
-                ptyId: this.ptyId,
-                data: text,
-              }).catch(console.error);
+              this.paste(text).catch(console.error);
             }
           })
           .catch((err: Error) => {
@@ -536,11 +714,15 @@ export class TerminalInstance {
         }
       });
 
-      // Resize the PTY
+      // Resize the PTY, with the text area's pixel size for image protocols
+      const screen = this.xterm.element?.querySelector(".xterm-screen");
+      const pixels = screen?.getBoundingClientRect();
       invoke("resize_pty", {
         ptyId: this.ptyId,
         rows: newRows,
         cols: newCols,
+        pixelWidth: pixels ? Math.round(pixels.width) : null,
+        pixelHeight: pixels ? Math.round(pixels.height) : null,
       }).catch((error) => {
         console.error("Error resizing PTY:", error);
       });
@@ -549,6 +731,65 @@ export class TerminalInstance {
     }
   }
 
+  // Start the PTY for the shell the terminal was mounted with, again when
+  // its profile restarts it on exit
+  private async spawn(): Promise<void> {
+    if (!this.xterm || !this.outputChannel || !this.spawnArgs) return;
+    const { command, args, options } = this.spawnArgs;
+    const cwd = options?.cwd;
+    const container = this.container;
+
+    const create = options?.elevated ? "create_elevated_pty" : "create_pty";
+    this.ptyId = await invoke<string>(create, {
+      cwd,
+      rows: this.xterm.rows,
+      cols: this.xterm.cols,
+      command,
+      args,
+      profile: options?.profile,
+      task: options?.task,
+      tabId: this.tabId,
+      decoration: options?.decoration,
+      outputChannel: this.outputChannel,
+      bufferSize: options?.bufferSize || 32768, // Default to 32KB for better performance
+      batchTimeoutMs: options?.batchTimeoutMs || 10, // Default to 10ms batch timeout
+      metricsIntervalMs: options?.metricsIntervalMs || 1000, // Default to 1 second metrics interval
+      coalesceFramesMs: options?.coalesceFramesMs, // Falls back to terminal.coalesce_frames_ms
+      compressMinBytes: options?.compressMinBytes, // Falls back to terminal.compress_min_bytes
+    });
+
+    console.log(
+      `Terminal created with ptyId=${this.ptyId}, tabId=${this.tabId}`
+    );
+
+    // Set the terminal ID and tab ID as data attributes on the container for debugging
+    if (container) {
+      container.dataset.ptyId = this.ptyId || "";
+      container.dataset.tabId = this.tabId || "";
+    }
+  }
+
+  // Show a PTY moved here from another window, its output comes to this
+  // terminal from now on
+  private async attach(ptyId: string): Promise<void> {
+    if (!this.outputChannel) return;
+    await attachPty(ptyId, this.outputChannel, this.tabId);
+    this.ptyId = ptyId;
+    if (this.container) {
+      this.container.dataset.ptyId = ptyId;
+      this.container.dataset.tabId = this.tabId || "";
+    }
+  }
+
+  // Let go of a PTY moved to another window, without destroying it
+  async release(): Promise<void> {
+    if (this.isBeingDestroyed) return;
+    this.isBeingDestroyed = true;
+    await this.cleanupResources();
+    this.ptyId = null;
+    this.container = null;
+  }
+
   async destroy(): Promise<void> {
     console.log(`Destroying terminal instance with ptyId=${this.ptyId}`);
 
@@ -603,6 +844,69 @@ export class TerminalInstance {
   }
 
   // Add a method to clean up resources without destroying the PTY
+  // Apply the settings resolved for this tab's profile, they can differ
+  // from the global ones used when the terminal was created
+  private applyTerminalSettings(settings: TerminalSettings): void {
+    if (!this.xterm) return;
+
+    if (settings.scrollback !== undefined) {
+      this.xterm.options.scrollback = settings.scrollback;
+    }
+    if (settings.cursor) {
+      this.xterm.options.cursorBlink = settings.cursor.blink;
+      this.xterm.options.cursorStyle = settings.cursor.style;
+      this.xterm.options.cursorInactiveStyle =
+        settings.cursor.unfocused_style ?? "outline";
+    }
+    if (settings.selection) {
+      this.xterm.options.wordSeparator = settings.selection.word_separators;
+      this.copyOnSelect = settings.selection.copy_on_select;
+    }
+    if (settings.padding && this.container) {
+      this.container.style.setProperty(
+        "--terminal-padding-x",
+        `${settings.padding.x}px`
+      );
+      this.container.style.setProperty(
+        "--terminal-padding-y",
+        `${settings.padding.y}px`
+      );
+      this.fit();
+    }
+  }
+
+  // Apply the backend selection rules before copying to the clipboard
+  private copySelection(selection: string): void {
+    invoke<string>("process_selection", { text: selection })
+      .catch(() => selection)
+      .then((text) => writeText(text))
+      .catch((err: Error) => {
+        console.error("Failed to copy to clipboard:", err);
+      });
+  }
+
+  private async paste(text: string): Promise<void> {
+    if (text.length <= PASTE_STREAM_CHUNK) {
+      await invoke("write_pty", { ptyId: this.ptyId, data: text });
+      return;
+    }
+    // Each chunk waits for the backend to take it, so the shell sets the
+    // pace. A failed chunk ends the stream on its own.
+    const streamId = await invoke<string>("begin_paste_stream", {
+      ptyId: this.ptyId,
+    });
+    let start = 0;
+    while (start < text.length && !this.isBeingDestroyed) {
+      let end = Math.min(start + PASTE_STREAM_CHUNK, text.length);
+      // Keep surrogate pairs in one chunk
+      const last = text.charCodeAt(end - 1);
+      if (last >= 0xd800 && last <= 0xdbff) end++;
+      await invoke("paste_chunk", { streamId, data: text.slice(start, end) });
+      start = end;
+    }
+    await invoke("end_paste", { streamId });
+  }
+
   private async cleanupResources(): Promise<void> {
     console.log(`Cleaning up resources for terminal with ptyId=${this.ptyId}`);
 
@@ -676,4 +980,30 @@ export class TerminalInstance {
   getTitle(): string {
     return this.currentTitle;
   }
+
+  // Name of the program running in the foreground, once the backend
+  // reported it
+  getForegroundProcess(): string | null {
+    return this.foregroundProcess;
+  }
+
+  // Whether the program in the terminal is reading a password
+  isSecureInput(): boolean {
+    return this.secureInput;
+  }
+
+  // Copy what the last finished command printed, as plain text
+  async copyLastCommandOutput(): Promise<void> {
+    if (!this.ptyId) return;
+    const blocks = await invoke<CommandBlock[]>("get_command_blocks", {
+      ptyId: this.ptyId,
+    });
+    const last = blocks.reverse().find((block) => block.end !== null);
+    if (!last) return;
+    const { output } = await invoke<{ output: string }>("get_command_output", {
+      ptyId: this.ptyId,
+      blockId: last.id,
+    });
+    await writeText(await invoke<string>("normalize_output", { text: output }));
+  }
 }
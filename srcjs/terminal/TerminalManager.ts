@@ -29,6 +29,15 @@ export class TerminalManager {
     }
   }
 
+  // Drop a terminal whose PTY moved to another window, the PTY lives on
+  async releaseTerminal(id: string): Promise<void> {
+    const terminal = this.terminals.get(id);
+    if (terminal) {
+      await terminal.release();
+      this.terminals.delete(id);
+    }
+  }
+
   public isTerminalFocused(terminalId: string): boolean {
     const terminal = this.terminals.get(terminalId);
     if (terminal) {
@@ -0,0 +1,65 @@
+import type { FontConfig } from "../config";
+
+// Family the configured regular, bold and italic fonts are registered under
+const FONT_ALIAS = "Termillion Mono";
+const STYLE_ID = "termillion-fonts";
+
+function quote(name: string): string {
+  return `"${name.replace(/["\\]/g, "\\$&")}"`;
+}
+
+function fontFace(src: string, weight: string, style: string): string {
+  return `@font-face {
+  font-family: ${quote(FONT_ALIAS)};
+  src: local(${quote(src)});
+  font-weight: ${weight};
+  font-style: ${style};
+}`;
+}
+
+// The WebGL renderer draws glyph by glyph, so ligatures and OpenType
+// features only show up with the DOM renderer
+export function needsDomRenderer(font: FontConfig): boolean {
+  return font.ligatures === true || Object.keys(font.features ?? {}).length > 0;
+}
+
+// Register the configured faces and ligature settings, returning the
+// font-family to give xterm
+export function applyFontConfig(font: FontConfig): string {
+  const rules: string[] = [];
+  let family =
+    font.family !== "monospace"
+      ? `${font.family}, ${font.fallback_family}`
+      : "monospace";
+
+  // xterm has no separate bold or italic family, so the faces are combined
+  // into one family and the browser picks between them
+  if (font.bold_font_family || font.italic_font_family) {
+    rules.push(fontFace(font.family, "normal", "normal"));
+    if (font.bold_font_family) {
+      rules.push(fontFace(font.bold_font_family, "bold", "normal"));
+    }
+    if (font.italic_font_family) {
+      rules.push(fontFace(font.italic_font_family, "normal", "italic"));
+    }
+    family = `${quote(FONT_ALIAS)}, ${family}`;
+  }
+
+  const features = Object.entries(font.features ?? {}).map(
+    ([tag, enabled]) => `${quote(tag)} ${enabled ? 1 : 0}`
+  );
+  rules.push(`.xterm-rows {
+  font-variant-ligatures: ${font.ligatures ? "normal" : "none"};
+  font-feature-settings: ${features.length ? features.join(", ") : "normal"};
+}`);
+
+  let style = document.getElementById(STYLE_ID);
+  if (!style) {
+    style = document.createElement("style");
+    style.id = STYLE_ID;
+    document.head.appendChild(style);
+  }
+  style.textContent = rules.join("\n");
+
+  return family;
+}
@@ -0,0 +1,16 @@
+import { expect, test } from "vitest";
+import { decompressLz4 } from "./lz4";
+
+// A compressed output frame as the backend builds it, the same one its
+// test_lz4_output_frame in src-tauri/src/pty/core.rs pins
+const FRAME = new Uint8Array([
+  2, 144, 0, 0, 0, 255, 3, 27, 91, 51, 50, 109, 111, 107, 27, 91, 48, 109, 32,
+  108, 105, 110, 101, 13, 10, 18, 0, 101, 96, 108, 105, 110, 101, 13, 10,
+]);
+
+test("decompresses an LZ4 output frame", () => {
+  const output = decompressLz4(FRAME.subarray(1));
+  expect(new TextDecoder().decode(output)).toBe(
+    "\x1b[32mok\x1b[0m line\r\n".repeat(8)
+  );
+});
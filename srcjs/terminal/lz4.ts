@@ -0,0 +1,48 @@
+// Lengths of 15 go on in the following bytes, until one isn't 255
+function extendLength(
+  input: Uint8Array,
+  index: number,
+  length: number
+): [number, number] {
+  if (length === 15) {
+    let byte;
+    do {
+      byte = input[index++];
+      length += byte;
+    } while (byte === 255);
+  }
+  return [length, index];
+}
+
+// Decompress an LZ4 block prefixed with its decompressed size as a 32-bit
+// little endian number, the format the backend compresses large output in
+export function decompressLz4(input: Uint8Array): Uint8Array {
+  const size =
+    (input[0] | (input[1] << 8) | (input[2] << 16) | (input[3] << 24)) >>> 0;
+  const output = new Uint8Array(size);
+  let i = 4;
+  let o = 0;
+  while (i < input.length) {
+    const token = input[i++];
+
+    // Literals are copied as they are
+    let literals;
+    [literals, i] = extendLength(input, i, token >> 4);
+    output.set(input.subarray(i, i + literals), o);
+    i += literals;
+    o += literals;
+    // The last sequence has no match
+    if (i >= input.length) break;
+
+    // A match repeats earlier output, possibly overlapping what it writes
+    const offset = input[i] | (input[i + 1] << 8);
+    i += 2;
+    let length;
+    [length, i] = extendLength(input, i, token & 15);
+    let from = o - offset;
+    for (let end = o + length + 4; o < end; ) {
+      output[o++] = output[from++];
+    }
+  }
+  return output;
+}
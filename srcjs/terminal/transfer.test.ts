@@ -0,0 +1,57 @@
+import { beforeEach, expect, test, vi } from "vitest";
+import { invoke } from "@tauri-apps/api/core";
+import { attachPty, handleTransfer, type PtyTransfer } from "./transfer";
+
+vi.mock("@tauri-apps/api/core", () => ({ invoke: vi.fn() }));
+
+const TRANSFER: PtyTransfer = {
+  ptyId: "pty-1",
+  fromWindow: "main",
+  toWindow: "window-2",
+  profile: "zsh",
+  title: "vim",
+};
+
+// A window's tabs, by the PTYs they show
+function fakeWindow(label: string, ptys: string[]) {
+  const tabs = new Set(ptys);
+  const target = {
+    adoptTerminal: async (transfer: PtyTransfer) => {
+      tabs.add(transfer.ptyId);
+      await attachPty(transfer.ptyId, "channel" as never, `tab-${label}`);
+    },
+    releaseTerminal: async (ptyId: string) => {
+      tabs.delete(ptyId);
+    },
+  };
+  return { label, tabs, target };
+}
+
+beforeEach(() => {
+  vi.mocked(invoke).mockReset();
+});
+
+test("moves the tab of a transferred PTY", async () => {
+  const source = fakeWindow("main", ["pty-1", "pty-2"]);
+  const dest = fakeWindow("window-2", ["pty-3"]);
+  for (const window of [source, dest]) {
+    await handleTransfer(TRANSFER, window.label, window.target);
+  }
+
+  expect([...source.tabs]).toEqual(["pty-2"]);
+  expect([...dest.tabs]).toEqual(["pty-3", "pty-1"]);
+  expect(invoke).toHaveBeenCalledOnce();
+  expect(invoke).toHaveBeenCalledWith("attach_pty", {
+    ptyId: "pty-1",
+    outputChannel: "channel",
+    tabId: "tab-window-2",
+  });
+});
+
+test("leaves other windows alone", async () => {
+  const other = fakeWindow("window-3", ["pty-4"]);
+  await handleTransfer(TRANSFER, other.label, other.target);
+
+  expect([...other.tabs]).toEqual(["pty-4"]);
+  expect(invoke).not.toHaveBeenCalled();
+});
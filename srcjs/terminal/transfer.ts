@@ -0,0 +1,57 @@
+import { invoke, type Channel } from "@tauri-apps/api/core";
+
+// Sent to both windows when a PTY is moved from one to the other
+export interface PtyTransfer {
+  ptyId: string;
+  fromWindow: string;
+  toWindow: string;
+  profile: string | null;
+  title: string | null;
+}
+
+// A PTY whose window closed while it kept running
+export interface DetachedPty {
+  id: string;
+  title: string | null;
+  shell: string;
+  profile: string | null;
+}
+
+// What a window does with its tabs when one of its PTYs moves
+export interface TransferTarget {
+  adoptTerminal(transfer: PtyTransfer): Promise<void>;
+  releaseTerminal(ptyId: string): Promise<void>;
+}
+
+// The window a PTY moved to shows it in a new tab, the one it left drops
+// its tab without destroying the PTY
+export async function handleTransfer(
+  transfer: PtyTransfer,
+  windowLabel: string,
+  target: TransferTarget
+): Promise<void> {
+  if (transfer.toWindow === windowLabel) {
+    await target.adoptTerminal(transfer);
+  } else if (transfer.fromWindow === windowLabel) {
+    await target.releaseTerminal(transfer.ptyId);
+  }
+}
+
+// Move a PTY to a window, which is told with a `pty-transferred` event
+export function transferPty(ptyId: string, windowLabel: string): Promise<void> {
+  return invoke("transfer_pty", { ptyId, windowLabel });
+}
+
+// The PTYs of closed windows, waiting for a window to take them over
+export function listDetachedPtys(): Promise<DetachedPty[]> {
+  return invoke<DetachedPty[]>("list_detached_ptys");
+}
+
+// Send the output of a PTY moved to this window to one of its terminals
+export function attachPty(
+  ptyId: string,
+  outputChannel: Channel<ArrayBuffer>,
+  tabId: string | null
+): Promise<void> {
+  return invoke("attach_pty", { ptyId, outputChannel, tabId });
+}
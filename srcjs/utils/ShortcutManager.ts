@@ -64,6 +64,7 @@ export class ShortcutManager {
 
   public getShortcutConfig(key: keyof Config["shortcuts"]): string {
     const shortcut = this.config.shortcuts[key];
+    if (!shortcut || !shortcut.key) return "";
     return [
       shortcut.ctrl ? "Ctrl" : "",
       shortcut.shift ? "Shift" : "",